@@ -1,14 +1,19 @@
 use core::panic;
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc};
 
 use common::{
     code::{Asm, Code},
     fileposition::FilePosition,
+    ttype::Width,
 };
 
 #[derive(Debug, Clone)]
 pub struct Assembler {
     pub runtime_error_table: HashMap<usize, FilePosition>,
+    // statements that start at an address, several when one emits no code
+    pub line_table: HashMap<usize, Vec<FilePosition>>,
+    // entry address of every function the compiler named
+    pub function_names: HashMap<usize, Rc<str>>,
     pub input: Vec<Asm>,
     pub nva: Vec<Asm>,
     pub output: Vec<u8>,
@@ -24,6 +29,8 @@ pub fn new(input: Vec<Asm>) -> Assembler {
         forwardjumps: vec![],
         nva: vec![],
         runtime_error_table: HashMap::default(),
+        line_table: HashMap::default(),
+        function_names: HashMap::default(),
     }
 }
 
@@ -36,6 +43,8 @@ impl Assembler {
             forwardjumps: vec![],
             nva: vec![],
             runtime_error_table: HashMap::default(),
+            line_table: HashMap::default(),
+            function_names: HashMap::default(),
         }
     }
     // pub fn assemble_from_nva(&mut self, fileinput: TokenList) {
@@ -331,8 +340,10 @@ impl Assembler {
     pub fn assemble(&mut self) {
         for instruction in self.input.iter().cloned() {
             match instruction {
-                Asm::EXIT => {
+                Asm::EXIT(file_position) => {
                     self.output.push(Code::EXIT);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
                 }
                 Asm::LABEL(label) => {
                     self.labels.insert(label, self.output.len() as u64);
@@ -478,6 +489,12 @@ impl Assembler {
                 Asm::FGTR => {
                     self.output.push(Code::FGTR);
                 }
+                Asm::LSS => {
+                    self.output.push(Code::LSS);
+                }
+                Asm::GTR => {
+                    self.output.push(Code::GTR);
+                }
                 Asm::EQUALS => {
                     self.output.push(Code::EQUALS);
                 }
@@ -548,14 +565,78 @@ impl Assembler {
                 Asm::CONCAT => self.output.push(Code::CONCAT),
                 Asm::Char(v) => {
                     self.output.push(Code::CHAR);
-                    let cast = v as u8;
-                    self.output.push(cast);
+                    self.output.extend_from_slice(&(v as u32).to_le_bytes());
                 }
                 Asm::ERROR(file_position) => {
                     self.output.push(Code::ERROR);
                     self.runtime_error_table
                         .insert(self.output.len(), file_position);
                 }
+                Asm::REQUIRES(file_position) => {
+                    self.output.push(Code::REQUIRES);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                }
+                Asm::ENSURES(file_position) => {
+                    self.output.push(Code::ENSURES);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                }
+                Asm::CALLSITE(file_position) => {
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                }
+                Asm::NONEFROM(file_position) => {
+                    self.output.push(Code::NONE);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                }
+                Asm::NAME(name) => {
+                    self.function_names.insert(self.output.len(), name);
+                }
+                Asm::LAZY(index, file_position) => {
+                    self.output.push(Code::LAZY);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                    self.output.extend_from_slice(&index.to_le_bytes());
+                }
+                Asm::DEFER => self.output.push(Code::DEFER),
+                Asm::UNDEFER => self.output.push(Code::UNDEFER),
+                Asm::TYPENAME => self.output.push(Code::TYPENAME),
+                Asm::SIZED(value, width) => {
+                    self.output.push(Code::SIZED);
+                    self.output.push(width.to_byte());
+                    self.output.extend_from_slice(&value.to_le_bytes());
+                }
+                Asm::CONVERT(width, overflow, file_position) => {
+                    self.output.push(Code::CONVERT);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                    // past the last width for a conversion to Int
+                    self.output.push(width.map_or(u8::MAX, Width::to_byte));
+                    self.output.push(overflow as u8);
+                }
+                Asm::TRY(target) => {
+                    self.output.push(Code::TRY);
+                    self.forwardjumps.push((target, self.output.len() as u64));
+                    self.output.extend_from_slice(&0u32.to_le_bytes());
+                }
+                Asm::ENDTRY => self.output.push(Code::ENDTRY),
+                Asm::RAISE(file_position) => {
+                    self.output.push(Code::RAISE);
+                    self.runtime_error_table
+                        .insert(self.output.len(), file_position);
+                }
+                Asm::LAZYSET(index) => {
+                    self.output.push(Code::LAZYSET);
+                    self.output.extend_from_slice(&index.to_le_bytes());
+                }
+                Asm::LINE(file_position) => {
+                    self.line_table
+                        .entry(self.output.len())
+                        .or_default()
+                        .push(file_position);
+                }
             }
         }
 
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use crate::fileposition::FilePosition;
+use crate::{fileposition::FilePosition, ttype::Width};
 
 pub struct Code {}
 
@@ -128,6 +128,56 @@ impl Code {
 
     pub const EXIT: u8 = 77;
     pub const ERROR: u8 = 78;
+
+    pub const REQUIRES: u8 = 79;
+    pub const ENSURES: u8 = 80;
+
+    // comparisons on the runtime type, for generics bounded by Comparable
+    pub const LSS: u8 = 81;
+    pub const GTR: u8 = 82;
+
+    // lazy values, the operand is the global holding the cached value
+    pub const LAZY: u8 = 83;
+    pub const LAZYSET: u8 = 84;
+
+    // the close functions of open with blocks, run if an error ends the program
+    pub const DEFER: u8 = 85;
+    pub const UNDEFER: u8 = 86;
+
+    // the runtime type of a value as a string, for narrowing union types
+    pub const TYPENAME: u8 = 87;
+
+    // sized integers, a literal with its width and a conversion between the
+    // integer types
+    pub const SIZED: u8 = 88;
+    pub const CONVERT: u8 = 89;
+
+    // try blocks, an error raised between TRY and ENDTRY jumps to the handler
+    // with its message instead of ending the program
+    pub const TRY: u8 = 90;
+    pub const ENDTRY: u8 = 91;
+    pub const RAISE: u8 = 92;
+}
+
+// what a conversion does with a value that does not fit the type it converts to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    // a runtime error
+    Error,
+    // keeps the low bits
+    Wrap,
+    // None instead of the value
+    Checked,
+}
+
+impl Overflow {
+    pub fn from_byte(byte: u8) -> Overflow {
+        match byte {
+            1 => Overflow::Wrap,
+            2 => Overflow::Checked,
+            _ => Overflow::Error,
+        }
+    }
 }
 
 pub fn byte_to_string(byte: u8) -> String {
@@ -207,6 +257,20 @@ pub fn byte_to_string(byte: u8) -> String {
         Code::DUP => "DUP",
         Code::ISSOME => "ISSOME",
         Code::UNWRAP => "UNWRAP",
+        Code::REQUIRES => "REQUIRES",
+        Code::ENSURES => "ENSURES",
+        Code::LSS => "LSS",
+        Code::GTR => "GTR",
+        Code::LAZY => "LAZY",
+        Code::LAZYSET => "LAZYSET",
+        Code::DEFER => "DEFER",
+        Code::UNDEFER => "UNDEFER",
+        Code::TYPENAME => "TYPENAME",
+        Code::SIZED => "SIZED",
+        Code::CONVERT => "CONVERT",
+        Code::TRY => "TRY",
+        Code::ENDTRY => "ENDTRY",
+        Code::RAISE => "RAISE",
         _ => "Unknown", // Handle the case where the byte is not in the enum.
     }
     .to_string()
@@ -268,6 +332,9 @@ pub enum Asm {
     FLSS,
     FGTR,
 
+    LSS,
+    GTR,
+
     EQUALS,
     NOT,
     NEG,
@@ -295,6 +362,42 @@ pub enum Asm {
     LIN(FilePosition),
     NATIVE(u64),
 
-    EXIT,
+    EXIT(FilePosition),
     ERROR(FilePosition),
+
+    // contracts
+    REQUIRES(FilePosition),
+    ENSURES(FilePosition),
+    // marks the return address of the call before it, or where the Int
+    // arithmetic before it was written, emits no code
+    CALLSITE(FilePosition),
+    // a None literal, remembers where it was written for unwrap errors
+    NONEFROM(FilePosition),
+    // marks where a source line starts, emits no code
+    LINE(FilePosition),
+    // names the function body that follows, emits no code
+    NAME(Rc<str>),
+    // pushes true when the lazy value cached in the global still has to be computed
+    LAZY(u32, FilePosition),
+    // caches the value on top of the stack in the global, leaving it there
+    LAZYSET(u32),
+    // remembers the close function on top of the stack and the resource under it
+    DEFER,
+    // forgets the close function of the innermost with block
+    UNDEFER,
+    // replaces the value on top of the stack with the name of its runtime type,
+    // Int, Float, Bool, Char, String, Function, the name of a struct or enum,
+    // or List for any other list
+    TYPENAME,
+    // pushes a sized integer, stored the way Width::wrap gives it
+    SIZED(i64, Width),
+    // converts the Int or sized integer on top of the stack to the width, or
+    // to an Int when there is none
+    CONVERT(Option<Width>, Overflow, FilePosition),
+    // starts a try block, an error before the matching ENDTRY unwinds to the
+    // label with the error message on top of the stack
+    TRY(u64),
+    ENDTRY,
+    // raises an error with the value on top of the stack as its message
+    RAISE(FilePosition),
 }
@@ -1,15 +1,19 @@
 use std::{collections::HashMap, rc::Rc};
 
+use indexmap::IndexMap;
+
 use crate::{
     fileposition::FilePosition,
-    nodes::{Symbol, SymbolKind},
+    nodes::{Bound, Symbol, SymbolKind},
     table::{self, Table},
     ttype::{generate_unique_string, TType},
 };
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Environment {
-    pub captured: Vec<HashMap<Rc<str>, Symbol>>,
+    // names each function being parsed found in an enclosing function, the
+    // ones its body really uses are what it captures
+    pub captured: Vec<IndexMap<Rc<str>, Symbol>>,
     pub custom_types: HashMap<Rc<str>, Vec<(Rc<str>, TType)>>,
     pub enums: table::Table<Rc<str>>,
     pub no_override: table::Table<Rc<str>>,
@@ -19,6 +23,13 @@ pub struct Environment {
     pub generic_type_map: HashMap<Rc<str>, Rc<str>>,
     pub live_generics: Vec<table::Table<Rc<str>>>,
     pub forward_declarations: HashMap<Rc<str>, (Vec<TType>, TType, FilePosition)>,
+    pub uninitialized: Vec<table::Table<Rc<str>>>,
+    // bounds of the generic functions being parsed, innermost last
+    pub live_bounds: Vec<Vec<Bound>>,
+    // where every named symbol was declared, struct names excepted
+    pub declarations: Vec<FilePosition>,
+    // try blocks open in each function being parsed, innermost function last
+    pub open_tries: Vec<usize>,
 }
 
 impl Default for Environment {
@@ -26,7 +37,7 @@ impl Default for Environment {
         Environment {
             custom_types: HashMap::default(),
             no_override: Table::new(),
-            captured: vec![HashMap::default()],
+            captured: vec![IndexMap::default()],
             values: vec![HashMap::default()],
             type_alias: HashMap::default(),
             generic_type_struct: HashMap::default(),
@@ -34,6 +45,10 @@ impl Default for Environment {
             live_generics: vec![Table::new()],
             enums: Table::new(),
             forward_declarations: HashMap::default(),
+            uninitialized: vec![Table::new()],
+            live_bounds: vec![],
+            declarations: vec![],
+            open_tries: vec![0],
         }
     }
 }
@@ -49,6 +64,9 @@ impl Environment {
         pos: Option<FilePosition>,
         kind: SymbolKind,
     ) {
+        if let Some(pos) = pos.as_ref().filter(|_| kind != SymbolKind::Constructor) {
+            self.declarations.push(pos.clone());
+        }
         match kind {
             SymbolKind::GenericFunction => {
                 let id: Rc<str> = id.into();
@@ -59,6 +77,7 @@ impl Environment {
                         ttype,
                         pos,
                         kind,
+                        bounds: vec![],
                     },
                 );
             }
@@ -76,6 +95,7 @@ impl Environment {
                             ttype,
                             pos,
                             kind,
+                            bounds: vec![],
                         },
                     );
                 } else {
@@ -84,6 +104,8 @@ impl Environment {
             }
             _ => {
                 let id: Rc<str> = id.into();
+                // a fresh binding shadows any pending declaration
+                self.mark_initialized(&id);
                 self.values.last_mut().unwrap().insert(
                     id.clone(),
                     Symbol {
@@ -91,12 +113,23 @@ impl Environment {
                         ttype,
                         pos,
                         kind,
+                        bounds: vec![],
                     },
                 );
             }
         }
     }
 
+    // where the symbol visible under the name was declared
+    pub fn declaration(&self, symbol: &str) -> Option<FilePosition> {
+        self.values
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(symbol))
+            .filter(|s| s.kind != SymbolKind::Constructor)
+            .and_then(|s| s.pos.clone())
+    }
+
     pub fn has(&mut self, symbol: &str) -> bool {
         if self.forward_declarations.contains_key(symbol) {
             self.forward_declarations.remove(symbol);
@@ -109,6 +142,54 @@ impl Environment {
         self.values.last().unwrap().get(symbol).cloned()
     }
 
+    // whether a generic of a function being parsed was declared with the bound
+    pub fn has_bound(&self, generic: &str, bound: &str) -> bool {
+        self.live_bounds
+            .iter()
+            .flatten()
+            .any(|(name, b)| &**name == generic && &**b == bound)
+    }
+
+    pub fn is_constant(&self, symbol: &str) -> bool {
+        self.values
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(symbol))
+            .is_some_and(|s| s.kind == SymbolKind::Constant)
+    }
+
+    pub fn is_lazy(&self, symbol: &str) -> bool {
+        self.values
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(symbol))
+            .is_some_and(|s| s.kind == SymbolKind::Lazy)
+    }
+
+    pub fn is_immutable(&self, symbol: &str) -> bool {
+        self.values
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(symbol))
+            .is_some_and(|s| s.kind == SymbolKind::Variable)
+    }
+
+    pub fn mark_mutable(&mut self, symbol: &str) {
+        if let Some(s) = self.values.last_mut().unwrap().get_mut(symbol) {
+            s.kind = SymbolKind::Mutable;
+        }
+    }
+
+    // the alias a type was most likely written as, aliases are expanded while
+    // parsing so this looks the expansion back up
+    pub fn alias_of(&self, ttype: &TType) -> Option<Rc<str>> {
+        self.type_alias
+            .iter()
+            .filter(|(_, expansion)| *expansion == ttype)
+            .map(|(alias, _)| alias.clone())
+            .min()
+    }
+
     pub fn get_type(&mut self, symbol: &str) -> Option<TType> {
         self.values
             .last()
@@ -191,9 +272,64 @@ impl Environment {
         }
     }
 
+    // variables declared without a value stay here until they are definitely assigned
+    pub fn declare_uninitialized(&mut self, symbol: Rc<str>) {
+        self.uninitialized.last_mut().unwrap().insert(symbol);
+    }
+
+    pub fn is_uninitialized(&self, symbol: &Rc<str>) -> bool {
+        self.uninitialized.last().unwrap().has(symbol)
+    }
+
+    pub fn mark_initialized(&mut self, symbol: &Rc<str>) {
+        let uninitialized = self.uninitialized.last_mut().unwrap();
+        if uninitialized.has(symbol) {
+            uninitialized.remove(symbol);
+        }
+    }
+
+    pub fn initialization_state(&self) -> Table<Rc<str>> {
+        self.uninitialized.last().unwrap().clone()
+    }
+
+    pub fn restore_initialization(&mut self, state: Table<Rc<str>>) {
+        *self.uninitialized.last_mut().unwrap() = state;
+    }
+
+    // nothing after a return, break or continue runs, so every variable counts as initialized
+    pub fn mark_unreachable(&mut self) {
+        self.uninitialized.last_mut().unwrap().clear();
+    }
+
+    // a variable is only initialized after the branches if every branch initialized it
+    pub fn merge_initialization(&mut self, branches: Vec<Table<Rc<str>>>) {
+        let mut merged = Table::new();
+        for branch in branches {
+            for symbol in branch.items {
+                merged.insert(symbol);
+            }
+        }
+        *self.uninitialized.last_mut().unwrap() = merged;
+    }
+
+    // resolves the type of an Auto variable, or one declared with _ holes in
+    // its type, in every scope that can see it
+    pub fn resolve_auto(&mut self, symbol: &str, ttype: TType) {
+        for scope in self.values.iter_mut() {
+            if let Some(s) = scope.get_mut(symbol) {
+                if s.ttype == TType::Auto || s.ttype.has_hole() {
+                    s.ttype = ttype.clone();
+                }
+            }
+        }
+    }
+
     pub fn push_scope(&mut self) {
         let mut scope = HashMap::default();
-        self.captured.push(self.captured.last().unwrap().clone());
+        self.uninitialized
+            .push(self.uninitialized.last().unwrap().clone());
+        self.captured.push(IndexMap::default());
+        self.open_tries.push(0);
         for (id, sym) in self.values.last().unwrap().iter() {
             match sym.kind {
                 SymbolKind::Function | SymbolKind::GenericFunction | SymbolKind::Constructor => {
@@ -206,8 +342,10 @@ impl Environment {
     }
 
     pub fn pop_scope(&mut self) {
+        self.uninitialized.pop();
         self.values.pop();
         self.captured.pop();
+        self.open_tries.pop();
     }
 
     pub fn push_block(&mut self) {
@@ -1,9 +1,16 @@
-use crate::{fileposition::FilePosition, ttype::TType};
-use colored::Colorize;
+use crate::{
+    fileposition::FilePosition,
+    ttype::{describe_type, TType},
+};
+use colored::{ColoredString, Colorize};
 use std::{
+    any::Any,
     borrow::Cow,
-    io::{self, BufRead},
+    cell::RefCell,
+    io::{self, BufRead, Write},
+    panic::{catch_unwind, AssertUnwindSafe},
     path::Path,
+    rc::Rc,
 };
 
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<std::fs::File>>>
@@ -15,6 +22,16 @@ where
 }
 
 pub fn print_line(position: &FilePosition, msg: &str) {
+    write_line(&mut io::stdout(), position, "^".red(), msg.bright_red());
+}
+
+fn write_line(
+    out: &mut impl Write,
+    position: &FilePosition,
+    marker: ColoredString,
+    msg: ColoredString,
+) {
+    let mut shown = false;
     if let Ok(lines) = read_lines(position.filepath.as_deref().unwrap_or(Path::new(""))) {
         let line_number_width = position.line.to_string().chars().count();
 
@@ -22,9 +39,11 @@ pub fn print_line(position: &FilePosition, msg: &str) {
             let current_line = linenumber + 1;
             if current_line == position.line {
                 if let Ok(line) = line_content {
+                    shown = true;
                     // Print line number and line content with padding
-                    println!("{:<width$} |", "", width = line_number_width);
-                    println!(
+                    let _ = writeln!(out, "{:<width$} |", "", width = line_number_width);
+                    let _ = writeln!(
+                        out,
                         "{:width$} | {}",
                         current_line,
                         line,
@@ -32,17 +51,122 @@ pub fn print_line(position: &FilePosition, msg: &str) {
                     );
 
                     // Print marker line with padding for alignment
-                    print!("{:<width$} |", "", width = line_number_width);
+                    let _ = write!(out, "{:<width$} |", "", width = line_number_width);
                     if let Some(mut row) = position.col.checked_sub(1) {
                         row += 1;
-                        println!("{: <row$}{} {}", "", "^".red(), msg.bright_red(), row = row);
+                        let _ = writeln!(out, "{: <row$}{} {}", "", marker, msg, row = row);
                     } else {
-                        println!(" {}", "^".red());
+                        let _ = writeln!(out, " {}", marker);
                     }
                 }
             }
         }
     }
+    // an empty file, or source that is not in a file, has no line to point at
+    if !shown {
+        let _ = writeln!(out, "{}", msg);
+    }
+}
+
+// a problem worth pointing out that does not stop the program from building,
+// printed to stderr so it never mixes with the output of the script
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub msg: Cow<'static, str>,
+    pub note: Cow<'static, str>,
+    pub position: FilePosition,
+    // edits nova fix can apply to silence the warning
+    pub fixes: Vec<Fix>,
+}
+
+impl Warning {
+    pub fn show(&self) {
+        let mut err = io::stderr().lock();
+        let _ = writeln!(
+            err,
+            "{} in {}:{}:{}",
+            "Warning".bright_yellow(),
+            self.position
+                .filepath
+                .as_deref()
+                .unwrap_or(Path::new("repl"))
+                .display(),
+            self.position.line,
+            self.position.col
+        );
+        write_line(
+            &mut err,
+            &self.position,
+            "^".yellow(),
+            self.msg.bright_yellow(),
+        );
+        let _ = writeln!(err, "{}: {}", "Note".bright_yellow(), self.note);
+        for fix in self.fixes.iter() {
+            let _ = writeln!(err, "{}: {}", "Fix".bright_green(), fix.msg.bright_green());
+        }
+    }
+}
+
+// an edit that resolves a diagnostic, the source from start up to end is
+// replaced with the replacement, start and end are the same for an insert
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub msg: Cow<'static, str>,
+    pub start: FilePosition,
+    pub end: FilePosition,
+    pub replacement: String,
+}
+
+impl Fix {
+    pub fn insert(msg: impl Into<Cow<'static, str>>, at: FilePosition, text: &str) -> Fix {
+        Fix {
+            msg: msg.into(),
+            start: at.clone(),
+            end: at,
+            replacement: text.to_owned(),
+        }
+    }
+
+    fn show(&self) {
+        println!("{}: {}", "Fix".bright_green(), self.msg.bright_green());
+    }
+}
+
+// the source with the fixes applied, fixes that overlap one applied before
+// them are left out. columns count chars like the lexer does
+pub fn apply_fixes(source: &str, fixes: &[Fix]) -> String {
+    let mut line_starts = vec![0];
+    line_starts.extend(source.match_indices('\n').map(|(index, _)| index + 1));
+    let offset = |position: &FilePosition| -> Option<usize> {
+        let start = *line_starts.get(position.line.checked_sub(1)?)?;
+        let line = &source[start..];
+        let col = position.col.checked_sub(1)?;
+        match line.char_indices().nth(col) {
+            Some((index, _)) => Some(start + index),
+            None if line.chars().count() == col => Some(source.len()),
+            None => None,
+        }
+    };
+    let mut edits: Vec<(usize, usize, &str)> = vec![];
+    for fix in fixes {
+        let (Some(start), Some(end)) = (offset(&fix.start), offset(&fix.end)) else {
+            continue;
+        };
+        if end < start
+            || edits
+                .iter()
+                .any(|(s, e, _)| start < *e && *s < end || start == *s)
+        {
+            continue;
+        }
+        edits.push((start, end, &fix.replacement));
+    }
+    edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+    let mut out = source.to_owned();
+    for (start, end, replacement) in edits {
+        out.replace_range(start..end, replacement);
+    }
+    out
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +184,8 @@ pub enum NovaError {
         note: Cow<'static, str>,
         position: FilePosition,
         extra: Option<Vec<(String, FilePosition)>>,
+        // boxed to keep NovaError small, most errors have none
+        fixes: Box<[Fix]>,
     },
     Compiler {
         msg: Cow<'static, str>,
@@ -79,17 +205,149 @@ pub enum NovaError {
         position: FilePosition,
     },
     TypeMismatch {
-        expected: TType,
-        found: TType,
+        expected: Box<TType>,
+        found: Box<TType>,
+        // the type aliases the two types were written as, if any
+        expected_alias: Option<Rc<str>>,
+        found_alias: Option<Rc<str>>,
         position: FilePosition,
     },
     SimpleTypeError {
         msg: Cow<'static, str>,
         position: FilePosition,
     },
+    // the host stopped the script through an interrupt handle
+    Interrupted,
+    // nova itself crashed, phase says what it was doing as in "parsing" and
+    // position how far into the program it got
+    Internal {
+        phase: Cow<'static, str>,
+        msg: Cow<'static, str>,
+        position: Option<FilePosition>,
+    },
+}
+
+// the message and place of a panic for each catch_panic running on this
+// thread, innermost last. the hook puts a panic into the innermost one
+thread_local! {
+    static CAUGHT: RefCell<Vec<Option<String>>> = const { RefCell::new(vec![]) };
+}
+
+// replaces the default panic hook, which prints a rust backtrace note, with
+// one that keeps the panic for the catch_panic it unwinds to. with
+// RUST_BACKTRACE set, or when nothing catches it, the default hook still runs
+pub fn quiet_panics() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info
+            .location()
+            .map(|location| format!(" at {}:{}", location.file(), location.line()))
+            .unwrap_or_default();
+        let msg = format!("{}{}", panic_message(info.payload()), location);
+        let caught = CAUGHT.with(|caught| match caught.borrow_mut().last_mut() {
+            Some(last) => {
+                *last = Some(msg);
+                true
+            }
+            None => false,
+        });
+        if !caught || std::env::var_os("RUST_BACKTRACE").is_some() {
+            default(info);
+        }
+    }));
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// runs the function, a panic inside it is returned as its message and the
+// place quiet_panics saw it happen
+pub fn catch_panic<T>(run: impl FnOnce() -> T) -> Result<T, String> {
+    CAUGHT.with(|caught| caught.borrow_mut().push(None));
+    let result = catch_unwind(AssertUnwindSafe(run));
+    let msg = CAUGHT.with(|caught| caught.borrow_mut().pop().flatten());
+    result.map_err(|payload| msg.unwrap_or_else(|| panic_message(payload.as_ref())))
+}
+
+// the error for a panic caught with catch_panic while nova was in the phase
+pub fn internal_error(
+    phase: impl Into<Cow<'static, str>>,
+    msg: String,
+    position: Option<FilePosition>,
+) -> NovaError {
+    NovaError::Internal {
+        phase: phase.into(),
+        msg: msg.into(),
+        position,
+    }
+}
+
+fn show_internal(phase: &str, msg: &str, position: Option<&FilePosition>) {
+    println!(
+        "{}: nova crashed while {}",
+        "Internal Error".bright_red(),
+        phase
+    );
+    println!("{}", msg.bright_red());
+    if let Some(position) = position {
+        println!(
+            "{}: {}:{}:{}",
+            "Reached".bright_yellow(),
+            position
+                .filepath
+                .as_deref()
+                .unwrap_or(Path::new("repl"))
+                .display(),
+            position.line,
+            position.col
+        );
+    }
+    println!(
+        "{}",
+        "This is a bug in nova, not in your program. Please report it with the \
+         program and this message at https://github.com/pyrotek45/nova-lang/issues"
+            .bright_yellow()
+    );
+    print!("{}", "".clear());
 }
 
 impl NovaError {
+    // what the cli exits with: 1 when the program failed while running, 2
+    // when it could not be built and 101 when nova itself crashed
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            NovaError::Runtime { .. }
+            | NovaError::RuntimeWithPos { .. }
+            | NovaError::Interrupted => 1,
+            NovaError::Internal { .. } => 101,
+            _ => 2,
+        }
+    }
+
+    // the quick fixes attached to the diagnostic, only parsing errors have any
+    pub fn fixes(&self) -> &[Fix] {
+        match self {
+            NovaError::Parsing { fixes, .. } => fixes,
+            _ => &[],
+        }
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> NovaError {
+        if let NovaError::Parsing { fixes, .. } = &mut self {
+            let mut all = std::mem::take(fixes).into_vec();
+            all.push(fix);
+            *fixes = all.into_boxed_slice();
+        }
+        self
+    }
+
     pub fn show_without_position(&self) {
         match &self {
             NovaError::File { msg } => {
@@ -102,7 +360,11 @@ impl NovaError {
                 print!("{}", "".clear());
             }
             NovaError::Parsing {
-                msg, note, extra, ..
+                msg,
+                note,
+                extra,
+                fixes,
+                ..
             } => {
                 println!("{}: {}", "Parsing Error".bright_red(), msg);
                 if let Some(extra_notes) = extra {
@@ -111,6 +373,7 @@ impl NovaError {
                     }
                 }
                 println!("{}: {}", "Note".bright_yellow(), note.bright_yellow());
+                fixes.iter().for_each(Fix::show);
                 print!("{}", "".clear());
             }
             NovaError::Runtime { msg } => {
@@ -137,20 +400,37 @@ impl NovaError {
                 print!("{}", "".clear());
             }
             NovaError::TypeMismatch {
-                expected, found, ..
+                expected,
+                found,
+                expected_alias,
+                found_alias,
+                ..
             } => {
                 println!(
                     "{}: {}",
                     "Type Mismatch".bright_red(),
                     "Type Mismatch".bright_red()
                 );
-                println!("Expected type: {expected}\nFound type: {found}",);
+                println!(
+                    "Expected type: {}\nFound type: {}",
+                    describe_type(expected, expected_alias.as_deref()),
+                    describe_type(found, found_alias.as_deref())
+                );
                 print!("{}", "".clear());
             }
             NovaError::SimpleTypeError { msg, .. } => {
                 println!("{}: {}", "Type Error".bright_red(), msg.bright_red());
                 print!("{}", "".clear());
             }
+            NovaError::Interrupted => {
+                println!(
+                    "{}: {}",
+                    "Runtime Error".bright_red(),
+                    "interrupted".bright_red()
+                );
+                print!("{}", "".clear());
+            }
+            NovaError::Internal { phase, msg, .. } => show_internal(phase, msg, None),
         }
     }
 
@@ -185,6 +465,7 @@ impl NovaError {
                 note,
                 position,
                 extra,
+                fixes,
             } => {
                 println!(
                     "{} in {}:{}:{}",
@@ -204,6 +485,7 @@ impl NovaError {
                     }
                 }
                 println!("{}: {}", "Note".bright_yellow(), note.bright_yellow());
+                fixes.iter().for_each(Fix::show);
                 print!("{}", "".clear());
             }
             NovaError::Runtime { msg } => {
@@ -256,6 +538,8 @@ impl NovaError {
             NovaError::TypeMismatch {
                 expected,
                 found,
+                expected_alias,
+                found_alias,
                 position,
             } => {
                 println!(
@@ -271,7 +555,11 @@ impl NovaError {
                 );
                 print_line(
                     position,
-                    &format!("Expected type: {}\nFound type: {}", expected, found),
+                    &format!(
+                        "Expected type: {}\nFound type: {}",
+                        describe_type(expected, expected_alias.as_deref()),
+                        describe_type(found, found_alias.as_deref())
+                    ),
                 );
                 print!("{}", "".clear());
             }
@@ -290,6 +578,19 @@ impl NovaError {
                 print_line(position, msg);
                 print!("{}", "".clear());
             }
+            NovaError::Interrupted => {
+                println!(
+                    "{}: {}",
+                    "Runtime Error".bright_red(),
+                    "interrupted".bright_red()
+                );
+                print!("{}", "".clear());
+            }
+            NovaError::Internal {
+                phase,
+                msg,
+                position,
+            } => show_internal(phase, msg, position.as_ref()),
         }
     }
 }
@@ -2,7 +2,7 @@ use std::{path::Path, rc::Rc};
 
 use crate::error::NovaError;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FilePosition {
     pub filepath: Option<Rc<Path>>,
     pub line: usize,
@@ -0,0 +1,281 @@
+use crate::{ttype::TType, utilities::format_float};
+
+// format strings, {} takes the next value and {:spec} formats it like rust
+// does: [[fill]align][+][#][0][width][.precision][type]. align is < ^ or >,
+// type is x X b o or e, and {{ and }} are literal braces
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Text(String),
+    Value(Spec),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Display,
+    Hex,
+    UpperHex,
+    Binary,
+    Octal,
+    Exponent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spec {
+    pub fill: char,
+    pub align: Option<Align>,
+    pub sign: bool,
+    // 0x, 0b or 0o in front of the digits
+    pub alternate: bool,
+    // pads numbers with zeros after the sign
+    pub zero: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    pub kind: Kind,
+}
+
+// a value ready to be formatted, anything that is not a number is formatted
+// as its text
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'a> {
+    Int(i128),
+    Float(f64),
+    Text(&'a str),
+}
+
+pub fn parse(format: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' => return Err("a } must be written }} when it is not closing a {".into()),
+            '{' => {
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err("a { is never closed, write {{ for a brace".into()),
+                    }
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Value(parse_spec(&inner)?));
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+fn parse_spec(inner: &str) -> Result<Spec, String> {
+    let mut spec = Spec {
+        fill: ' ',
+        align: None,
+        sign: false,
+        alternate: false,
+        zero: false,
+        width: None,
+        precision: None,
+        kind: Kind::Display,
+    };
+    if inner.is_empty() {
+        return Ok(spec);
+    }
+    let Some(rest) = inner.strip_prefix(':') else {
+        return Err(format!(
+            "{{{}}} is not a placeholder, values are taken in order with {{}} or {{:spec}}",
+            inner
+        ));
+    };
+    let chars: Vec<char> = rest.chars().collect();
+    let mut at = 0;
+    let align = |c: char| match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    };
+    if let Some(found) = chars.get(1).and_then(|c| align(*c)) {
+        spec.fill = chars[0];
+        spec.align = Some(found);
+        at = 2;
+    } else if let Some(found) = chars.first().and_then(|c| align(*c)) {
+        spec.align = Some(found);
+        at = 1;
+    }
+    if chars.get(at) == Some(&'+') {
+        spec.sign = true;
+        at += 1;
+    }
+    if chars.get(at) == Some(&'#') {
+        spec.alternate = true;
+        at += 1;
+    }
+    if chars.get(at) == Some(&'0') {
+        spec.zero = true;
+        at += 1;
+    }
+    let digits = |at: &mut usize| {
+        let start = *at;
+        while chars.get(*at).is_some_and(char::is_ascii_digit) {
+            *at += 1;
+        }
+        chars[start..*at].iter().collect::<String>().parse().ok()
+    };
+    spec.width = digits(&mut at);
+    if chars.get(at) == Some(&'.') {
+        at += 1;
+        spec.precision = digits(&mut at);
+        if spec.precision.is_none() {
+            return Err(format!(
+                "{{:{}}} is missing the precision after the .",
+                rest
+            ));
+        }
+    }
+    spec.kind = match &chars[at..] {
+        [] => Kind::Display,
+        ['x'] => Kind::Hex,
+        ['X'] => Kind::UpperHex,
+        ['b'] => Kind::Binary,
+        ['o'] => Kind::Octal,
+        ['e'] => Kind::Exponent,
+        _ => {
+            return Err(format!(
+                "{{:{}}} is not a valid format spec, expected [[fill]align][+][#][0][width][.precision][x|X|b|o|e]",
+                rest
+            ))
+        }
+    };
+    if spec.alternate && !spec.is_radix() {
+        return Err(format!("{{:{}}} uses # without x, X, b or o", rest));
+    }
+    Ok(spec)
+}
+
+impl Spec {
+    fn is_radix(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::Hex | Kind::UpperHex | Kind::Binary | Kind::Octal
+        )
+    }
+
+    // why a value of the type cannot be formatted with this spec, types only
+    // known when the program runs are let through
+    pub fn check(&self, ttype: &TType) -> Result<(), String> {
+        let int = matches!(ttype, TType::Int | TType::Sized { .. });
+        let number = int || *ttype == TType::Float;
+        if matches!(
+            ttype,
+            TType::Generic { .. } | TType::Any | TType::Union { .. }
+        ) {
+            return Ok(());
+        }
+        if self.is_radix() && !int {
+            return Err(format!("{} cannot be formatted in another base", ttype));
+        }
+        if self.kind == Kind::Exponent && *ttype != TType::Float {
+            return Err(format!("only a Float can use e, not {}", ttype));
+        }
+        if (self.sign || self.zero) && !number {
+            return Err(format!("+ and 0 are for numbers, not {}", ttype));
+        }
+        if self.precision.is_some() && !matches!(ttype, TType::Float | TType::String) {
+            return Err(format!(
+                "a precision is the decimals of a Float or the length of a String, not {}",
+                ttype
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn render(&self, value: Value) -> Result<String, String> {
+        // the sign and base prefix go before any zeros
+        let (negative, prefix, body, numeric) = match value {
+            Value::Int(int) => {
+                if self.precision.is_some() || self.kind == Kind::Exponent {
+                    return Err("an Int cannot have a precision or use e".into());
+                }
+                let magnitude = int.unsigned_abs();
+                let digits = match self.kind {
+                    Kind::Hex => format!("{:x}", magnitude),
+                    Kind::UpperHex => format!("{:X}", magnitude),
+                    Kind::Binary => format!("{:b}", magnitude),
+                    Kind::Octal => format!("{:o}", magnitude),
+                    _ => magnitude.to_string(),
+                };
+                let prefix = match self.kind {
+                    Kind::Hex | Kind::UpperHex if self.alternate => "0x",
+                    Kind::Binary if self.alternate => "0b",
+                    Kind::Octal if self.alternate => "0o",
+                    _ => "",
+                };
+                (int < 0, prefix, digits, true)
+            }
+            Value::Float(float) => {
+                if self.is_radix() {
+                    return Err("a Float cannot be formatted in another base".into());
+                }
+                let magnitude = float.abs();
+                let digits = match (self.kind, self.precision) {
+                    (Kind::Exponent, Some(precision)) => format!("{:.*e}", precision, magnitude),
+                    (Kind::Exponent, None) => format!("{:e}", magnitude),
+                    (_, Some(precision)) => format!("{:.*}", precision, magnitude),
+                    (_, None) => format_float(magnitude),
+                };
+                (float.is_sign_negative() && float != 0.0, "", digits, true)
+            }
+            Value::Text(text) => {
+                if self.kind != Kind::Display || self.sign || self.zero {
+                    return Err(format!("\"{}\" is not a number", text));
+                }
+                let text = match self.precision {
+                    Some(precision) => text.chars().take(precision).collect(),
+                    None => text.to_string(),
+                };
+                (false, "", text, false)
+            }
+        };
+        let sign = match (negative, self.sign) {
+            (true, _) => format!("-{}", prefix),
+            (false, true) => format!("+{}", prefix),
+            (false, false) => prefix.to_string(),
+        };
+        let length = sign.chars().count() + body.chars().count();
+        let padding = self.width.unwrap_or(0).saturating_sub(length);
+        if self.zero && numeric {
+            return Ok(format!("{}{}{}", sign, "0".repeat(padding), body));
+        }
+        let align = self
+            .align
+            .unwrap_or(if numeric { Align::Right } else { Align::Left });
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+        Ok(format!("{}{}{}{}", fill(before), sign, body, fill(after)))
+    }
+}
@@ -2,8 +2,10 @@ pub mod code;
 pub mod environment;
 pub mod error;
 pub mod fileposition;
+pub mod format;
 pub mod gen;
 pub mod nodes;
+pub mod rewrite;
 pub mod table;
 pub mod tokens;
 pub mod ttype;
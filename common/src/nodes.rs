@@ -29,10 +29,16 @@ pub struct Function {
 pub enum SymbolKind {
     Function,
     GenericFunction,
+    // bound once, only let mut variables can be assigned again
     Variable,
+    Mutable,
     Constructor,
     Parameter,
     Captured,
+    // a value that can be read but never assigned to
+    Constant,
+    // a module level value computed the first time it is read
+    Lazy,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +47,64 @@ pub struct Symbol {
     pub ttype: TType,
     pub pos: Option<FilePosition>,
     pub kind: SymbolKind,
+    // the where clause of a generic function
+    pub bounds: Vec<Bound>,
+}
+
+// what a name in the source refers to: a function, variable or parameter by
+// where it was declared, or a field or variant by its type and name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolRef {
+    Declaration(FilePosition),
+    Member(Rc<str>, Rc<str>),
+}
+
+// a name in the source and the symbol it refers to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub position: FilePosition,
+    pub symbol: SymbolRef,
+}
+
+// a generic of a function and the bound a where clause gives it
+pub type Bound = (Rc<str>, Rc<str>);
+
+// binder on the left of a destructuring, parsed before the type it matches is known
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Bind(Rc<str>, FilePosition),
+    Tuple(Vec<Pattern>, FilePosition),
+    Struct(Vec<(Rc<str>, Pattern)>, FilePosition),
+}
+
+impl Pattern {
+    // every name the pattern binds, _ binds nothing
+    pub fn names(&self) -> Vec<(Rc<str>, FilePosition)> {
+        match self {
+            Pattern::Bind(name, _) if &**name == "_" => vec![],
+            Pattern::Bind(name, pos) => vec![(name.clone(), pos.clone())],
+            Pattern::Tuple(elements, _) => elements.iter().flat_map(Pattern::names).collect(),
+            Pattern::Struct(fields, _) => fields
+                .iter()
+                .flat_map(|(_, pattern)| pattern.names())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContractKind {
+    Requires,
+    Ensures,
+}
+
+// pre and post conditions attached to a function, checked at runtime unless
+// the program is compiled in release mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    pub kind: ContractKind,
+    pub test: Expr,
+    pub position: FilePosition,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -48,6 +112,113 @@ pub struct Ast {
     pub program: Vec<Statement>,
 }
 
+impl Ast {
+    // number of statements and expressions in the tree, used for --timings
+    pub fn node_count(&self) -> usize {
+        statements_node_count(&self.program)
+    }
+}
+
+fn statements_node_count(statements: &[Statement]) -> usize {
+    statements.iter().map(Statement::node_count).sum()
+}
+
+impl Statement {
+    pub fn node_count(&self) -> usize {
+        let children = match self {
+            Statement::Line { .. } => return 0,
+            Statement::Continue
+            | Statement::Break
+            | Statement::Pass
+            | Statement::Struct { .. }
+            | Statement::Enum { .. }
+            | Statement::ForwardDec { .. } => 0,
+            Statement::Function {
+                body, contracts, ..
+            } => {
+                statements_node_count(body)
+                    + contracts
+                        .iter()
+                        .map(|contract| contract.test.node_count())
+                        .sum::<usize>()
+            }
+            Statement::Return { expr, .. }
+            | Statement::Expression { expr, .. }
+            | Statement::Lazy { expr, .. } => expr.node_count(),
+            Statement::If {
+                test,
+                body,
+                alternative,
+                ..
+            } => {
+                test.node_count()
+                    + statements_node_count(body)
+                    + alternative.as_deref().map_or(0, statements_node_count)
+            }
+            Statement::Unwrap {
+                body, alternative, ..
+            } => {
+                statements_node_count(body)
+                    + alternative.as_deref().map_or(0, statements_node_count)
+            }
+            Statement::IfLet {
+                expr,
+                body,
+                alternative,
+                ..
+            } => {
+                expr.node_count()
+                    + statements_node_count(body)
+                    + alternative.as_deref().map_or(0, statements_node_count)
+            }
+            Statement::While { test: expr, body }
+            | Statement::WhileLet { expr, body, .. }
+            | Statement::Foreach { expr, body, .. } => {
+                expr.node_count() + statements_node_count(body)
+            }
+            Statement::For {
+                init,
+                test,
+                inc,
+                body,
+            } => {
+                init.node_count()
+                    + test.node_count()
+                    + inc.node_count()
+                    + statements_node_count(body)
+            }
+            Statement::ForRange {
+                start,
+                end,
+                step,
+                body,
+                ..
+            } => {
+                start.node_count()
+                    + end.node_count()
+                    + step.as_ref().map_or(0, Expr::node_count)
+                    + statements_node_count(body)
+            }
+            Statement::Block { body, .. } => statements_node_count(body),
+            Statement::With { expr, body, .. } => expr.node_count() + statements_node_count(body),
+            Statement::Match {
+                expr,
+                arms,
+                default,
+                ..
+            } => {
+                expr.node_count()
+                    + arms
+                        .iter()
+                        .map(|(_, _, body)| statements_node_count(body))
+                        .sum::<usize>()
+                    + default.as_deref().map_or(0, statements_node_count)
+            }
+        };
+        children + 1
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     Continue,
@@ -59,16 +230,21 @@ pub enum Statement {
         parameters: Vec<Arg>,
         body: Vec<Statement>,
         captures: Vec<Rc<str>>,
+        contracts: Vec<Contract>,
+        // the /// comment above the declaration
+        doc: Option<Rc<str>>,
     },
     Struct {
         ttype: TType,
         identifier: Rc<str>,
         fields: Vec<Field>,
+        doc: Option<Rc<str>>,
     },
     Enum {
         ttype: TType,
         identifier: Rc<str>,
         fields: Vec<Field>,
+        doc: Option<Rc<str>>,
     },
     Return {
         ttype: TType,
@@ -141,6 +317,25 @@ pub enum Statement {
     ForwardDec {
         identifier: Rc<str>,
     },
+    // a module level value, the expression runs the first time it is read
+    Lazy {
+        ttype: TType,
+        identifier: Rc<str>,
+        expr: Expr,
+        captures: Vec<Rc<str>>,
+        position: FilePosition,
+    },
+    // with expr as identifier { body }, close runs on every way out of the body
+    With {
+        identifier: Rc<str>,
+        expr: Expr,
+        body: Vec<Statement>,
+        close: Expr,
+    },
+    // where the next statement starts, the vm reports it through on_line
+    Line {
+        position: FilePosition,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -260,11 +455,84 @@ pub enum Expr {
         ttype: TType,
         body: Vec<Statement>,
     },
+    // try { ... }, a Result holding the value of the body or the message of
+    // the error that ended it
+    Try {
+        ttype: TType,
+        body: Vec<Statement>,
+    },
+    // expr? inside a try block, the value of a Some or Ok and an error for a
+    // None or Err
+    Propagate {
+        ttype: TType,
+        expr: Box<Expr>,
+        position: FilePosition,
+    },
     None,
     Void,
 }
 
 impl Expr {
+    pub fn node_count(&self) -> usize {
+        let children = match self {
+            Expr::Let { expr, .. }
+            | Expr::Field { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Propagate { expr, .. }
+            | Expr::Return { expr, .. } => expr.node_count(),
+            Expr::Closure { body, .. } | Expr::Block { body, .. } | Expr::Try { body, .. } => {
+                statements_node_count(body)
+            }
+            Expr::ListConstructor { elements, .. } => elements.iter().map(Expr::node_count).sum(),
+            Expr::ListCompConstructor {
+                loops,
+                expr,
+                guards,
+                ..
+            } => loops
+                .iter()
+                .map(|(_, expr)| expr)
+                .chain(expr)
+                .chain(guards)
+                .map(Expr::node_count)
+                .sum(),
+            Expr::Indexed {
+                container, index, ..
+            } => container.node_count() + index.node_count(),
+            Expr::Sliced {
+                container,
+                start,
+                end,
+                step,
+                ..
+            } => {
+                container.node_count()
+                    + [start, end, step]
+                        .into_iter()
+                        .flatten()
+                        .map(|expr| expr.node_count())
+                        .sum::<usize>()
+            }
+            Expr::Call { function, args, .. } => {
+                function.node_count() + args.iter().map(Expr::node_count).sum::<usize>()
+            }
+            Expr::Binop { lhs, rhs, .. } => lhs.node_count() + rhs.node_count(),
+            Expr::Literal {
+                value: Atom::Call { arguments, .. },
+                ..
+            } => arguments.iter().map(Expr::node_count).sum(),
+            Expr::Literal { .. } | Expr::None | Expr::Void => 0,
+            Expr::StoreExpr { expr, body, .. } => expr.node_count() + statements_node_count(body),
+            Expr::IfExpr {
+                test,
+                body,
+                alternative,
+                ..
+            } => test.node_count() + body.node_count() + alternative.node_count(),
+        };
+        children + 1
+    }
+
     pub fn get_type(&self) -> TType {
         match self {
             Expr::Unary { ttype, .. } => ttype.clone(),
@@ -282,8 +550,151 @@ impl Expr {
             Expr::Return { ttype, .. } => ttype.clone(),
             Expr::IfExpr { ttype, .. } => ttype.clone(),
             Expr::Block { ttype, .. } => ttype.clone(),
+            Expr::Try { ttype, .. } => ttype.clone(),
+            Expr::Propagate { ttype, .. } => ttype.clone(),
             Expr::Let { ttype, .. } => ttype.clone(),
             Expr::Void => TType::Void,
         }
     }
+
+    // folds literals and operators on literals into a single literal, None when
+    // the value can only be known by running the program or overflows
+    pub fn const_value(&self) -> Option<Expr> {
+        self.fold().ok()
+    }
+
+    // why an Int operation on literals has no value, it would wrap or stop
+    // the program at runtime depending on the overflow checks
+    pub fn const_error(&self) -> Option<ConstError> {
+        match self.fold() {
+            Err(Fold::Failed(error)) => Some(error),
+            _ => None,
+        }
+    }
+
+    fn fold(&self) -> Result<Expr, Fold> {
+        // sized literals are kept as they are, operators on them wrap at
+        // runtime
+        if let TType::Sized { .. } = self.get_type() {
+            return match self {
+                Expr::Literal {
+                    value: Atom::Integer { .. },
+                    ..
+                } => Ok(self.clone()),
+                _ => Err(Fold::Unknown),
+            };
+        }
+        let value = match self {
+            Expr::Literal {
+                value:
+                    value @ (Atom::Integer { .. }
+                    | Atom::Float { .. }
+                    | Atom::Bool { .. }
+                    | Atom::String { .. }
+                    | Atom::Char { .. }),
+                ..
+            } => value.clone(),
+            Expr::Unary { op, expr, .. } => match (op, literal_atom(expr.fold()?)) {
+                (Unary::Negative, Atom::Integer { value }) => Atom::Integer {
+                    value: value
+                        .checked_neg()
+                        .ok_or(Fold::Failed(ConstError::Overflow))?,
+                },
+                (Unary::Negative, Atom::Float { value }) => Atom::Float { value: -value },
+                (Unary::Positive, value @ (Atom::Integer { .. } | Atom::Float { .. })) => value,
+                (Unary::Not, Atom::Bool { value }) => Atom::Bool { value: !value },
+                _ => return Err(Fold::Unknown),
+            },
+            Expr::Binop { op, lhs, rhs, .. } => {
+                fold_binop(*op, literal_atom(lhs.fold()?), literal_atom(rhs.fold()?))?
+            }
+            _ => return Err(Fold::Unknown),
+        };
+        let ttype = match value {
+            Atom::Integer { .. } => TType::Int,
+            Atom::Float { .. } => TType::Float,
+            Atom::Bool { .. } => TType::Bool,
+            Atom::String { .. } => TType::String,
+            _ => TType::Char,
+        };
+        Ok(Expr::Literal { ttype, value })
+    }
+}
+
+// why an expression could not be folded
+enum Fold {
+    Unknown,
+    Failed(ConstError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstError {
+    Overflow,
+    DivisionByZero,
+}
+
+fn literal_atom(expr: Expr) -> Atom {
+    match expr {
+        Expr::Literal { value, .. } => value,
+        _ => Atom::None,
+    }
+}
+
+fn fold_binop(op: Operator, lhs: Atom, rhs: Atom) -> Result<Atom, Fold> {
+    use Operator::*;
+    let value = match (lhs, rhs) {
+        (Atom::Integer { .. }, Atom::Integer { value: 0 }) if matches!(op, Division | Modulo) => {
+            return Err(Fold::Failed(ConstError::DivisionByZero))
+        }
+        (Atom::Integer { value: a }, Atom::Integer { value: b }) => {
+            let value = match op {
+                Addition => a.checked_add(b),
+                Subtraction => a.checked_sub(b),
+                Multiplication => a.checked_mul(b),
+                Division => a.checked_div(b),
+                Modulo => a.checked_rem(b),
+                _ => return compare(op, a.partial_cmp(&b)),
+            };
+            Atom::Integer {
+                value: value.ok_or(Fold::Failed(ConstError::Overflow))?,
+            }
+        }
+        (Atom::Float { value: a }, Atom::Float { value: b }) => match op {
+            Addition => Atom::Float { value: a + b },
+            Subtraction => Atom::Float { value: a - b },
+            Multiplication => Atom::Float { value: a * b },
+            Division => Atom::Float { value: a / b },
+            _ => return compare(op, a.partial_cmp(&b)),
+        },
+        (Atom::String { value: a }, Atom::String { value: b }) => match op {
+            Addition => Atom::String {
+                value: format!("{a}{b}").into(),
+            },
+            _ => return compare(op, Some(a.cmp(&b))),
+        },
+        (Atom::Char { value: a }, Atom::Char { value: b }) => return compare(op, Some(a.cmp(&b))),
+        (Atom::Bool { value: a }, Atom::Bool { value: b }) => match op {
+            And => Atom::Bool { value: a && b },
+            Or => Atom::Bool { value: a || b },
+            Equal => Atom::Bool { value: a == b },
+            NotEqual => Atom::Bool { value: a != b },
+            _ => return Err(Fold::Unknown),
+        },
+        _ => return Err(Fold::Unknown),
+    };
+    Ok(value)
+}
+
+fn compare(op: Operator, ordering: Option<std::cmp::Ordering>) -> Result<Atom, Fold> {
+    let ordering = ordering.ok_or(Fold::Unknown)?;
+    let value = match op {
+        Operator::Equal => ordering.is_eq(),
+        Operator::NotEqual => ordering.is_ne(),
+        Operator::Less => ordering.is_lt(),
+        Operator::LessOrEqual => ordering.is_le(),
+        Operator::Greater => ordering.is_gt(),
+        Operator::GreaterOrEqual => ordering.is_ge(),
+        _ => return Err(Fold::Unknown),
+    };
+    Ok(Atom::Bool { value })
 }
@@ -0,0 +1,141 @@
+use std::ops::Range;
+
+use crate::{
+    fileposition::FilePosition,
+    tokens::{Token, TokenList, TokenValue},
+};
+
+// edits to a token list that keep every position where the lexer would put
+// the token if the edited source were read again. new tokens are written the
+// way TokenValue::source_text spells them, one space apart, on the line of
+// the first token they replace. tokens after the edit on the same line move
+// over by the change in width, and lines below move up when the replaced
+// tokens spanned several lines
+
+// replaces tokens[range] with values, returns where the new tokens ended up
+pub fn splice(
+    tokens: &mut TokenList,
+    range: Range<usize>,
+    values: impl IntoIterator<Item = TokenValue>,
+) -> Range<usize> {
+    let start = start_position(tokens, range.start);
+    // where the replaced source ended, the edit has no width for an insert
+    let (old_line, old_col) = match tokens.get(range.clone()).and_then(<[Token]>::last) {
+        Some(last) => (last.line(), last.col() + last.value.width()),
+        None => (start.line, start.col),
+    };
+
+    let mut col = start.col;
+    let new_tokens: Vec<Token> = values
+        .into_iter()
+        .map(|value| {
+            let position = FilePosition {
+                col,
+                ..start.clone()
+            };
+            col += value.width() + 1;
+            Token {
+                value,
+                position,
+                doc: None,
+            }
+        })
+        .collect();
+    let mut new_col = match new_tokens.last() {
+        Some(last) => last.col() + last.value.width(),
+        None => start.col,
+    };
+    // inserted tokens need a space before the token they were put in front of
+    if range.is_empty() && !new_tokens.is_empty() {
+        new_col += 1;
+    }
+
+    let inserted = range.start..range.start + new_tokens.len();
+    tokens.splice(range, new_tokens);
+    for token in tokens[inserted.end..].iter_mut() {
+        if token.position.filepath != start.filepath {
+            continue;
+        }
+        if token.position.line == old_line {
+            token.position.line = start.line;
+            token.position.col = (token.position.col + new_col).saturating_sub(old_col);
+        } else if token.position.line > old_line {
+            token.position.line -= old_line - start.line;
+        }
+    }
+    inserted
+}
+
+pub fn replace(tokens: &mut TokenList, index: usize, value: TokenValue) {
+    splice(tokens, index..index + 1, [value]);
+}
+
+// puts values in front of tokens[index], or after the last token
+pub fn insert(
+    tokens: &mut TokenList,
+    index: usize,
+    values: impl IntoIterator<Item = TokenValue>,
+) -> Range<usize> {
+    splice(tokens, index..index, values)
+}
+
+pub fn remove(tokens: &mut TokenList, range: Range<usize>) {
+    splice(tokens, range, []);
+}
+
+// source text with every token at its position, lexing it again gives back
+// the same tokens and positions
+pub fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let (mut line, mut col) = (1, 1);
+    for token in tokens {
+        if token.line() > line {
+            out.extend(std::iter::repeat_n('\n', token.line() - line));
+            (line, col) = (token.line(), 1);
+        }
+        if token.col() > col {
+            out.extend(std::iter::repeat_n(' ', token.col() - col));
+            col = token.col();
+        } else if token.col() < col {
+            // overlapping positions, keep the tokens apart
+            out.push(' ');
+            col += 1;
+        }
+        out.push_str(&token.value.source_text());
+        col += token.value.width();
+    }
+    out
+}
+
+// the source of a run of tokens on its own, spaced the way they were written
+pub fn source_text(tokens: &[Token]) -> String {
+    let Some(first) = tokens.first() else {
+        return String::new();
+    };
+    let (line, col) = (first.line(), first.col());
+    let shifted: Vec<Token> = tokens
+        .iter()
+        .map(|token| {
+            let mut token = token.clone();
+            if token.line() == line {
+                token.position.col -= col - 1;
+            }
+            token.position.line -= line - 1;
+            token
+        })
+        .collect();
+    render(&shifted)
+}
+
+// where a token put at index starts, in front of the token there or one space
+// after the last token
+fn start_position(tokens: &[Token], index: usize) -> FilePosition {
+    match (tokens.get(index), tokens.last()) {
+        (Some(token), _) => token.position(),
+        (None, Some(last)) => FilePosition {
+            col: last.col() + last.value.width() + 1,
+            ..last.position()
+        },
+        (None, None) => FilePosition::default(),
+    }
+}
@@ -76,9 +76,65 @@ pub enum Operator {
     /// pipe arrow
     PipeArrow,
 }
+impl Operator {
+    // how the operator is written, empty for the ones the lexer never produces
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::AddAssign => "+=",
+            Operator::SubAssign => "-=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::DoubleColon => "::",
+            Operator::Colon => ":",
+            Operator::GreaterOrEqual => ">=",
+            Operator::LessOrEqual => "<=",
+            Operator::Equal => "==",
+            Operator::Assignment => "=",
+            Operator::RightArrow => "->",
+            Operator::Greater => ">",
+            Operator::Less => "<",
+            Operator::Addition => "+",
+            Operator::Subtraction => "-",
+            Operator::Division => "/",
+            Operator::Multiplication => "*",
+            Operator::Modulo => "%",
+            Operator::NotEqual => "!=",
+            Operator::Not => "!",
+            Operator::RightTilde => "~>",
+            Operator::LeftTilde => "<~",
+            Operator::InclusiveRange => "..=",
+            Operator::ExclusiveRange => "..",
+            Operator::FatArrow => "=>",
+            Operator::PipeArrow => "|>",
+            Operator::Concat | Operator::Access | Operator::ListAccess | Operator::Call => "",
+        }
+    }
+}
+
+// words the lexer never turns into identifiers, see lexer::KEYWORDS
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum KeyWord {
     In,
+    If,
+    Else,
+    Let,
+    Return,
+    Extends,
+    Mod,
+}
+
+impl KeyWord {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyWord::In => "in",
+            KeyWord::If => "if",
+            KeyWord::Else => "else",
+            KeyWord::Let => "let",
+            KeyWord::Return => "return",
+            KeyWord::Extends => "extends",
+            KeyWord::Mod => "mod",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
@@ -117,6 +173,29 @@ pub enum StructuralSymbol {
     Pipe,
 }
 
+impl StructuralSymbol {
+    pub fn as_char(&self) -> char {
+        match self {
+            StructuralSymbol::Semicolon => ';',
+            StructuralSymbol::LeftParen => '(',
+            StructuralSymbol::RightParen => ')',
+            StructuralSymbol::LeftSquareBracket => '[',
+            StructuralSymbol::RightSquareBracket => ']',
+            StructuralSymbol::Comma => ',',
+            StructuralSymbol::LeftBrace => '{',
+            StructuralSymbol::RightBrace => '}',
+            StructuralSymbol::DollarSign => '$',
+            StructuralSymbol::At => '@',
+            StructuralSymbol::QuestionMark => '?',
+            StructuralSymbol::Pound => '#',
+            StructuralSymbol::Dot => '.',
+            StructuralSymbol::Ampersand => '&',
+            StructuralSymbol::Tilde => '~',
+            StructuralSymbol::Pipe => '|',
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenValue {
     Keyword(KeyWord),
@@ -130,10 +209,48 @@ pub enum TokenValue {
     Operator(Operator),
 }
 
+impl TokenValue {
+    // the token written out as source the lexer reads back to the same value
+    pub fn source_text(&self) -> String {
+        fn escape(c: char, quote: char) -> String {
+            match c {
+                '\n' => "\\n".into(),
+                '\r' => "\\r".into(),
+                '\t' => "\\t".into(),
+                '\0' => "\\0".into(),
+                '\\' => "\\\\".into(),
+                c if c == quote => format!("\\{c}"),
+                c => c.to_string(),
+            }
+        }
+        match self {
+            TokenValue::Keyword(keyword) => keyword.as_str().into(),
+            TokenValue::Identifier(name) => name.to_string(),
+            TokenValue::Integer(value) => value.to_string(),
+            TokenValue::Float(value) => format!("{value:?}"),
+            TokenValue::StringLiteral(value) => {
+                let body: String = value.chars().map(|c| escape(c, '"')).collect();
+                format!("\"{body}\"")
+            }
+            TokenValue::Char(value) => format!("'{}'", escape(*value, '\'')),
+            TokenValue::StructuralSymbol(symbol) => symbol.as_char().into(),
+            TokenValue::Bool(value) => value.to_string(),
+            TokenValue::Operator(operator) => operator.as_str().into(),
+        }
+    }
+
+    // columns the token takes up when written as source_text
+    pub fn width(&self) -> usize {
+        self.source_text().chars().count()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub value: TokenValue,
     pub position: FilePosition,
+    // the /// comment written above the token, one line of text per line
+    pub doc: Option<Rc<str>>,
 }
 
 impl Token {
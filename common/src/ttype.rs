@@ -8,6 +8,13 @@ pub enum TType {
     None,
     Any,
     Int,
+    // U8, I32 and the other fixed width integers
+    Sized {
+        width: Width,
+    },
+    // an arbitrary precision integer, its operators are the BigInt::__add__
+    // family of natives
+    BigInt,
     Float,
     Bool,
     String,
@@ -34,9 +41,145 @@ pub enum TType {
     Tuple {
         elements: Vec<TType>,
     },
+    // Int | String, a value of one of the members
+    Union {
+        members: Vec<TType>,
+    },
+}
+
+// the fixed width integer types, values wrap around at their width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl Width {
+    pub const ALL: [Width; 8] = [
+        Width::I8,
+        Width::I16,
+        Width::I32,
+        Width::I64,
+        Width::U8,
+        Width::U16,
+        Width::U32,
+        Width::U64,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Width> {
+        Width::ALL.into_iter().find(|width| width.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Width::I8 => "I8",
+            Width::I16 => "I16",
+            Width::I32 => "I32",
+            Width::I64 => "I64",
+            Width::U8 => "U8",
+            Width::U16 => "U16",
+            Width::U32 => "U32",
+            Width::U64 => "U64",
+        }
+    }
+
+    // the byte a width is written as in bytecode
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Width> {
+        Width::ALL.get(byte as usize).copied()
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            Width::I8 | Width::U8 => 8,
+            Width::I16 | Width::U16 => 16,
+            Width::I32 | Width::U32 => 32,
+            Width::I64 | Width::U64 => 64,
+        }
+    }
+
+    pub fn signed(self) -> bool {
+        matches!(self, Width::I8 | Width::I16 | Width::I32 | Width::I64)
+    }
+
+    pub fn min(self) -> i128 {
+        if self.signed() {
+            -(1 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(self) -> i128 {
+        if self.signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            (1 << self.bits()) - 1
+        }
+    }
+
+    pub fn fits(self, value: i128) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+
+    // the value of a sized integer as the vm stores it, a U64 keeps its bits
+    // in an i64
+    pub fn value(self, raw: i64) -> i128 {
+        match self {
+            Width::U64 => raw as u64 as i128,
+            _ => raw as i128,
+        }
+    }
+
+    // the stored form of a value, keeping only the low bits of the width
+    pub fn wrap(self, value: i128) -> i64 {
+        let shift = 128 - self.bits();
+        let low = if self.signed() {
+            (value << shift) >> shift
+        } else {
+            ((value as u128) << shift >> shift) as i128
+        };
+        low as i64
+    }
+}
+
+impl Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl TType {
+    // the union of types, nested unions are flattened and repeated members
+    // dropped, a single member is that type on its own
+    pub fn union(types: impl IntoIterator<Item = TType>) -> TType {
+        let mut members: Vec<TType> = vec![];
+        for ttype in types {
+            let flat = match ttype {
+                TType::Union { members } => members,
+                ttype => vec![ttype],
+            };
+            for member in flat {
+                if !members.contains(&member) {
+                    members.push(member);
+                }
+            }
+        }
+        if members.len() == 1 {
+            return members.pop().unwrap();
+        }
+        TType::Union { members }
+    }
+
     pub fn get_inner(&self) -> Option<&TType> {
         match self {
             TType::List { inner } | TType::Option { inner } => Some(inner),
@@ -45,10 +188,73 @@ impl TType {
         }
     }
 
+    // a _ written where a type was expected, an unnamed generic that
+    // inference fills in
+    pub fn is_hole(&self) -> bool {
+        matches!(self, TType::Generic { name } if name.starts_with('_'))
+    }
+
+    pub fn has_hole(&self) -> bool {
+        match self {
+            TType::List { inner } | TType::Option { inner } => inner.has_hole(),
+            TType::Tuple { elements } | TType::Union { members: elements } => {
+                elements.iter().any(TType::has_hole)
+            }
+            TType::Custom { type_params, .. } => type_params.iter().any(TType::has_hole),
+            TType::Function {
+                parameters,
+                return_type,
+            } => parameters.iter().any(TType::has_hole) || return_type.has_hole(),
+            _ => self.is_hole(),
+        }
+    }
+
+    pub fn is_union(&self) -> bool {
+        matches!(self, TType::Union { .. })
+    }
+
+    pub fn has_union(&self) -> bool {
+        match self {
+            TType::List { inner } | TType::Option { inner } => inner.has_union(),
+            TType::Tuple { elements } => elements.iter().any(TType::has_union),
+            TType::Custom { type_params, .. } => type_params.iter().any(TType::has_union),
+            TType::Function {
+                parameters,
+                return_type,
+            } => parameters.iter().any(TType::has_union) || return_type.has_union(),
+            _ => self.is_union(),
+        }
+    }
+
     pub fn is_function(&self) -> bool {
         matches!(self, TType::Function { .. })
     }
 
+    // whether values of this type are lists at runtime, structs, enums and
+    // tuples included
+    pub fn is_list_like(&self) -> bool {
+        matches!(
+            self,
+            TType::List { .. } | TType::Tuple { .. } | TType::Custom { .. }
+        )
+    }
+
+    // the name the vm gives a value of this member of a union, None for the
+    // one list-like member of a union that has only one, which is told apart
+    // by not being any of the others
+    pub fn union_tag(&self, members: &[TType]) -> Option<&str> {
+        match self {
+            TType::Function { .. } => Some("Function"),
+            TType::Custom { name, .. }
+                if members.iter().filter(|t| t.is_list_like()).count() > 1 =>
+            {
+                Some(name)
+            }
+            _ if self.is_list_like() => None,
+            _ => self.custom_to_string(),
+        }
+    }
+
     pub fn custom_to_string(&self) -> Option<&str> {
         match self {
             TType::Custom { name, .. } => Some(name),
@@ -59,6 +265,8 @@ impl TType {
             TType::Char => Some("Char"),
             TType::String => Some("String"),
             TType::Int => Some("Int"),
+            TType::Sized { width } => Some(width.name()),
+            TType::BigInt => Some("BigInt"),
             TType::Float => Some("Float"),
             TType::Bool => Some("Bool"),
             _ => None,
@@ -86,6 +294,8 @@ impl Display for TType {
         let literal = match self {
             TType::Any => "Any",
             TType::Int => "Int",
+            TType::Sized { width } => width.name(),
+            TType::BigInt => "BigInt",
             TType::Float => "Float",
             TType::Bool => "Bool",
             TType::String => "String",
@@ -101,6 +311,7 @@ impl Display for TType {
                 }
                 name
             }
+            TType::Generic { .. } if self.is_hole() => "_",
             TType::Generic { name } => return write!(f, "${name}"),
             TType::List { inner } => return write!(f, "[{inner}]"),
             TType::Option { inner } => return write!(f, "Option({inner})"),
@@ -111,11 +322,23 @@ impl Display for TType {
                 parameters: args,
                 return_type,
             } => return write!(f, "fn({params}) -> {return_type}", params = TypeList(args)),
+            TType::Union { members } => {
+                let members: Vec<String> = members.iter().map(TType::to_string).collect();
+                return f.write_str(&members.join(" | "));
+            }
         };
         f.write_str(literal)
     }
 }
 
+// a type written through an alias reads as "Handler (= fn(Request) -> Response)"
+pub fn describe_type(ttype: &TType, alias: Option<&str>) -> String {
+    match alias {
+        Some(alias) => format!("{alias} (= {ttype})"),
+        None => ttype.to_string(),
+    }
+}
+
 // Generate a unique string representation
 pub fn generate_unique_string(input: &str, types: &[TType]) -> String {
     if types.is_empty() {
@@ -3,14 +3,14 @@ use std::ops::Deref;
 use std::path::Path;
 use std::rc::Rc;
 
-use common::code::Asm;
+use common::code::{Asm, Overflow};
 use common::error::NovaError;
 use common::fileposition::FilePosition;
 use common::gen::Gen;
 use common::nodes::Statement::{Block, Expression, For, Function, If, Return, Struct, While};
-use common::nodes::{Ast, Atom, Expr};
+use common::nodes::{Ast, Atom, Contract, ContractKind, Expr};
 use common::table::Table;
-use common::ttype::TType;
+use common::ttype::{TType, Width};
 
 #[derive(Debug, Clone)]
 pub struct Compiler {
@@ -29,6 +29,19 @@ pub struct Compiler {
     pub continues: Vec<u64>,
     pub global_strings: HashMap<Rc<str>, usize>,
     pub unrolled_index: HashMap<Rc<str>, usize>,
+    pub contracts: bool,
+    pub ensures: Vec<Contract>,
+    // global slot of the __lt__ of each type that defines one, used by the vm
+    // to compare values of a bounded generic
+    pub operators: HashMap<Rc<str>, u32>,
+    // the same for __eq__, so == on a generic compares the way the type says
+    pub equality: HashMap<Rc<str>, u32>,
+    // close of each open with block, with how many loops were open around it
+    pub withs: Vec<(usize, Expr)>,
+    // how many loops were open around each open try block
+    pub tries: Vec<usize>,
+    // start of the statement being compiled, where Int overflow is reported
+    pub line: Option<FilePosition>,
 }
 
 pub fn new() -> Compiler {
@@ -48,10 +61,113 @@ pub fn new() -> Compiler {
         native_functions_types: HashMap::default(),
         global_strings: HashMap::default(),
         unrolled_index: HashMap::default(),
+        contracts: true,
+        ensures: vec![],
+        operators: HashMap::default(),
+        equality: HashMap::default(),
+        withs: vec![],
+        tries: vec![],
+        line: None,
     }
 }
 
 impl Compiler {
+    // Int arithmetic that can overflow, marked with the statement it is in
+    fn int_operation(&mut self, operation: Asm) {
+        self.asm.push(operation);
+        if let Some(line) = self.line.clone() {
+            self.asm.push(Asm::CALLSITE(line));
+        }
+    }
+
+    // the member of a union the value is, found from its runtime type name
+    fn compile_union_typeof(&mut self, value: &Expr, members: &[TType]) -> Result<(), NovaError> {
+        self.compile_expr(value)?;
+        self.asm.push(Asm::TYPENAME);
+        let end = self.gen.generate();
+        // the member without a tag is whatever the others are not
+        let fallback = members
+            .iter()
+            .find(|member| member.union_tag(members).is_none())
+            .unwrap_or(&members[members.len() - 1]);
+        for member in members.iter().filter(|member| *member != fallback) {
+            let Some(tag) = member.union_tag(members) else {
+                continue;
+            };
+            let next = self.gen.generate();
+            self.asm.push(Asm::DUP);
+            self.compile_string_literal(tag);
+            self.asm.push(Asm::EQUALS);
+            self.asm.push(Asm::JUMPIFFALSE(next));
+            self.asm.push(Asm::POP);
+            self.compile_string_literal(&member.to_string());
+            self.asm.push(Asm::JMP(end));
+            self.asm.push(Asm::LABEL(next));
+        }
+        self.asm.push(Asm::POP);
+        self.compile_string_literal(&fallback.to_string());
+        self.asm.push(Asm::LABEL(end));
+        Ok(())
+    }
+
+    // raises at the call when the assertion does not hold, the message is
+    // only built then
+    fn compile_assert(
+        &mut self,
+        caller: &str,
+        list: &[Expr],
+        position: &FilePosition,
+    ) -> Result<(), NovaError> {
+        let end = self.gen.generate();
+        if caller == "assert" {
+            self.compile_expr(&list[0])?;
+            self.asm.push(Asm::NOT);
+            self.asm.push(Asm::JUMPIFFALSE(end));
+            match list.get(1) {
+                Some(msg) => {
+                    self.compile_string_literal("Assertion failed: ");
+                    self.compile_expr(msg)?;
+                    self.asm.push(Asm::CONCAT);
+                }
+                None => self.compile_string_literal("Assertion failed"),
+            }
+            self.asm.push(Asm::RAISE(position.clone()));
+            self.asm.push(Asm::LABEL(end));
+            return Ok(());
+        }
+        // both sides are kept to show them when they differ
+        let mut sides = vec![];
+        for side in list {
+            self.compile_expr(side)?;
+            self.variables
+                .insert(format!("___assert___{}", self.gen.generate()).into());
+            let temp = self.variables.len() as u32 - 1;
+            self.asm.push(Asm::STORE(temp));
+            sides.push(temp);
+        }
+        self.asm.push(Asm::GET(sides[0]));
+        self.asm.push(Asm::GET(sides[1]));
+        self.asm.push(Asm::EQUALS);
+        self.asm.push(Asm::NOT);
+        self.asm.push(Asm::JUMPIFFALSE(end));
+        let show = self.native_functions.get_index("Cast::string");
+        self.compile_string_literal("Assertion failed, left is ");
+        for (side, text) in sides.into_iter().zip([Some(" but right is "), None]) {
+            self.asm.push(Asm::GET(side));
+            if let Some(index) = show {
+                self.asm.push(Asm::NATIVE(index as u64));
+            }
+            self.asm.push(Asm::CONCAT);
+            if let Some(text) = text {
+                self.compile_string_literal(text);
+                self.asm.push(Asm::CONCAT);
+            }
+        }
+        self.asm.push(Asm::RAISE(position.clone()));
+        self.asm.push(Asm::LABEL(end));
+        Ok(())
+    }
+
     fn compile_string_literal(&mut self, string: &str) {
         let index = self.insert_string_global(string.into());
         self.asm.push(Asm::GETGLOBAL(index as u32));
@@ -87,6 +203,7 @@ impl Compiler {
         let w_index = self.global.len() - 1;
         let jump = self.gen.generate();
         self.asm.push(Asm::FUNCTION(jump));
+        self.asm.push(Asm::NAME("print".into()));
         self.asm.push(Asm::OFFSET(1, 0));
         self.asm.push(Asm::PRINT);
         self.asm.push(Asm::RET(false));
@@ -98,6 +215,7 @@ impl Compiler {
         let w_index = self.global.len() - 1;
         let jump = self.gen.generate();
         self.asm.push(Asm::FUNCTION(jump));
+        self.asm.push(Asm::NAME("println".into()));
         self.asm.push(Asm::OFFSET(1, 0));
         self.asm.push(Asm::PRINT);
         self.compile_string_literal("\n");
@@ -117,6 +235,7 @@ impl Compiler {
                         let w_index = self.global.len() - 1;
                         let jump = self.gen.generate();
                         self.asm.push(Asm::FUNCTION(jump));
+                        self.asm.push(Asm::NAME(native.clone()));
                         self.asm.push(Asm::OFFSET(parameters.len() as u32, 0));
                         self.asm.push(Asm::NATIVE(index as u64));
                         if **return_type != TType::Void {
@@ -261,18 +380,37 @@ impl Compiler {
                     self.continues.pop();
                 }
                 common::nodes::Statement::Pass => {}
+                common::nodes::Statement::Line { position } => {
+                    self.line = Some(position.clone());
+                    self.asm.push(Asm::LINE(position.clone()));
+                }
                 Function {
+                    ttype,
                     identifier,
                     parameters,
                     body,
                     captures: captured,
-                    ..
+                    contracts,
+                    doc: _,
                 } => {
                     self.global.insert(identifier.clone());
+                    if let Some((owner, method)) = identifier.split_once("::") {
+                        if method == "__lt__" || method.starts_with("__lt___") {
+                            let index = self.global.get_index(identifier).unwrap();
+                            self.operators.insert(owner.into(), index as u32);
+                        }
+                        if method == "__eq__" || method.starts_with("__eq___") {
+                            let index = self.global.get_index(identifier).unwrap();
+                            self.equality.insert(owner.into(), index as u32);
+                        }
+                    }
                     // Clone the current state to prepare for function compilation
                     let mut function_compile = self.clone();
                     function_compile.variables.clear();
                     function_compile.asm.clear();
+                    function_compile.ensures.clear();
+                    function_compile.withs.clear();
+                    function_compile.tries.clear();
 
                     // Register parameter names in the function's local variable scope
                     for param in parameters.iter() {
@@ -284,6 +422,28 @@ impl Compiler {
                         function_compile.variables.insert(capture.clone());
                     }
 
+                    // contracts are stripped entirely in release mode
+                    if self.contracts {
+                        if ttype != &TType::Void
+                            && contracts.iter().any(|c| c.kind == ContractKind::Ensures)
+                        {
+                            function_compile.variables.insert("result".into());
+                        }
+                        for contract in contracts.iter() {
+                            match contract.kind {
+                                ContractKind::Requires => {
+                                    function_compile.compile_expr(&contract.test)?;
+                                    function_compile
+                                        .asm
+                                        .push(Asm::REQUIRES(contract.position.clone()));
+                                }
+                                ContractKind::Ensures => {
+                                    function_compile.ensures.push(contract.clone());
+                                }
+                            }
+                        }
+                    }
+
                     // Compile captured variables for the closure
                     //dbg!(captured);
                     for captured_var in captured {
@@ -310,6 +470,7 @@ impl Compiler {
                         self.asm.push(Asm::LIST(captured.len() as u64));
                         self.asm.push(Asm::CLOSURE(closure_jump_label));
                     }
+                    self.asm.push(Asm::NAME(identifier.clone()));
 
                     // Compile the function body
                     let function_body = Ast {
@@ -356,14 +517,17 @@ impl Compiler {
                     ttype: _,
                     identifier,
                     fields,
+                    doc: _,
                 } => {
                     self.global.insert(identifier.clone());
                     let index = self.global.len() - 1;
                     let structjump = self.gen.generate();
                     self.asm.push(Asm::FUNCTION(structjump));
-                    self.asm.push(Asm::OFFSET((fields.len() - 1) as u32, 0_u32));
+                    // the name goes after the fields, the vm reads it to tell
+                    // types apart and print them
+                    self.asm.push(Asm::OFFSET(fields.len() as u32, 0_u32));
                     self.compile_string_literal(identifier);
-                    self.asm.push(Asm::LIST(fields.len() as u64));
+                    self.asm.push(Asm::LIST(fields.len() as u64 + 1));
                     self.asm.push(Asm::RET(true));
                     self.asm.push(Asm::LABEL(structjump));
 
@@ -372,6 +536,8 @@ impl Compiler {
 
                 Return { ttype, expr } => {
                     self.compile_expr(expr)?;
+                    self.compile_ensures(ttype != &TType::Void)?;
+                    self.close_withs(0)?;
                     if ttype != &TType::Void {
                         self.asm.push(Asm::RET(true))
                     } else {
@@ -487,6 +653,7 @@ impl Compiler {
                     self.continues.pop();
                 }
                 common::nodes::Statement::Break => {
+                    self.close_withs(self.breaks.len())?;
                     if let Some(target) = self.breaks.last() {
                         self.asm.push(Asm::JMP(*target));
                     } else {
@@ -494,6 +661,7 @@ impl Compiler {
                     }
                 }
                 common::nodes::Statement::Continue => {
+                    self.close_withs(self.breaks.len())?;
                     if let Some(target) = self.continues.last() {
                         self.asm.push(Asm::BJMP(*target));
                     } else {
@@ -602,10 +770,6 @@ impl Compiler {
                     identifier, fields, ..
                 } => {
                     for (tag, field) in fields.iter().enumerate() {
-                        if field.identifier.as_ref() == "type" {
-                            continue;
-                        }
-
                         self.global
                             .insert(format!("{}::{}", identifier, field.identifier).into());
                         let index = self.global.len() - 1;
@@ -865,10 +1029,121 @@ impl Compiler {
                         }
                     }
                 }
+                common::nodes::Statement::With {
+                    identifier,
+                    expr,
+                    body,
+                    close,
+                } => {
+                    let index = if let Some(index) = self.variables.get_index(identifier) {
+                        index
+                    } else {
+                        self.variables.insert(identifier.clone());
+                        self.variables.len() - 1
+                    };
+                    self.compile_expr(expr)?;
+                    self.asm.push(Asm::STORE(index as u32));
+
+                    // the vm closes the resource itself if an error ends the program
+                    let Expr::Literal {
+                        value: Atom::Call { name, .. },
+                        ..
+                    } = close
+                    else {
+                        unreachable!()
+                    };
+                    let Some(function) = self.global.get_index(name) else {
+                        return Err(NovaError::Compiler {
+                            msg: format!("Cannot close with \"{}\"", name).into(),
+                            note: "The close of a with block must be written in nova".into(),
+                        });
+                    };
+                    self.asm.push(Asm::GET(index as u32));
+                    self.asm.push(Asm::GETGLOBAL(function as u32));
+                    self.asm.push(Asm::DEFER);
+
+                    self.withs.push((self.breaks.len(), close.clone()));
+                    let body = Ast {
+                        program: body.clone(),
+                    };
+                    self.compile_program(body, self.filepath.clone(), false, false, false, false)?;
+                    self.asm.pop();
+                    self.withs.pop();
+                    self.asm.push(Asm::UNDEFER);
+                    self.compile_expr(close)?;
+                    if close.get_type() != TType::Void {
+                        self.asm.push(Asm::POP);
+                    }
+                }
                 common::nodes::Statement::ForwardDec { identifier, .. } => {
                     //create a wrapper function
                     self.global.insert(identifier.clone());
                 }
+                common::nodes::Statement::Lazy {
+                    identifier,
+                    expr,
+                    captures,
+                    position,
+                    ..
+                } => {
+                    // a function that runs the initializer on the first call and
+                    // returns the value cached in a global after that
+                    self.global.insert(identifier.clone());
+                    let cache: Rc<str> = format!("__cache__{}", identifier).into();
+                    self.global.insert(cache.clone());
+                    let cache = self.global.get_index(&cache).unwrap() as u32;
+
+                    let mut function_compile = self.clone();
+                    function_compile.variables.clear();
+                    function_compile.asm.clear();
+                    function_compile.ensures.clear();
+                    function_compile.withs.clear();
+                    function_compile.tries.clear();
+                    for capture in captures.iter() {
+                        function_compile.variables.insert(capture.clone());
+                    }
+                    for captured_var in captures {
+                        if let Some(index) = self.variables.get_index(captured_var) {
+                            self.asm.push(Asm::GET(index as u32));
+                        } else if let Some(index) = self.global.get_index(captured_var) {
+                            self.asm.push(Asm::GETGLOBAL(index as u32));
+                        }
+                    }
+
+                    let skip = function_compile.gen.generate();
+                    let cached = function_compile.gen.generate();
+                    if captures.is_empty() {
+                        self.asm.push(Asm::FUNCTION(skip));
+                    } else {
+                        self.asm.push(Asm::LIST(captures.len() as u64));
+                        self.asm.push(Asm::CLOSURE(skip));
+                    }
+                    self.asm.push(Asm::NAME(identifier.clone()));
+
+                    function_compile
+                        .asm
+                        .push(Asm::LAZY(cache, position.clone()));
+                    function_compile.asm.push(Asm::JUMPIFFALSE(cached));
+                    function_compile.compile_expr(expr)?;
+                    function_compile.asm.push(Asm::LAZYSET(cache));
+                    function_compile.asm.push(Asm::RET(true));
+                    function_compile.asm.push(Asm::LABEL(cached));
+                    function_compile.asm.push(Asm::GETGLOBAL(cache));
+                    function_compile.asm.push(Asm::RET(true));
+
+                    let num_captures = captures.len() as u32;
+                    let local_vars = function_compile.variables.len() as u32;
+                    self.asm
+                        .push(Asm::OFFSET(num_captures, local_vars - num_captures));
+
+                    self.gen = function_compile.gen;
+                    self.global = function_compile.global;
+                    self.global_strings = function_compile.global_strings;
+                    self.asm.extend_from_slice(&function_compile.asm);
+                    self.asm.push(Asm::LABEL(skip));
+                    let index = self.global.get_index(identifier).unwrap();
+                    self.asm.push(Asm::STOREGLOBAL(index as u32));
+                }
                 common::nodes::Statement::WhileLet {
                     identifier,
                     expr,
@@ -930,10 +1205,12 @@ impl Compiler {
             //println!("global len {}", self.global.len());
             let mut package = vec![Asm::ALLOCGLOBBALS(self.global.len() as u32)];
 
-            for s in self.global_strings.iter() {
-                //println!("s0 {} s1 {}", s.0, s.1);
-                package.push(Asm::STRING(s.0.clone()));
-                package.push(Asm::STOREGLOBAL(*s.1 as u32));
+            // emit the string pool in index order so builds are reproducible
+            let mut strings: Vec<_> = self.global_strings.iter().collect();
+            strings.sort_by_key(|(_, index)| **index);
+            for (string, index) in strings {
+                package.push(Asm::STRING(string.clone()));
+                package.push(Asm::STOREGLOBAL(*index as u32));
             }
 
             package.extend_from_slice(&self.asm);
@@ -1005,6 +1282,8 @@ impl Compiler {
             Expr::Return { .. } => todo!(),
             Expr::IfExpr { .. } => todo!(),
             Expr::Block { .. } => todo!(),
+            Expr::Try { .. } => todo!(),
+            Expr::Propagate { .. } => todo!(),
             Expr::Let { .. } => todo!(),
             Expr::Void => {}
         }
@@ -1135,7 +1414,7 @@ impl Compiler {
                 }
                 common::tokens::Unary::Negative => {
                     self.compile_expr(expr)?;
-                    self.asm.push(Asm::NEG);
+                    self.int_operation(Asm::NEG);
                     Ok(())
                 }
                 common::tokens::Unary::Not => {
@@ -1155,10 +1434,15 @@ impl Compiler {
                     common::tokens::Operator::Greater => {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
+                        if matches!(
+                            lhs.get_type(),
+                            TType::Int | TType::Sized { .. } | TType::Char
+                        ) {
                             self.asm.push(Asm::IGTR);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FGTR);
+                        } else if let TType::Generic { .. } | TType::String = lhs.get_type() {
+                            self.asm.push(Asm::GTR);
                         } else {
                             dbg!(&ttype);
                         }
@@ -1166,14 +1450,36 @@ impl Compiler {
                     common::tokens::Operator::Less => {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
+                        if matches!(
+                            lhs.get_type(),
+                            TType::Int | TType::Sized { .. } | TType::Char
+                        ) {
                             self.asm.push(Asm::ILSS);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FLSS);
+                        } else if let TType::Generic { .. } | TType::String = lhs.get_type() {
+                            self.asm.push(Asm::LSS);
                         } else {
                             todo!();
                         }
                     }
+                    // bounded generics only define less than, a >= b is !(a < b)
+                    common::tokens::Operator::GreaterOrEqual
+                        if matches!(lhs.get_type(), TType::Generic { .. }) =>
+                    {
+                        self.compile_expr(lhs)?;
+                        self.compile_expr(rhs)?;
+                        self.asm.push(Asm::LSS);
+                        self.asm.push(Asm::NOT);
+                    }
+                    common::tokens::Operator::LessOrEqual
+                        if matches!(lhs.get_type(), TType::Generic { .. }) =>
+                    {
+                        self.compile_expr(lhs)?;
+                        self.compile_expr(rhs)?;
+                        self.asm.push(Asm::GTR);
+                        self.asm.push(Asm::NOT);
+                    }
                     common::tokens::Operator::Assignment => {
                         self.compile_expr(rhs)?;
                         self.getref_expr(lhs)?;
@@ -1184,7 +1490,7 @@ impl Compiler {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
                         match lhs.get_type() {
-                            TType::Int => self.asm.push(Asm::IADD),
+                            TType::Int | TType::Sized { .. } => self.int_operation(Asm::IADD),
                             TType::Float => self.asm.push(Asm::FADD),
                             TType::String => self.asm.push(Asm::CONCAT),
                             TType::List { .. } => self.asm.push(Asm::CONCAT),
@@ -1196,8 +1502,8 @@ impl Compiler {
                     common::tokens::Operator::Subtraction => {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
-                            self.asm.push(Asm::ISUB);
+                        if matches!(lhs.get_type(), TType::Int | TType::Sized { .. }) {
+                            self.int_operation(Asm::ISUB);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FSUB);
                         } else {
@@ -1207,8 +1513,8 @@ impl Compiler {
                     common::tokens::Operator::Division => {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
-                            self.asm.push(Asm::IDIV);
+                        if matches!(lhs.get_type(), TType::Int | TType::Sized { .. }) {
+                            self.int_operation(Asm::IDIV);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FDIV);
                         } else {
@@ -1218,8 +1524,8 @@ impl Compiler {
                     common::tokens::Operator::Multiplication => {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
-                            self.asm.push(Asm::IMUL);
+                        if matches!(lhs.get_type(), TType::Int | TType::Sized { .. }) {
+                            self.int_operation(Asm::IMUL);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FMUL);
                         } else {
@@ -1259,7 +1565,10 @@ impl Compiler {
                         // else return the other value
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
+                        if matches!(
+                            lhs.get_type(),
+                            TType::Int | TType::Sized { .. } | TType::Char
+                        ) {
                             self.asm.push(Asm::IGTR);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FGTR);
@@ -1282,7 +1591,10 @@ impl Compiler {
                         // else return the other value
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
+                        if matches!(
+                            lhs.get_type(),
+                            TType::Int | TType::Sized { .. } | TType::Char
+                        ) {
                             self.asm.push(Asm::ILSS);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FLSS);
@@ -1324,10 +1636,10 @@ impl Compiler {
                     }
                     common::tokens::Operator::AddAssign => {
                         match lhs.get_type() {
-                            TType::Int => {
+                            TType::Int | TType::Sized { .. } => {
                                 self.compile_expr(rhs)?;
                                 self.compile_expr(lhs)?;
-                                self.asm.push(Asm::IADD);
+                                self.int_operation(Asm::IADD);
                             }
                             TType::Float => {
                                 self.compile_expr(rhs)?;
@@ -1354,8 +1666,8 @@ impl Compiler {
                     common::tokens::Operator::SubAssign => {
                         self.compile_expr(lhs)?;
                         self.compile_expr(rhs)?;
-                        if lhs.get_type() == TType::Int {
-                            self.asm.push(Asm::ISUB);
+                        if matches!(lhs.get_type(), TType::Int | TType::Sized { .. }) {
+                            self.int_operation(Asm::ISUB);
                         } else if lhs.get_type() == TType::Float {
                             self.asm.push(Asm::FSUB);
                         } else {
@@ -1383,6 +1695,15 @@ impl Compiler {
                 }
                 Ok(())
             }
+            // an Int literal the parser gave a sized type
+            Expr::Literal {
+                ttype: TType::Sized { width },
+                value: Atom::Integer { value },
+            } => {
+                self.asm
+                    .push(Asm::SIZED(width.wrap(*value as i128), *width));
+                Ok(())
+            }
             Expr::Literal {
                 ttype: _,
                 value: atom,
@@ -1403,6 +1724,9 @@ impl Compiler {
                 function_compile.variables.clear();
                 //dbg!(&function_compile.variables);
                 function_compile.asm.clear();
+                function_compile.ensures.clear();
+                function_compile.withs.clear();
+                function_compile.tries.clear();
                 //dbg!(&parameters, &captured);
                 // Register parameter names in the function's local variable scope
                 for param in parameters.iter() {
@@ -1440,6 +1764,7 @@ impl Compiler {
                     self.asm.push(Asm::LIST(captured.len() as u64));
                     self.asm.push(Asm::CLOSURE(closure_jump_label));
                 }
+                self.asm.push(Asm::NAME("closure".into()));
 
                 // Compile the function body
                 let function_body_ast = Ast {
@@ -1692,6 +2017,8 @@ impl Compiler {
             }
             Expr::Return { expr, .. } => {
                 self.compile_expr(expr)?;
+                self.compile_ensures(true)?;
+                self.close_withs(0)?;
                 self.asm.push(Asm::RET(true));
                 Ok(())
             }
@@ -1721,12 +2048,104 @@ impl Compiler {
                 self.asm.pop();
                 Ok(())
             }
+            // Result is an enum of Ok and Err, stored as [value, tag, "Result"]
+            Expr::Try { body, .. } => {
+                let handler = self.gen.generate();
+                let end = self.gen.generate();
+                self.asm.push(Asm::TRY(handler));
+                self.tries.push(self.breaks.len());
+                let b = Ast {
+                    program: body.clone(),
+                };
+                self.compile_program(b, self.filepath.clone(), false, false, false, true)?;
+                self.asm.pop();
+                self.tries.pop();
+                let value = match body.last() {
+                    Some(common::nodes::Statement::Expression { ttype, .. }) => ttype.clone(),
+                    _ => TType::Void,
+                };
+                if value == TType::Void {
+                    self.asm.push(Asm::BOOL(true));
+                }
+                self.asm.push(Asm::ENDTRY);
+                self.asm.push(Asm::INTEGER(0));
+                self.compile_string_literal("Result");
+                self.asm.push(Asm::LIST(3));
+                self.asm.push(Asm::JMP(end));
+                // the vm leaves the error message on the stack
+                self.asm.push(Asm::LABEL(handler));
+                self.asm.push(Asm::INTEGER(1));
+                self.compile_string_literal("Result");
+                self.asm.push(Asm::LIST(3));
+                self.asm.push(Asm::LABEL(end));
+                Ok(())
+            }
+            Expr::Propagate { expr, position, .. } => {
+                let end = self.gen.generate();
+                self.compile_expr(expr)?;
+                if let TType::Option { .. } = expr.get_type() {
+                    self.asm.push(Asm::DUP);
+                    self.asm.push(Asm::ISSOME);
+                    self.asm.push(Asm::NOT);
+                    self.asm.push(Asm::JUMPIFFALSE(end));
+                    self.asm.push(Asm::POP);
+                    self.compile_string_literal("Tried to propagate a None value");
+                    self.asm.push(Asm::RAISE(position.clone()));
+                    self.asm.push(Asm::LABEL(end));
+                    return Ok(());
+                }
+                let failed = self.gen.generate();
+                self.variables
+                    .insert(format!("___propagated___{}", self.gen.generate()).into());
+                let temp = self.variables.len() - 1;
+                self.asm.push(Asm::STORE(temp as u32));
+                self.asm.push(Asm::INTEGER(1));
+                self.asm.push(Asm::GET(temp as u32));
+                self.asm.push(Asm::LIN(position.clone()));
+                self.asm.push(Asm::INTEGER(0));
+                self.asm.push(Asm::EQUALS);
+                self.asm.push(Asm::JUMPIFFALSE(failed));
+                self.asm.push(Asm::INTEGER(0));
+                self.asm.push(Asm::GET(temp as u32));
+                self.asm.push(Asm::LIN(position.clone()));
+                self.asm.push(Asm::JMP(end));
+                self.asm.push(Asm::LABEL(failed));
+                self.asm.push(Asm::INTEGER(0));
+                self.asm.push(Asm::GET(temp as u32));
+                self.asm.push(Asm::LIN(position.clone()));
+                // errors are reported as strings
+                if let TType::Custom { type_params, .. } = expr.get_type() {
+                    if type_params[1] != TType::String {
+                        if let Some(index) = self.native_functions.get_index("Cast::string") {
+                            self.asm.push(Asm::NATIVE(index as u64))
+                        }
+                    }
+                }
+                self.asm.push(Asm::RAISE(position.clone()));
+                self.asm.push(Asm::LABEL(end));
+                Ok(())
+            }
             Expr::Let {
                 identifier,
                 expr,
                 global,
                 ..
             } => {
+                // _ = expr keeps nothing
+                if &**identifier == "_" {
+                    self.compile_expr(expr)?;
+                    self.asm.push(Asm::POP);
+                    return Ok(());
+                }
+                // declared without a value, only reserve the slot
+                if let Expr::None = **expr {
+                    if *global {
+                        self.global.insert(identifier.clone());
+                    } else {
+                        self.variables.insert(identifier.clone());
+                    }
+                    return Ok(());
+                }
                 self.compile_expr(expr)?;
 
                 if *global {
@@ -1750,6 +2169,47 @@ impl Compiler {
         }
     }
 
+    // checks the postconditions of the current function against the value
+    // sitting on top of the stack, which is bound to result
+    // closes the with blocks and try blocks opened while at least depth loops
+    // were open, innermost first, before a jump leaves them
+    fn close_withs(&mut self, depth: usize) -> Result<(), NovaError> {
+        for _ in self.tries.iter().rev().take_while(|loops| **loops >= depth) {
+            self.asm.push(Asm::ENDTRY);
+        }
+        for (_, close) in self
+            .withs
+            .clone()
+            .iter()
+            .rev()
+            .take_while(|(loops, _)| *loops >= depth)
+        {
+            self.asm.push(Asm::UNDEFER);
+            self.compile_expr(close)?;
+            if close.get_type() != TType::Void {
+                self.asm.push(Asm::POP);
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_ensures(&mut self, with_result: bool) -> Result<(), NovaError> {
+        if self.ensures.is_empty() {
+            return Ok(());
+        }
+        if with_result {
+            if let Some(index) = self.variables.get_index("result") {
+                self.asm.push(Asm::DUP);
+                self.asm.push(Asm::STORE(index as u32));
+            }
+        }
+        for contract in self.ensures.clone().iter() {
+            self.compile_expr(&contract.test)?;
+            self.asm.push(Asm::ENSURES(contract.position.clone()));
+        }
+        Ok(())
+    }
+
     // needs to be recursive function
     #[allow(clippy::too_many_arguments)]
     fn for_in_loop(
@@ -1901,9 +2361,15 @@ impl Compiler {
             } => {
                 // println!("Call: {}", caller);
                 if caller.deref() == "typeof" {
+                    if let TType::Union { members } = list[0].get_type() {
+                        return self.compile_union_typeof(&list[0], &members);
+                    }
                     self.compile_string_literal(&list[0].get_type().to_string());
                     return Ok(());
                 }
+                if let "assert" | "assert_eq" = caller.deref() {
+                    return self.compile_assert(caller, list, position);
+                }
                 for expr in list {
                     self.compile_expr(expr)?;
                 }
@@ -1915,21 +2381,27 @@ impl Compiler {
                         self.asm.push(Asm::PRINT);
                         self.asm.push(Asm::ERROR(position.clone()));
                     }
-                    "None" => self.asm.push(Asm::NONE),
+                    "None" => self.asm.push(Asm::NONEFROM(position.clone())),
                     "Option::unwrap" => self.asm.push(Asm::UNWRAP(position.clone())),
                     "Some" => {}
                     "Option::isSome" => self.asm.push(Asm::ISSOME),
+                    "__typename" => self.asm.push(Asm::TYPENAME),
                     "free" => self.asm.push(Asm::FREE),
                     "clone" => self.asm.push(Asm::CLONE),
-                    "exit" => self.asm.push(Asm::EXIT),
+                    "exit" => self.asm.push(Asm::EXIT(position.clone())),
                     "error" => self.asm.push(Asm::ERROR(position.clone())),
                     identifier => {
                         //dbg!(identifier);
-                        if let Some(index) = self.native_functions.get_index(identifier) {
+                        if let Some((width, overflow)) = sized_conversion(identifier) {
+                            self.asm
+                                .push(Asm::CONVERT(width, overflow, position.clone()));
+                        } else if let Some(index) = self.native_functions.get_index(identifier) {
                             self.asm.push(Asm::NATIVE(index as u64));
+                            self.asm.push(Asm::CALLSITE(position.clone()));
                         } else if let Some(index) = self.variables.get_index(identifier) {
                             self.asm.push(Asm::GET(index as u32));
                             self.asm.push(Asm::CALL);
+                            self.asm.push(Asm::CALLSITE(position.clone()));
                         } else if let Some(index) = self.global.get_index(identifier) {
                             //dbg!(identifier, &index);
                             if "println" == identifier || "print" == identifier {
@@ -1954,6 +2426,7 @@ impl Compiler {
                             }
 
                             self.asm.push(Asm::DCALL(index as u32));
+                            self.asm.push(Asm::CALLSITE(position.clone()));
                         } else if let Some(value) = self.unrolled_index.get(identifier) {
                             self.asm.push(Asm::INTEGER(*value as i64));
                         } else {
@@ -1971,3 +2444,17 @@ impl Compiler {
         Ok(())
     }
 }
+
+// U8(x), U8::wrap(x) and U8::checked(x) convert an Int to a sized integer and
+// U8::toInt(x) converts back, the parser gives them their types
+fn sized_conversion(name: &str) -> Option<(Option<Width>, Overflow)> {
+    let (type_name, function) = name.split_once("::").unwrap_or((name, ""));
+    let width = Width::from_name(type_name)?;
+    match function {
+        "" => Some((Some(width), Overflow::Error)),
+        "wrap" => Some((Some(width), Overflow::Wrap)),
+        "checked" => Some((Some(width), Overflow::Checked)),
+        "toInt" => Some((None, Overflow::Error)),
+        _ => None,
+    }
+}
@@ -1,6 +1,7 @@
 use common::{
-    code::{Asm, Code},
+    code::{Asm, Code, Overflow},
     table::Table,
+    ttype::Width,
 };
 
 pub fn new() -> Disassembler {
@@ -22,7 +23,7 @@ impl Disassembler {
         println!("main:");
         for instruction in asm {
             match instruction {
-                Asm::EXIT => println!("    exit"),
+                Asm::EXIT(_) => println!("    exit"),
                 Asm::ALLOCGLOBBALS(v) => println!("    global: {v}"),
                 Asm::ALLOCLOCALS(v) => println!("    local: {v}"),
                 Asm::OFFSET(v, l) => println!("    offset: {v} {l}"),
@@ -59,6 +60,21 @@ impl Disassembler {
                 Asm::IGTR => println!("    igtr"),
                 Asm::FLSS => println!("    flss"),
                 Asm::FGTR => println!("    fgtr"),
+                Asm::LSS => println!("    lss"),
+                Asm::GTR => println!("    gtr"),
+                Asm::LAZY(v, _) => println!("    lazy: {v}"),
+                Asm::LAZYSET(v) => println!("    lazyset: {v}"),
+                Asm::DEFER => println!("    defer"),
+                Asm::UNDEFER => println!("    undefer"),
+                Asm::TRY(v) => println!("    try: {v}"),
+                Asm::ENDTRY => println!("    endtry"),
+                Asm::RAISE(_) => println!("    raise"),
+                Asm::TYPENAME => println!("    typename"),
+                Asm::SIZED(v, width) => println!("    push{width}: {}", width.value(v)),
+                Asm::CONVERT(width, overflow, _) => match width {
+                    Some(width) => println!("    convert: {width} {overflow:?}"),
+                    None => println!("    convert: Int {overflow:?}"),
+                },
                 Asm::EQUALS => println!("    equ"),
                 Asm::FREE => println!("    free"),
                 Asm::CLONE => println!("    clone"),
@@ -73,12 +89,15 @@ impl Disassembler {
                 Asm::NATIVE(v) => println!("    native: {v}"),
                 Asm::DUP => println!("    dup"),
                 Asm::POP => println!("    pop"),
-                Asm::NONE => println!("    none"),
+                Asm::NONE | Asm::NONEFROM(_) => println!("    none"),
                 Asm::ISSOME => println!("    issome"),
                 Asm::UNWRAP(_) => println!("    unwrap"),
                 Asm::CONCAT => println!("    concat"),
                 Asm::Char(v) => println!("    char: {v}"),
                 Asm::ERROR(_) => println!("    error"),
+                Asm::REQUIRES(_) => println!("    requires"),
+                Asm::ENSURES(_) => println!("    ensures"),
+                Asm::CALLSITE(_) | Asm::LINE(_) | Asm::NAME(_) => {}
             }
         }
         println!();
@@ -206,6 +225,8 @@ impl Disassembler {
                 }
                 Code::IGTR => self.out("Greater than"),
                 Code::ILSS => self.out("Less than"),
+                Code::GTR => self.out("Greater than (dynamic)"),
+                Code::LSS => self.out("Less than (dynamic)"),
                 Code::JUMPIFFALSE => {
                     let jump = u32::from_le_bytes(self.next_arr(&mut input).unwrap());
                     self.out(&format!("Jump if false: {}", jump))
@@ -272,10 +293,48 @@ impl Disassembler {
                     self.out(&format!("Store Global ID {}", index))
                 }
 
+                Code::DEFER => self.out("Defer"),
+                Code::UNDEFER => self.out("Undefer"),
+                Code::TRY => {
+                    let int = u32::from_le_bytes(self.next_arr(&mut input).unwrap());
+                    self.out(&format!("Try, handler at +{}", int))
+                }
+                Code::ENDTRY => self.out("End Try"),
+                Code::RAISE => self.out("Raise"),
+                Code::TYPENAME => self.out("Type Name"),
+                Code::SIZED => {
+                    let width = self.next(&mut input).unwrap();
+                    let int = i64::from_le_bytes(self.next_arr(&mut input).unwrap());
+                    match Width::from_byte(width) {
+                        Some(width) => self.out(&format!("Push {} {}", width, width.value(int))),
+                        None => self.out(&format!("Push sized {}", int)),
+                    }
+                }
+                Code::CONVERT => {
+                    let width = self.next(&mut input).unwrap();
+                    let overflow = Overflow::from_byte(self.next(&mut input).unwrap());
+                    match Width::from_byte(width) {
+                        Some(width) => self.out(&format!("Convert to {} {:?}", width, overflow)),
+                        None => self.out(&format!("Convert to Int {:?}", overflow)),
+                    }
+                }
+
+                Code::LAZY => {
+                    let index = u32::from_le_bytes(self.next_arr(&mut input).unwrap());
+
+                    self.out(&format!("Lazy Global ID {}", index))
+                }
+
+                Code::LAZYSET => {
+                    let index = u32::from_le_bytes(self.next_arr(&mut input).unwrap());
+
+                    self.out(&format!("Cache Lazy Global ID {}", index))
+                }
+
                 Code::CHAR => {
-                    let c = self.next(&mut input).unwrap();
+                    let c = u32::from_le_bytes(self.next_arr(&mut input).unwrap());
 
-                    self.out(&format!("Push Char {}", c as char))
+                    self.out(&format!("Push Char {}", char::from_u32(c).unwrap()))
                 }
 
                 Code::POP => self.out("Pop"),
@@ -9,6 +9,12 @@ mod keywords {
     };
     pub const KEYWORDS: phf::Map<&'static str, TokenValue> = phf::phf_map! {
         "in" => Keyword(In),
+        "if" => Keyword(If),
+        "else" => Keyword(Else),
+        "let" => Keyword(Let),
+        "return" => Keyword(Return),
+        "extends" => Keyword(Extends),
+        "mod" => Keyword(Mod),
         "true" => Bool(true),
         "false" => Bool(false),
     };
@@ -26,6 +32,8 @@ pub struct Lexer {
     pos: FilePosition,
     pub source: Rc<str>,
     remaining: Range<usize>,
+    // the lines of the /// comment read since the last token
+    doc: Option<String>,
 }
 
 impl Lexer {
@@ -49,6 +57,7 @@ impl Lexer {
                 line: 1,
                 col: 1,
             },
+            doc: None,
         }
     }
     fn remaining(&self) -> &str {
@@ -84,13 +93,19 @@ impl Lexer {
     fn match_literal(&mut self, literal: &str) -> bool {
         let matches = self.peek_literal(literal);
         if matches {
+            // literals never hold a newline
             self.remaining.start += literal.len();
+            self.pos.col += literal.chars().count();
         }
         matches
     }
     fn peek_literal(&mut self, literal: &str) -> bool {
         self.remaining().starts_with(literal)
     }
+    // how far tokenizing got
+    pub fn position(&self) -> FilePosition {
+        self.pos.clone()
+    }
     pub fn span(&self) -> Span {
         Span {
             pos: self.pos.clone(),
@@ -120,6 +135,55 @@ impl Lexer {
             _ => return None,
         })
     }
+    // the character an escape sequence stands for, called after its \. None
+    // when the source ends in the middle of it
+    fn escape_sequence(&mut self, literal: &str) -> Option<Result<char, NovaError>> {
+        let position = self.current_position();
+        let c = self.advance()?;
+        if c == 'u' {
+            return Some(self.unicode_escape(literal, position));
+        }
+        Some(Self::escape(c).ok_or_else(|| NovaError::Lexing {
+            msg: format!("Invalid escape sequence in {literal} literal.").into(),
+            note: format!(
+                "Attempted to use escape sequence \\{c}, the escapes are \\n \\r \\t \\0 \\' \\\" \\\\ and \\u{{...}}"
+            )
+            .into(),
+            position,
+        }))
+    }
+    // \u{1F600}, one to six hex digits naming a unicode scalar value
+    fn unicode_escape(&mut self, literal: &str, position: FilePosition) -> Result<char, NovaError> {
+        let error = |note: String| NovaError::Lexing {
+            msg: format!("Invalid unicode escape in {literal} literal.").into(),
+            note: note.into(),
+            position: position.clone(),
+        };
+        if !self.consume_if(|c| c == '{') {
+            return Err(error(
+                "Unicode escapes are written with braces, as in \\u{1F600}".into(),
+            ));
+        }
+        let digits = self.span();
+        while self.consume_if(|c| c.is_ascii_hexdigit()) {}
+        let digits = self.consumed_from(&digits).to_owned();
+        if !self.consume_if(|c| c == '}') {
+            return Err(error(format!(
+                "Expected }} after \\u{{{digits}, only hex digits can go between the braces"
+            )));
+        }
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(error(format!(
+                "\\u{{{digits}}} needs one to six hex digits"
+            )));
+        }
+        let value = u32::from_str_radix(&digits, 16).unwrap();
+        char::from_u32(value).ok_or_else(|| {
+            error(format!(
+                "\\u{{{digits}}} is not a unicode character, surrogates and values above 10FFFF are not allowed"
+            ))
+        })
+    }
     pub fn tokenize(&mut self) -> Result<Vec<Token>, NovaError> {
         self.collect()
     }
@@ -143,8 +207,29 @@ impl Iterator for Lexer {
             out += text;
             out.into()
         }
+        // _ can separate digits anywhere, 1_000_000
         fn capture_int_digits(scanner: &mut Lexer) {
-            while scanner.consume_if(|c| matches!(c, '0'..='9' | 'a'..='f' )) {}
+            while scanner.consume_if(|c| matches!(c, '0'..='9' | '_')) {}
+        }
+        // e or E with an optional sign and at least one digit, 1.5e-3 and 2E10
+        fn capture_exponent(scanner: &mut Lexer) -> bool {
+            let rest = scanner.remaining();
+            let Some(rest) = rest.strip_prefix(['e', 'E']) else {
+                return false;
+            };
+            let rest = rest.strip_prefix(['+', '-']).unwrap_or(rest);
+            if !rest.starts_with(|c: char| c.is_ascii_digit()) {
+                return false;
+            }
+            scanner.advance();
+            scanner.advance_if(|c| c == '+' || c == '-');
+            capture_int_digits(scanner);
+            true
+        }
+        // takes every letter after 0x, 0b or 0o so a digit the radix does not
+        // have is an error instead of the start of the next token
+        fn capture_radix_digits(scanner: &mut Lexer) {
+            while scanner.consume_if(|c| c.is_ascii_alphanumeric() || c == '_') {}
         }
         fn try_parse_int(
             scanner: &Lexer,
@@ -152,7 +237,7 @@ impl Iterator for Lexer {
             radix: u32,
             kind: &str,
         ) -> Result<TokenValue, NovaError> {
-            match i64::from_str_radix(body, radix) {
+            match i64::from_str_radix(&body.replace('_', ""), radix) {
                 Ok(n) => Ok(Integer(n)),
                 Err(err) => Err(NovaError::Lexing {
                     msg: format!("Invalid integer literal {body}").into(),
@@ -167,6 +252,20 @@ impl Iterator for Lexer {
             let value = match self.advance()? {
                 '\n' | '\r' | ' ' | '\t' => continue,
 
+                // /// documents the item below it, //// is a plain comment again
+                '/' if self.peek_literal("//") && !self.peek_literal("///") => {
+                    self.match_literal("//");
+                    let start = self.remaining.start;
+                    while self.consume_if(|c| c != '\n') {}
+                    let text = &self.source[start..self.remaining.start];
+                    let line = text.strip_prefix(' ').unwrap_or(text).trim_end();
+                    let doc = self.doc.get_or_insert_with(String::new);
+                    if !doc.is_empty() {
+                        doc.push('\n');
+                    }
+                    doc.push_str(line);
+                    continue;
+                }
                 '/' if self.match_literal("/") => {
                     while self.consume_if(|c| c != '\n') {}
                     continue;
@@ -174,7 +273,11 @@ impl Iterator for Lexer {
                 '/' if self.match_literal("*") => {
                     match self.remaining().find("*/") {
                         Some(star) => {
-                            self.remaining.start += star + 2;
+                            // step through the comment so lines and columns stay right
+                            let end = self.remaining.start + star + 2;
+                            while self.remaining.start < end {
+                                self.advance();
+                            }
                         }
                         None => {
                             self.remaining.start = self.remaining.end;
@@ -203,19 +306,11 @@ impl Iterator for Lexer {
                         return unterminated_err(self);
                     };
                     let c = match c {
-                        '\\' => {
-                            let Some(c) = self.advance() else {
-                                return unterminated_err(self);
-                            };
-                            let Some(escaped) = Self::escape(c) else {
-                                return Some(Err(NovaError::Lexing {
-                                    msg: "Invalid escape sequence in char literal.".into(),
-                                    note: format!("Attempted to use escape sequence \\{c}").into(),
-                                    position: self.current_position(),
-                                }));
-                            };
-                            escaped
-                        }
+                        '\\' => match self.escape_sequence("char") {
+                            Some(Ok(escaped)) => escaped,
+                            Some(Err(error)) => return Some(Err(error)),
+                            None => return unterminated_err(self),
+                        },
                         c => c,
                     };
                     if !self.consume_if(|c| c == '\'') {
@@ -290,23 +385,13 @@ impl Iterator for Lexer {
                         };
                         match c {
                             '"' => break,
-                            '\\' => {
-                                let Some(c) = self.advance() else {
-                                    // Ignore \ without following symbol as that means the string
-                                    // is unterminated
-                                    continue;
-                                };
-                                // TODO: Hex ASCII/BYTE escapes using \x41 syntax?
-                                let Some(escaped) = Self::escape(c) else {
-                                    return Some(Err(NovaError::Lexing {
-                                        msg: "Invalid escape sequence in string literal.".into(),
-                                        note: format!("Attempted to use escape sequence \\{c}")
-                                            .into(),
-                                        position: self.current_position(),
-                                    }));
-                                };
-                                body.push(escaped);
-                            }
+                            '\\' => match self.escape_sequence("string") {
+                                Some(Ok(escaped)) => body.push(escaped),
+                                Some(Err(error)) => return Some(Err(error)),
+                                // Ignore \ without following symbol as that means the string
+                                // is unterminated
+                                None => continue,
+                            },
                             c => body.push(c),
                         }
                     }
@@ -314,12 +399,13 @@ impl Iterator for Lexer {
                     return Some(Ok(Token {
                         value: TokenValue::StringLiteral(body.into()),
                         position: span.pos,
+                        doc: self.doc.take().map(Into::into),
                     }));
                 }
 
                 '0' if self.match_literal("b") => {
                     let body = self.span();
-                    capture_int_digits(self);
+                    capture_radix_digits(self);
                     match try_parse_int(self, self.consumed_from(&body), 2, "binary") {
                         Ok(n) => n,
                         Err(err) => return Some(Err(err)),
@@ -327,7 +413,7 @@ impl Iterator for Lexer {
                 }
                 '0' if self.match_literal("o") => {
                     let body = self.span();
-                    capture_int_digits(self);
+                    capture_radix_digits(self);
                     match try_parse_int(self, self.consumed_from(&body), 8, "octal") {
                         Ok(n) => n,
                         Err(err) => return Some(Err(err)),
@@ -335,7 +421,7 @@ impl Iterator for Lexer {
                 }
                 '0' if self.match_literal("x") => {
                     let body = self.span();
-                    capture_int_digits(self);
+                    capture_radix_digits(self);
                     match try_parse_int(self, self.consumed_from(&body), 16, "hex") {
                         Ok(n) => n,
                         Err(err) => return Some(Err(err)),
@@ -345,7 +431,6 @@ impl Iterator for Lexer {
                     if c != '.' || self.peek().is_some_and(|c| c.is_ascii_digit()) =>
                 {
                     capture_int_digits(self);
-                    let int_part = self.consumed_from(&span);
 
                     let float = self.remaining().starts_with('.')
                         && self.remaining()[1..]
@@ -359,7 +444,10 @@ impl Iterator for Lexer {
                         self.advance_if(|c| c == '.');
                         // Capture rest of the digits
                         capture_int_digits(self);
-                        let float = self.consumed_from(&span);
+                    }
+                    let float = capture_exponent(self) || float;
+                    if float {
+                        let float = self.consumed_from(&span).replace('_', "");
                         match float.parse() {
                             Ok(f) => Float(f),
                             Err(err) => {
@@ -372,7 +460,7 @@ impl Iterator for Lexer {
                             }
                         }
                     } else {
-                        match try_parse_int(self, int_part, 10, "decimal") {
+                        match try_parse_int(self, self.consumed_from(&span), 10, "decimal") {
                             Ok(n) => n,
                             Err(err) => {
                                 return Some(Err(err));
@@ -447,6 +535,7 @@ impl Iterator for Lexer {
         Some(Ok(Token {
             value,
             position: span.pos,
+            doc: self.doc.take().map(Into::into),
         }))
     }
 }
@@ -95,6 +95,35 @@ fn escaped_chars() {
     )
 }
 
+#[test]
+fn unicode_escapes() {
+    assert_input_output(
+        r#" "\u{1F600} caf\u{e9}" '\u{41}' '\u{10FFFF}' "#,
+        [
+            StringLiteral("\u{1F600} caf\u{e9}".into()),
+            Char('A'),
+            Char('\u{10FFFF}'),
+        ],
+    )
+}
+
+#[test]
+fn invalid_escapes() {
+    for source in [
+        r#""\q""#,
+        r#"'\q'"#,
+        r#""\u41""#,
+        r#""\u{}""#,
+        r#""\u{1234567}""#,
+        r#""\u{12g}""#,
+        r#""\u{D800}""#,
+        r#""\u{110000}""#,
+    ] {
+        let tokens: Result<Vec<Token>, NovaError> = Lexer::new(source, None).collect();
+        assert!(tokens.is_err(), "{source} should not lex");
+    }
+}
+
 #[test]
 fn non_decimal_ints() {
     assert_input_output(
@@ -110,6 +139,42 @@ fn non_decimal_ints() {
     )
 }
 
+#[test]
+fn digit_separators() {
+    assert_input_output(
+        "1_000_000 0xFF_FF 0b1010_1010 0o7_55 1_000.5",
+        [
+            Integer(1_000_000),
+            Integer(0xffff),
+            Integer(0b1010_1010),
+            Integer(0o755),
+            Float(1000.5),
+        ],
+    )
+}
+
+#[test]
+fn scientific_floats() {
+    assert_input_output(
+        "1.5e-3 2E10 6.02e+23 1e0 3.e",
+        [
+            Float(1.5e-3),
+            Float(2e10),
+            Float(6.02e23),
+            Float(1.0),
+            Integer(3),
+            StructuralSymbol(Dot),
+            Identifier("e".into()),
+        ],
+    )
+}
+
+#[test]
+fn invalid_radix_digit() {
+    let tokens: Result<Vec<Token>, NovaError> = Lexer::new("0b102 0o8", None).collect();
+    assert!(tokens.is_err());
+}
+
 #[test]
 fn idents() {
     assert_input_output(
@@ -185,11 +250,124 @@ fn structural_symbols() {
 #[test]
 fn keywords() {
     assert_input_output(
-        "true false in",
+        "true false in if else let return extends mod",
         [
             Bool(true),
             Bool(false),
             Keyword(common::tokens::KeyWord::In),
+            Keyword(common::tokens::KeyWord::If),
+            Keyword(common::tokens::KeyWord::Else),
+            Keyword(common::tokens::KeyWord::Let),
+            Keyword(common::tokens::KeyWord::Return),
+            Keyword(common::tokens::KeyWord::Extends),
+            Keyword(common::tokens::KeyWord::Mod),
         ],
     );
 }
+
+fn lex(input: &str) -> Vec<Token> {
+    Lexer::new(input, None)
+        .tokenize()
+        .expect("Lexing failied unexpectedly")
+}
+
+// the positions of rewritten tokens must be the ones lexing the rendered
+// source gives
+#[track_caller]
+fn assert_positions_consistent(tokens: &[Token]) {
+    let source = common::rewrite::render(tokens);
+    assert_eq!(lex(&source), tokens, "rendered as {source:?}");
+}
+
+#[test]
+fn render_round_trip() {
+    let source = "let x: [Int] = [1, 2]\nx += 1\nprintln(\"a\\n\\\"b\\\"\", 'c', 1.5, x..=3)";
+    assert_eq!(common::rewrite::render(&lex(source)), source);
+    // comments are dropped but the tokens after them keep their place
+    assert_positions_consistent(&lex("let x = 1 /* a\n  b */ + 2 // c\nx += 1"));
+}
+
+#[test]
+fn splice_keeps_positions() {
+    let mut tokens = lex("let total = 1 + 2 + count\nprintln(total)");
+    common::rewrite::splice(&mut tokens, 3..6, [Integer(3)]);
+    assert_positions_consistent(&tokens);
+    assert_eq!(tokens[5].position.col, 17);
+
+    common::rewrite::replace(&mut tokens, 1, Identifier("sum".into()));
+    assert_positions_consistent(&tokens);
+
+    common::rewrite::insert(
+        &mut tokens,
+        3,
+        [Identifier("base".into()), Operator(Addition)],
+    );
+    assert_positions_consistent(&tokens);
+    assert_eq!(
+        common::rewrite::render(&tokens),
+        "let sum = base + 3 + count\nprintln(total)"
+    );
+}
+
+#[test]
+fn splice_across_lines() {
+    let mut tokens = lex("f(1,\n  2)\ng()\n\nh()");
+    common::rewrite::remove(&mut tokens, 2..5);
+    assert_positions_consistent(&tokens);
+    assert_eq!(common::rewrite::render(&tokens), "f()\ng()\n\nh()");
+
+    let end = tokens.len();
+    common::rewrite::insert(&mut tokens, end, [StructuralSymbol(Semicolon)]);
+    assert_positions_consistent(&tokens);
+}
+
+#[test]
+fn fixes_apply_at_token_positions() {
+    use common::error::{apply_fixes, Fix};
+
+    let source = "f(\"é\", 1)\nlet xs = []";
+    let tokens = lex(source);
+    let end_of = |token: &Token| common::fileposition::FilePosition {
+        col: token.col() + token.value.width(),
+        ..token.position()
+    };
+    let swap = Fix {
+        msg: "reorder the arguments".into(),
+        start: tokens[2].position(),
+        end: end_of(&tokens[4]),
+        replacement: format!(
+            "{}, {}",
+            common::rewrite::source_text(&tokens[4..5]),
+            common::rewrite::source_text(&tokens[2..3])
+        ),
+    };
+    let annotate = Fix::insert("annotate the list", end_of(tokens.last().unwrap()), ": Int");
+    // a fix overlapping one before it is left out
+    let overlapping = Fix::insert("insert ';'", tokens[4].position(), ";");
+    assert_eq!(
+        apply_fixes(source, &[swap, annotate, overlapping]),
+        "f(1, \"é\")\nlet xs = []: Int"
+    );
+}
+
+#[test]
+fn sized_integers_wrap_at_their_width() {
+    use common::ttype::Width;
+
+    assert_eq!(Width::U8.wrap(300), 44);
+    assert_eq!(Width::I8.wrap(128), -128);
+    assert_eq!(Width::U16.wrap(-1), 65535);
+    // a U64 keeps its bits in the i64
+    assert_eq!(Width::U64.wrap(-1), -1);
+    assert_eq!(Width::U64.value(-1), u64::MAX as i128);
+    assert!(Width::I32.fits(i32::MIN as i128) && !Width::I32.fits(i32::MAX as i128 + 1));
+    assert_eq!(Width::from_name("U32"), Some(Width::U32));
+    assert_eq!(Width::from_byte(Width::I16.to_byte()), Some(Width::I16));
+}
+
+#[test]
+fn doc_comments_go_on_the_next_token() {
+    let tokens = lex("/// first\n///second\n// plain\nfn f //// not a doc\nlet");
+    let docs: Vec<Option<&str>> = tokens.iter().map(|token| token.doc.as_deref()).collect();
+    assert_eq!(docs, [Some("first\nsecond"), None, None]);
+}
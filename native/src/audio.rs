@@ -0,0 +1,165 @@
+use std::{cell::RefCell, fs::File, io::BufReader, time::Duration};
+
+use common::error::NovaError;
+use rodio::{source::SineWave, Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use vm::state::{self, Heap, VmData};
+
+// the sound output, opened the first time something is played. every sound
+// file gets a sink of its own so they play over each other, tones queue up on
+// one sink so a melody plays note after note
+struct Audio {
+    // playback stops when the stream is dropped
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    tones: Sink,
+    sounds: Vec<Sink>,
+    volume: f32,
+}
+
+thread_local! {
+    static AUDIO: RefCell<Option<Audio>> = const { RefCell::new(None) };
+}
+
+fn pop_int(state: &mut state::State) -> Result<i64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Int(value)) => Ok(value),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected an integer".into(),
+        }),
+    }
+}
+
+fn pop_float(state: &mut state::State) -> Result<f64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Float(value)) => Ok(value),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a float".into(),
+        }),
+    }
+}
+
+fn pop_string(state: &mut state::State) -> Result<String, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::String(index)) => match state.get_ref(index) {
+            Heap::String(string) => Ok(string.to_string()),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a string".into(),
+            }),
+        },
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a string".into(),
+        }),
+    }
+}
+
+fn audio_error(error: impl std::fmt::Display) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Could not play audio: {}", error).into(),
+    }
+}
+
+fn with_audio<T>(play: impl FnOnce(&mut Audio) -> Result<T, NovaError>) -> Result<T, NovaError> {
+    AUDIO.with(|audio| {
+        let mut audio = audio.borrow_mut();
+        if audio.is_none() {
+            let (stream, handle) = OutputStream::try_default().map_err(audio_error)?;
+            let tones = Sink::try_new(&handle).map_err(audio_error)?;
+            *audio = Some(Audio {
+                _stream: stream,
+                handle,
+                tones,
+                sounds: vec![],
+                volume: 1.0,
+            });
+        }
+        play(audio.as_mut().unwrap())
+    })
+}
+
+// plays a wav file in the background
+pub fn play(state: &mut state::State) -> Result<(), NovaError> {
+    let path = pop_string(state)?;
+    let file = File::open(&path).map_err(|error| NovaError::Runtime {
+        msg: format!("Could not open {}: {}", path, error).into(),
+    })?;
+    let source = Decoder::new(BufReader::new(file)).map_err(|error| NovaError::Runtime {
+        msg: format!("Could not play {}: {}", path, error).into(),
+    })?;
+    with_audio(|audio| {
+        audio.sounds.retain(|sink| !sink.empty());
+        let sink = Sink::try_new(&audio.handle).map_err(audio_error)?;
+        sink.set_volume(audio.volume);
+        sink.append(source);
+        audio.sounds.push(sink);
+        Ok(())
+    })
+}
+
+fn queue_tone(frequency: f64, millis: i64) -> Result<(), NovaError> {
+    if frequency <= 0.0 || millis < 0 {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot play a tone of {} Hz for {} ms", frequency, millis).into(),
+        });
+    }
+    let tone = SineWave::new(frequency as f32)
+        .take_duration(Duration::from_millis(millis as u64))
+        // a full scale sine wave is unpleasantly loud
+        .amplify(0.25);
+    with_audio(|audio| {
+        audio.tones.append(tone);
+        Ok(())
+    })
+}
+
+// a sine wave of the frequency in Hz, played after the tones queued before it
+pub fn tone(state: &mut state::State) -> Result<(), NovaError> {
+    let millis = pop_int(state)?;
+    let frequency = pop_float(state)?;
+    queue_tone(frequency, millis)
+}
+
+pub fn beep(_state: &mut state::State) -> Result<(), NovaError> {
+    queue_tone(880.0, 150)
+}
+
+// from 0.0 for silence to 1.0 for full volume, for everything playing and
+// everything played later
+pub fn set_volume(state: &mut state::State) -> Result<(), NovaError> {
+    let volume = pop_float(state)?.clamp(0.0, 1.0) as f32;
+    with_audio(|audio| {
+        audio.volume = volume;
+        audio.tones.set_volume(volume);
+        for sink in audio.sounds.iter() {
+            sink.set_volume(volume);
+        }
+        Ok(())
+    })
+}
+
+// stops every sound and drops the tones still queued
+pub fn stop(_state: &mut state::State) -> Result<(), NovaError> {
+    AUDIO.with(|audio| {
+        if let Some(audio) = audio.borrow_mut().as_mut() {
+            audio.tones.clear();
+            audio.tones.play();
+            for sink in audio.sounds.drain(..) {
+                sink.stop();
+            }
+        }
+    });
+    Ok(())
+}
+
+// blocks until everything has finished playing, a script that exits right
+// away cuts its sounds off
+pub fn wait(_state: &mut state::State) -> Result<(), NovaError> {
+    AUDIO.with(|audio| {
+        if let Some(audio) = audio.borrow().as_ref() {
+            audio.tones.sleep_until_end();
+            for sink in audio.sounds.iter() {
+                sink.sleep_until_end();
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use common::error::NovaError;
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+use vm::state::{self, Heap, VmData};
+
+fn pop_bigint(state: &mut state::State) -> Result<BigInt, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::BigInt(index)) => Ok(state.get_bigint(index)?.clone()),
+        Some(_) => Err(NovaError::Runtime {
+            msg: "Expected a BigInt on the stack".into(),
+        }),
+        None => Err(NovaError::Runtime {
+            msg: "Stack is empty".into(),
+        }),
+    }
+}
+
+fn pop_bigints(state: &mut state::State) -> Result<(BigInt, BigInt), NovaError> {
+    let rhs = pop_bigint(state)?;
+    let lhs = pop_bigint(state)?;
+    Ok((lhs, rhs))
+}
+
+fn push_bigint(state: &mut state::State, value: BigInt) {
+    let index = state.allocate_bigint(value);
+    state.stack.push(VmData::BigInt(index));
+}
+
+fn arithmetic(
+    state: &mut state::State,
+    operation: fn(BigInt, BigInt) -> BigInt,
+) -> Result<(), NovaError> {
+    let (lhs, rhs) = pop_bigints(state)?;
+    push_bigint(state, operation(lhs, rhs));
+    Ok(())
+}
+
+// division and remainder round toward zero like Int's
+fn division(
+    state: &mut state::State,
+    operation: fn(BigInt, BigInt) -> BigInt,
+) -> Result<(), NovaError> {
+    let (lhs, rhs) = pop_bigints(state)?;
+    if rhs.is_zero() {
+        return Err(NovaError::Runtime {
+            msg: "Integer division by zero".into(),
+        });
+    }
+    push_bigint(state, operation(lhs, rhs));
+    Ok(())
+}
+
+fn comparison(
+    state: &mut state::State,
+    operation: fn(&BigInt, &BigInt) -> bool,
+) -> Result<(), NovaError> {
+    let (lhs, rhs) = pop_bigints(state)?;
+    state.stack.push(VmData::Bool(operation(&lhs, &rhs)));
+    Ok(())
+}
+
+pub fn from_int(state: &mut state::State) -> Result<(), NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Int(value)) => {
+            push_bigint(state, BigInt::from(value));
+            Ok(())
+        }
+        _ => Err(NovaError::Runtime {
+            msg: "Expected an integer on the stack".into(),
+        }),
+    }
+}
+
+// None when the string is not a whole number in base 10
+pub fn parse(state: &mut state::State) -> Result<(), NovaError> {
+    let Some(VmData::String(index)) = state.stack.pop() else {
+        return Err(NovaError::Runtime {
+            msg: "Expected a string on the stack".into(),
+        });
+    };
+    let Heap::String(text) = state.get_ref(index) else {
+        return Err(NovaError::Runtime {
+            msg: "Expected a string in the heap".into(),
+        });
+    };
+    match text.trim().parse::<BigInt>() {
+        Ok(value) => push_bigint(state, value),
+        Err(_) => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+// None when the value does not fit in an Int
+pub fn to_int(state: &mut state::State) -> Result<(), NovaError> {
+    let value = pop_bigint(state)?;
+    match value.to_i64() {
+        Some(value) => state.stack.push(VmData::Int(value)),
+        None => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+pub fn pow(state: &mut state::State) -> Result<(), NovaError> {
+    let Some(VmData::Int(exponent)) = state.stack.pop() else {
+        return Err(NovaError::Runtime {
+            msg: "Expected an integer on the stack".into(),
+        });
+    };
+    let Ok(exponent) = u32::try_from(exponent) else {
+        return Err(NovaError::Runtime {
+            msg: format!("BigInt::pow needs an exponent from 0 to {}", u32::MAX).into(),
+        });
+    };
+    let base = pop_bigint(state)?;
+    push_bigint(state, base.pow(exponent));
+    Ok(())
+}
+
+// base ^ exponent % modulus without building the full power, the result has
+// the sign of the modulus
+pub fn mod_pow(state: &mut state::State) -> Result<(), NovaError> {
+    let modulus = pop_bigint(state)?;
+    let (base, exponent) = pop_bigints(state)?;
+    if modulus.is_zero() {
+        return Err(NovaError::Runtime {
+            msg: "Integer division by zero".into(),
+        });
+    }
+    if exponent < BigInt::zero() {
+        return Err(NovaError::Runtime {
+            msg: "BigInt::modPow needs an exponent of 0 or more".into(),
+        });
+    }
+    push_bigint(state, base.modpow(&exponent, &modulus));
+    Ok(())
+}
+
+pub fn add(state: &mut state::State) -> Result<(), NovaError> {
+    arithmetic(state, |lhs, rhs| lhs + rhs)
+}
+
+pub fn sub(state: &mut state::State) -> Result<(), NovaError> {
+    arithmetic(state, |lhs, rhs| lhs - rhs)
+}
+
+pub fn mul(state: &mut state::State) -> Result<(), NovaError> {
+    arithmetic(state, |lhs, rhs| lhs * rhs)
+}
+
+pub fn div(state: &mut state::State) -> Result<(), NovaError> {
+    division(state, |lhs, rhs| lhs / rhs)
+}
+
+pub fn rem(state: &mut state::State) -> Result<(), NovaError> {
+    division(state, |lhs, rhs| lhs % rhs)
+}
+
+pub fn neg(state: &mut state::State) -> Result<(), NovaError> {
+    let value = pop_bigint(state)?;
+    push_bigint(state, -value);
+    Ok(())
+}
+
+pub fn lt(state: &mut state::State) -> Result<(), NovaError> {
+    comparison(state, |lhs, rhs| lhs < rhs)
+}
+
+pub fn le(state: &mut state::State) -> Result<(), NovaError> {
+    comparison(state, |lhs, rhs| lhs <= rhs)
+}
+
+pub fn gt(state: &mut state::State) -> Result<(), NovaError> {
+    comparison(state, |lhs, rhs| lhs > rhs)
+}
+
+pub fn ge(state: &mut state::State) -> Result<(), NovaError> {
+    comparison(state, |lhs, rhs| lhs >= rhs)
+}
@@ -0,0 +1,183 @@
+use common::{error::NovaError, ttype::Width};
+use num_bigint::BigInt;
+use vm::state::{self, Heap, VmData};
+
+// the binary form of a nova value, a tag byte followed by the payload with
+// numbers in little endian. structs and enums are lists whose first field is
+// the type name, so they need no tag of their own. the encoding carries no
+// types, the reader has to expect what the writer sent
+const NONE: u8 = 0;
+const INT: u8 = 1;
+const FLOAT: u8 = 2;
+const BOOL: u8 = 3;
+const CHAR: u8 = 4;
+const STRING: u8 = 5;
+const LIST: u8 = 6;
+const BIGINT: u8 = 7;
+const SIZED: u8 = 8;
+
+fn length(bytes: &mut Vec<u8>, length: usize) {
+    bytes.extend_from_slice(&(length as u32).to_le_bytes());
+}
+
+fn encode_value(state: &state::State, value: VmData, bytes: &mut Vec<u8>) -> Result<(), NovaError> {
+    match value {
+        VmData::None(_) => bytes.push(NONE),
+        VmData::Int(value) => {
+            bytes.push(INT);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        VmData::Sized(value, width) => {
+            bytes.push(SIZED);
+            bytes.push(Width::ALL.iter().position(|w| *w == width).unwrap() as u8);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        VmData::Float(value) => {
+            bytes.push(FLOAT);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        VmData::Bool(value) => bytes.extend_from_slice(&[BOOL, value as u8]),
+        VmData::Char(value) => {
+            bytes.push(CHAR);
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        VmData::String(index) => {
+            let Heap::String(string) = state.get_ref(index) else {
+                return Err(NovaError::Runtime {
+                    msg: "Expected a string".into(),
+                });
+            };
+            bytes.push(STRING);
+            length(bytes, string.len());
+            bytes.extend_from_slice(string.as_bytes());
+        }
+        VmData::BigInt(index) => {
+            let value = state.get_bigint(index)?.to_signed_bytes_le();
+            bytes.push(BIGINT);
+            length(bytes, value.len());
+            bytes.extend_from_slice(&value);
+        }
+        VmData::List(index) | VmData::Struct(index) => {
+            let Heap::List(items) = state.get_ref(index) else {
+                return Err(NovaError::Runtime {
+                    msg: "Expected a list".into(),
+                });
+            };
+            bytes.push(LIST);
+            length(bytes, items.len());
+            for item in items.iter() {
+                encode_value(state, state.to_vmdata(*item), bytes)?;
+            }
+        }
+        VmData::Function(_) | VmData::Closure(_) | VmData::StackAddress(_) => {
+            return Err(NovaError::Runtime {
+                msg: "Cannot encode a function, only data can be sent".into(),
+            })
+        }
+    }
+    Ok(())
+}
+
+pub fn encode(state: &state::State, value: VmData) -> Result<Vec<u8>, NovaError> {
+    let mut bytes = vec![];
+    encode_value(state, value, &mut bytes)?;
+    Ok(bytes)
+}
+
+// Cast::hash, fnv-1a over the binary form so equal values hash the same
+pub fn hash(state: &mut state::State) -> Result<(), NovaError> {
+    let value = state.stack.pop().ok_or_else(|| NovaError::Runtime {
+        msg: "Stack is empty".into(),
+    })?;
+    let hash = encode(state, value)?
+        .iter()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+    state.stack.push(VmData::Int(hash as i64));
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const LEN: usize>(&mut self) -> Result<[u8; LEN], NovaError> {
+        self.slice(LEN).map(|bytes| bytes.try_into().unwrap())
+    }
+
+    fn slice(&mut self, length: usize) -> Result<&[u8], NovaError> {
+        if self.bytes.len() < length {
+            return Err(malformed("the value ends early"));
+        }
+        let (head, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn length(&mut self) -> Result<usize, NovaError> {
+        Ok(u32::from_le_bytes(self.take()?) as usize)
+    }
+}
+
+fn malformed(reason: &str) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Could not decode a value, {}", reason).into(),
+    }
+}
+
+fn decode_value(state: &mut state::State, reader: &mut Reader) -> Result<VmData, NovaError> {
+    let [tag] = reader.take()?;
+    Ok(match tag {
+        NONE => state.none(),
+        INT => VmData::Int(i64::from_le_bytes(reader.take()?)),
+        SIZED => {
+            let [width] = reader.take()?;
+            let width = *Width::ALL
+                .get(width as usize)
+                .ok_or_else(|| malformed("unknown integer width"))?;
+            VmData::Sized(i64::from_le_bytes(reader.take()?), width)
+        }
+        FLOAT => VmData::Float(f64::from_le_bytes(reader.take()?)),
+        BOOL => VmData::Bool(reader.take::<1>()?[0] != 0),
+        CHAR => VmData::Char(
+            char::from_u32(u32::from_le_bytes(reader.take()?))
+                .ok_or_else(|| malformed("invalid character"))?,
+        ),
+        STRING => {
+            let length = reader.length()?;
+            let string = std::str::from_utf8(reader.slice(length)?)
+                .map_err(|_| malformed("invalid utf-8 in a string"))?;
+            VmData::String(state.allocate_string(string.into()))
+        }
+        BIGINT => {
+            let length = reader.length()?;
+            let value = BigInt::from_signed_bytes_le(reader.slice(length)?);
+            VmData::BigInt(state.allocate_bigint(value))
+        }
+        LIST => {
+            let length = reader.length()?;
+            let mut items = Vec::with_capacity(length.min(reader.bytes.len()));
+            for _ in 0..length {
+                let item = decode_value(state, reader)?;
+                items.push(state.allocate_vmdata_to_heap(item));
+            }
+            VmData::List(state.allocate_array(items))
+        }
+        _ => return Err(malformed("unknown tag")),
+    })
+}
+
+// rebuilds a value written by encode on the heap
+pub fn decode(state: &mut state::State, bytes: &[u8]) -> Result<VmData, NovaError> {
+    let mut reader = Reader { bytes };
+    state.gclock = true;
+    let value = decode_value(state, &mut reader);
+    state.gclock = false;
+    let value = value?;
+    if !reader.bytes.is_empty() {
+        return Err(malformed("trailing bytes after the value"));
+    }
+    Ok(value)
+}
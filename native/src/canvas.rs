@@ -0,0 +1,422 @@
+use std::{cell::RefCell, collections::VecDeque};
+
+use common::error::NovaError;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use vm::state::{self, Heap, VmData};
+
+// the open window and the frame being drawn, shown by present. colors are
+// 0xRRGGBB Ints
+struct Canvas {
+    window: Window,
+    frame: Vec<u32>,
+    width: usize,
+    height: usize,
+    // keys that went down during the last present
+    pressed: Vec<Key>,
+    // what changed in the window, oldest first
+    events: VecDeque<WindowEvent>,
+    mouse: Option<(i64, i64)>,
+    buttons: [bool; 3],
+}
+
+// a change in the window seen by present, the Input module turns them into
+// nova events
+#[derive(Debug, Clone, Copy)]
+pub enum WindowEvent {
+    KeyDown(Key),
+    KeyUp(Key),
+    MouseMove(i64, i64),
+    MouseDown(MouseButton),
+    MouseUp(MouseButton),
+    Scroll(f32),
+}
+
+const MOUSE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Middle, MouseButton::Right];
+
+// events nobody reads are dropped, oldest first, past this many
+const EVENT_LIMIT: usize = 256;
+
+thread_local! {
+    static CANVAS: RefCell<Option<Canvas>> = const { RefCell::new(None) };
+}
+
+fn pop_int(state: &mut state::State) -> Result<i64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Int(value)) => Ok(value),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected an integer".into(),
+        }),
+    }
+}
+
+fn pop_string(state: &mut state::State) -> Result<String, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::String(index)) => match state.get_ref(index) {
+            Heap::String(string) => Ok(string.to_string()),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a string".into(),
+            }),
+        },
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a string".into(),
+        }),
+    }
+}
+
+fn with_canvas<T>(draw: impl FnOnce(&mut Canvas) -> T) -> Result<T, NovaError> {
+    CANVAS.with(|canvas| match canvas.borrow_mut().as_mut() {
+        Some(canvas) => Ok(draw(canvas)),
+        None => Err(NovaError::Runtime {
+            msg: "No canvas is open, call Canvas::open first".into(),
+        }),
+    })
+}
+
+impl Canvas {
+    fn record_events(&mut self) {
+        self.pressed = self.window.get_keys_pressed(KeyRepeat::No);
+        let mut events: Vec<WindowEvent> = self
+            .pressed
+            .iter()
+            .map(|key| WindowEvent::KeyDown(*key))
+            .collect();
+        events.extend(
+            self.window
+                .get_keys_released()
+                .into_iter()
+                .map(WindowEvent::KeyUp),
+        );
+        if let Some((x, y)) = self.window.get_mouse_pos(MouseMode::Discard) {
+            let position = (x as i64, y as i64);
+            if self.mouse != Some(position) {
+                self.mouse = Some(position);
+                events.push(WindowEvent::MouseMove(position.0, position.1));
+            }
+        }
+        for (down, button) in self.buttons.iter_mut().zip(MOUSE_BUTTONS) {
+            if self.window.get_mouse_down(button) != *down {
+                *down = !*down;
+                events.push(if *down {
+                    WindowEvent::MouseDown(button)
+                } else {
+                    WindowEvent::MouseUp(button)
+                });
+            }
+        }
+        if let Some((_, lines)) = self.window.get_scroll_wheel() {
+            events.push(WindowEvent::Scroll(lines));
+        }
+        self.events.extend(events);
+        let excess = self.events.len().saturating_sub(EVENT_LIMIT);
+        self.events.drain(..excess);
+    }
+
+    // pixels outside the frame are skipped
+    fn fill(&mut self, x: i64, y: i64, width: i64, height: i64, color: i64) {
+        let left = x.clamp(0, self.width as i64) as usize;
+        let right = x.saturating_add(width).clamp(0, self.width as i64) as usize;
+        let top = y.clamp(0, self.height as i64) as usize;
+        let bottom = y.saturating_add(height).clamp(0, self.height as i64) as usize;
+        if left >= right {
+            return;
+        }
+        for row in top..bottom {
+            self.frame[row * self.width + left..row * self.width + right].fill(color as u32);
+        }
+    }
+
+    fn text(&mut self, x: i64, y: i64, text: &str, color: i64) {
+        for (index, character) in text.chars().enumerate() {
+            let glyph = match character {
+                ' '..='~' => FONT[character as usize - ' ' as usize],
+                _ => FONT['?' as usize - ' ' as usize],
+            };
+            let left = x.saturating_add(index as i64 * GLYPH_ADVANCE);
+            for (column, bits) in glyph.iter().enumerate() {
+                for row in 0..7 {
+                    if bits & (1 << row) != 0 {
+                        self.fill(left + column as i64, y + row, 1, 1, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// opens a window of the given size, replacing the one already open
+pub fn open(state: &mut state::State) -> Result<(), NovaError> {
+    let height = pop_int(state)?;
+    let width = pop_int(state)?;
+    let title = pop_string(state)?;
+    if width <= 0 || height <= 0 {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot open a {}x{} canvas", width, height).into(),
+        });
+    }
+    let (width, height) = (width as usize, height as usize);
+    let mut window =
+        Window::new(&title, width, height, WindowOptions::default()).map_err(|error| {
+            NovaError::Runtime {
+                msg: format!("Could not open a window: {}", error).into(),
+            }
+        })?;
+    window.set_target_fps(60);
+    CANVAS.with(|canvas| {
+        *canvas.borrow_mut() = Some(Canvas {
+            window,
+            frame: vec![0; width * height],
+            width,
+            height,
+            pressed: vec![],
+            events: VecDeque::new(),
+            mouse: None,
+            buttons: [false; 3],
+        })
+    });
+    Ok(())
+}
+
+pub fn close(_state: &mut state::State) -> Result<(), NovaError> {
+    CANVAS.with(|canvas| canvas.borrow_mut().take());
+    Ok(())
+}
+
+// false once the window was closed by the user or with Canvas::close
+pub fn is_open(state: &mut state::State) -> Result<(), NovaError> {
+    let open = CANVAS.with(|canvas| {
+        canvas
+            .borrow()
+            .as_ref()
+            .is_some_and(|canvas| canvas.window.is_open())
+    });
+    state.stack.push(VmData::Bool(open));
+    Ok(())
+}
+
+// shows the frame drawn so far and reads the input that arrived since the
+// last call, at most 60 times a second
+pub fn present(_state: &mut state::State) -> Result<(), NovaError> {
+    with_canvas(|canvas| {
+        let result = canvas
+            .window
+            .update_with_buffer(&canvas.frame, canvas.width, canvas.height);
+        canvas.record_events();
+        result
+    })?
+    .map_err(|error| NovaError::Runtime {
+        msg: format!("Could not draw the canvas: {}", error).into(),
+    })
+}
+
+// the oldest window event not read yet
+pub fn next_event() -> Option<WindowEvent> {
+    CANVAS.with(|canvas| {
+        canvas
+            .borrow_mut()
+            .as_mut()
+            .and_then(|canvas| canvas.events.pop_front())
+    })
+}
+
+pub fn clear(state: &mut state::State) -> Result<(), NovaError> {
+    let color = pop_int(state)?;
+    with_canvas(|canvas| canvas.frame.fill(color as u32))
+}
+
+pub fn pixel(state: &mut state::State) -> Result<(), NovaError> {
+    let color = pop_int(state)?;
+    let y = pop_int(state)?;
+    let x = pop_int(state)?;
+    with_canvas(|canvas| canvas.fill(x, y, 1, 1, color))
+}
+
+pub fn rect(state: &mut state::State) -> Result<(), NovaError> {
+    let color = pop_int(state)?;
+    let height = pop_int(state)?;
+    let width = pop_int(state)?;
+    let y = pop_int(state)?;
+    let x = pop_int(state)?;
+    with_canvas(|canvas| canvas.fill(x, y, width, height, color))
+}
+
+// text in a 5x7 pixel font with its top left corner at x, y
+pub fn text(state: &mut state::State) -> Result<(), NovaError> {
+    let color = pop_int(state)?;
+    let text = pop_string(state)?;
+    let y = pop_int(state)?;
+    let x = pop_int(state)?;
+    with_canvas(|canvas| canvas.text(x, y, &text, color))
+}
+
+// combines 0 to 255 channels into a color
+pub fn rgb(state: &mut state::State) -> Result<(), NovaError> {
+    let blue = pop_int(state)?.clamp(0, 255);
+    let green = pop_int(state)?.clamp(0, 255);
+    let red = pop_int(state)?.clamp(0, 255);
+    state
+        .stack
+        .push(VmData::Int((red << 16) | (green << 8) | blue));
+    Ok(())
+}
+
+// keys are named the way minifb names them: A, Key1, Space, Left, Escape
+pub fn key_down(state: &mut state::State) -> Result<(), NovaError> {
+    let name = pop_string(state)?;
+    let down = with_canvas(|canvas| {
+        canvas
+            .window
+            .get_keys()
+            .iter()
+            .any(|key| format!("{:?}", key) == name)
+    })?;
+    state.stack.push(VmData::Bool(down));
+    Ok(())
+}
+
+// the keys that went down before the last present, in the order they did
+pub fn keys_pressed(state: &mut state::State) -> Result<(), NovaError> {
+    let names: Vec<String> = with_canvas(|canvas| {
+        canvas
+            .pressed
+            .iter()
+            .map(|key| format!("{:?}", key))
+            .collect()
+    })?;
+    state.gclock = true;
+    let keys = names
+        .into_iter()
+        .map(|name| {
+            let string = state.allocate_string(name.into());
+            state.allocate_vmdata_to_heap(VmData::String(string))
+        })
+        .collect();
+    let index = state.allocate_array(keys);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
+
+fn mouse(state: &mut state::State, coordinate: fn((f32, f32)) -> f32) -> Result<(), NovaError> {
+    let position = with_canvas(|canvas| canvas.window.get_mouse_pos(MouseMode::Clamp))?;
+    let value = position.map_or(0, |position| coordinate(position) as i64);
+    state.stack.push(VmData::Int(value));
+    Ok(())
+}
+
+pub fn mouse_x(state: &mut state::State) -> Result<(), NovaError> {
+    mouse(state, |(x, _)| x)
+}
+
+pub fn mouse_y(state: &mut state::State) -> Result<(), NovaError> {
+    mouse(state, |(_, y)| y)
+}
+
+pub fn mouse_down(state: &mut state::State) -> Result<(), NovaError> {
+    let down = with_canvas(|canvas| canvas.window.get_mouse_down(MouseButton::Left))?;
+    state.stack.push(VmData::Bool(down));
+    Ok(())
+}
+
+// each character is 5 pixels wide with a column of space after it
+const GLYPH_ADVANCE: i64 = 6;
+
+// printable ascii from ' ' to '~', one byte per column with the top row in
+// the lowest bit
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x5F, 0x00, 0x00],
+    [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14],
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12],
+    [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50],
+    [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00],
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08],
+    [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00],
+    [0x08, 0x08, 0x08, 0x08, 0x08],
+    [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3E, 0x51, 0x49, 0x45, 0x3E],
+    [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46],
+    [0x21, 0x41, 0x45, 0x4B, 0x31],
+    [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39],
+    [0x3C, 0x4A, 0x49, 0x49, 0x30],
+    [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36],
+    [0x06, 0x49, 0x49, 0x29, 0x1E],
+    [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00],
+    [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08],
+    [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E],
+    [0x7F, 0x49, 0x49, 0x49, 0x36],
+    [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C],
+    [0x7F, 0x49, 0x49, 0x49, 0x41],
+    [0x7F, 0x09, 0x09, 0x01, 0x01],
+    [0x3E, 0x41, 0x41, 0x51, 0x32],
+    [0x7F, 0x08, 0x08, 0x08, 0x7F],
+    [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01],
+    [0x7F, 0x08, 0x14, 0x22, 0x41],
+    [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x04, 0x02, 0x7F],
+    [0x7F, 0x04, 0x08, 0x10, 0x7F],
+    [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06],
+    [0x3E, 0x41, 0x51, 0x21, 0x5E],
+    [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7F, 0x01, 0x01],
+    [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F],
+    [0x7F, 0x20, 0x18, 0x20, 0x7F],
+    [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x03, 0x04, 0x78, 0x04, 0x03],
+    [0x61, 0x51, 0x49, 0x45, 0x43],
+    [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20],
+    [0x00, 0x41, 0x41, 0x7F, 0x00],
+    [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00],
+    [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38],
+    [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18],
+    [0x08, 0x7E, 0x09, 0x01, 0x02],
+    [0x08, 0x14, 0x54, 0x54, 0x3C],
+    [0x7F, 0x08, 0x04, 0x04, 0x78],
+    [0x00, 0x44, 0x7D, 0x40, 0x00],
+    [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x00, 0x7F, 0x10, 0x28, 0x44],
+    [0x00, 0x41, 0x7F, 0x40, 0x00],
+    [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78],
+    [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C],
+    [0x7C, 0x08, 0x04, 0x04, 0x08],
+    [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20],
+    [0x3C, 0x40, 0x40, 0x20, 0x7C],
+    [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C],
+    [0x44, 0x28, 0x10, 0x28, 0x44],
+    [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44],
+    [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00],
+    [0x08, 0x04, 0x08, 0x10, 0x08],
+];
@@ -3,7 +3,14 @@ use vm::state::{self, VmData};
 
 pub fn int_to_char(state: &mut state::State) -> Result<(), NovaError> {
     match state.stack.pop() {
-        Some(VmData::Int(ch)) => state.stack.push(VmData::Char((ch as u8) as char)),
+        Some(VmData::Int(ch)) => match u32::try_from(ch).ok().and_then(char::from_u32) {
+            Some(ch) => state.stack.push(VmData::Char(ch)),
+            None => {
+                return Err(NovaError::Runtime {
+                    msg: format!("{} is not a unicode character", ch).into(),
+                })
+            }
+        },
         Some(_) => {
             return Err(NovaError::Runtime {
                 msg: "Expected an integer on the stack".into(),
@@ -17,3 +24,33 @@ pub fn int_to_char(state: &mut state::State) -> Result<(), NovaError> {
     }
     Ok(())
 }
+
+// a test on the character on top of the stack, these follow unicode so 'é'
+// is lower case and a letter
+fn char_test(state: &mut state::State, test: fn(&char) -> bool) -> Result<(), NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Char(ch)) => {
+            state.stack.push(VmData::Bool(test(&ch)));
+            Ok(())
+        }
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a character on the stack".into(),
+        }),
+    }
+}
+
+pub fn is_whitespace(state: &mut state::State) -> Result<(), NovaError> {
+    char_test(state, |ch| ch.is_whitespace())
+}
+
+pub fn is_upper(state: &mut state::State) -> Result<(), NovaError> {
+    char_test(state, |ch| ch.is_uppercase())
+}
+
+pub fn is_lower(state: &mut state::State) -> Result<(), NovaError> {
+    char_test(state, |ch| ch.is_lowercase())
+}
+
+pub fn is_alnum(state: &mut state::State) -> Result<(), NovaError> {
+    char_test(state, |ch| ch.is_alphanumeric())
+}
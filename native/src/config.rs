@@ -0,0 +1,104 @@
+use common::error::NovaError;
+use vm::state;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::json::{decode_with, Json};
+
+// toml and yaml read into the tree json is read into, so Toml::decode and
+// Yaml::decode take the same shapes and JsonValue holds any document
+
+pub fn decode_toml(state: &mut state::State) -> Result<(), NovaError> {
+    decode_with(state, parse_toml)
+}
+
+pub fn decode_yaml(state: &mut state::State) -> Result<(), NovaError> {
+    decode_with(state, parse_yaml)
+}
+
+fn parse_toml(text: &str) -> Result<Json, String> {
+    let table = text.parse::<::toml::Table>().map_err(|error| {
+        let message = error.message().trim().replace('\n', ", ");
+        match error.span() {
+            Some(span) => {
+                let before = &text[..span.start.min(text.len())];
+                let line = before.matches('\n').count() + 1;
+                let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+                format!("{} at line {} column {}", message, line, column)
+            }
+            None => message,
+        }
+    })?;
+    Ok(from_toml(::toml::Value::Table(table)))
+}
+
+fn from_toml(value: ::toml::Value) -> Json {
+    use ::toml::Value;
+    match value {
+        Value::String(text) => Json::String(text),
+        Value::Integer(int) => Json::Number(int.to_string()),
+        Value::Float(float) => Json::Number(float.to_string()),
+        Value::Boolean(value) => Json::Bool(value),
+        // dates are read as their text, 1979-05-27T07:32:00Z
+        Value::Datetime(date) => Json::String(date.to_string()),
+        Value::Array(items) => Json::Array(items.into_iter().map(from_toml).collect()),
+        Value::Table(table) => Json::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+// anchors are read as the value they name, a text with several documents
+// is refused rather than read in part
+fn parse_yaml(text: &str) -> Result<Json, String> {
+    let mut documents = YamlLoader::load_from_str(text).map_err(|error| {
+        let at = error.marker();
+        format!(
+            "{} at line {} column {}",
+            error.info(),
+            at.line(),
+            at.col() + 1
+        )
+    })?;
+    if documents.len() > 1 {
+        return Err("expected one document, found several".into());
+    }
+    documents.pop().map_or(Ok(Json::Null), from_yaml)
+}
+
+fn from_yaml(value: Yaml) -> Result<Json, String> {
+    Ok(match value {
+        Yaml::String(text) => Json::String(text),
+        Yaml::Integer(int) => Json::Number(int.to_string()),
+        // .inf and .nan are read as inf and NaN like they are in toml
+        Yaml::Real(_) => match value.as_f64() {
+            Some(float) => Json::Number(float.to_string()),
+            None => return Err("invalid number".into()),
+        },
+        Yaml::Boolean(value) => Json::Bool(value),
+        Yaml::Null => Json::Null,
+        Yaml::Array(items) => {
+            Json::Array(items.into_iter().map(from_yaml).collect::<Result<_, _>>()?)
+        }
+        Yaml::Hash(hash) => Json::Object(
+            hash.into_iter()
+                .map(|(key, value)| Ok((yaml_key(key)?, from_yaml(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Yaml::Alias(_) => return Err("unknown anchor".into()),
+        Yaml::BadValue => return Err("invalid value".into()),
+    })
+}
+
+// a json object has text for keys, so 8080: or true: are read as their text
+fn yaml_key(key: Yaml) -> Result<String, String> {
+    match key {
+        Yaml::String(text) | Yaml::Real(text) => Ok(text),
+        Yaml::Integer(int) => Ok(int.to_string()),
+        Yaml::Boolean(value) => Ok(value.to_string()),
+        Yaml::Null => Ok("null".into()),
+        _ => Err("a mapping key must be a scalar".into()),
+    }
+}
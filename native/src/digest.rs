@@ -0,0 +1,49 @@
+use common::error::NovaError;
+use hmac::{digest::KeyInit, Hmac, Mac};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use vm::state;
+
+use crate::str::{pop_bytes, push_bytes};
+
+// the digest of a [U8], std/hash.nv wraps these
+fn digest<D: Digest>(state: &mut state::State) -> Result<(), NovaError> {
+    let data = pop_bytes(state)?;
+    push_bytes(state, &D::digest(&data));
+    Ok(())
+}
+
+// the signature of a message with a key
+fn sign<M: Mac + KeyInit>(state: &mut state::State) -> Result<(), NovaError> {
+    let message = pop_bytes(state)?;
+    let key = pop_bytes(state)?;
+    let mut mac = <M as KeyInit>::new_from_slice(&key).expect("hmac takes keys of any length");
+    mac.update(&message);
+    push_bytes(state, &mac.finalize().into_bytes());
+    Ok(())
+}
+
+pub fn sha256(state: &mut state::State) -> Result<(), NovaError> {
+    digest::<Sha256>(state)
+}
+
+pub fn sha1(state: &mut state::State) -> Result<(), NovaError> {
+    digest::<Sha1>(state)
+}
+
+pub fn md5(state: &mut state::State) -> Result<(), NovaError> {
+    digest::<Md5>(state)
+}
+
+pub fn hmac_sha256(state: &mut state::State) -> Result<(), NovaError> {
+    sign::<Hmac<Sha256>>(state)
+}
+
+pub fn hmac_sha1(state: &mut state::State) -> Result<(), NovaError> {
+    sign::<Hmac<Sha1>>(state)
+}
+
+pub fn hmac_md5(state: &mut state::State) -> Result<(), NovaError> {
+    sign::<Hmac<Md5>>(state)
+}
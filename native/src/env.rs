@@ -0,0 +1,56 @@
+use std::env;
+
+use common::error::NovaError;
+use vm::state::{self, VmData};
+
+use crate::str::{pop_string, push_string};
+
+// None when the variable is not set or is not valid unicode
+pub fn get(state: &mut state::State) -> Result<(), NovaError> {
+    let name = pop_string(state)?;
+    match env::var(&*name) {
+        Ok(value) => push_string(state, value),
+        Err(_) => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+// for this process and the ones it starts from now on
+pub fn set(state: &mut state::State) -> Result<(), NovaError> {
+    let value = pop_string(state)?;
+    let name = pop_string(state)?;
+    if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot set the environment variable '{name}'").into(),
+        });
+    }
+    env::set_var(&*name, &*value);
+    Ok(())
+}
+
+// (name, value) of every variable, sorted by name
+pub fn vars(state: &mut state::State) -> Result<(), NovaError> {
+    let mut vars: Vec<(String, String)> = env::vars_os()
+        .map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    vars.sort();
+    state.gclock = true;
+    let mut items = vec![];
+    for (name, value) in vars {
+        let pair = [name, value].map(|text| {
+            let index = state.allocate_string(text.into());
+            state.allocate_vmdata_to_heap(VmData::String(index))
+        });
+        let pair = state.allocate_array(pair.to_vec());
+        items.push(state.allocate_vmdata_to_heap(VmData::List(pair)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
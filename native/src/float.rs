@@ -2,6 +2,27 @@ use common::error::NovaError;
 use vm::state::Heap;
 use vm::state::{self, VmData};
 
+use crate::{
+    int::group_digits,
+    str::{pop_int, pop_string, push_string},
+};
+
+fn pop_float(state: &mut state::State) -> Result<f64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Float(value)) => Ok(value),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a float on the stack".into(),
+        }),
+    }
+}
+
+fn pop_decimals(state: &mut state::State) -> Result<usize, NovaError> {
+    let decimals = pop_int(state)?;
+    usize::try_from(decimals).map_err(|_| NovaError::Runtime {
+        msg: format!("Cannot show {} decimals", decimals).into(),
+    })
+}
+
 pub fn int_to_float(state: &mut state::State) -> Result<(), NovaError> {
     let data = match state.stack.pop() {
         Some(data) => data,
@@ -25,7 +46,7 @@ pub fn int_to_float(state: &mut state::State) -> Result<(), NovaError> {
             if let Ok(parsed) = value.to_string().parse::<f64>() {
                 parsed
             } else {
-                state.stack.push(VmData::None);
+                state.stack.push(state.none());
                 return Ok(());
             }
         }
@@ -34,19 +55,121 @@ pub fn int_to_float(state: &mut state::State) -> Result<(), NovaError> {
                 if let Ok(parsed) = str.parse::<f64>() {
                     parsed
                 } else {
-                    state.stack.push(VmData::None);
+                    state.stack.push(state.none());
                     return Ok(());
                 }
             } else {
-                state.stack.push(VmData::None);
+                state.stack.push(state.none());
                 return Ok(());
             }
         }
         _ => {
-            state.stack.push(VmData::None);
+            state.stack.push(state.none());
             return Ok(());
         }
     };
     state.stack.push(VmData::Float(float));
     Ok(())
 }
+
+// rounded to a number of decimals, toFixed(0) has no decimal point
+pub fn to_fixed(state: &mut state::State) -> Result<(), NovaError> {
+    let decimals = pop_decimals(state)?;
+    let value = pop_float(state)?;
+    state.gclock = true;
+    push_string(state, format!("{:.*}", decimals, value));
+    state.gclock = false;
+    Ok(())
+}
+
+// like toFixed with a separator between each group of three digits
+pub fn to_grouped(state: &mut state::State) -> Result<(), NovaError> {
+    let separator = pop_string(state)?;
+    let decimals = pop_decimals(state)?;
+    let value = pop_float(state)?;
+    let text = format!("{:.*}", decimals, value);
+    let text = if value.is_finite() {
+        group_digits(&text, &separator)
+    } else {
+        text
+    };
+    state.gclock = true;
+    push_string(state, text);
+    state.gclock = false;
+    Ok(())
+}
+
+// scientific notation with a number of decimals, 1234.5 is 1.23e3 with two
+pub fn to_exponent(state: &mut state::State) -> Result<(), NovaError> {
+    let decimals = pop_decimals(state)?;
+    let value = pop_float(state)?;
+    state.gclock = true;
+    push_string(state, format!("{:.*e}", decimals, value));
+    state.gclock = false;
+    Ok(())
+}
+
+fn float_test(state: &mut state::State, test: fn(f64) -> bool) -> Result<(), NovaError> {
+    let value = pop_float(state)?;
+    state.stack.push(VmData::Bool(test(value)));
+    Ok(())
+}
+
+pub fn is_nan(state: &mut state::State) -> Result<(), NovaError> {
+    float_test(state, f64::is_nan)
+}
+
+// true for both infinities, false for NaN
+pub fn is_infinite(state: &mut state::State) -> Result<(), NovaError> {
+    float_test(state, f64::is_infinite)
+}
+
+pub fn is_finite(state: &mut state::State) -> Result<(), NovaError> {
+    float_test(state, f64::is_finite)
+}
+
+// NaN stays NaN. bounds that are NaN or the wrong way round are an error
+// instead of a value outside them
+pub fn clamp(state: &mut state::State) -> Result<(), NovaError> {
+    let max = pop_float(state)?;
+    let min = pop_float(state)?;
+    let value = pop_float(state)?;
+    if min.is_nan() || max.is_nan() || min > max {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot clamp between {} and {}", min, max).into(),
+        });
+    }
+    state.stack.push(VmData::Float(value.clamp(min, max)));
+    Ok(())
+}
+
+// a at t = 0 and b at t = 1 exactly, t outside 0 to 1 goes past them
+pub fn lerp(state: &mut state::State) -> Result<(), NovaError> {
+    let t = pop_float(state)?;
+    let b = pop_float(state)?;
+    let a = pop_float(state)?;
+    state.stack.push(VmData::Float((1.0 - t) * a + t * b));
+    Ok(())
+}
+
+// the float nearest to the value rounded to a number of decimals. the exact
+// value of the float is rounded, so 2.675 is 2.67 because the float is a
+// little below it, and a value exactly halfway goes to the even digit
+pub fn round_to(state: &mut state::State) -> Result<(), NovaError> {
+    let places = pop_int(state)?;
+    let value = pop_float(state)?;
+    let Ok(places) = usize::try_from(places) else {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot round to {} decimal places", places).into(),
+        });
+    };
+    // the smallest float is about 5e-324, so rounding to 350 places or more
+    // changes nothing
+    let rounded = if value.is_finite() && places < 350 {
+        format!("{:.*}", places, value).parse().unwrap_or(value)
+    } else {
+        value
+    };
+    state.stack.push(VmData::Float(rounded));
+    Ok(())
+}
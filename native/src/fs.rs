@@ -0,0 +1,130 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use common::error::NovaError;
+use vm::state::{self, Heap, VmData};
+
+fn pop_handle(state: &mut state::State) -> Result<i64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Int(handle)) if state.files.contains_key(&handle) => Ok(handle),
+        Some(VmData::Int(_)) => Err(NovaError::Runtime {
+            msg: "File is already closed".into(),
+        }),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a file handle".into(),
+        }),
+    }
+}
+
+fn pop_string(state: &mut state::State) -> Result<String, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::String(index)) => match state.get_ref(index) {
+            Heap::String(string) => Ok(string.to_string()),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a string".into(),
+            }),
+        },
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a string".into(),
+        }),
+    }
+}
+
+// opens a file for reading and appending, it is created if missing
+pub fn open(state: &mut state::State) -> Result<(), NovaError> {
+    let path = pop_string(state)?;
+    let file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+        .map_err(|e| NovaError::Runtime {
+            msg: format!("Error opening file {path}: {e}").into(),
+        })?;
+    let handle = state.add_file(file);
+    state.stack.push(VmData::Int(handle));
+    Ok(())
+}
+
+// the whole file, whatever was read before
+pub fn read(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    let mut file = &*state.files[&handle];
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.read_to_string(&mut contents))
+        .map_err(|e| NovaError::Runtime {
+            msg: format!("Error reading file: {e}").into(),
+        })?;
+    let index = state.allocate_string(contents.into());
+    state.stack.push(VmData::String(index));
+    Ok(())
+}
+
+pub fn write(state: &mut state::State) -> Result<(), NovaError> {
+    let text = pop_string(state)?;
+    let handle = pop_handle(state)?;
+    let mut file = &*state.files[&handle];
+    file.write_all(text.as_bytes())
+        .map_err(|e| NovaError::Runtime {
+            msg: format!("Error writing file: {e}").into(),
+        })
+}
+
+pub fn close(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    state.files.remove(&handle);
+    Ok(())
+}
+
+// (path, name, isDir, size, modified) for each entry of a directory, sorted by name.
+// links are not followed, so a link to a directory is not a directory here
+// and walking the tree cannot go around in circles
+pub fn list_dir(state: &mut state::State) -> Result<(), NovaError> {
+    let path = pop_string(state)?;
+    let error = |e: std::io::Error| NovaError::Runtime {
+        msg: format!("Error reading directory {path}: {e}").into(),
+    };
+    let mut entries = vec![];
+    for entry in fs::read_dir(&path).map_err(error)? {
+        let entry = entry.map_err(error)?;
+        let metadata = entry.metadata().map_err(error)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |time| time.as_secs() as i64);
+        entries.push((
+            entry.file_name(),
+            metadata.is_dir(),
+            metadata.len() as i64,
+            modified,
+        ));
+    }
+    entries.sort();
+    state.gclock = true;
+    let mut items = vec![];
+    for (name, is_dir, size, modified) in entries {
+        let full = Path::new(&path).join(&name).to_string_lossy().into();
+        let full = state.allocate_string(full);
+        let name = state.allocate_string(name.to_string_lossy().into());
+        let fields = [
+            VmData::String(full),
+            VmData::String(name),
+            VmData::Bool(is_dir),
+            VmData::Int(size),
+            VmData::Int(modified),
+        ]
+        .map(|field| state.allocate_vmdata_to_heap(field));
+        let entry = state.allocate_array(fields.to_vec());
+        items.push(state.allocate_vmdata_to_heap(VmData::List(entry)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::cell::RefCell;
+
+use common::error::NovaError;
+use gilrs::{EventType, Gilrs};
+use vm::state::{self, VmData};
+
+use crate::canvas::{self, WindowEvent};
+
+thread_local! {
+    // None until the first poll, and when gamepads cannot be read at all
+    static GAMEPADS: RefCell<Option<Option<Gilrs>>> = const { RefCell::new(None) };
+}
+
+// an event as std/input.nv reads it: kind, key or button name, x or gamepad
+// id, y and an axis or scroll value
+type Raw = (&'static str, String, i64, i64, f64);
+
+fn window_event(event: WindowEvent) -> Raw {
+    match event {
+        WindowEvent::KeyDown(key) => ("KeyDown", format!("{:?}", key), 0, 0, 0.0),
+        WindowEvent::KeyUp(key) => ("KeyUp", format!("{:?}", key), 0, 0, 0.0),
+        WindowEvent::MouseMove(x, y) => ("MouseMove", String::new(), x, y, 0.0),
+        WindowEvent::MouseDown(button) => ("MouseDown", format!("{:?}", button), 0, 0, 0.0),
+        WindowEvent::MouseUp(button) => ("MouseUp", format!("{:?}", button), 0, 0, 0.0),
+        WindowEvent::Scroll(lines) => ("Scroll", String::new(), 0, 0, lines as f64),
+    }
+}
+
+fn gamepad_event() -> Option<Raw> {
+    GAMEPADS.with(|gamepads| {
+        let mut gamepads = gamepads.borrow_mut();
+        let gamepads = gamepads.get_or_insert_with(|| Gilrs::new().ok()).as_mut()?;
+        while let Some(event) = gamepads.next_event() {
+            let id = usize::from(event.id) as i64;
+            let raw = match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    ("ButtonDown", format!("{:?}", button), id, 0, 0.0)
+                }
+                EventType::ButtonReleased(button, _) => {
+                    ("ButtonUp", format!("{:?}", button), id, 0, 0.0)
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    ("Axis", format!("{:?}", axis), id, 0, value as f64)
+                }
+                EventType::Connected => ("Connected", String::new(), id, 0, 0.0),
+                EventType::Disconnected => ("Disconnected", String::new(), id, 0, 0.0),
+                _ => continue,
+            };
+            return Some(raw);
+        }
+        None
+    })
+}
+
+// the next keyboard, mouse or gamepad event, window events arrive when
+// Canvas::present runs
+pub fn poll(state: &mut state::State) -> Result<(), NovaError> {
+    let Some((kind, name, x, y, value)) = canvas::next_event()
+        .map(window_event)
+        .or_else(gamepad_event)
+    else {
+        state.stack.push(state.none());
+        return Ok(());
+    };
+    state.gclock = true;
+    let kind = state.allocate_string(kind.into());
+    let name = state.allocate_string(name.into());
+    let fields = [
+        VmData::String(kind),
+        VmData::String(name),
+        VmData::Int(x),
+        VmData::Int(y),
+        VmData::Float(value),
+    ]
+    .map(|field| state.allocate_vmdata_to_heap(field));
+    let index = state.allocate_array(fields.to_vec());
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use common::error::NovaError;
+use vm::state::{self, VmData};
+
+use crate::str::{pop_int, pop_string, push_string};
+
+fn pop_ints(state: &mut state::State) -> Result<(i64, i64), NovaError> {
+    match (state.stack.pop(), state.stack.pop()) {
+        (Some(VmData::Int(rhs)), Some(VmData::Int(lhs))) => Ok((lhs, rhs)),
+        (Some(_), Some(_)) => Err(NovaError::Runtime {
+            msg: "Expected two integers on the stack".into(),
+        }),
+        _ => Err(NovaError::Runtime {
+            msg: "Stack is empty".into(),
+        }),
+    }
+}
+
+// pushes None when the operation overflows
+fn checked(
+    state: &mut state::State,
+    operation: fn(i64, i64) -> Option<i64>,
+) -> Result<(), NovaError> {
+    let (lhs, rhs) = pop_ints(state)?;
+    match operation(lhs, rhs) {
+        Some(value) => state.stack.push(VmData::Int(value)),
+        None => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+// operations that always give an Int, saturating ones clamp to Int's minimum
+// or maximum when they overflow and wrapping ones keep the low 64 bits
+fn binary(state: &mut state::State, operation: fn(i64, i64) -> i64) -> Result<(), NovaError> {
+    let (lhs, rhs) = pop_ints(state)?;
+    state.stack.push(VmData::Int(operation(lhs, rhs)));
+    Ok(())
+}
+
+pub fn checked_add(state: &mut state::State) -> Result<(), NovaError> {
+    checked(state, i64::checked_add)
+}
+
+pub fn checked_sub(state: &mut state::State) -> Result<(), NovaError> {
+    checked(state, i64::checked_sub)
+}
+
+pub fn checked_mul(state: &mut state::State) -> Result<(), NovaError> {
+    checked(state, i64::checked_mul)
+}
+
+pub fn saturating_add(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, i64::saturating_add)
+}
+
+pub fn saturating_sub(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, i64::saturating_sub)
+}
+
+pub fn saturating_mul(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, i64::saturating_mul)
+}
+
+pub fn wrapping_add(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, i64::wrapping_add)
+}
+
+pub fn wrapping_sub(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, i64::wrapping_sub)
+}
+
+pub fn wrapping_mul(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, i64::wrapping_mul)
+}
+
+// a | b and a & b on Ints
+pub fn bit_or(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, |a, b| a | b)
+}
+
+pub fn bit_and(state: &mut state::State) -> Result<(), NovaError> {
+    binary(state, |a, b| a & b)
+}
+
+// the digits with a separator between each group of three from the right,
+// any sign or decimals are left alone
+pub(crate) fn group_digits(number: &str, separator: &str) -> String {
+    let (sign, rest) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    let (whole, decimals) = match rest.find('.') {
+        Some(at) => rest.split_at(at),
+        None => (rest, ""),
+    };
+    let mut grouped = String::from(sign);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(decimals);
+    grouped
+}
+
+fn check_radix(radix: i64) -> Result<u32, NovaError> {
+    if (2..=36).contains(&radix) {
+        Ok(radix as u32)
+    } else {
+        Err(NovaError::Runtime {
+            msg: format!("A radix must be between 2 and 36, not {}", radix).into(),
+        })
+    }
+}
+
+// digits in any base from 2 to 36 with lowercase letters, negatives get a -
+pub fn to_string_radix(state: &mut state::State) -> Result<(), NovaError> {
+    let radix = pop_int(state)?;
+    let value = pop_int(state)?;
+    check_radix(radix)?;
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = vec![];
+    loop {
+        let digit = (magnitude % radix as u64) as u32;
+        digits.push(char::from_digit(digit, radix as u32).unwrap_or('?'));
+        magnitude /= radix as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push('-');
+    }
+    state.gclock = true;
+    push_string(state, digits.iter().rev().collect::<String>());
+    state.gclock = false;
+    Ok(())
+}
+
+// a whole number in a base from 2 to 36, letters in either case. a sign can
+// come first and then 0x, 0o or 0b when the base is 16, 8 or 2. None when
+// anything else is in the text or the number does not fit in an Int
+pub fn parse(state: &mut state::State) -> Result<(), NovaError> {
+    let radix = check_radix(pop_int(state)?)?;
+    let text = pop_string(state)?;
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(&text)),
+    };
+    let prefix = match radix {
+        16 => Some("0x"),
+        8 => Some("0o"),
+        2 => Some("0b"),
+        _ => None,
+    };
+    let digits = match (prefix, digits.get(..2)) {
+        (Some(prefix), Some(start)) if start.eq_ignore_ascii_case(prefix) => &digits[2..],
+        _ => digits,
+    };
+    // from_str_radix takes a sign of its own, which would allow two of them
+    let value = if digits.starts_with(['+', '-']) {
+        None
+    } else if negative {
+        i64::from_str_radix(&format!("-{digits}"), radix).ok()
+    } else {
+        i64::from_str_radix(digits, radix).ok()
+    };
+    match value {
+        Some(value) => state.stack.push(VmData::Int(value)),
+        None => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+// zeros in front up to the width, after the sign
+pub fn to_padded(state: &mut state::State) -> Result<(), NovaError> {
+    let width = pop_int(state)?;
+    let value = pop_int(state)?;
+    let width = width.max(0) as usize;
+    let text = if value < 0 {
+        format!("-{:0>1$}", value.unsigned_abs(), width.saturating_sub(1))
+    } else {
+        format!("{:0>1$}", value, width)
+    };
+    state.gclock = true;
+    push_string(state, text);
+    state.gclock = false;
+    Ok(())
+}
+
+pub fn to_grouped(state: &mut state::State) -> Result<(), NovaError> {
+    let separator = pop_string(state)?;
+    let value = pop_int(state)?;
+    state.gclock = true;
+    push_string(state, group_digits(&value.to_string(), &separator));
+    state.gclock = false;
+    Ok(())
+}
@@ -1,19 +1,45 @@
 //use common::error::{runtime_error, NovaError};
-use common::error::NovaError;
-use std::{fs, io, rc::Rc};
+use common::{
+    error::NovaError,
+    format::{self, Piece, Value},
+};
+use std::{fs, io};
 use vm::state::{self, Heap, VmData};
 
-pub fn read_line(state: &mut state::State) -> Result<(), NovaError> {
+use crate::str;
+
+// a line of stdin without its \n or \r\n, None once stdin has ended
+fn next_line() -> Result<Option<String>, NovaError> {
     let mut input = String::new();
-    io::stdin()
+    let read = io::stdin()
         .read_line(&mut input)
         .map_err(|e| NovaError::Runtime {
             msg: format!("Error reading line: {e}").into(),
         })?;
-    // removing newline token
-    input.pop();
-    let index = state.allocate_string(input.into());
-    state.stack.push(VmData::String(index));
+    if read == 0 {
+        return Ok(None);
+    }
+    if input.ends_with('\n') {
+        input.pop();
+        if input.ends_with('\r') {
+            input.pop();
+        }
+    }
+    Ok(Some(input))
+}
+
+// an empty string once stdin has ended
+pub fn read_line(state: &mut state::State) -> Result<(), NovaError> {
+    let input = next_line()?.unwrap_or_default();
+    str::push_string(state, input);
+    Ok(())
+}
+
+pub fn read_line_option(state: &mut state::State) -> Result<(), NovaError> {
+    match next_line()? {
+        Some(input) => str::push_string(state, input),
+        None => state.stack.push(state.none()),
+    }
     Ok(())
 }
 
@@ -36,131 +62,75 @@ pub fn read_file(state: &mut state::State) -> Result<(), NovaError> {
     Ok(())
 }
 
-fn printf_with_array(format_string: &str, args: Vec<Rc<str>>) {
-    let mut arg_iter = args.iter();
-    let mut formatted = String::new();
-    let mut segments = format_string.split("{}").peekable();
-
-    while let Some(segment) = segments.next() {
-        formatted.push_str(segment);
-        if segments.peek().is_some() {
-            if let Some(arg) = arg_iter.next() {
-                formatted.push_str(arg);
-            } else {
-                formatted.push_str("{}");
-            }
-        }
+fn format_error(reason: impl std::fmt::Display) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Invalid format string, {}", reason).into(),
     }
-
-    print!("{}", formatted);
 }
 
-fn format_with_array(format_string: &str, args: Vec<Rc<str>>) -> String {
-    let mut arg_iter = args.iter();
+// the format string on the stack with each placeholder replaced by the next
+// value of the list above it
+fn format_values(state: &mut state::State) -> Result<String, NovaError> {
+    let args = state.stack.pop();
+    let format_string = state.stack.pop();
+    let (Some(VmData::List(args)), Some(VmData::String(format_string))) = (args, format_string)
+    else {
+        return Err(NovaError::Runtime {
+            msg: "Invalid arguments for format".into(),
+        });
+    };
+    let pieces = format::parse(state.get_ref(format_string).get_string()).map_err(format_error)?;
+    let Heap::List(args) = state.get_ref(args) else {
+        return Err(NovaError::Runtime {
+            msg: "Expected a list of values to format".into(),
+        });
+    };
+    let values: Vec<VmData> = args.iter().map(|arg| state.to_vmdata(*arg)).collect();
+    let placeholders = pieces
+        .iter()
+        .filter(|piece| matches!(piece, Piece::Value(_)))
+        .count();
+    if placeholders != values.len() {
+        return Err(format_error(format!(
+            "it has {} placeholders but {} values were given",
+            placeholders,
+            values.len()
+        )));
+    }
+    let mut values = values.into_iter();
     let mut formatted = String::new();
-    let mut segments = format_string.split("{}").peekable();
-
-    while let Some(segment) = segments.next() {
-        formatted.push_str(segment);
-        if segments.peek().is_some() {
-            if let Some(arg) = arg_iter.next() {
-                formatted.push_str(arg);
-            } else {
-                formatted.push_str("{}");
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => formatted.push_str(&text),
+            Piece::Value(spec) => {
+                let text;
+                let value = match values.next().unwrap() {
+                    VmData::Int(value) => Value::Int(value as i128),
+                    VmData::Sized(value, width) => Value::Int(width.value(value)),
+                    VmData::Float(value) => Value::Float(value),
+                    other => {
+                        text = str::display(state, other)?;
+                        Value::Text(&text)
+                    }
+                };
+                formatted.push_str(&spec.render(value).map_err(format_error)?);
             }
         }
     }
-
-    formatted.to_string()
+    Ok(formatted)
 }
 
-// printf function for the VM that takes an array of strings // and the format string as arguments
+// prints the format string with the values of the list filled in
 pub fn printf(state: &mut state::State) -> Result<(), NovaError> {
-    let mut strings = vec![];
-    let args = state.stack.pop();
-    let str_index = state.stack.pop();
-
-    if let (Some(VmData::List(args)), Some(VmData::String(str_index))) = (args, str_index) {
-        if let Heap::String(format_string) = state.get_ref(str_index) {
-            if let Heap::List(args) = state.get_ref(args) {
-                // gather string arguments
-                for arg in args.iter() {
-                    if let Heap::StringAddress(string) = state.get_ref(*arg) {
-                        if let Heap::String(string) = state.get_ref(*string) {
-                            strings.push(string);
-                        } else {
-                            return Err(NovaError::Runtime {
-                                msg: "Invalid arguments for printf".into(),
-                            });
-                        }
-                    } else {
-                        return Err(NovaError::Runtime {
-                            msg: "Invalid arguments for printf".into(),
-                        });
-                    }
-                }
-                printf_with_array(format_string, strings.into_iter().cloned().collect());
-            } else {
-                return Err(NovaError::Runtime {
-                    msg: "Invalid arguments for printf".into(),
-                });
-            }
-        } else {
-            return Err(NovaError::Runtime {
-                msg: "Invalid arguments for printf".into(),
-            });
-        }
-    } else {
-        return Err(NovaError::Runtime {
-            msg: "Invalid arguments for printf".into(),
-        });
-    }
+    let formatted = format_values(state)?;
+    print!("{}", formatted);
     Ok(())
 }
 
-// format function for the VM that takes an array of strings and returns string
+// same as printf but returns the string
 pub fn format(state: &mut state::State) -> Result<(), NovaError> {
-    let mut strings = vec![];
-    let args = state.stack.pop();
-    let str_index = state.stack.pop();
-
-    if let (Some(VmData::List(args)), Some(VmData::String(str_index))) = (args, str_index) {
-        if let Heap::String(format_string) = state.get_ref(str_index) {
-            if let Heap::List(args) = state.get_ref(args) {
-                // gather string arguments
-                for arg in args.iter() {
-                    if let Heap::StringAddress(string) = state.get_ref(*arg) {
-                        if let Heap::String(string) = state.get_ref(*string) {
-                            strings.push(string);
-                        } else {
-                            return Err(NovaError::Runtime {
-                                msg: "Invalid arguments for printf".into(),
-                            });
-                        }
-                    } else {
-                        return Err(NovaError::Runtime {
-                            msg: "Invalid arguments for printf".into(),
-                        });
-                    }
-                }
-                let index = state.allocate_string(
-                    format_with_array(format_string, strings.into_iter().cloned().collect()).into(),
-                );
-                state.stack.push(VmData::String(index));
-            } else {
-                return Err(NovaError::Runtime {
-                    msg: "Invalid arguments for printf".into(),
-                });
-            }
-        } else {
-            return Err(NovaError::Runtime {
-                msg: "Invalid arguments for printf".into(),
-            });
-        }
-    } else {
-        return Err(NovaError::Runtime {
-            msg: "Invalid arguments for printf".into(),
-        });
-    }
+    let formatted = format_values(state)?;
+    let index = state.allocate_string(formatted.into());
+    state.stack.push(VmData::String(index));
     Ok(())
 }
@@ -0,0 +1,662 @@
+use std::{collections::HashMap, fmt::Write};
+
+use common::{error::NovaError, utilities::format_float};
+use vm::state::{self, Heap, VmData};
+
+use crate::str::{pop_string, push_string};
+
+// JSON read into and written from nova values. values carry no type when the
+// program runs, so the parser hands each call the shape of the type it reads
+// or writes as a string:
+//
+//     i f b s c    Int, Float, Bool, String and Char
+//     j            a JsonValue from std/json.nv
+//     ?x [x] (xy)  Option, list and tuple of other shapes
+//     {T|a:x,}    a struct type T and its fields, later written <T>
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    // the text of the number, so an Int keeps all of its digits
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// the tags of JsonValue's variants, in the order std/json.nv declares them
+const NULL: i64 = 0;
+const BOOL: i64 = 1;
+const NUMBER: i64 = 2;
+const TEXT: i64 = 3;
+const ARRAY: i64 = 4;
+const OBJECT: i64 = 5;
+
+// deeper nesting than this is refused instead of overflowing the stack
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, msg: impl std::fmt::Display) -> String {
+        let before = &self.text[..self.at];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        format!("{} at line {} column {}", msg, line, column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.at..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.at += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), String> {
+        if self.text[self.at..].starts_with(word) {
+            self.at += word.len();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {}", word)))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('n') => self.expect("null").map(|_| Json::Null)?,
+            Some('t') => self.expect("true").map(|_| Json::Bool(true))?,
+            Some('f') => self.expect("false").map(|_| Json::Bool(false))?,
+            Some('"') => Json::String(self.string()?),
+            Some('-' | '0'..='9') => self.number()?,
+            Some('[') => {
+                self.at += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                } else {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        self.skip_whitespace();
+                        match self.next() {
+                            Some(',') => continue,
+                            Some(']') => break,
+                            _ => return Err(self.error("expected , or ] in an array")),
+                        }
+                    }
+                }
+                Json::Array(items)
+            }
+            Some('{') => {
+                self.at += 1;
+                let mut members = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        if self.peek() != Some('"') {
+                            return Err(self.error("expected a key in quotes"));
+                        }
+                        let key = self.string()?;
+                        self.skip_whitespace();
+                        self.expect(":")?;
+                        members.push((key, self.value(depth + 1)?));
+                        self.skip_whitespace();
+                        match self.next() {
+                            Some(',') => continue,
+                            Some('}') => break,
+                            _ => return Err(self.error("expected , or } in an object")),
+                        }
+                    }
+                }
+                Json::Object(members)
+            }
+            Some(c) => return Err(self.error(format!("unexpected {:?}", c))),
+            None => return Err(self.error("unexpected end of text")),
+        };
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.at;
+        let digits = |reader: &mut Self| {
+            let from = reader.at;
+            while reader.peek().is_some_and(|c| c.is_ascii_digit()) {
+                reader.at += 1;
+            }
+            reader.at > from
+        };
+        if self.peek() == Some('-') {
+            self.at += 1;
+        }
+        if self.peek() == Some('0') {
+            self.at += 1;
+        } else if !digits(self) {
+            return Err(self.error("expected a digit"));
+        }
+        if self.peek() == Some('.') {
+            self.at += 1;
+            if !digits(self) {
+                return Err(self.error("expected a digit after the ."));
+            }
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.at += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.at += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+        }
+        Ok(Json::Number(self.text[start..self.at].to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.at += 1;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let high = self.hex()?;
+                        let code = if (0xD800..0xDC00).contains(&high) {
+                            self.expect("\\u")?;
+                            let low = self.hex()?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(self.error("invalid surrogate pair"));
+                            }
+                            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                        } else {
+                            high
+                        };
+                        match char::from_u32(code) {
+                            Some(c) => string.push(c),
+                            None => return Err(self.error("invalid unicode escape")),
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in a string"))
+                }
+                Some(c) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self.text.get(self.at..self.at + 4).unwrap_or("");
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.len() == 4 => {
+                self.at += 4;
+                Ok(code)
+            }
+            _ => Err(self.error("expected four hex digits")),
+        }
+    }
+}
+
+fn parse(text: &str) -> Result<Json, String> {
+    let mut reader = Reader { text, at: 0 };
+    let value = reader.value(0)?;
+    reader.skip_whitespace();
+    if reader.at != text.len() {
+        return Err(reader.error("unexpected text after the value"));
+    }
+    Ok(value)
+}
+
+enum Shape {
+    Int,
+    Float,
+    Bool,
+    String,
+    Char,
+    Json,
+    Option(usize),
+    List(usize),
+    Tuple(Vec<usize>),
+    Struct {
+        name: String,
+        fields: Vec<(String, usize)>,
+    },
+}
+
+// the shapes of a type, each struct once so a recursive one can refer to itself
+struct Shapes {
+    shapes: Vec<Shape>,
+    names: HashMap<String, usize>,
+}
+
+impl Shapes {
+    fn read(text: &str) -> Result<(Shapes, usize), NovaError> {
+        let mut shapes = Shapes {
+            shapes: vec![],
+            names: HashMap::new(),
+        };
+        let mut chars = text.chars().peekable();
+        let root = shapes.shape(&mut chars).ok_or_else(|| NovaError::Runtime {
+            msg: format!("Invalid json shape {}", text).into(),
+        })?;
+        Ok((shapes, root))
+    }
+
+    fn shape(&mut self, chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+        let until = |chars: &mut std::iter::Peekable<std::str::Chars>, end: char| {
+            let mut name = String::new();
+            for c in chars.by_ref() {
+                if c == end {
+                    return Some(name);
+                }
+                name.push(c);
+            }
+            None
+        };
+        let shape = match chars.next()? {
+            'i' => Shape::Int,
+            'f' => Shape::Float,
+            'b' => Shape::Bool,
+            's' => Shape::String,
+            'c' => Shape::Char,
+            'j' => Shape::Json,
+            '?' => Shape::Option(self.shape(chars)?),
+            '[' => {
+                let inner = self.shape(chars)?;
+                (chars.next()? == ']').then_some(Shape::List(inner))?
+            }
+            '(' => {
+                let mut elements = vec![];
+                while chars.peek() != Some(&')') {
+                    elements.push(self.shape(chars)?);
+                }
+                chars.next();
+                Shape::Tuple(elements)
+            }
+            '<' => return self.names.get(&until(chars, '>')?).copied(),
+            '{' => {
+                // the key names the type with its parameters, the value
+                // only carries the name of the struct
+                let key = until(chars, '|')?;
+                let index = self.shapes.len();
+                self.shapes.push(Shape::Struct {
+                    name: key.split('(').next().unwrap_or_default().into(),
+                    fields: vec![],
+                });
+                self.names.insert(key, index);
+                let mut fields = vec![];
+                while chars.peek() != Some(&'}') {
+                    let field = until(chars, ':')?;
+                    fields.push((field, self.shape(chars)?));
+                    (chars.next()? == ',').then_some(())?;
+                }
+                chars.next();
+                if let Shape::Struct { fields: slot, .. } = &mut self.shapes[index] {
+                    *slot = fields;
+                }
+                return Some(index);
+            }
+            _ => return None,
+        };
+        self.shapes.push(shape);
+        Some(self.shapes.len() - 1)
+    }
+}
+
+fn describe(json: &Json) -> &'static str {
+    match json {
+        Json::Null => "null",
+        Json::Bool(_) => "a boolean",
+        Json::Number(_) => "a number",
+        Json::String(_) => "a string",
+        Json::Array(_) => "an array",
+        Json::Object(_) => "an object",
+    }
+}
+
+fn list(state: &mut state::State, items: Vec<VmData>) -> VmData {
+    let items = items
+        .into_iter()
+        .map(|item| state.allocate_vmdata_to_heap(item))
+        .collect();
+    VmData::List(state.allocate_array(items))
+}
+
+fn string(state: &mut state::State, text: &str) -> VmData {
+    VmData::String(state.allocate_string(text.into()))
+}
+
+// an enum value, the variant's value, its tag and the enum's name
+fn variant(state: &mut state::State, value: VmData, tag: i64, name: &str) -> VmData {
+    let name = string(state, name);
+    list(state, vec![value, VmData::Int(tag), name])
+}
+
+fn json_value(state: &mut state::State, json: &Json) -> VmData {
+    let (value, tag) = match json {
+        Json::Null => (state.none(), NULL),
+        Json::Bool(value) => (VmData::Bool(*value), BOOL),
+        Json::Number(text) => (VmData::Float(text.parse().unwrap_or(f64::NAN)), NUMBER),
+        Json::String(text) => (string(state, text), TEXT),
+        Json::Array(items) => {
+            let items = items.iter().map(|item| json_value(state, item)).collect();
+            (list(state, items), ARRAY)
+        }
+        Json::Object(members) => {
+            let mut pairs = vec![];
+            for (key, value) in members {
+                let key = string(state, key);
+                let value = json_value(state, value);
+                pairs.push(list(state, vec![key, value]));
+            }
+            (list(state, pairs), OBJECT)
+        }
+    };
+    variant(state, value, tag, "json::JsonValue")
+}
+
+// the nova value of the json, shaped like the type it is read into
+fn build(
+    state: &mut state::State,
+    shapes: &Shapes,
+    shape: usize,
+    json: &Json,
+    path: &str,
+) -> Result<VmData, String> {
+    let mismatch = |expected: &str| {
+        format!(
+            "expected {} at {}, found {}",
+            expected,
+            path,
+            describe(json)
+        )
+    };
+    Ok(match (&shapes.shapes[shape], json) {
+        (Shape::Json, json) => json_value(state, json),
+        (Shape::Option(_), Json::Null) => state.none(),
+        (Shape::Option(inner), json) => build(state, shapes, *inner, json, path)?,
+        (Shape::Int, Json::Number(text)) => VmData::Int(
+            text.parse()
+                .map_err(|_| format!("expected an Int at {}, found {}", path, text))?,
+        ),
+        (Shape::Float, Json::Number(text)) => {
+            VmData::Float(text.parse().map_err(|_| mismatch("a Float"))?)
+        }
+        (Shape::Bool, Json::Bool(value)) => VmData::Bool(*value),
+        (Shape::String, Json::String(text)) => string(state, text),
+        (Shape::Char, Json::String(text)) => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => VmData::Char(c),
+                _ => return Err(mismatch("a single character")),
+            }
+        }
+        (Shape::List(inner), Json::Array(items)) => {
+            let mut values = vec![];
+            for (index, item) in items.iter().enumerate() {
+                let path = format!("{}[{}]", path, index);
+                values.push(build(state, shapes, *inner, item, &path)?);
+            }
+            list(state, values)
+        }
+        (Shape::Tuple(elements), Json::Array(items)) => {
+            if elements.len() != items.len() {
+                return Err(mismatch(&format!("an array of {}", elements.len())));
+            }
+            let mut values = vec![];
+            for (index, (element, item)) in elements.iter().zip(items).enumerate() {
+                let path = format!("{}[{}]", path, index);
+                values.push(build(state, shapes, *element, item, &path)?);
+            }
+            list(state, values)
+        }
+        (Shape::Struct { name, fields }, Json::Object(members)) => {
+            let mut values = vec![];
+            for (field, shape) in fields {
+                let path = format!("{}.{}", path, field);
+                // the last of repeated keys wins, like in most parsers
+                match members.iter().rev().find(|(key, _)| key == field) {
+                    Some((_, value)) => values.push(build(state, shapes, *shape, value, &path)?),
+                    None if matches!(shapes.shapes[*shape], Shape::Option(_)) => {
+                        values.push(state.none())
+                    }
+                    None => return Err(format!("missing {} at {}", field, path)),
+                }
+            }
+            // a struct ends with the name of its type
+            values.push(string(state, name));
+            list(state, values)
+        }
+        (Shape::Int, _) => return Err(mismatch("an Int")),
+        (Shape::Float, _) => return Err(mismatch("a Float")),
+        (Shape::Bool, _) => return Err(mismatch("a Bool")),
+        (Shape::String | Shape::Char, _) => return Err(mismatch("a string")),
+        (Shape::List(_) | Shape::Tuple(_), _) => return Err(mismatch("an array")),
+        (Shape::Struct { name, .. }, _) => {
+            return Err(mismatch(&format!("an object for {}", name)))
+        }
+    })
+}
+
+fn items(state: &state::State, value: VmData) -> Result<Vec<VmData>, NovaError> {
+    match value {
+        VmData::List(index) | VmData::Struct(index) => match state.get_ref(index) {
+            Heap::List(items) => Ok(items.iter().map(|item| state.to_vmdata(*item)).collect()),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a list in the heap".into(),
+            }),
+        },
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a list".into(),
+        }),
+    }
+}
+
+fn quote(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn float(value: f64, out: &mut String) -> Result<(), NovaError> {
+    if !value.is_finite() {
+        return Err(NovaError::Runtime {
+            msg: format!("{} cannot be written as JSON", value).into(),
+        });
+    }
+    // whole numbers are written without a fraction, the way json usually has them
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        let _ = write!(out, "{}", value as i64);
+    } else {
+        out.push_str(&format_float(value));
+    }
+    Ok(())
+}
+
+// a JsonValue, read by its tag
+fn write_json(state: &state::State, value: VmData, out: &mut String) -> Result<(), NovaError> {
+    let variant = items(state, value)?;
+    let (Some(value), Some(VmData::Int(tag))) = (variant.first(), variant.get(1)) else {
+        return Err(NovaError::Runtime {
+            msg: "Expected a JsonValue".into(),
+        });
+    };
+    match (*tag, *value) {
+        (NULL, _) => out.push_str("null"),
+        (BOOL, VmData::Bool(value)) => out.push_str(if value { "true" } else { "false" }),
+        (NUMBER, VmData::Float(value)) => float(value, out)?,
+        (TEXT, VmData::String(index)) => quote(state.get_ref(index).get_string(), out),
+        (ARRAY, value) => {
+            out.push('[');
+            for (i, item) in items(state, value)?.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json(state, item, out)?;
+            }
+            out.push(']');
+        }
+        (OBJECT, value) => {
+            out.push('{');
+            for (i, pair) in items(state, value)?.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let pair = items(state, pair)?;
+                let (Some(VmData::String(key)), Some(value)) = (pair.first(), pair.get(1)) else {
+                    return Err(NovaError::Runtime {
+                        msg: "Expected a key and a value in a JsonValue::Object".into(),
+                    });
+                };
+                quote(state.get_ref(*key).get_string(), out);
+                out.push(':');
+                write_json(state, *value, out)?;
+            }
+            out.push('}');
+        }
+        _ => {
+            return Err(NovaError::Runtime {
+                msg: "Expected a JsonValue".into(),
+            })
+        }
+    }
+    Ok(())
+}
+
+fn write(
+    state: &state::State,
+    shapes: &Shapes,
+    shape: usize,
+    value: VmData,
+    out: &mut String,
+) -> Result<(), NovaError> {
+    match (&shapes.shapes[shape], value) {
+        (Shape::Json, value) => write_json(state, value, out)?,
+        (Shape::Option(_), VmData::None(_)) => out.push_str("null"),
+        (Shape::Option(inner), value) => write(state, shapes, *inner, value, out)?,
+        (Shape::Int, VmData::Int(value)) => {
+            let _ = write!(out, "{}", value);
+        }
+        (Shape::Float, VmData::Float(value)) => float(value, out)?,
+        (Shape::Bool, VmData::Bool(value)) => out.push_str(if value { "true" } else { "false" }),
+        (Shape::String, VmData::String(index)) => quote(state.get_ref(index).get_string(), out),
+        (Shape::Char, VmData::Char(c)) => quote(c.encode_utf8(&mut [0; 4]), out),
+        (Shape::List(inner), value) => {
+            out.push('[');
+            for (i, item) in items(state, value)?.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write(state, shapes, *inner, item, out)?;
+            }
+            out.push(']');
+        }
+        (Shape::Tuple(elements), value) => {
+            out.push('[');
+            for (i, (element, item)) in elements.iter().zip(items(state, value)?).enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write(state, shapes, *element, item, out)?;
+            }
+            out.push(']');
+        }
+        (Shape::Struct { fields, .. }, value) => {
+            out.push('{');
+            for (i, ((field, shape), item)) in fields.iter().zip(items(state, value)?).enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                quote(field, out);
+                out.push(':');
+                write(state, shapes, *shape, item, out)?;
+            }
+            out.push('}');
+        }
+        _ => {
+            return Err(NovaError::Runtime {
+                msg: "A value does not have the type it was written as".into(),
+            })
+        }
+    }
+    Ok(())
+}
+
+// Ok with the value read into the shape, or Err with why it could not be
+pub fn decode(state: &mut state::State) -> Result<(), NovaError> {
+    decode_with(state, parse)
+}
+
+// decode for any text that reads into the same tree json does
+pub(crate) fn decode_with(
+    state: &mut state::State,
+    parse: fn(&str) -> Result<Json, String>,
+) -> Result<(), NovaError> {
+    let shape = pop_string(state)?;
+    let text = pop_string(state)?;
+    let (shapes, root) = Shapes::read(&shape)?;
+    state.gclock = true;
+    let result = match parse(&text).and_then(|json| build(state, &shapes, root, &json, "$")) {
+        Ok(value) => variant(state, value, 0, "Result"),
+        Err(msg) => {
+            let msg = string(state, &msg);
+            variant(state, msg, 1, "Result")
+        }
+    };
+    state.stack.push(result);
+    state.gclock = false;
+    Ok(())
+}
+
+pub fn encode(state: &mut state::State) -> Result<(), NovaError> {
+    let shape = pop_string(state)?;
+    let value = state.stack.pop().ok_or_else(|| NovaError::Runtime {
+        msg: "Stack is empty".into(),
+    })?;
+    let (shapes, root) = Shapes::read(&shape)?;
+    let mut out = String::new();
+    write(state, &shapes, root, value, &mut out)?;
+    state.gclock = true;
+    push_string(state, out);
+    state.gclock = false;
+    Ok(())
+}
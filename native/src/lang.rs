@@ -0,0 +1,200 @@
+use std::rc::Rc;
+
+use common::{
+    error::NovaError,
+    nodes::{Atom, Expr},
+    tokens::TokenValue,
+    utilities::format_float,
+};
+use lexer::Lexer;
+use vm::state::{self, Heap, VmData};
+
+fn pop_source(state: &mut state::State) -> Result<Rc<str>, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::String(index)) => match state.get_ref(index) {
+            Heap::String(str) => Ok(str.clone()),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a string in the heap".into(),
+            }),
+        },
+        Some(_) => Err(NovaError::Runtime {
+            msg: "Expected a string on the stack".into(),
+        }),
+        None => Err(NovaError::Runtime {
+            msg: "Stack is empty".into(),
+        }),
+    }
+}
+
+fn string_item(state: &mut state::State, value: &str) -> usize {
+    let string = state.allocate_string(value.into());
+    state.allocate_vmdata_to_heap(VmData::String(string))
+}
+
+fn int_item(state: &mut state::State, value: i64) -> usize {
+    state.allocate_vmdata_to_heap(VmData::Int(value))
+}
+
+// (kind, text, line, col) for every token in the source
+pub fn tokenize(state: &mut state::State) -> Result<(), NovaError> {
+    let source = pop_source(state)?;
+    let tokens = match Lexer::new(source, None).tokenize() {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            return Err(NovaError::Runtime {
+                msg: format!("Lang::tokenize failed: {}", error_message(&error)).into(),
+            })
+        }
+    };
+    state.gclock = true;
+    let mut items = vec![];
+    for token in tokens {
+        let (kind, text) = match &token.value {
+            TokenValue::Keyword(keyword) => ("Keyword", format!("{:?}", keyword)),
+            TokenValue::Identifier(name) => ("Identifier", name.to_string()),
+            TokenValue::Integer(value) => ("Integer", value.to_string()),
+            TokenValue::Float(value) => ("Float", format_float(*value)),
+            TokenValue::StringLiteral(value) => ("String", value.to_string()),
+            TokenValue::Char(value) => ("Char", value.to_string()),
+            TokenValue::StructuralSymbol(symbol) => ("Symbol", format!("{:?}", symbol)),
+            TokenValue::Bool(value) => ("Bool", value.to_string()),
+            TokenValue::Operator(operator) => ("Operator", format!("{:?}", operator)),
+        };
+        let fields = vec![
+            string_item(state, kind),
+            string_item(state, &text),
+            int_item(state, token.position.line as i64),
+            int_item(state, token.position.col as i64),
+        ];
+        let tuple = state.allocate_array(fields);
+        items.push(state.allocate_vmdata_to_heap(VmData::List(tuple)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
+
+// the typed tree of an expression as a flat list of (kind, value, type, children),
+// children are indices into the same list and the root is the first node.
+// None when the source is not a valid expression
+pub fn parse_expr(state: &mut state::State) -> Result<(), NovaError> {
+    let source = pop_source(state)?;
+    let expr = Lexer::new(source, None).tokenize().and_then(|tokens| {
+        let mut parser = parser::default();
+        parser.input = tokens;
+        parser.parse_expr()
+    });
+    let Ok(expr) = expr else {
+        state.stack.push(state.none());
+        return Ok(());
+    };
+    let mut nodes = vec![];
+    flatten(&expr, &mut nodes);
+    state.gclock = true;
+    let mut items = vec![];
+    for (kind, value, ttype, children) in nodes {
+        let children: Vec<usize> = children
+            .into_iter()
+            .map(|child| int_item(state, child as i64))
+            .collect();
+        let children = state.allocate_array(children);
+        let fields = vec![
+            string_item(state, kind),
+            string_item(state, &value),
+            string_item(state, &ttype),
+            state.allocate_vmdata_to_heap(VmData::List(children)),
+        ];
+        let tuple = state.allocate_array(fields);
+        items.push(state.allocate_vmdata_to_heap(VmData::List(tuple)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
+
+type Node = (&'static str, String, String, Vec<usize>);
+
+fn flatten(expr: &Expr, nodes: &mut Vec<Node>) -> usize {
+    let index = nodes.len();
+    let ttype = expr.get_type().to_string();
+    nodes.push(("", String::new(), ttype, vec![]));
+    let (kind, value, children): (&'static str, String, Vec<&Expr>) = match expr {
+        Expr::Literal { value, .. } => match value {
+            Atom::Integer { value } => ("Int", value.to_string(), vec![]),
+            Atom::Float { value } => ("Float", format_float(*value), vec![]),
+            Atom::Bool { value } => ("Bool", value.to_string(), vec![]),
+            Atom::String { value } => ("String", value.to_string(), vec![]),
+            Atom::Char { value } => ("Char", value.to_string(), vec![]),
+            Atom::Id { name } => ("Id", name.to_string(), vec![]),
+            Atom::Call {
+                name, arguments, ..
+            } => ("Call", name.to_string(), arguments.iter().collect()),
+            Atom::None => ("None", String::new(), vec![]),
+        },
+        Expr::Binop { op, lhs, rhs, .. } => ("Binop", format!("{:?}", op), vec![lhs, rhs]),
+        Expr::Unary { op, expr, .. } => ("Unary", format!("{:?}", op), vec![expr]),
+        Expr::Call {
+            name,
+            function,
+            args,
+            ..
+        } => {
+            let mut children = vec![function.as_ref()];
+            children.extend(args.iter());
+            ("Apply", name.to_string(), children)
+        }
+        Expr::Field { name, expr, .. } => ("Field", name.to_string(), vec![expr]),
+        Expr::Indexed {
+            container, index, ..
+        } => ("Index", String::new(), vec![container, index]),
+        Expr::Sliced {
+            container,
+            start,
+            end,
+            step,
+            ..
+        } => {
+            let mut children = vec![container.as_ref()];
+            children.extend([start, end, step].into_iter().flatten().map(|e| e.as_ref()));
+            ("Slice", String::new(), children)
+        }
+        Expr::ListConstructor { elements, .. } => {
+            ("List", String::new(), elements.iter().collect())
+        }
+        Expr::IfExpr {
+            test,
+            body,
+            alternative,
+            ..
+        } => ("If", String::new(), vec![test, body, alternative]),
+        Expr::Let {
+            identifier, expr, ..
+        } => ("Let", identifier.to_string(), vec![expr]),
+        Expr::Return { expr, .. } => ("Return", String::new(), vec![expr]),
+        Expr::Closure { .. } => ("Closure", String::new(), vec![]),
+        Expr::ListCompConstructor { .. } => ("ListComprehension", String::new(), vec![]),
+        Expr::StoreExpr { name, expr, .. } => ("Store", name.to_string(), vec![expr]),
+        Expr::Block { .. } => ("Block", String::new(), vec![]),
+        Expr::Try { .. } => ("Try", String::new(), vec![]),
+        Expr::Propagate { expr, .. } => ("Propagate", String::new(), vec![expr]),
+        Expr::None => ("None", String::new(), vec![]),
+        Expr::Void => ("Void", String::new(), vec![]),
+    };
+    let children = children
+        .into_iter()
+        .map(|child| flatten(child, nodes))
+        .collect();
+    nodes[index] = (kind, value, nodes[index].2.clone(), children);
+    index
+}
+
+fn error_message(error: &NovaError) -> String {
+    match error {
+        NovaError::Lexing { msg, position, .. } => {
+            format!("{} at {}:{}", msg, position.line, position.col)
+        }
+        _ => "invalid source".into(),
+    }
+}
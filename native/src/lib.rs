@@ -1,9 +1,29 @@
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bigint;
+pub mod binary;
+#[cfg(feature = "canvas")]
+pub mod canvas;
 pub mod char;
+pub mod config;
+pub mod digest;
+pub mod env;
 pub mod float;
+pub mod fs;
+#[cfg(feature = "input")]
+pub mod input;
+pub mod int;
 pub mod io;
+pub mod json;
+pub mod lang;
 pub mod list;
+pub mod net;
+pub mod process;
 pub mod random;
 pub mod regex;
 pub mod str;
+pub mod sys;
 pub mod terminal;
 pub mod time;
+pub mod timer;
+pub mod worker;
@@ -12,9 +12,11 @@ pub fn len(state: &mut state::State) -> Result<(), NovaError> {
 
 pub fn push(state: &mut state::State) -> Result<(), NovaError> {
     if let (Some(data), Some(VmData::List(index))) = (state.stack.pop(), state.stack.pop()) {
-        if let Heap::List(mut array) = state.get_ref(index).clone() {
-            array.push(state.allocate_vmdata_to_heap(data));
-            state.heap[index] = Heap::List(array);
+        // pushed in place, copying the list made a loop of pushes quadratic.
+        // the vec doubles its capacity when full so a push is O(1) amortized
+        let item = state.allocate_vmdata_to_heap(data);
+        if let Heap::List(array) = &mut state.heap[index] {
+            array.push(item);
         } else {
             panic!()
         }
@@ -26,13 +28,14 @@ pub fn push(state: &mut state::State) -> Result<(), NovaError> {
 
 pub fn pop(state: &mut state::State) -> Result<(), NovaError> {
     if let Some(VmData::List(index)) = state.stack.pop() {
-        if let Heap::List(mut array) = state.get_ref(index).clone() {
-            if let Some(item) = array.pop() {
-                state.stack.push(state.to_vmdata(item));
-            } else {
-                state.stack.push(VmData::None);
-            }
-            state.heap[index] = Heap::List(array);
+        let none = state.none();
+        // taken off the end in place, copying the list made every pop O(n)
+        if let Heap::List(array) = &mut state.heap[index] {
+            let data = match array.pop() {
+                Some(item) => state.to_vmdata(item),
+                None => none,
+            };
+            state.stack.push(data);
         } else {
             panic!()
         }
@@ -42,19 +45,78 @@ pub fn pop(state: &mut state::State) -> Result<(), NovaError> {
     Ok(())
 }
 
+// the list and an index into it, which may be one past the end when inserting
+fn list_at(state: &mut state::State, end: bool) -> Result<(usize, usize), NovaError> {
+    let (Some(VmData::Int(index)), Some(VmData::List(list))) =
+        (state.stack.pop(), state.stack.pop())
+    else {
+        panic!()
+    };
+    let Heap::List(array) = state.get_ref(list) else {
+        panic!()
+    };
+    let len = array.len() as i64;
+    if index < 0 || index > len || (index == len && !end) {
+        return Err(NovaError::Runtime {
+            msg: format!("Index {index} is out of range for a list of length {len}").into(),
+        });
+    }
+    Ok((list, index as usize))
+}
+
 // remove at index
 pub fn remove(state: &mut state::State) -> Result<(), NovaError> {
-    if let (Some(VmData::Int(index)), Some(VmData::List(list_index))) =
-        (state.stack.pop(), state.stack.pop())
-    {
-        if let Heap::List(mut array) = state.get_ref(list_index).clone() {
-            array.remove(index as usize);
-            state.heap[list_index] = Heap::List(array.clone());
-        } else {
-            panic!()
-        }
-    } else {
+    let (list, index) = list_at(state, false)?;
+    if let Heap::List(array) = &mut state.heap[list] {
+        array.remove(index);
+    }
+    Ok(())
+}
+
+// remove at index and give back the item, the items after it move down
+pub fn remove_at(state: &mut state::State) -> Result<(), NovaError> {
+    let (list, index) = list_at(state, false)?;
+    if let Heap::List(array) = &mut state.heap[list] {
+        let item = array.remove(index);
+        let data = state.to_vmdata(item);
+        state.stack.push(data);
+    }
+    Ok(())
+}
+
+// insert before index, the items from it on move up
+pub fn insert(state: &mut state::State) -> Result<(), NovaError> {
+    let Some(data) = state.stack.pop() else {
+        panic!()
+    };
+    let (list, index) = list_at(state, true)?;
+    let item = state.allocate_vmdata_to_heap(data);
+    if let Heap::List(array) = &mut state.heap[list] {
+        array.insert(index, item);
+    }
+    Ok(())
+}
+
+// the items of each list one after another, in time linear in the result
+pub fn concat(state: &mut state::State) -> Result<(), NovaError> {
+    let Some(VmData::List(index)) = state.stack.pop() else {
         panic!()
+    };
+    let Heap::List(lists) = state.get_ref(index) else {
+        panic!()
+    };
+    let mut items = vec![];
+    for list in lists {
+        if let VmData::List(inner) = state.to_vmdata(*list) {
+            if let Heap::List(array) = state.get_ref(inner) {
+                items.extend(array.iter().copied());
+            }
+        }
     }
+    // the new list shares the items, like + on two lists does
+    state.gclock = true;
+    let index = state.allocate_array(items);
+    state.gclock = false;
+    state.stack.push(VmData::List(index));
     Ok(())
 }
@@ -0,0 +1,250 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    time::Duration,
+};
+
+use common::error::NovaError;
+use vm::state::{self, Socket, VmData};
+
+use crate::str::{pop_int, pop_string, push_string};
+
+fn net_error(what: &str, error: impl std::fmt::Display) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Error {}: {}", what, error).into(),
+    }
+}
+
+fn with_socket<T>(
+    state: &state::State,
+    id: i64,
+    use_socket: impl FnOnce(&mut Socket) -> Result<T, NovaError>,
+) -> Result<T, NovaError> {
+    match state.sockets.get(&id) {
+        Some(socket) => use_socket(&mut socket.borrow_mut()),
+        None => Err(NovaError::Runtime {
+            msg: "Socket is already closed".into(),
+        }),
+    }
+}
+
+fn wrong_kind(expected: &str) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Expected a {} socket", expected).into(),
+    }
+}
+
+// the text in the bytes after what was pending, keeping a character that is
+// not complete yet for the next read. bytes that are not utf-8 become �
+fn decode(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    pending.extend_from_slice(bytes);
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(error) if error.error_len().is_none() => error.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+    pending.drain(..valid);
+    text
+}
+
+fn push_handle(state: &mut state::State, socket: Socket) {
+    let id = state.add_socket(socket);
+    state.stack.push(VmData::Int(id));
+}
+
+pub fn tcp_connect(state: &mut state::State) -> Result<(), NovaError> {
+    let address = pop_string(state)?;
+    let stream = TcpStream::connect(&*address)
+        .map_err(|e| net_error(&format!("connecting to {address}"), e))?;
+    push_handle(
+        state,
+        Socket::Stream {
+            reader: BufReader::new(stream),
+            pending: vec![],
+        },
+    );
+    Ok(())
+}
+
+// port 0 picks a free port, Sys::socketAddr tells which
+pub fn tcp_listen(state: &mut state::State) -> Result<(), NovaError> {
+    let address = pop_string(state)?;
+    let listener = TcpListener::bind(&*address)
+        .map_err(|e| net_error(&format!("listening on {address}"), e))?;
+    push_handle(state, Socket::Listener(listener));
+    Ok(())
+}
+
+// waits for the next connection to a listener
+pub fn tcp_accept(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    let stream = with_socket(state, id, |socket| match socket {
+        Socket::Listener(listener) => listener
+            .accept()
+            .map(|(stream, _)| stream)
+            .map_err(|e| net_error("accepting a connection", e)),
+        _ => Err(wrong_kind("listening")),
+    })?;
+    push_handle(
+        state,
+        Socket::Stream {
+            reader: BufReader::new(stream),
+            pending: vec![],
+        },
+    );
+    Ok(())
+}
+
+pub fn tcp_send(state: &mut state::State) -> Result<(), NovaError> {
+    let text = pop_string(state)?;
+    let id = pop_int(state)?;
+    with_socket(state, id, |socket| match socket {
+        Socket::Stream { reader, .. } => reader
+            .get_mut()
+            .write_all(text.as_bytes())
+            .map_err(|e| net_error("sending", e)),
+        _ => Err(wrong_kind("connected")),
+    })
+}
+
+// waits for up to max bytes, "" once the other side has closed
+pub fn tcp_recv(state: &mut state::State) -> Result<(), NovaError> {
+    let max = pop_int(state)?.max(1) as usize;
+    let id = pop_int(state)?;
+    let text = with_socket(state, id, |socket| match socket {
+        Socket::Stream { reader, pending } => {
+            let mut buffer = vec![0; max];
+            loop {
+                let read = reader
+                    .read(&mut buffer)
+                    .map_err(|e| net_error("receiving", e))?;
+                if read == 0 {
+                    // what is left of a cut character will never be completed
+                    return Ok(String::from_utf8_lossy(&std::mem::take(pending)).into_owned());
+                }
+                let text = decode(pending, &buffer[..read]);
+                if !text.is_empty() {
+                    return Ok(text);
+                }
+            }
+        }
+        _ => Err(wrong_kind("connected")),
+    })?;
+    push_string(state, text);
+    Ok(())
+}
+
+// the next line without its line ending, None once the other side has closed
+pub fn tcp_recv_line(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    let line = with_socket(state, id, |socket| match socket {
+        Socket::Stream { reader, pending } => {
+            let mut bytes = std::mem::take(pending);
+            let had_pending = !bytes.is_empty();
+            let read = reader
+                .read_until(b'\n', &mut bytes)
+                .map_err(|e| net_error("receiving", e))?;
+            if read == 0 && !had_pending {
+                return Ok(None);
+            }
+            while bytes
+                .last()
+                .is_some_and(|byte| *byte == b'\n' || *byte == b'\r')
+            {
+                bytes.pop();
+            }
+            Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+        }
+        _ => Err(wrong_kind("connected")),
+    })?;
+    match line {
+        Some(line) => push_string(state, line),
+        None => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+pub fn udp_bind(state: &mut state::State) -> Result<(), NovaError> {
+    let address = pop_string(state)?;
+    let socket =
+        UdpSocket::bind(&*address).map_err(|e| net_error(&format!("binding {address}"), e))?;
+    push_handle(state, Socket::Udp(socket));
+    Ok(())
+}
+
+pub fn udp_send_to(state: &mut state::State) -> Result<(), NovaError> {
+    let address = pop_string(state)?;
+    let text = pop_string(state)?;
+    let id = pop_int(state)?;
+    with_socket(state, id, |socket| match socket {
+        Socket::Udp(socket) => socket
+            .send_to(text.as_bytes(), &*address)
+            .map(|_| ())
+            .map_err(|e| net_error(&format!("sending to {address}"), e)),
+        _ => Err(wrong_kind("udp")),
+    })
+}
+
+// (text, address of the sender) of the next datagram, cut to max bytes
+pub fn udp_recv_from(state: &mut state::State) -> Result<(), NovaError> {
+    let max = pop_int(state)?.max(1) as usize;
+    let id = pop_int(state)?;
+    let (text, from) = with_socket(state, id, |socket| match socket {
+        Socket::Udp(socket) => {
+            let mut buffer = vec![0; max];
+            let (read, from) = socket
+                .recv_from(&mut buffer)
+                .map_err(|e| net_error("receiving", e))?;
+            Ok((String::from_utf8_lossy(&buffer[..read]).into_owned(), from))
+        }
+        _ => Err(wrong_kind("udp")),
+    })?;
+    state.gclock = true;
+    let mut items = vec![];
+    for text in [text, from.to_string()] {
+        let index = state.allocate_string(text.into());
+        items.push(state.allocate_vmdata_to_heap(VmData::String(index)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
+
+// the address the socket is bound to, as in 127.0.0.1:4000
+pub fn socket_addr(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    let address: io::Result<SocketAddr> = with_socket(state, id, |socket| {
+        Ok(match socket {
+            Socket::Stream { reader, .. } => reader.get_ref().local_addr(),
+            Socket::Listener(listener) => listener.local_addr(),
+            Socket::Udp(socket) => socket.local_addr(),
+        })
+    })?;
+    let address = address.map_err(|e| net_error("reading the address", e))?;
+    push_string(state, address.to_string());
+    Ok(())
+}
+
+// how long a receive waits in milliseconds before it is a runtime error, 0
+// waits forever
+pub fn socket_timeout(state: &mut state::State) -> Result<(), NovaError> {
+    let millis = pop_int(state)?;
+    let id = pop_int(state)?;
+    let timeout = (millis > 0).then(|| Duration::from_millis(millis as u64));
+    with_socket(state, id, |socket| {
+        match socket {
+            Socket::Stream { reader, .. } => reader.get_ref().set_read_timeout(timeout),
+            Socket::Udp(socket) => socket.set_read_timeout(timeout),
+            Socket::Listener(_) => return Err(wrong_kind("connected or udp")),
+        }
+        .map_err(|e| net_error("setting the timeout", e))
+    })
+}
+
+pub fn socket_close(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    state.sockets.remove(&id);
+    Ok(())
+}
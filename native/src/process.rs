@@ -0,0 +1,193 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Command, Stdio},
+};
+
+use common::error::NovaError;
+use vm::state::{self, Heap, Process, VmData};
+
+use crate::str::{pop_int, pop_string, push_string};
+
+fn pop_handle(state: &mut state::State) -> Result<i64, NovaError> {
+    let handle = pop_int(state)?;
+    if state.processes.contains_key(&handle) {
+        Ok(handle)
+    } else {
+        Err(NovaError::Runtime {
+            msg: "Process is already closed".into(),
+        })
+    }
+}
+
+// the program and its arguments, the arguments are on top of the stack
+fn pop_command(state: &mut state::State) -> Result<(String, Command), NovaError> {
+    let args = match state.stack.pop() {
+        Some(VmData::List(index)) => match state.get_ref(index) {
+            Heap::List(array) => array.clone(),
+            _ => panic!(),
+        },
+        _ => panic!(),
+    };
+    let program = pop_string(state)?.to_string();
+    let mut command = Command::new(&program);
+    for arg in args {
+        if let VmData::String(index) = state.to_vmdata(arg) {
+            command.arg(state.get_ref(index).get_string());
+        }
+    }
+    Ok((program, command))
+}
+
+fn start_error(program: &str, e: std::io::Error) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Error running {program}: {e}").into(),
+    }
+}
+
+// -1 when the program was stopped by a signal
+fn exit_code(status: std::process::ExitStatus) -> i64 {
+    status.code().map_or(-1, i64::from)
+}
+
+// (exit code, stdout, stderr) once the program has finished. it reads
+// nothing, the way a program started from a closed terminal would
+pub fn run(state: &mut state::State) -> Result<(), NovaError> {
+    let (program, mut command) = pop_command(state)?;
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| start_error(&program, e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    state.gclock = true;
+    let mut items = vec![state.allocate_vmdata_to_heap(VmData::Int(exit_code(output.status)))];
+    for text in [stdout, stderr] {
+        let index = state.allocate_string(text.into());
+        items.push(state.allocate_vmdata_to_heap(VmData::String(index)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
+
+// starts the program with pipes for all three streams and returns its handle
+pub fn spawn(state: &mut state::State) -> Result<(), NovaError> {
+    let (program, mut command) = pop_command(state)?;
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| start_error(&program, e))?;
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    let handle = state.add_process(Process { child, stdout });
+    state.stack.push(VmData::Int(handle));
+    Ok(())
+}
+
+pub fn write(state: &mut state::State) -> Result<(), NovaError> {
+    let text = pop_string(state)?;
+    let handle = pop_handle(state)?;
+    let mut process = state.processes[&handle].borrow_mut();
+    let Some(stdin) = process.child.stdin.as_mut() else {
+        return Err(NovaError::Runtime {
+            msg: "The input of the process is closed".into(),
+        });
+    };
+    stdin
+        .write_all(text.as_bytes())
+        .and_then(|_| stdin.flush())
+        .map_err(|e| NovaError::Runtime {
+            msg: format!("Error writing to process: {e}").into(),
+        })
+}
+
+// the program sees the end of its input
+pub fn close_input(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    state.processes[&handle].borrow_mut().child.stdin = None;
+    Ok(())
+}
+
+// the next line of stdout without its line ending, None at the end
+pub fn read_line(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    let mut line = String::new();
+    let read = state.processes[&handle]
+        .borrow_mut()
+        .stdout
+        .read_line(&mut line)
+        .map_err(|e| NovaError::Runtime {
+            msg: format!("Error reading from process: {e}").into(),
+        })?;
+    if read == 0 {
+        state.stack.push(state.none());
+    } else {
+        let end = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(end);
+        push_string(state, line);
+    }
+    Ok(())
+}
+
+// the rest of stdout, or of stderr, up to the end
+fn read_rest(state: &mut state::State, stderr: bool) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    let mut text = String::new();
+    let mut process = state.processes[&handle].borrow_mut();
+    let read = if stderr {
+        match process.child.stderr.as_mut() {
+            Some(stream) => stream.read_to_string(&mut text),
+            None => Ok(0),
+        }
+    } else {
+        process.stdout.read_to_string(&mut text)
+    };
+    drop(process);
+    read.map_err(|e| NovaError::Runtime {
+        msg: format!("Error reading from process: {e}").into(),
+    })?;
+    push_string(state, text);
+    Ok(())
+}
+
+pub fn read_all(state: &mut state::State) -> Result<(), NovaError> {
+    read_rest(state, false)
+}
+
+pub fn read_err(state: &mut state::State) -> Result<(), NovaError> {
+    read_rest(state, true)
+}
+
+// closes the input and waits for the program to finish, waiting again
+// gives the same exit code
+pub fn wait(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    let mut process = state.processes[&handle].borrow_mut();
+    process.child.stdin = None;
+    let status = process.child.wait().map_err(|e| NovaError::Runtime {
+        msg: format!("Error waiting for process: {e}").into(),
+    })?;
+    drop(process);
+    state.stack.push(VmData::Int(exit_code(status)));
+    Ok(())
+}
+
+// waits for the program and lets go of its pipes, using the handle
+// afterwards is an error
+pub fn close(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    let process = state.processes.remove(&handle).unwrap();
+    let mut process = process.borrow_mut();
+    process.child.stdin = None;
+    let _ = process.child.wait();
+    Ok(())
+}
+
+pub fn kill(state: &mut state::State) -> Result<(), NovaError> {
+    let handle = pop_handle(state)?;
+    // a program that already exited cannot be killed, which is fine
+    let _ = state.processes[&handle].borrow_mut().child.kill();
+    Ok(())
+}
@@ -1,14 +1,64 @@
 use common::error::NovaError;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 use vm::state::{self, VmData};
 
-pub fn random_int(state: &mut state::State) -> Result<(), NovaError> {
-    if let (Some(VmData::Int(high)), Some(VmData::Int(low))) =
-        (state.stack.pop(), state.stack.pop())
-    {
-        let mut rng = rand::thread_rng();
-        // Generate a random integer between lower_bound (inclusive) and upper_bound (exclusive)
-        state.stack.push(VmData::Int(rng.gen_range(low..=high)));
+use crate::str::pop_int;
+
+// std/random.nv wraps the handles of seeded generators in Rng, handle 0 is
+// the generator of the thread, seeded by the system
+fn with_generator<T>(
+    state: &state::State,
+    id: i64,
+    use_generator: impl FnOnce(&mut dyn RngCore) -> T,
+) -> Result<T, NovaError> {
+    if id == 0 {
+        return Ok(use_generator(&mut rand::thread_rng()));
+    }
+    let generator = state.generators.get(&id).ok_or(NovaError::Runtime {
+        msg: "unknown random generator handle".into(),
+    })?;
+    Ok(use_generator(&mut *generator.borrow_mut()))
+}
+
+// a number from low up to and including high
+fn pick(rng: &mut dyn RngCore, low: i64, high: i64) -> Result<i64, NovaError> {
+    if low > high {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot pick a random number from {} to {}", low, high).into(),
+        });
     }
+    Ok(rng.gen_range(low..=high))
+}
+
+pub fn random_int(state: &mut state::State) -> Result<(), NovaError> {
+    let high = pop_int(state)?;
+    let low = pop_int(state)?;
+    let value = pick(&mut rand::thread_rng(), low, high)?;
+    state.stack.push(VmData::Int(value));
+    Ok(())
+}
+
+// the same seed gives the same numbers on every run
+pub fn seeded(state: &mut state::State) -> Result<(), NovaError> {
+    let seed = pop_int(state)?;
+    let id = state.add_generator(StdRng::seed_from_u64(seed as u64));
+    state.stack.push(VmData::Int(id));
+    Ok(())
+}
+
+pub fn int(state: &mut state::State) -> Result<(), NovaError> {
+    let high = pop_int(state)?;
+    let low = pop_int(state)?;
+    let id = pop_int(state)?;
+    let value = with_generator(state, id, |rng| pick(rng, low, high))??;
+    state.stack.push(VmData::Int(value));
+    Ok(())
+}
+
+// from 0 up to but not including 1
+pub fn float(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    let value = with_generator(state, id, |rng| rng.gen::<f64>())?;
+    state.stack.push(VmData::Float(value));
     Ok(())
 }
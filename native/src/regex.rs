@@ -193,7 +193,7 @@ pub fn regex_first(state: &mut state::State) -> Result<(), NovaError> {
 
     // if no captures return none
     if captures.is_none() {
-        state.stack.push(VmData::None);
+        state.stack.push(state.none());
         state.gclock = false;
         return Ok(());
     }
@@ -1,13 +1,76 @@
 use std::rc::Rc;
 
-use common::error::NovaError;
+use common::{error::NovaError, ttype::Width, utilities::format_float};
+use num_traits::ToPrimitive;
+use unicode_segmentation::UnicodeSegmentation;
 use vm::state::{self, Heap, VmData};
 
+pub(crate) fn pop_string(state: &mut state::State) -> Result<Rc<str>, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::String(index)) => match state.get_ref(index) {
+            Heap::String(str) => Ok(str.clone()),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a string in the heap".into(),
+            }),
+        },
+        _ => Err(NovaError::Runtime {
+            msg: "Expected a string on the stack".into(),
+        }),
+    }
+}
+
+pub(crate) fn pop_int(state: &mut state::State) -> Result<i64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Int(value)) => Ok(value),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected an integer on the stack".into(),
+        }),
+    }
+}
+
+pub(crate) fn push_string(state: &mut state::State, str: impl Into<Rc<str>>) {
+    let index = state.allocate_string(str.into());
+    state.stack.push(VmData::String(index));
+}
+
+// a [U8] off the stack
+pub(crate) fn pop_bytes(state: &mut state::State) -> Result<Vec<u8>, NovaError> {
+    let Some(VmData::List(index)) = state.stack.pop() else {
+        return Err(NovaError::Runtime {
+            msg: "Expected a list on the stack".into(),
+        });
+    };
+    let Heap::List(items) = state.get_ref(index) else {
+        return Err(NovaError::Runtime {
+            msg: "Expected a list in the heap".into(),
+        });
+    };
+    Ok(items
+        .iter()
+        .map(|item| match state.to_vmdata(*item) {
+            VmData::Sized(value, _) | VmData::Int(value) => value as u8,
+            _ => 0,
+        })
+        .collect())
+}
+
+pub(crate) fn push_bytes(state: &mut state::State, bytes: &[u8]) {
+    state.gclock = true;
+    let items = bytes
+        .iter()
+        .map(|byte| state.allocate_vmdata_to_heap(VmData::Sized(*byte as i64, Width::U8)))
+        .collect();
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+}
+
+// the length in characters, byte_len gives the size in utf-8
 pub fn strlen(state: &mut state::State) -> Result<(), NovaError> {
     match state.stack.pop() {
         Some(VmData::String(index)) => match state.get_ref(index) {
             Heap::String(str) => {
-                state.stack.push(VmData::Int(str.len() as i64));
+                state.stack.push(VmData::Int(str.chars().count() as i64));
                 Ok(())
             }
             _ => Err(NovaError::Runtime {
@@ -23,6 +86,111 @@ pub fn strlen(state: &mut state::State) -> Result<(), NovaError> {
     }
 }
 
+pub fn byte_len(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    state.stack.push(VmData::Int(str.len() as i64));
+    Ok(())
+}
+
+// the utf-8 of the string
+pub fn to_bytes(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    push_bytes(state, str.as_bytes());
+    Ok(())
+}
+
+// None when the bytes are not utf-8
+pub fn from_bytes(state: &mut state::State) -> Result<(), NovaError> {
+    let bytes = pop_bytes(state)?;
+    match String::from_utf8(bytes) {
+        Ok(str) => push_string(state, str),
+        Err(_) => state.stack.push(state.none()),
+    }
+    Ok(())
+}
+
+// the characters from start up to end, counted in characters like len so a
+// slice never splits one in half
+pub fn slice(state: &mut state::State) -> Result<(), NovaError> {
+    let end = pop_int(state)?;
+    let start = pop_int(state)?;
+    let str = pop_string(state)?;
+    let len = str.chars().count() as i64;
+    if start < 0 || end < start || end > len {
+        return Err(NovaError::Runtime {
+            msg: format!(
+                "Cannot slice {}..{} out of a string of length {}",
+                start, end, len
+            )
+            .into(),
+        });
+    }
+    let sliced: String = str
+        .chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect();
+    push_string(state, sliced);
+    Ok(())
+}
+
+pub fn char_at(state: &mut state::State) -> Result<(), NovaError> {
+    let index = pop_int(state)?;
+    let str = pop_string(state)?;
+    let char = usize::try_from(index)
+        .ok()
+        .and_then(|index| str.chars().nth(index));
+    state.stack.push(char.map_or(state.none(), VmData::Char));
+    Ok(())
+}
+
+// the user-perceived characters, an accented letter written as a letter and
+// a combining mark or a flag made of two code points is one of them
+pub fn graphemes(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    state.gclock = true;
+    let mut graphemes = vec![];
+    for grapheme in str.graphemes(true) {
+        let index = state.allocate_string(grapheme.into());
+        graphemes.push(state.allocate_vmdata_to_heap(VmData::String(index)));
+    }
+    let index = state.allocate_array(graphemes);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
+
+pub fn to_upper(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    push_string(state, str.to_uppercase());
+    Ok(())
+}
+
+pub fn to_lower(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    push_string(state, str.to_lowercase());
+    Ok(())
+}
+
+// whitespace is what unicode calls whitespace, not only spaces and tabs
+pub fn trim(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    push_string(state, str.trim());
+    Ok(())
+}
+
+pub fn trim_start(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    push_string(state, str.trim_start());
+    Ok(())
+}
+
+pub fn trim_end(state: &mut state::State) -> Result<(), NovaError> {
+    let str = pop_string(state)?;
+    push_string(state, str.trim_end());
+    Ok(())
+}
+
 pub fn str_to_chars(state: &mut state::State) -> Result<(), NovaError> {
     match state.stack.pop() {
         Some(VmData::String(index)) => match state.get_ref(index).clone() {
@@ -99,6 +267,68 @@ pub fn chars_to_str(state: &mut state::State) -> Result<(), NovaError> {
     Ok(())
 }
 
+// the strings of a list one after another, in time linear in the result
+pub fn strings_to_str(state: &mut state::State) -> Result<(), NovaError> {
+    join_strings(state, "")
+}
+
+// the strings of a list with the separator between each two
+pub fn join(state: &mut state::State) -> Result<(), NovaError> {
+    let separator = pop_string(state)?;
+    join_strings(state, &separator)
+}
+
+fn join_strings(state: &mut state::State, separator: &str) -> Result<(), NovaError> {
+    let array = match state.stack.pop() {
+        Some(VmData::List(index)) => match state.get_ref(index) {
+            Heap::List(array) => array.clone(),
+            _ => {
+                return Err(NovaError::Runtime {
+                    msg: "Expected a list in the heap".into(),
+                })
+            }
+        },
+        _ => {
+            return Err(NovaError::Runtime {
+                msg: "Expected a list on the stack".into(),
+            })
+        }
+    };
+    let mut str = String::new();
+    for (i, item) in array.into_iter().enumerate() {
+        if i > 0 {
+            str.push_str(separator);
+        }
+        match state.to_vmdata(item) {
+            VmData::String(index) => str.push_str(state.get_ref(index).get_string()),
+            _ => {
+                return Err(NovaError::Runtime {
+                    msg: "Expected a string in the list".into(),
+                })
+            }
+        }
+    }
+    state.gclock = true;
+    push_string(state, str);
+    state.gclock = false;
+    Ok(())
+}
+
+// the string count times over, a negative count is a runtime error
+pub fn repeat(state: &mut state::State) -> Result<(), NovaError> {
+    let count = pop_int(state)?;
+    let str = pop_string(state)?;
+    let Ok(count) = usize::try_from(count) else {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot repeat a string {count} times").into(),
+        });
+    };
+    state.gclock = true;
+    push_string(state, str.repeat(count));
+    state.gclock = false;
+    Ok(())
+}
+
 pub fn to_string(state: &mut state::State) -> Result<(), NovaError> {
     let data = match state.stack.pop() {
         Some(data) => data,
@@ -108,13 +338,21 @@ pub fn to_string(state: &mut state::State) -> Result<(), NovaError> {
             })
         }
     };
+    let string = display(state, data)?;
+    let index = state.allocate_string(string);
+    state.stack.push(VmData::String(index));
+    Ok(())
+}
 
-    let string: Rc<str> = match data {
+// the text Cast::string gives for a value
+pub fn display(state: &state::State, data: VmData) -> Result<Rc<str>, NovaError> {
+    Ok(match data {
         VmData::StackAddress(v) => format!("Stack pointer: {v}").into(),
         VmData::Function(v) => format!("function pointer: {v}").into(),
         VmData::Closure(v) => format!("closure pointer: {v}").into(),
         VmData::Int(v) => format!("{v}").into(),
-        VmData::Float(v) => format!("{v}").into(),
+        VmData::Sized(v, width) => format!("{}", width.value(v)).into(),
+        VmData::Float(v) => format_float(v).into(),
         VmData::Bool(v) => format!("{v}").into(),
         VmData::Char(v) => format!("{v}").into(),
         VmData::List(v) => {
@@ -144,12 +382,9 @@ pub fn to_string(state: &mut state::State) -> Result<(), NovaError> {
             };
             s.clone()
         }
-        VmData::None => "None".into(),
-    };
-
-    let index = state.allocate_string(string);
-    state.stack.push(VmData::String(index));
-    Ok(())
+        VmData::BigInt(v) => state.get_bigint(v)?.to_string().into(),
+        VmData::None(_) => "None".into(),
+    })
 }
 
 pub fn to_int(state: &mut state::State) -> Result<(), NovaError> {
@@ -164,6 +399,13 @@ pub fn to_int(state: &mut state::State) -> Result<(), NovaError> {
 
     let int = match data {
         VmData::Int(value) => value, // Already an integer, no conversion needed
+        VmData::Sized(value, width) => match i64::try_from(width.value(value)) {
+            Ok(value) => value,
+            Err(_) => {
+                state.stack.push(state.none());
+                return Ok(());
+            }
+        },
         VmData::Float(value) => value as i64, // Convert float to integer
         VmData::Bool(value) => {
             if value {
@@ -176,25 +418,32 @@ pub fn to_int(state: &mut state::State) -> Result<(), NovaError> {
             if let Ok(parsed) = value.to_string().parse::<i64>() {
                 parsed
             } else {
-                state.stack.push(VmData::None);
+                state.stack.push(state.none());
                 return Ok(());
             }
         }
+        VmData::BigInt(v) => match state.get_bigint(v)?.to_i64() {
+            Some(value) => value,
+            None => {
+                state.stack.push(state.none());
+                return Ok(());
+            }
+        },
         VmData::String(v) => {
             if let Heap::String(str) = state.get_ref(v) {
                 if let Ok(parsed) = str.parse::<i64>() {
                     parsed
                 } else {
-                    state.stack.push(VmData::None);
+                    state.stack.push(state.none());
                     return Ok(());
                 }
             } else {
-                state.stack.push(VmData::None);
+                state.stack.push(state.none());
                 return Ok(());
             }
         }
         _ => {
-            state.stack.push(VmData::None);
+            state.stack.push(state.none());
             return Ok(());
         }
     };
@@ -0,0 +1,16 @@
+use common::error::NovaError;
+use vm::state::{self, VmData};
+
+// (heap size, live objects, gc collections)
+pub fn mem_stats(state: &mut state::State) -> Result<(), NovaError> {
+    let stats = [state.heap.len(), state.live_objects(), state.gc_count];
+    state.gclock = true;
+    let mut items = vec![];
+    for stat in stats {
+        items.push(state.allocate_vmdata_to_heap(VmData::Int(stat as i64)));
+    }
+    let index = state.allocate_array(items);
+    state.stack.push(VmData::List(index));
+    state.gclock = false;
+    Ok(())
+}
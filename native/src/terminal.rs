@@ -30,7 +30,7 @@ pub fn getch(state: &mut state::State) -> Result<(), NovaError> {
     {
         state.stack.push(VmData::Char(character))
     } else {
-        state.stack.push(VmData::None);
+        state.stack.push(state.none());
     }
     Ok(())
 }
@@ -47,10 +47,10 @@ pub fn rawread(state: &mut state::State) -> Result<(), NovaError> {
             {
                 state.stack.push(VmData::Char(character));
             } else {
-                state.stack.push(VmData::None);
+                state.stack.push(state.none());
             }
         } else {
-            state.stack.push(VmData::None);
+            state.stack.push(state.none());
         }
     }
     Ok(())
@@ -72,21 +72,31 @@ pub fn show_cursor(_state: &mut state::State) -> Result<(), NovaError> {
     Ok(())
 }
 
-pub fn retrieve_command_line_args(state: &mut state::State) -> Result<(), NovaError> {
-    let args = std::env::args().skip(3);
-    let mut myarray = vec![];
+fn allocate_args(state: &mut state::State) -> usize {
     state.gclock = true;
-    let len = args.len();
-    for arg in args {
-        let string_pos = state.allocate_string(arg.into());
+    let mut myarray = vec![];
+    for arg in state.args.clone() {
+        let string_pos = state.allocate_string(arg);
         myarray.push(state.allocate_vmdata_to_heap(VmData::String(string_pos)));
     }
-    if len == 0 {
-        state.stack.push(VmData::None);
+    let index = state.allocate_array(myarray);
+    state.gclock = false;
+    index
+}
+
+pub fn retrieve_command_line_args(state: &mut state::State) -> Result<(), NovaError> {
+    if state.args.is_empty() {
+        state.stack.push(state.none());
     } else {
-        let index = state.allocate_array(myarray);
+        let index = allocate_args(state);
         state.stack.push(VmData::List(index));
     }
-    state.gclock = false;
+    Ok(())
+}
+
+// args() is the same list, empty instead of None when nothing was passed
+pub fn args(state: &mut state::State) -> Result<(), NovaError> {
+    let index = allocate_args(state);
+    state.stack.push(VmData::List(index));
     Ok(())
 }
@@ -3,10 +3,36 @@ use std::{thread, time};
 use common::error::NovaError;
 use vm::state::{self, VmData};
 
+use crate::str::pop_int;
+
+// see State::origin
+fn nanos(state: &state::State) -> i64 {
+    i64::try_from(state.origin.elapsed().as_nanos()).unwrap_or(i64::MAX)
+}
+
 pub fn sleep(state: &mut state::State) -> Result<(), NovaError> {
-    if let Some(VmData::Int(time)) = state.stack.pop() {
-        let delay = time::Duration::from_millis(time as u64);
-        thread::sleep(delay);
+    let ms = pop_int(state)?;
+    if ms < 0 {
+        return Err(NovaError::Runtime {
+            msg: format!("Cannot sleep for a negative time, got {}ms", ms).into(),
+        });
     }
+    thread::sleep(time::Duration::from_millis(ms as u64));
+    Ok(())
+}
+
+// a point in time from a clock that never goes backwards, only good for
+// comparing with another instant
+pub fn instant(state: &mut state::State) -> Result<(), NovaError> {
+    state.stack.push(VmData::Int(nanos(state)));
+    Ok(())
+}
+
+// the nanoseconds since an instant
+pub fn elapsed(state: &mut state::State) -> Result<(), NovaError> {
+    let start = pop_int(state)?;
+    state
+        .stack
+        .push(VmData::Int(nanos(state).saturating_sub(start)));
     Ok(())
 }
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use common::error::NovaError;
+use vm::state::{self, VmData};
+
+fn pop_timer(state: &mut state::State) -> Result<(VmData, Duration), NovaError> {
+    match (state.stack.pop(), state.stack.pop()) {
+        (Some(callback), Some(VmData::Int(ms))) if ms >= 0 => {
+            Ok((callback, Duration::from_millis(ms as u64)))
+        }
+        (Some(_), Some(VmData::Int(ms))) => Err(NovaError::Runtime {
+            msg: format!("Timer delay must not be negative, got {}ms", ms).into(),
+        }),
+        _ => Err(NovaError::Runtime {
+            msg: "Timer expects a delay and a callback".into(),
+        }),
+    }
+}
+
+// runs the callback once, ms after the call
+pub fn after(state: &mut state::State) -> Result<(), NovaError> {
+    let (callback, delay) = pop_timer(state)?;
+    let id = state.add_timer(delay, None, callback);
+    state.stack.push(VmData::Int(id));
+    Ok(())
+}
+
+// runs the callback every ms until the timer is cancelled
+pub fn every(state: &mut state::State) -> Result<(), NovaError> {
+    let (callback, interval) = pop_timer(state)?;
+    if interval.is_zero() {
+        return Err(NovaError::Runtime {
+            msg: "Timer::every needs an interval of at least 1ms".into(),
+        });
+    }
+    let id = state.add_timer(interval, Some(interval), callback);
+    state.stack.push(VmData::Int(id));
+    Ok(())
+}
+
+pub fn cancel(state: &mut state::State) -> Result<(), NovaError> {
+    if let Some(VmData::Int(id)) = state.stack.pop() {
+        state.timers.retain(|timer| timer.id != id);
+    }
+    Ok(())
+}
+
+// cancels the timer whose callback is running
+pub fn stop(state: &mut state::State) -> Result<(), NovaError> {
+    if let Some(id) = state.current_timer {
+        state.timers.retain(|timer| timer.id != id);
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::{
+    cell::RefCell,
+    env,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    path::PathBuf,
+    process::{Child, Command},
+    rc::Rc,
+    thread,
+    time::Duration,
+};
+
+use common::error::NovaError;
+use rand::{distributions::Alphanumeric, Rng};
+use vm::state::{self, VmData, Worker};
+
+use crate::binary;
+
+// a worker is another nova process running a script, connected to the one
+// that spawned it by a local socket so its stdin and stdout stay free. values
+// go over it in the binary format, each one prefixed by its length. the worker
+// proves it is the child that was started by sending the token it was given
+
+// how a worker finds the socket of its parent
+const ADDRESS: &str = "NOVA_WORKER_ADDRESS";
+
+// what a worker sends first so its parent knows the connection is the
+// worker's and not another local program's
+const TOKEN: &str = "NOVA_WORKER_TOKEN";
+const TOKEN_LEN: usize = 32;
+
+// the nova to run a worker with when the host did not set one
+const BINARY: &str = "NOVA_BIN";
+
+// the id a worker uses for its parent
+const PARENT: i64 = 0;
+
+fn pop_int(state: &mut state::State) -> Result<i64, NovaError> {
+    match state.stack.pop() {
+        Some(VmData::Int(value)) => Ok(value),
+        _ => Err(NovaError::Runtime {
+            msg: "Expected an integer".into(),
+        }),
+    }
+}
+
+fn worker_error(id: i64, error: impl std::fmt::Display) -> NovaError {
+    NovaError::Runtime {
+        msg: format!("Worker {}: {}", id, error).into(),
+    }
+}
+
+// the worker with the id, a worker connects to its parent the first time it
+// uses id 0
+fn get_worker(state: &mut state::State, id: i64) -> Result<Rc<RefCell<Worker>>, NovaError> {
+    if id == PARENT && !state.workers.contains_key(&PARENT) {
+        let address = env::var(ADDRESS).map_err(|_| NovaError::Runtime {
+            msg: "This program was not started by Worker::spawn, it has no parent".into(),
+        })?;
+        let token = env::var(TOKEN).unwrap_or_default();
+        let mut stream = TcpStream::connect(&address).map_err(|e| worker_error(id, e))?;
+        stream
+            .write_all(token.as_bytes())
+            .map_err(|e| worker_error(id, e))?;
+        state.workers.insert(
+            PARENT,
+            Rc::new(RefCell::new(Worker {
+                stream,
+                child: None,
+            })),
+        );
+    }
+    match state.workers.get(&id) {
+        Some(worker) => Ok(worker.clone()),
+        None => Err(worker_error(id, "no such worker, it may have been closed")),
+    }
+}
+
+// waits for the worker to connect, giving up if it exits first. a script
+// that does not compile never gets that far. connections that do not start
+// with the token are dropped
+fn accept(listener: &TcpListener, child: &mut Child, token: &str) -> Result<TcpStream, String> {
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    loop {
+        match listener.accept() {
+            Ok((mut stream, _)) => {
+                stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+                stream
+                    .set_read_timeout(Some(Duration::from_secs(5)))
+                    .map_err(|e| e.to_string())?;
+                let mut sent = [0; TOKEN_LEN];
+                if stream.read_exact(&mut sent).is_ok() && sent == token.as_bytes() {
+                    stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+                    return Ok(stream);
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                if let Ok(Some(status)) = child.try_wait() {
+                    return Err(format!("it exited before connecting ({})", status));
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
+
+// the nova the host set, then NOVA_BIN, then nova on the PATH. the program
+// running the vm may not be nova at all when it is embedded
+fn nova_binary(state: &state::State) -> PathBuf {
+    state
+        .nova_binary
+        .clone()
+        .or_else(|| env::var_os(BINARY).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("nova"))
+}
+
+// starts `nova run` on the script and returns the worker's id
+pub fn spawn(state: &mut state::State) -> Result<(), NovaError> {
+    let path = match state.stack.pop() {
+        Some(VmData::String(index)) => state.get_ref(index).get_string().to_string(),
+        _ => {
+            return Err(NovaError::Runtime {
+                msg: "Expected a string".into(),
+            })
+        }
+    };
+    let error = |reason: String| NovaError::Runtime {
+        msg: format!("Could not spawn a worker for {}: {}", path, reason).into(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| error(e.to_string()))?;
+    let address = listener.local_addr().map_err(|e| error(e.to_string()))?;
+    let token: String = rand::thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect();
+    let mut child = Command::new(nova_binary(state))
+        .arg("run")
+        .arg(&path)
+        .env(ADDRESS, address.to_string())
+        .env(TOKEN, &token)
+        .spawn()
+        .map_err(|e| error(e.to_string()))?;
+    let stream = accept(&listener, &mut child, &token).map_err(error)?;
+    let id = state.add_worker(Worker {
+        stream,
+        child: Some(child),
+    });
+    state.stack.push(VmData::Int(id));
+    Ok(())
+}
+
+// sends a value to a worker, or with id 0 from a worker to its parent
+pub fn send(state: &mut state::State) -> Result<(), NovaError> {
+    let value = state.stack.pop().ok_or_else(|| NovaError::Runtime {
+        msg: "Expected a value".into(),
+    })?;
+    let id = pop_int(state)?;
+    let bytes = binary::encode(state, value)?;
+    let mut message = (bytes.len() as u32).to_le_bytes().to_vec();
+    message.extend_from_slice(&bytes);
+    get_worker(state, id)?
+        .borrow_mut()
+        .stream
+        .write_all(&message)
+        .map_err(|e| worker_error(id, e))
+}
+
+// a message and its length, None when the connection was closed before one
+fn read_message(stream: &mut TcpStream) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    match stream.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) if error.kind() == io::ErrorKind::ConnectionReset => return Ok(None),
+        Err(error) => return Err(error),
+    }
+    let mut message = vec![0; u32::from_le_bytes(length) as usize];
+    stream.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+// blocks until the next value arrives, None once the other side has closed
+// the connection or exited
+pub fn recv(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    let worker = get_worker(state, id)?;
+    let message = read_message(&mut worker.borrow_mut().stream).map_err(|e| worker_error(id, e))?;
+    let value = match message {
+        Some(message) => binary::decode(state, &message)?,
+        None => state.none(),
+    };
+    state.stack.push(value);
+    Ok(())
+}
+
+// closes the connection and waits for the worker to exit, its recv returns
+// None from then on
+pub fn close(state: &mut state::State) -> Result<(), NovaError> {
+    let id = pop_int(state)?;
+    if let Some(worker) = state.workers.remove(&id) {
+        let mut worker = worker.borrow_mut();
+        // the worker may have closed its end already
+        let _ = worker.stream.shutdown(Shutdown::Both);
+        if let Some(child) = worker.child.as_mut() {
+            child.wait().map_err(|e| worker_error(id, e))?;
+        }
+    }
+    Ok(())
+}
+
+// true in a program started by Worker::spawn
+pub fn is_worker(state: &mut state::State) -> Result<(), NovaError> {
+    state
+        .stack
+        .push(VmData::Bool(env::var_os(ADDRESS).is_some()));
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::{fmt, path::PathBuf};
+
+use common::{
+    error::{NovaError, Warning},
+    fileposition::FilePosition,
+    ttype::describe_type,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Kind {
+    // a file could not be read
+    File,
+    // the source could not be tokenized or parsed, which includes most type
+    // errors since nova typechecks while parsing
+    Parse,
+    Type,
+    Compile,
+    Runtime,
+    // the program was stopped from outside
+    Interrupted,
+    // nova itself crashed, a bug in nova rather than in the program
+    Internal,
+    // a problem that did not stop the build
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    // None for source that did not come from a file
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub column: usize,
+}
+
+// an error or warning from building or running a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub kind: Kind,
+    pub message: String,
+    pub note: Option<String>,
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    fn new(
+        kind: Kind,
+        message: impl Into<String>,
+        note: Option<String>,
+        at: Option<&FilePosition>,
+    ) -> Self {
+        Diagnostic {
+            kind,
+            message: message.into(),
+            note: note.filter(|note| !note.is_empty()),
+            location: at.map(|position| Location {
+                file: position.filepath.as_deref().map(PathBuf::from),
+                line: position.line,
+                column: position.col,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            if let Some(file) = &location.file {
+                write!(f, "{}:", file.display())?;
+            }
+            write!(f, "{}:{}: ", location.line, location.column)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(note) = &self.note {
+            write!(f, " ({})", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl From<NovaError> for Diagnostic {
+    fn from(error: NovaError) -> Self {
+        match error {
+            NovaError::File { msg } => Diagnostic::new(Kind::File, msg, None, None),
+            NovaError::Lexing {
+                msg,
+                note,
+                position,
+            }
+            | NovaError::Parsing {
+                msg,
+                note,
+                position,
+                ..
+            } => Diagnostic::new(Kind::Parse, msg, Some(note.into()), Some(&position)),
+            NovaError::Compiler { msg, note } => {
+                Diagnostic::new(Kind::Compile, msg, Some(note.into()), None)
+            }
+            NovaError::Runtime { msg } => Diagnostic::new(Kind::Runtime, msg, None, None),
+            NovaError::RuntimeWithPos { msg, position } => {
+                Diagnostic::new(Kind::Runtime, msg, None, Some(&position))
+            }
+            NovaError::TypeError {
+                msg,
+                expected,
+                found,
+                position,
+            } => Diagnostic::new(
+                Kind::Type,
+                msg,
+                Some(format!("expected {}, found {}", expected, found)),
+                Some(&position),
+            ),
+            NovaError::TypeMismatch {
+                expected,
+                found,
+                expected_alias,
+                found_alias,
+                position,
+            } => Diagnostic::new(
+                Kind::Type,
+                "Type mismatch",
+                Some(format!(
+                    "expected {}, found {}",
+                    describe_type(&expected, expected_alias.as_deref()),
+                    describe_type(&found, found_alias.as_deref())
+                )),
+                Some(&position),
+            ),
+            NovaError::SimpleTypeError { msg, position } => {
+                Diagnostic::new(Kind::Type, msg, None, Some(&position))
+            }
+            NovaError::Interrupted => Diagnostic::new(Kind::Interrupted, "interrupted", None, None),
+            NovaError::Internal {
+                phase,
+                msg,
+                position,
+            } => Diagnostic::new(
+                Kind::Internal,
+                msg,
+                Some(format!("nova crashed while {}", phase)),
+                position.as_ref(),
+            ),
+        }
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        Diagnostic::new(
+            Kind::Warning,
+            warning.msg.clone(),
+            Some(warning.note.to_string()),
+            Some(&warning.position),
+        )
+    }
+}
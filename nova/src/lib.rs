@@ -0,0 +1,262 @@
+//! The stable way to embed nova. The other crates of the workspace are free
+//! to change between releases, this one only grows.
+//!
+//! ```
+//! use nova::{Nova, Type, Value};
+//!
+//! let mut nova = Nova::new();
+//! nova.register_fn("double", &[Type::Int], Type::Int, |args| match args {
+//!     [Value::Int(n)] => Ok(Value::Int(n * 2)),
+//!     _ => Err("expected an Int".into()),
+//! });
+//! nova.run("println(double(21))")?;
+//! assert_eq!(nova.eval("double(4) + 1")?, Value::Int(9));
+//! # Ok::<(), nova::Diagnostic>(())
+//! ```
+
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use common::{
+    error::NovaError,
+    nodes::{Atom, Expr, Statement, SymbolKind},
+    ttype::TType,
+};
+use novacore::{plugin::Plugin, NovaCore, WarningLevel};
+use vm::state::State;
+
+mod diagnostic;
+mod value;
+
+pub use diagnostic::{Diagnostic, Kind, Location};
+pub use value::{Type, Value};
+
+type HostFn = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+// eval builds its expression as the argument of a call to this
+const EVAL: &str = "__eval__";
+
+// where eval leaves the value of its expression
+type EvalResult = Rc<RefCell<Option<Value>>>;
+
+#[derive(Clone)]
+struct HostFunction {
+    name: String,
+    parameters: Vec<Type>,
+    returns: Type,
+    function: HostFn,
+}
+
+// the host functions and anything else a build needs added to its core
+struct Host {
+    functions: Vec<HostFunction>,
+    result: Option<EvalResult>,
+}
+
+impl Plugin for Host {
+    fn name(&self) -> &str {
+        "host"
+    }
+
+    fn register(&self, core: &mut NovaCore) {
+        for host in self.functions.iter() {
+            if let Some((module, _)) = host.name.rsplit_once("::") {
+                core.add_module(module);
+            }
+            let parameters = if host.parameters.is_empty() {
+                vec![TType::None]
+            } else {
+                host.parameters.iter().map(Type::ttype).collect()
+            };
+            let arity = host.parameters.len();
+            let returns_value = host.returns != Type::Void;
+            let function = host.function.clone();
+            core.add_function(
+                &host.name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(host.returns.ttype()),
+                },
+                SymbolKind::Function,
+                move |state: &mut State| {
+                    let at = state
+                        .stack
+                        .len()
+                        .checked_sub(arity)
+                        .ok_or(NovaError::Runtime {
+                            msg: "Not enough arguments for a host function".into(),
+                        })?;
+                    let arguments: Vec<Value> = state
+                        .stack
+                        .drain(at..)
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .map(|data| Value::read(state, data))
+                        .collect();
+                    let value = function(&arguments)
+                        .map_err(|msg| NovaError::Runtime { msg: msg.into() })?;
+                    if returns_value {
+                        state.gclock = true;
+                        let data = value.write(state);
+                        state.gclock = false;
+                        state.stack.push(data?);
+                    }
+                    Ok(())
+                },
+            );
+        }
+        if let Some(result) = self.result.clone() {
+            core.add_function(
+                EVAL,
+                TType::Function {
+                    parameters: vec![TType::Generic { name: "a".into() }],
+                    return_type: Box::new(TType::Void),
+                },
+                SymbolKind::GenericFunction,
+                move |state: &mut State| {
+                    if let Some(data) = state.stack.pop() {
+                        *result.borrow_mut() = Some(Value::read(state, data));
+                    }
+                    Ok(())
+                },
+            );
+        }
+    }
+}
+
+// builds and runs nova programs with the host functions registered on it
+#[derive(Clone, Default)]
+pub struct Nova {
+    functions: Vec<HostFunction>,
+    search_paths: Vec<PathBuf>,
+}
+
+impl Nova {
+    pub fn new() -> Nova {
+        Nova::default()
+    }
+
+    // a directory imports are looked up in, before the program's own folder
+    pub fn add_search_path(&mut self, directory: impl Into<PathBuf>) {
+        self.search_paths.push(directory.into());
+    }
+
+    // makes a Rust function callable from nova. the arguments arrive in the
+    // declared types and an Err stops the program with a runtime error. a name
+    // like "Host::log" puts the function in a module
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        parameters: &[Type],
+        returns: Type,
+        function: impl Fn(&[Value]) -> Result<Value, String> + 'static,
+    ) {
+        self.functions.push(HostFunction {
+            name: name.into(),
+            parameters: parameters.to_vec(),
+            returns,
+            function: Rc::new(function),
+        });
+    }
+
+    fn prepare(&self, mut core: NovaCore, result: Option<EvalResult>) -> NovaCore {
+        core.set_warnings(WarningLevel::None);
+        for directory in self.search_paths.iter() {
+            core.add_search_path(directory.clone());
+        }
+        core.add_plugin(Rc::new(Host {
+            functions: self.functions.clone(),
+            result,
+        }));
+        core
+    }
+
+    fn build(&self, core: NovaCore) -> Result<Program, Diagnostic> {
+        let mut core = self.prepare(core, None);
+        core.build()?;
+        let warnings = core.parser.warnings.iter().map(Diagnostic::from).collect();
+        Ok(Program { core, warnings })
+    }
+
+    // builds a script, it needs no module declaration
+    pub fn compile(&self, source: &str) -> Result<Program, Diagnostic> {
+        self.build(NovaCore::from_script(source))
+    }
+
+    // builds a file and the files it imports
+    pub fn compile_file(&self, path: impl AsRef<Path>) -> Result<Program, Diagnostic> {
+        self.build(NovaCore::new(path.as_ref())?)
+    }
+
+    pub fn run(&self, source: &str) -> Result<(), Diagnostic> {
+        self.compile(source)?.run()
+    }
+
+    // the value of a single expression. tuples come back as Value::Tuple,
+    // the values of structs and enums as lists of their fields
+    pub fn eval(&self, expression: &str) -> Result<Value, Diagnostic> {
+        let result = Rc::new(RefCell::new(None));
+        let source = format!("{}({})", EVAL, expression);
+        let mut core = self.prepare(NovaCore::from_script(&source), Some(result.clone()));
+        core.build().map_err(unwrap_eval)?;
+        let ttype = eval_type(&core.parser.ast.program);
+        core.run_built().map_err(unwrap_eval)?;
+        let value = result.borrow_mut().take().unwrap_or(Value::None);
+        Ok(match ttype {
+            Some(ttype) => value.shape(&ttype),
+            None => value,
+        })
+    }
+}
+
+// the type of the expression eval wrapped, its call is the last statement
+fn eval_type(program: &[Statement]) -> Option<TType> {
+    match program.last()? {
+        Statement::Expression {
+            expr:
+                Expr::Literal {
+                    value: Atom::Call { arguments, .. },
+                    ..
+                },
+            ..
+        } => arguments.first().map(Expr::get_type),
+        _ => None,
+    }
+}
+
+// a position on the first line counts the call eval wrapped the expression in
+fn unwrap_eval(error: NovaError) -> Diagnostic {
+    let mut diagnostic = Diagnostic::from(error);
+    if let Some(location) = diagnostic.location.as_mut() {
+        if location.file.is_none() && location.line == 1 {
+            location.column = location.column.saturating_sub(EVAL.len() + 1).max(1);
+        }
+    }
+    diagnostic
+}
+
+// a built program, ready to run
+pub struct Program {
+    core: NovaCore,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Program {
+    // what the build found that did not stop it
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    pub fn run(self) -> Result<(), Diagnostic> {
+        self.run_status().map(|_| ())
+    }
+
+    // runs the program and returns the status it passed to exit, 0 when it
+    // ran to the end
+    pub fn run_status(self) -> Result<i32, Diagnostic> {
+        Ok(self.core.run_built()?)
+    }
+}
@@ -0,0 +1,133 @@
+use common::{error::NovaError, ttype::TType};
+use vm::state::{Heap, State, VmData};
+
+// a nova value copied out of the vm. structs and enums come out as lists of
+// their fields with their type name first, so do tuples where the type of the
+// value is not known, as in the arguments of a host function
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    // an arbitrary precision integer in decimal
+    BigInt(String),
+    // a function or closure, it cannot be called from the host
+    Function,
+    None,
+}
+
+// the types host functions take and return
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    Char,
+    String,
+    List(Box<Type>),
+    Option(Box<Type>),
+    Void,
+}
+
+impl Type {
+    pub(crate) fn ttype(&self) -> TType {
+        match self {
+            Type::Int => TType::Int,
+            Type::Float => TType::Float,
+            Type::Bool => TType::Bool,
+            Type::Char => TType::Char,
+            Type::String => TType::String,
+            Type::List(inner) => TType::List {
+                inner: Box::new(inner.ttype()),
+            },
+            Type::Option(inner) => TType::Option {
+                inner: Box::new(inner.ttype()),
+            },
+            Type::Void => TType::Void,
+        }
+    }
+}
+
+impl Value {
+    pub(crate) fn read(state: &State, data: VmData) -> Value {
+        match data {
+            VmData::Int(value) => Value::Int(value),
+            VmData::Sized(value, _) => Value::Int(value),
+            VmData::Float(value) => Value::Float(value),
+            VmData::Bool(value) => Value::Bool(value),
+            VmData::Char(value) => Value::Char(value),
+            VmData::String(index) => Value::String(state.get_ref(index).get_string().to_string()),
+            VmData::BigInt(index) => match state.get_bigint(index) {
+                Ok(value) => Value::BigInt(value.to_string()),
+                Err(_) => Value::None,
+            },
+            VmData::List(index) | VmData::Struct(index) => match state.get_ref(index) {
+                Heap::List(items) => Value::List(
+                    items
+                        .iter()
+                        .map(|item| Value::read(state, state.to_vmdata(*item)))
+                        .collect(),
+                ),
+                _ => Value::None,
+            },
+            VmData::Function(_) | VmData::Closure(_) | VmData::StackAddress(_) => Value::Function,
+            VmData::None(_) => Value::None,
+        }
+    }
+
+    // turns the lists read out of tuples of ttype back into tuples
+    pub(crate) fn shape(self, ttype: &TType) -> Value {
+        match (self, ttype) {
+            (Value::List(items), TType::Tuple { elements }) => Value::Tuple(
+                items
+                    .into_iter()
+                    .zip(elements)
+                    .map(|(item, ttype)| item.shape(ttype))
+                    .collect(),
+            ),
+            (Value::List(items), TType::List { inner }) => {
+                Value::List(items.into_iter().map(|item| item.shape(inner)).collect())
+            }
+            (value, TType::Option { inner }) => value.shape(inner),
+            (value, _) => value,
+        }
+    }
+
+    pub(crate) fn write(self, state: &mut State) -> Result<VmData, NovaError> {
+        Ok(match self {
+            Value::Int(value) => VmData::Int(value),
+            Value::Float(value) => VmData::Float(value),
+            Value::Bool(value) => VmData::Bool(value),
+            Value::Char(value) => VmData::Char(value),
+            Value::String(value) => VmData::String(state.allocate_string(value.into())),
+            Value::List(items) | Value::Tuple(items) => {
+                let mut indices = Vec::with_capacity(items.len());
+                for item in items {
+                    let item = item.write(state)?;
+                    indices.push(state.allocate_vmdata_to_heap(item));
+                }
+                VmData::List(state.allocate_array(indices))
+            }
+            Value::BigInt(digits) => match digits.parse() {
+                Ok(value) => VmData::BigInt(state.allocate_bigint(value)),
+                Err(_) => {
+                    return Err(NovaError::Runtime {
+                        msg: format!("{} is not an integer", digits).into(),
+                    })
+                }
+            },
+            Value::Function => {
+                return Err(NovaError::Runtime {
+                    msg: "A host function cannot return a function".into(),
+                })
+            }
+            Value::None => state.none(),
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nova::{Kind, Nova, Type, Value};
+
+#[test]
+fn register_fn() {
+    let seen = Rc::new(RefCell::new(vec![]));
+    let mut nova = Nova::new();
+    let log = seen.clone();
+    nova.register_fn("Host::log", &[Type::String], Type::Void, move |args| {
+        log.borrow_mut().extend(args.iter().cloned());
+        Ok(Value::None)
+    });
+    nova.register_fn("double", &[Type::Int], Type::Int, |args| match args {
+        [Value::Int(n)] => Ok(Value::Int(n * 2)),
+        _ => Err("expected an Int".into()),
+    });
+    nova.run("Host::log(\"twice \" + Cast::string(double(21)))")
+        .unwrap();
+    assert_eq!(*seen.borrow(), [Value::String("twice 42".into())]);
+}
+
+#[test]
+fn host_errors_stop_the_program() {
+    let mut nova = Nova::new();
+    nova.register_fn("fail", &[], Type::Int, |_| Err("no".into()));
+    let error = nova.run("let x = fail()").unwrap_err();
+    assert_eq!(error.kind, Kind::Runtime);
+    assert_eq!(error.message, "no");
+}
+
+#[test]
+fn run() {
+    let nova = Nova::new();
+    nova.run("let x = 1 + 2\nassert(x == 3)").unwrap();
+}
+
+#[test]
+fn eval() {
+    let nova = Nova::new();
+    assert_eq!(nova.eval("1 + 2").unwrap(), Value::Int(3));
+    assert_eq!(
+        nova.eval("[1, 2]").unwrap(),
+        Value::List(vec![Value::Int(1), Value::Int(2)])
+    );
+    assert_eq!(
+        nova.eval("(1, \"a\")").unwrap(),
+        Value::Tuple(vec![Value::Int(1), Value::String("a".into())])
+    );
+    assert_eq!(
+        nova.eval("[(1, 'b')]").unwrap(),
+        Value::List(vec![Value::Tuple(vec![Value::Int(1), Value::Char('b')])])
+    );
+}
+
+#[test]
+fn diagnostics() {
+    let nova = Nova::new();
+    let error = nova.run("let x = 1\nlet y: String = x").unwrap_err();
+    assert_eq!(error.kind, Kind::Parse);
+    assert_eq!(error.message, "Cannot assign Int to String");
+    let location = error.location.expect("type errors have a location");
+    assert_eq!(
+        (location.file, location.line, location.column),
+        (None, 2, 5)
+    );
+}
+
+#[test]
+fn eval_diagnostics_point_into_the_expression() {
+    let nova = Nova::new();
+    let error = nova.eval("1 + missing").unwrap_err();
+    let location = error.location.expect("unknown names have a location");
+    assert_eq!((location.line, location.column), (1, 5));
+}
+
+#[test]
+fn run_status() {
+    let nova = Nova::new();
+    assert_eq!(nova.compile("let x = 1").unwrap().run_status().unwrap(), 0);
+    assert_eq!(nova.compile("exit(3)").unwrap().run_status().unwrap(), 3);
+}
@@ -0,0 +1,292 @@
+// nova completions bash|zsh|fish|powershell prints a script that completes the
+// commands, flags and files of the cli. new commands and flags go in the
+// tables here so every shell picks them up
+
+// the commands with what they do, in the order the help lists them
+const COMMANDS: [(&str, &str); 17] = [
+    ("run", "run the file"),
+    ("dbg", "debug the file"),
+    ("time", "time the file"),
+    ("check", "check if the file compiles"),
+    ("dis", "disassemble the file"),
+    ("install", "fetch the dependencies in nova.toml"),
+    ("add", "add a dependency to nova.toml"),
+    ("fix", "apply the suggested fixes"),
+    ("test", "run the test functions"),
+    ("bench", "time the bench functions"),
+    ("graph", "print the import and call graphs"),
+    ("doc", "print the docs of a module"),
+    ("rename", "rename a symbol everywhere it is used"),
+    ("config", "print the effective configuration"),
+    ("help", "display the help menu"),
+    ("repl", "start the repl"),
+    ("completions", "print a shell completion script"),
+];
+
+// the commands that take a .nv file
+const FILE_COMMANDS: [&str; 10] = [
+    "run", "dbg", "dis", "time", "check", "test", "bench", "graph", "doc", "rename",
+];
+
+// flags any command takes, a trailing = means a value follows
+const FLAGS: [(&str, &str); 10] = [
+    ("--release", "strip contract checks"),
+    ("--timings", "print how long each phase took"),
+    ("--gc-stats", "print garbage collector stats on exit"),
+    ("--trace=", "write trace events to stderr"),
+    ("--lib=", "look for imports in a folder first"),
+    ("--gc-threshold=", "heap size the collector first runs at"),
+    ("--stack-limit=", "most nested calls"),
+    ("--overflow-checks", "stop when Int arithmetic overflows"),
+    ("--warnings=", "all, none or error"),
+    ("--plugin=", "load a native plugin library"),
+];
+
+// flags only one command takes
+const COMMAND_FLAGS: [(&str, &[&str]); 7] = [
+    ("fix", &["--dry-run"]),
+    ("test", &["--filter="]),
+    ("bench", &["--filter="]),
+    ("rename", &["--dry-run"]),
+    ("graph", &["--format"]),
+    ("doc", &["--format"]),
+    (
+        "add",
+        &["--git=", "--rev=", "--version=", "--url=", "--path="],
+    ),
+];
+
+// what --format takes for each command that has it
+const FORMATS: [(&str, &str); 2] = [("graph", "dot text"), ("doc", "markdown html")];
+
+fn formats(command: &str) -> &'static str {
+    FORMATS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or("", |(_, formats)| formats)
+}
+
+pub const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+
+pub fn script(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        "powershell" => Some(powershell()),
+        _ => None,
+    }
+}
+
+fn names(list: &[(&str, &str)]) -> String {
+    list.iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bash() -> String {
+    let mut command_flags = String::new();
+    for (command, flags) in COMMAND_FLAGS {
+        command_flags += &format!("        {}) extra=\"{}\" ;;\n", command, flags.join(" "));
+    }
+    let mut format_values = String::new();
+    for (command, formats) in FORMATS {
+        format_values += &format!("                {}) extra=\"{}\" ;;\n", command, formats);
+    }
+    format!(
+        r#"# nova completions bash > ~/.local/share/bash-completion/completions/nova
+_nova() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    if [ "$COMP_CWORD" -eq 1 ]; then
+        COMPREPLY=($(compgen -W "{commands}" -- "$cur"))
+        return
+    fi
+    case "$prev" in
+        --format)
+            local extra=""
+            case "${{COMP_WORDS[1]}}" in
+{format_values}            esac
+            COMPREPLY=($(compgen -W "$extra" -- "$cur"))
+            return ;;
+    esac
+    if [ "${{COMP_WORDS[1]}}" = completions ]; then
+        COMPREPLY=($(compgen -W "{shells}" -- "$cur"))
+        return
+    fi
+    if [[ "$cur" == -* ]]; then
+        local extra=""
+        case "${{COMP_WORDS[1]}}" in
+{command_flags}        esac
+        COMPREPLY=($(compgen -W "{flags} $extra" -- "$cur"))
+        # a flag that takes a value goes on right after its =
+        [[ "${{COMPREPLY[0]}}" == *= ]] && compopt -o nospace
+        return
+    fi
+    case "${{COMP_WORDS[1]}}" in
+        {files}) COMPREPLY=($(compgen -f -X '!*.nv' -- "$cur") $(compgen -d -- "$cur")) ;;
+    esac
+}}
+complete -o filenames -F _nova nova
+"#,
+        commands = names(&COMMANDS),
+        shells = SHELLS.join(" "),
+        flags = names(&FLAGS),
+        files = FILE_COMMANDS.join("|"),
+        format_values = format_values,
+    )
+}
+
+fn zsh() -> String {
+    let commands: Vec<String> = COMMANDS
+        .iter()
+        .map(|(name, about)| format!("        '{}:{}'", name, about))
+        .collect();
+    let flags: Vec<String> = FLAGS
+        .iter()
+        .map(|(flag, about)| match flag.strip_suffix('=') {
+            Some(flag) => format!("        '{}=[{}]:value:'", flag, about),
+            None => format!("        '{}[{}]'", flag, about),
+        })
+        .collect();
+    let mut command_flags = String::new();
+    for (command, extra) in COMMAND_FLAGS {
+        let extra: Vec<String> = extra
+            .iter()
+            .map(|flag| match (*flag, flag.strip_suffix('=')) {
+                ("--format", _) => {
+                    format!("'--format[output format]:format:({})'", formats(command))
+                }
+                (_, Some(flag)) => format!("'{}=[{}]:value:'", flag, &flag[2..]),
+                (flag, None) => format!("'{}[{}]'", flag, &flag[2..]),
+            })
+            .collect();
+        command_flags += &format!("        {}) flags+=({}) ;;\n", command, extra.join(" "));
+    }
+    format!(
+        r#"#compdef nova
+# nova completions zsh > "${{fpath[1]}}/_nova"
+_nova() {{
+    local -a commands flags
+    commands=(
+{commands}
+    )
+    flags=(
+{flags}
+    )
+    if (( CURRENT == 2 )); then
+        _describe 'command' commands
+        return
+    fi
+    case $words[2] in
+        completions) _values 'shell' {shells}; return ;;
+{command_flags}    esac
+    case $words[2] in
+        {files}) _arguments -s $flags '*:file:_files -g "*.nv"' ;;
+        *) _arguments -s $flags ;;
+    esac
+}}
+if [ "$funcstack[1]" = "_nova" ]; then
+    _nova "$@"
+else
+    compdef _nova nova
+fi
+"#,
+        commands = commands.join("\n"),
+        flags = flags.join("\n"),
+        shells = SHELLS.join(" "),
+        command_flags = command_flags,
+        files = FILE_COMMANDS.join("|"),
+    )
+}
+
+fn fish() -> String {
+    let mut out = String::from(
+        "# nova completions fish > ~/.config/fish/completions/nova.fish\ncomplete -c nova -f\n",
+    );
+    for (name, about) in COMMANDS {
+        out += &format!(
+            "complete -c nova -n __fish_use_subcommand -a {} -d '{}'\n",
+            name, about
+        );
+    }
+    out += &format!(
+        "complete -c nova -n '__fish_seen_subcommand_from {}' -F\n",
+        FILE_COMMANDS.join(" ")
+    );
+    out += &format!(
+        "complete -c nova -n '__fish_seen_subcommand_from completions' -xa '{}'\n",
+        SHELLS.join(" ")
+    );
+    for (flag, about) in FLAGS {
+        out += &match flag.strip_suffix('=') {
+            Some("--warnings") => format!(
+                "complete -c nova -l warnings -xa 'all none error' -d '{}'\n",
+                about
+            ),
+            Some(flag) => format!("complete -c nova -l {} -r -d '{}'\n", &flag[2..], about),
+            None => format!("complete -c nova -l {} -d '{}'\n", &flag[2..], about),
+        };
+    }
+    for (command, flags) in COMMAND_FLAGS {
+        for flag in flags {
+            let condition = format!("-n '__fish_seen_subcommand_from {}'", command);
+            out += &match (*flag, flag.strip_suffix('=')) {
+                ("--format", _) => format!(
+                    "complete -c nova {} -l format -xa '{}'\n",
+                    condition,
+                    formats(command)
+                ),
+                (_, Some(flag)) => format!("complete -c nova {} -l {} -r\n", condition, &flag[2..]),
+                (flag, None) => format!("complete -c nova {} -l {}\n", condition, &flag[2..]),
+            };
+        }
+    }
+    out
+}
+
+fn powershell() -> String {
+    let quoted = |names: Vec<&str>| {
+        names
+            .iter()
+            .map(|name| format!("'{}'", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut command_flags = String::new();
+    for (command, flags) in COMMAND_FLAGS {
+        command_flags += &format!(
+            "        '{}' {{ $candidates += @({}) }}\n",
+            command,
+            quoted(flags.to_vec())
+        );
+    }
+    format!(
+        r#"# nova completions powershell | Out-String | Invoke-Expression
+Register-ArgumentCompleter -Native -CommandName nova -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements | ForEach-Object {{ $_.ToString() }})
+    if ($words.Count -eq 1 -or ($words.Count -eq 2 -and $wordToComplete)) {{
+        $candidates = @({commands})
+    }} elseif ($words[1] -eq 'completions') {{
+        $candidates = @({shells})
+    }} elseif ($wordToComplete -like '-*') {{
+        $candidates = @({flags})
+        switch ($words[1]) {{
+{command_flags}        }}
+    }} else {{
+        # the files of the folder
+        return
+    }}
+    $candidates | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }}
+}}
+"#,
+        commands = quoted(COMMANDS.iter().map(|(name, _)| *name).collect()),
+        shells = quoted(SHELLS.to_vec()),
+        flags = quoted(FLAGS.iter().map(|(flag, _)| *flag).collect()),
+        command_flags = command_flags,
+    )
+}
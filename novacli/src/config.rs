@@ -0,0 +1,397 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use common::error::NovaError;
+use novacore::{
+    plugin::{self, Plugin},
+    NovaCore, WarningLevel,
+};
+use serde::Deserialize;
+
+// settings for the vm and the build, each one taken from the highest layer
+// that sets it: defaults < config file < environment variables < flags
+//
+//     # ~/.config/nova/config.toml, or the file named by NOVA_CONFIG
+//     gc-stats = false
+//     gc-threshold = 100000
+//     stack-limit = 10000
+//     overflow-checks = true
+//     warnings = "error"
+//     paths = ["~/nova/std"]
+//     plugins = ["~/nova/plugins/libsqlite.so"]
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub gc_stats: Setting<bool>,
+    pub gc_threshold: Setting<usize>,
+    pub stack_limit: Setting<usize>,
+    pub overflow_checks: Setting<bool>,
+    pub warnings: Setting<WarningLevel>,
+    // directories imports are looked up in, earlier entries win
+    pub paths: Vec<Setting<PathBuf>>,
+    // native plugin libraries loaded before anything is built
+    pub plugins: Vec<Setting<PathBuf>>,
+    // what load_plugins found in them, apply gives them to every core
+    loaded: Vec<Rc<dyn Plugin>>,
+    // the config file that was read, if any
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+// the layer a setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    File,
+    Env(&'static str),
+    Flag(&'static str),
+    Project,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ConfigFile {
+    gc_stats: Option<bool>,
+    gc_threshold: Option<usize>,
+    stack_limit: Option<usize>,
+    overflow_checks: Option<bool>,
+    warnings: Option<String>,
+    #[serde(default)]
+    paths: Vec<PathBuf>,
+    #[serde(default)]
+    plugins: Vec<PathBuf>,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T) -> Setting<T> {
+        Setting {
+            value,
+            source: Source::Default,
+        }
+    }
+
+    fn set(&mut self, value: T, source: Source) {
+        self.value = value;
+        self.source = source;
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "config file"),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::Flag(name) => write!(f, "flag {}", name),
+            Source::Project => write!(f, "nova.toml"),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let vm = vm::Config::default();
+        Config {
+            gc_stats: Setting::new(vm.gc_stats),
+            gc_threshold: Setting::new(vm.gc_threshold),
+            stack_limit: Setting::new(vm.stack_limit),
+            overflow_checks: Setting::new(vm.overflow_checks),
+            warnings: Setting::new(WarningLevel::default()),
+            paths: vec![],
+            plugins: vec![],
+            loaded: vec![],
+            file: None,
+        }
+    }
+}
+
+impl Config {
+    // the defaults overridden by the config file and then the environment,
+    // flags are applied on top with apply_flag
+    pub fn load() -> Result<Config, NovaError> {
+        let mut config = Config::default();
+        if let Some(path) = config_file() {
+            if path.is_file() {
+                config.read_file(&path)?;
+                config.file = Some(path);
+            }
+        }
+        config.read_env()?;
+        Ok(config)
+    }
+
+    fn read_file(&mut self, path: &Path) -> Result<(), NovaError> {
+        let text = std::fs::read_to_string(path).map_err(|error| NovaError::File {
+            msg: format!("Could not read {}: {}", path.display(), error).into(),
+        })?;
+        let file: ConfigFile = toml::from_str(&text).map_err(|error| NovaError::File {
+            msg: format!(
+                "Invalid config file {}: {}",
+                path.display(),
+                error.message()
+            )
+            .into(),
+        })?;
+        if let Some(gc_stats) = file.gc_stats {
+            self.gc_stats.set(gc_stats, Source::File);
+        }
+        if let Some(threshold) = file.gc_threshold {
+            self.gc_threshold.set(threshold, Source::File);
+        }
+        if let Some(limit) = file.stack_limit {
+            self.stack_limit.set(limit, Source::File);
+        }
+        if let Some(overflow_checks) = file.overflow_checks {
+            self.overflow_checks.set(overflow_checks, Source::File);
+        }
+        if let Some(level) = file.warnings {
+            self.warnings.set(warning_level(&level)?, Source::File);
+        }
+        // later layers are searched first, so the file's paths go last
+        let base = path.parent().unwrap_or(Path::new(""));
+        self.paths.extend(file.paths.iter().map(|dir| Setting {
+            value: base.join(expand_home(dir)),
+            source: Source::File,
+        }));
+        self.plugins
+            .extend(file.plugins.iter().map(|library| Setting {
+                value: base.join(expand_home(library)),
+                source: Source::File,
+            }));
+        Ok(())
+    }
+
+    fn read_env(&mut self) -> Result<(), NovaError> {
+        if let Some(value) = env("NOVA_GC_STATS") {
+            let gc_stats = parse_bool(&value).ok_or_else(|| invalid("NOVA_GC_STATS", &value))?;
+            self.gc_stats.set(gc_stats, Source::Env("NOVA_GC_STATS"));
+        }
+        if let Some(value) = env("NOVA_GC_THRESHOLD") {
+            let threshold = value
+                .parse()
+                .map_err(|_| invalid("NOVA_GC_THRESHOLD", &value))?;
+            self.gc_threshold
+                .set(threshold, Source::Env("NOVA_GC_THRESHOLD"));
+        }
+        if let Some(value) = env("NOVA_STACK_LIMIT") {
+            let limit = value
+                .parse()
+                .map_err(|_| invalid("NOVA_STACK_LIMIT", &value))?;
+            self.stack_limit.set(limit, Source::Env("NOVA_STACK_LIMIT"));
+        }
+        if let Some(value) = env("NOVA_OVERFLOW_CHECKS") {
+            let overflow_checks =
+                parse_bool(&value).ok_or_else(|| invalid("NOVA_OVERFLOW_CHECKS", &value))?;
+            self.overflow_checks
+                .set(overflow_checks, Source::Env("NOVA_OVERFLOW_CHECKS"));
+        }
+        if let Some(value) = env("NOVA_WARNINGS") {
+            self.warnings
+                .set(warning_level(&value)?, Source::Env("NOVA_WARNINGS"));
+        }
+        if let Some(nova_path) = std::env::var_os("NOVA_PATH") {
+            let paths = std::env::split_paths(&nova_path).map(|dir| Setting {
+                value: dir,
+                source: Source::Env("NOVA_PATH"),
+            });
+            self.paths.splice(0..0, paths);
+        }
+        if let Some(nova_plugins) = std::env::var_os("NOVA_PLUGINS") {
+            self.plugins
+                .extend(std::env::split_paths(&nova_plugins).map(|library| Setting {
+                    value: library,
+                    source: Source::Env("NOVA_PLUGINS"),
+                }));
+        }
+        Ok(())
+    }
+
+    // applies a command line flag, returns false if it is not a config flag
+    pub fn apply_flag(&mut self, flag: &str) -> Result<bool, NovaError> {
+        if flag == "--gc-stats" {
+            self.gc_stats.set(true, Source::Flag("--gc-stats"));
+        } else if let Some(value) = flag.strip_prefix("--gc-threshold=") {
+            let threshold = value
+                .parse()
+                .map_err(|_| invalid("--gc-threshold", value))?;
+            self.gc_threshold
+                .set(threshold, Source::Flag("--gc-threshold"));
+        } else if let Some(value) = flag.strip_prefix("--stack-limit=") {
+            let limit = value.parse().map_err(|_| invalid("--stack-limit", value))?;
+            self.stack_limit.set(limit, Source::Flag("--stack-limit"));
+        } else if flag == "--overflow-checks" {
+            self.overflow_checks
+                .set(true, Source::Flag("--overflow-checks"));
+        } else if let Some(value) = flag.strip_prefix("--warnings=") {
+            self.warnings
+                .set(warning_level(value)?, Source::Flag("--warnings"));
+        } else if let Some(value) = flag.strip_prefix("--plugin=") {
+            self.plugins.push(Setting {
+                value: PathBuf::from(value),
+                source: Source::Flag("--plugin"),
+            });
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    // --lib directories are searched before every other layer
+    pub fn add_flag_path(&mut self, directory: PathBuf) {
+        let flags = self
+            .paths
+            .iter()
+            .take_while(|path| path.source == Source::Flag("--lib"))
+            .count();
+        self.paths.insert(
+            flags,
+            Setting {
+                value: directory,
+                source: Source::Flag("--lib"),
+            },
+        );
+    }
+
+    // project sources and dependencies rank between flags and the environment
+    pub fn add_project_paths(&mut self, directories: Vec<PathBuf>) {
+        let flags = self
+            .paths
+            .iter()
+            .take_while(|path| matches!(path.source, Source::Flag(_) | Source::Project))
+            .count();
+        let paths = directories.into_iter().map(|value| Setting {
+            value,
+            source: Source::Project,
+        });
+        self.paths.splice(flags..flags, paths);
+    }
+
+    // every layer's plugins are loaded, they only add functions
+    pub fn load_plugins(&mut self) -> Result<(), NovaError> {
+        for library in self.plugins.iter() {
+            self.loaded.push(plugin::load(&library.value)?);
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, novacore: &mut NovaCore) {
+        novacore.set_gc_stats(self.gc_stats.value);
+        novacore.set_gc_threshold(self.gc_threshold.value);
+        novacore.set_stack_limit(self.stack_limit.value);
+        novacore.set_overflow_checks(self.overflow_checks.value);
+        novacore.set_warnings(self.warnings.value);
+        for path in self.paths.iter() {
+            novacore.add_search_path(&path.value);
+        }
+        for plugin in self.loaded.iter() {
+            novacore.add_plugin(plugin.clone());
+        }
+        // workers run on the same nova as the program spawning them
+        if let Ok(nova) = std::env::current_exe() {
+            novacore.set_nova_binary(nova);
+        }
+    }
+
+    // the effective configuration, printed by nova config
+    pub fn show(&self) {
+        match (&self.file, config_file()) {
+            (Some(file), _) => println!("config file     {}", file.display()),
+            (None, Some(file)) => println!("config file     {} (not found)", file.display()),
+            (None, None) => println!("config file     none"),
+        }
+        println!();
+        show_setting("gc-stats", &self.gc_stats.value, &self.gc_stats.source);
+        show_setting(
+            "gc-threshold",
+            &self.gc_threshold.value,
+            &self.gc_threshold.source,
+        );
+        show_setting(
+            "stack-limit",
+            &self.stack_limit.value,
+            &self.stack_limit.source,
+        );
+        show_setting(
+            "overflow-checks",
+            &self.overflow_checks.value,
+            &self.overflow_checks.source,
+        );
+        show_setting(
+            "warnings",
+            &self.warnings.value.name(),
+            &self.warnings.source,
+        );
+        if self.paths.is_empty() {
+            println!("{:<16}{:<40}", "paths", "none");
+        }
+        for (index, path) in self.paths.iter().enumerate() {
+            let name = if index == 0 { "paths" } else { "" };
+            show_setting(name, &path.value.display(), &path.source);
+        }
+        if self.plugins.is_empty() {
+            println!("{:<16}{:<40}", "plugins", "none");
+        }
+        for (index, library) in self.plugins.iter().enumerate() {
+            let name = if index == 0 { "plugins" } else { "" };
+            show_setting(name, &library.value.display(), &library.source);
+        }
+    }
+}
+
+fn show_setting(name: &str, value: &dyn Display, source: &Source) {
+    println!("{:<16}{:<40}({})", name, value.to_string(), source);
+}
+
+// NOVA_CONFIG, or config.toml in the user's config folder
+fn config_file() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("NOVA_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("nova").join("config.toml"))
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+// an unset or empty variable does not override the layers below it
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn warning_level(level: &str) -> Result<WarningLevel, NovaError> {
+    WarningLevel::parse(level).ok_or_else(|| NovaError::File {
+        msg: format!(
+            "Unknown warning level '{}', expected all, none or error",
+            level
+        )
+        .into(),
+    })
+}
+
+fn invalid(name: &str, value: &str) -> NovaError {
+    NovaError::File {
+        msg: format!("Invalid value '{}' for {}", value, name).into(),
+    }
+}
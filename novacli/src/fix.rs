@@ -0,0 +1,216 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::error::{apply_fixes, NovaError};
+use novacore::NovaCore;
+
+use crate::config::Config;
+
+// a parse error stops at the first problem, so a file is checked again after
+// each round of fixes until nothing changes
+const MAX_ROUNDS: usize = 32;
+
+// lines kept around each change in a dry run diff
+const CONTEXT: usize = 3;
+
+// applies the quick fixes of every .nv file under the paths, with dry_run the
+// files are left alone and the changes are printed as a diff instead
+pub fn fix(paths: &[PathBuf], dry_run: bool, config: &Config) -> Result<(), NovaError> {
+    let mut files = vec![];
+    for path in paths {
+        collect_files(path, &mut files)?;
+    }
+    let (mut changed, mut failed) = (0, 0);
+    for file in files.iter() {
+        let source = fs::read_to_string(file).map_err(|error| NovaError::File {
+            msg: format!("Could not read {}: {}", file.display(), error).into(),
+        })?;
+        let (fixed, applied, error) = fix_source(&source, file, config);
+        if let Some(error) = error {
+            error.show();
+            failed += 1;
+        }
+        if fixed == source {
+            continue;
+        }
+        changed += 1;
+        if dry_run {
+            print!("{}", diff(&source, &fixed, file));
+        } else {
+            fs::write(file, &fixed).map_err(|error| NovaError::File {
+                msg: format!("Could not write {}: {}", file.display(), error).into(),
+            })?;
+            println!("Fixed {} ({} fixes)", file.display(), applied);
+        }
+    }
+    let verb = if dry_run { "would change" } else { "changed" };
+    println!("{} of {} files {}", changed, files.len(), verb);
+    if failed > 0 {
+        return Err(NovaError::File {
+            msg: format!("{} files still have errors nova fix cannot fix", failed).into(),
+        });
+    }
+    Ok(())
+}
+
+// the source with its fixes applied, how many were applied and the error that
+// is left over if the file still does not parse
+fn fix_source(source: &str, file: &Path, config: &Config) -> (String, usize, Option<NovaError>) {
+    let mut source = source.to_owned();
+    let mut applied = 0;
+    for _ in 0..MAX_ROUNDS {
+        let mut novacore = NovaCore::from_source(&source, file);
+        config.apply(&mut novacore);
+        let fixes = match novacore.fixes() {
+            Ok(fixes) => fixes,
+            Err(error) => return (source, applied, Some(error)),
+        };
+        // fixes for imported files are made when those files are fixed
+        let fixes: Vec<_> = fixes
+            .into_iter()
+            .filter(|fix| fix.start.filepath.as_deref() == Some(file))
+            .collect();
+        let fixed = apply_fixes(&source, &fixes);
+        if fixed == source {
+            break;
+        }
+        applied += fixes.len();
+        source = fixed;
+    }
+    (source, applied, None)
+}
+
+// the path itself or every .nv file below it, hidden folders are skipped
+pub(crate) fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), NovaError> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let entries = fs::read_dir(path).map_err(|error| NovaError::File {
+        msg: format!("Could not read {}: {}", path.display(), error).into(),
+    })?;
+    let mut entries: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if entry.is_dir() && !hidden {
+            collect_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|extension| extension == "nv") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// a unified diff of the two sources
+pub(crate) fn diff(old: &str, new: &str, file: &Path) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+    // the line each entry starts at in the old and the new source
+    let mut starts = Vec::with_capacity(lines.len() + 1);
+    let (mut old_line, mut new_line) = (1, 1);
+    for line in lines.iter() {
+        starts.push((old_line, new_line));
+        match line {
+            Line::Same(_) => (old_line, new_line) = (old_line + 1, new_line + 1),
+            Line::Removed(_) => old_line += 1,
+            Line::Added(_) => new_line += 1,
+        }
+    }
+    starts.push((old_line, new_line));
+
+    let changes: Vec<usize> = (0..lines.len())
+        .filter(|index| !matches!(lines[*index], Line::Same(_)))
+        .collect();
+    let mut out = format!("--- {}\n+++ {}\n", file.display(), file.display());
+    let mut index = 0;
+    while index < changes.len() {
+        // changes close enough to share their context go in one hunk
+        let mut last = index;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT + 1 {
+            last += 1;
+        }
+        let start = changes[index].saturating_sub(CONTEXT);
+        let end = (changes[last] + CONTEXT + 1).min(lines.len());
+        let (old_start, new_start) = starts[start];
+        let (old_end, new_end) = starts[end];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start,
+            old_end - old_start,
+            new_start,
+            new_end - new_start
+        ));
+        for line in lines[start..end].iter() {
+            match line {
+                Line::Same(text) => out.push_str(&format!(" {}\n", text)),
+                Line::Removed(text) => out.push_str(&format!("-{}\n", text)),
+                Line::Added(text) => out.push_str(&format!("+{}\n", text)),
+            }
+        }
+        index = last + 1;
+    }
+    out
+}
+
+// the shortest edit from old to new, lines both share at the start and the
+// end are left out of the search since fixes only touch a few lines
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    // longest common subsequence of old_middle[i..] and new_middle[j..]
+    let mut common = vec![vec![0; new_middle.len() + 1]; old_middle.len() + 1];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            common[i][j] = if old_middle[i] == new_middle[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|line| Line::Same(line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() || j < new_middle.len() {
+        if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+            lines.push(Line::Same(old_middle[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < old_middle.len()
+            && (j == new_middle.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(Line::Removed(old_middle[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new_middle[j]));
+            j += 1;
+        }
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| Line::Same(line)),
+    );
+    lines
+}
@@ -1,5 +1,7 @@
 use common::error::NovaError;
-use novacore::NovaCore;
+use config::Config;
+use novacore::{NovaCore, ShowLimits};
+use project::{Project, Source};
 use rand::Rng;
 use reedline::{
     default_emacs_keybindings, ColumnarMenu, DefaultCompleter, DefaultPromptSegment,
@@ -11,58 +13,350 @@ use std::{
     path::{Path, PathBuf},
     process::exit,
 };
+
+mod completions;
+mod config;
+mod fix;
+mod package;
+mod project;
+mod rename;
+mod testing;
+
 fn main() {
-    if entry_command().is_none() {
-        print_help();
-        // TODO: add a repl
+    // a panic the phases of novacore did not catch still ends as an internal
+    // error instead of a rust panic message
+    common::error::quiet_panics();
+    match common::error::catch_panic(entry_command) {
+        Ok(Some(())) => {}
+        Ok(None) => print_help(),
+        Err(msg) => {
+            let error = common::error::internal_error("running the cli", msg, None);
+            error.show();
+            exit(error.exit_code());
+        }
     }
 }
 
 fn entry_command() -> Option<()> {
-    let mut args = std::env::args();
-    args.next(); // Skip the file path
+    let args: Vec<String> = std::env::args().skip(1).collect(); // Skip the file path
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(error) => {
+            error.show();
+            exit(error.exit_code());
+        }
+    };
+    let mut flags = Flags {
+        release: false,
+        timings: false,
+        trace: None,
+        args: vec![],
+    };
+    let mut rest = vec![];
+    // everything after the script path, or after --, goes to the script
+    let mut forward = false;
+    for arg in args {
+        if forward {
+            flags.args.push(arg);
+            continue;
+        }
+        match arg.as_str() {
+            "--" => forward = true,
+            "--release" => flags.release = true,
+            "--timings" => flags.timings = true,
+            "--trace" => flags.trace = Some(String::new()),
+            _ if arg.starts_with("--trace=") => flags.trace = Some(arg[8..].to_string()),
+            _ if arg.starts_with("--lib=") => config.add_flag_path(PathBuf::from(&arg[6..])),
+            _ => match config.apply_flag(&arg) {
+                Ok(true) => {}
+                Ok(false) => {
+                    rest.push(arg);
+                    forward = rest.len() == 2 && matches!(rest[0].as_str(), "run" | "dbg" | "time");
+                }
+                Err(error) => {
+                    error.show();
+                    exit(error.exit_code());
+                }
+            },
+        }
+    }
+    let mut args = rest.into_iter();
     let command = args.next()?;
+    if command != "config" {
+        if let Err(error) = config.load_plugins() {
+            error.show();
+            exit(error.exit_code());
+        }
+    }
+    // nova graph and nova doc take --format dot or --format=dot anywhere after
+    // them, the first format is the default
+    let formats: &[&str] = match command.as_str() {
+        "graph" => &["dot", "text"],
+        "doc" => &["markdown", "html"],
+        _ => &[],
+    };
+    let mut format = formats.first().copied().unwrap_or_default().to_string();
+    if !formats.is_empty() {
+        let mut positional = vec![];
+        while let Some(arg) = args.next() {
+            if arg == "--format" {
+                format = args.next()?;
+            } else if let Some(value) = arg.strip_prefix("--format=") {
+                format = value.to_string();
+            } else {
+                positional.push(arg);
+            }
+        }
+        if !formats.contains(&format.as_str()) {
+            eprintln!(
+                "Unknown format {} for nova {}, use {}",
+                format,
+                command,
+                formats.join(" or ")
+            );
+            exit(2);
+        }
+        args = positional.into_iter();
+    }
+    let mut file = None;
+    if matches!(
+        command.as_str(),
+        "run" | "dbg" | "dis" | "time" | "check" | "graph" | "doc" | "rename"
+    ) {
+        file = args.next().map(PathBuf::from);
+        // inside a project the file can be left out to build its entry point
+        let project = match discover_project(file.as_deref()) {
+            Ok(project) => project,
+            Err(error) => {
+                error.show();
+                exit(error.exit_code());
+            }
+        };
+        if let Some(project) = project {
+            file = file.or_else(|| Some(project.entry()));
+            match project.search_paths() {
+                Ok(paths) => config.add_project_paths(paths),
+                Err(error) => {
+                    error.show();
+                    exit(error.exit_code());
+                }
+            }
+        }
+    }
 
     let handle_error = |result: Result<(), NovaError>| {
         if let Err(e) = result {
             e.show();
-            exit(1);
+            exit(e.exit_code());
         }
     };
 
-    let execute_command = |filepath: &Path, action: fn(NovaCore) -> Result<(), NovaError>| {
-        let novacore = compile_file_or_exit(filepath);
-        handle_error(action(novacore));
+    // a program that ran ends the cli with the status it passed to exit
+    let handle_status = |result: Result<i32, NovaError>| match result {
+        Ok(0) => {}
+        Ok(status) => exit(status),
+        Err(e) => {
+            e.show();
+            exit(e.exit_code());
+        }
+    };
+
+    let execute_command = |filepath: &Path, action: fn(NovaCore) -> Result<i32, NovaError>| {
+        let novacore = compile_file_or_exit(filepath, &flags, &config);
+        handle_status(action(novacore));
     };
 
     match command.as_str() {
-        "run" => execute_command(args.next().as_ref().map(Path::new)?, NovaCore::run),
-        "dbg" => execute_command(args.next().as_ref().map(Path::new)?, NovaCore::run_debug),
-        "dis" => execute_command(args.next().as_ref().map(Path::new)?, NovaCore::dis_file),
+        "run" => execute_command(file.as_deref()?, NovaCore::run),
+        "dbg" => execute_command(file.as_deref()?, NovaCore::run_debug),
+        "dis" => {
+            let novacore = compile_file_or_exit(file.as_deref()?, &flags, &config);
+            handle_error(novacore.dis_file());
+        }
         "time" => {
-            let filepath: PathBuf = args.next()?.into();
-            let novacore = compile_file_or_exit(&filepath);
+            let filepath = file?;
+            let novacore = compile_file_or_exit(&filepath, &flags, &config);
             let start_time = std::time::Instant::now();
             let execution_result = novacore.run();
             println!("Execution time: {}ms", start_time.elapsed().as_millis());
-            handle_error(execution_result);
+            handle_status(execution_result);
         }
         "check" => {
-            let filepath: PathBuf = args.next()?.into();
+            let filepath = file?;
             let start_time = std::time::Instant::now();
-            let novacore = compile_file_or_exit(&filepath);
+            let novacore = compile_file_or_exit(&filepath, &flags, &config);
             handle_error(novacore.check());
             println!("OK | Compile time: {}ms", start_time.elapsed().as_millis());
         }
-        "repl" => repl_session(),
+        "fix" => {
+            let mut dry_run = false;
+            let mut paths = vec![];
+            for arg in args {
+                match arg.as_str() {
+                    "--dry-run" => dry_run = true,
+                    _ => paths.push(PathBuf::from(arg)),
+                }
+            }
+            // the whole project, or the working directory outside of one
+            let project = discover_project(None).unwrap_or_else(|error| {
+                error.show();
+                exit(error.exit_code());
+            });
+            if let Some(project) = &project {
+                handle_error(
+                    project
+                        .search_paths()
+                        .map(|paths| config.add_project_paths(paths)),
+                );
+            }
+            if paths.is_empty() {
+                paths.push(match project {
+                    Some(project) => project.root,
+                    None => std::env::current_dir().unwrap_or_default(),
+                });
+            }
+            handle_error(fix::fix(&paths, dry_run, &config));
+        }
+        "test" | "bench" => {
+            let mut filter = String::new();
+            let mut paths = vec![];
+            for arg in args {
+                match arg.strip_prefix("--filter=") {
+                    Some(text) => filter = text.to_string(),
+                    None => paths.push(PathBuf::from(arg)),
+                }
+            }
+            // the whole project, or the working directory outside of one
+            let project = discover_project(None).unwrap_or_else(|error| {
+                error.show();
+                exit(error.exit_code());
+            });
+            if let Some(project) = &project {
+                handle_error(
+                    project
+                        .search_paths()
+                        .map(|paths| config.add_project_paths(paths)),
+                );
+            }
+            if paths.is_empty() {
+                paths.push(match project {
+                    Some(project) => project.root,
+                    None => std::env::current_dir().unwrap_or_default(),
+                });
+            }
+            handle_status(match command.as_str() {
+                "test" => testing::test(&paths, &filter, &config),
+                _ => testing::bench(&paths, &filter, &config),
+            });
+        }
+        "graph" => {
+            let filepath = file?;
+            let novacore = compile_file_or_exit(&filepath, &flags, &config);
+            let graph = novacore.graph().unwrap_or_else(|error| {
+                error.show();
+                exit(error.exit_code());
+            });
+            if format == "dot" {
+                print!("{}", graph.dot(&filepath));
+            } else {
+                print!("{}", graph.text(&filepath));
+            }
+        }
+        "doc" => {
+            let filepath = file?;
+            let novacore = compile_file_or_exit(&filepath, &flags, &config);
+            let docs = novacore.docs().unwrap_or_else(|error| {
+                error.show();
+                exit(error.exit_code());
+            });
+            if format == "markdown" {
+                print!("{}", docs.markdown());
+            } else {
+                print!("{}", docs.html());
+            }
+        }
+        "rename" => {
+            let filepath = file?;
+            let mut dry_run = false;
+            let mut positional = vec![];
+            for arg in args {
+                match arg.as_str() {
+                    "--dry-run" => dry_run = true,
+                    _ => positional.push(arg),
+                }
+            }
+            let [at, new_name] = positional.as_slice() else {
+                eprintln!("nova rename needs a file, a line:col and the new name");
+                exit(2);
+            };
+            handle_error(rename::rename(&filepath, at, new_name, dry_run, &config));
+        }
+        "repl" => repl_session(&config),
+        "completions" => {
+            let shell = args.next()?;
+            match completions::script(&shell) {
+                Some(script) => print!("{}", script),
+                None => {
+                    eprintln!(
+                        "Unknown shell {}, use {}",
+                        shell,
+                        completions::SHELLS.join(", ")
+                    );
+                    exit(2);
+                }
+            }
+        }
+        "config" => config.show(),
+        "install" => handle_error(package::install(&current_project_or_exit())),
+        "add" => {
+            let name = args.next()?;
+            let mut source = Source::default();
+            for option in args {
+                if let Some(git) = option.strip_prefix("--git=") {
+                    source.git = Some(git.into());
+                } else if let Some(rev) = option.strip_prefix("--rev=") {
+                    source.rev = Some(rev.into());
+                } else if let Some(version) = option.strip_prefix("--version=") {
+                    source.version = Some(version.into());
+                } else if let Some(url) = option.strip_prefix("--url=") {
+                    source.url = Some(url.into());
+                } else if let Some(path) = option.strip_prefix("--path=") {
+                    source.path = Some(path.into());
+                } else {
+                    eprintln!("Unknown option {} for nova add", option);
+                    exit(2);
+                }
+            }
+            let sources = [
+                source.path.is_some(),
+                source.git.is_some(),
+                source.url.is_some(),
+            ];
+            let pinned = source.rev.is_some() as usize + source.version.is_some() as usize;
+            if sources.iter().filter(|given| **given).count() != 1
+                || pinned > 1
+                || (pinned == 1 && source.git.is_none())
+            {
+                eprintln!(
+                    "nova add needs one of --git=url [--rev=rev | --version=req], --url=archive or --path=dir"
+                );
+                exit(2);
+            }
+            handle_error(package::add(&current_project_or_exit(), &name, source));
+        }
         _ => print_help(),
     }
 
     Some(())
 }
 
-fn repl_session() -> ! {
-    let mut novarepl = NovaCore::repl();
+fn repl_session(config: &Config) -> ! {
+    let new_session = || {
+        let mut novarepl = NovaCore::repl();
+        config.apply(&mut novarepl);
+        novarepl
+    };
+    let mut novarepl = new_session();
     // print pretty welcome message in ascii art
     let banners = [
         r#"
@@ -199,6 +493,7 @@ fn repl_session() -> ! {
         "banner".into(),
         "back".into(),
         "ast".into(),
+        "set".into(),
         // common functions
         "println".into(),
     ];
@@ -226,6 +521,7 @@ fn repl_session() -> ! {
 
     let mut prompt = DefaultPrompt::default();
     let mut states = vec![novarepl.clone()];
+    let mut limits = ShowLimits::default();
     prompt.left_prompt = DefaultPromptSegment::Basic(format!("Session: {}  $", states.len()));
     prompt.right_prompt = DefaultPromptSegment::WorkingDirectory;
     loop {
@@ -235,6 +531,29 @@ fn repl_session() -> ! {
                 line_editor.sync_history().unwrap();
                 io::stdout().flush().unwrap();
                 //dbg!(line.clone());
+                // commands can start with a colon too, as in :save session.nv
+                if let Some(command) = line.strip_prefix(':') {
+                    line = command.to_string();
+                }
+                novarepl.show_limits = limits;
+                // set depth 3 or set width 100 changes how much of a value is printed
+                if let ["set", setting, value] = line.split_whitespace().collect::<Vec<_>>()[..] {
+                    match (setting, value.parse::<usize>()) {
+                        ("depth", Ok(depth)) => limits.depth = depth,
+                        ("width", Ok(width)) => limits.width = width,
+                        _ => println!("Usage: set depth [num] or set width [num]"),
+                    }
+                    continue;
+                }
+                // print full shows the whole value without keeping the line
+                if let Some(expr) = line.strip_prefix("print full ") {
+                    let mut once = novarepl.clone();
+                    once.show_limits = ShowLimits::FULL;
+                    if let Err(e) = once.run_line(&format!("{expr}\n"), false) {
+                        e.show_without_position();
+                    }
+                    continue;
+                }
                 match line.as_str() {
                     "show" => {
                         // print current session
@@ -251,7 +570,7 @@ fn repl_session() -> ! {
                     }
                     "new" => {
                         states.clear();
-                        novarepl = NovaCore::repl();
+                        novarepl = new_session();
                         states.push(novarepl.clone());
                         prompt.left_prompt =
                             DefaultPromptSegment::Basic(format!("Session: {}  $", states.len()));
@@ -300,8 +619,10 @@ fn repl_session() -> ! {
                         }
                         // save to file
                         if pline.starts_with("save") {
-                            let file = pline.split_whitespace().collect::<Vec<&str>>()[1];
-                            // save the current session to a file
+                            let Some(file) = pline.split_whitespace().nth(1) else {
+                                println!("Usage: save [file]");
+                                continue;
+                            };
                             // check if the file exists
                             if std::path::Path::new(file).exists() {
                                 println!("File already exists, do you want to overwrite it? (y/n)");
@@ -311,9 +632,13 @@ fn repl_session() -> ! {
                                     continue;
                                 }
                             }
-                            let mut file = std::fs::File::create(file).unwrap();
-                            file.write_all(b"module repl\n\n").unwrap();
-                            file.write_all(novarepl.current_repl.as_bytes()).unwrap();
+                            // only the definitions, the lines that printed or
+                            // ran something are left out
+                            let source = format!("module repl\n\n{}", novarepl.repl_definitions);
+                            match std::fs::write(file, source) {
+                                Ok(_) => println!("Saved the session to {}", file),
+                                Err(e) => println!("Could not save to {}: {}", file, e),
+                            }
                             continue;
                         }
 
@@ -415,13 +740,60 @@ fn repl_session() -> ! {
 fn print_help() {
     println!("Nova 0.1.0: by pyrotek45\n");
     println!("HELP MENU");
-    println!("\trun   [file]  // runs the file using the nova vm");
+    println!("\trun   [file] [args]  // runs the file using the nova vm, inside a project the file defaults to its entry");
+    println!(
+        "\t              // the arguments after the file, or after --, are what args() returns"
+    );
     println!("\tdbg   [file]  // debug the file");
     println!("\ttime  [file]  // time the file");
     println!("\tcheck [file]  // check if the file compiles");
     println!("\tdis   [file]  // disassemble the file");
+    println!("\tinstall       // fetch the git and url dependencies in nova.toml");
+    println!(
+        "\tadd [name] --git=[url] [--rev=[rev] | --version=[req]] | --url=[archive] | --path=[dir]"
+    );
+    println!("\t              // add a dependency to nova.toml and install it");
+    println!("\tfix [paths] [--dry-run]");
+    println!("\t              // apply the suggested fixes and renames to the project or the given files,");
+    println!("\t              // --dry-run prints them as a diff instead");
+    println!("\ttest [paths] [--filter=text]");
+    println!("\t              // run the test_ functions of the project or the given files, each on its own");
+    println!("\tbench [paths] [--filter=text]");
+    println!("\t              // time the bench_ functions of the project or the given files in ns per call");
+    println!("\tgraph [file] [--format dot|text]");
+    println!("\t              // print the import graph and the call graph of the program, dot is for graphviz");
+    println!("\tdoc [file] [--format markdown|html]");
+    println!("\t              // print the pub functions, structs and enums of a module with their /// comments");
+    println!("\trename [file] [line:col] [name] [--dry-run]");
+    println!("\t              // rename the function, variable, field or variant at line:col everywhere it is used,");
+    println!("\t              // --dry-run prints the changes as a diff instead");
+    println!("\tconfig        // print the effective configuration and where each value came from");
     println!("\thelp          // displays this menu");
     println!("\trepl          // starts the repl");
+    println!("\tcompletions bash|zsh|fish|powershell");
+    println!("\t              // print a script that completes nova commands, flags and files in the shell");
+    println!("\nFLAGS");
+    println!("\t--release     // strip requires/ensures contract checks");
+    println!(
+        "\t--timings     // print how long each compiler phase took, no optimization pass runs yet"
+    );
+    println!("\t--gc-stats    // print heap and garbage collector stats on exit");
+    println!("\t--trace=calls,allocs,gc  // write chrome trace events to stderr as json lines");
+    println!("\t--lib=[dir]   // look for imports in dir before NOVA_PATH and the file's folder");
+    println!("\t--gc-threshold=[n]  // heap size the garbage collector first runs at");
+    println!("\t--stack-limit=[n]   // most nested calls before the script is stopped");
+    println!("\t--overflow-checks   // stop with an error when Int arithmetic overflows");
+    println!("\t--warnings=all|none|error  // show, hide or fail the build on warnings");
+    println!("\t--plugin=[library]  // load a native plugin library before building");
+    println!("\nEXIT CODES");
+    println!("\t0    // success");
+    println!("\t1    // the program failed while running");
+    println!("\t2    // the program could not be built, or nova was called wrong");
+    println!("\t101  // nova itself crashed, please report it");
+    println!("\nCONFIG");
+    println!("\tsettings come from defaults < config file < environment < flags");
+    println!("\tconfig file  // NOVA_CONFIG or ~/.config/nova/config.toml");
+    println!("\tenvironment  // NOVA_GC_STATS, NOVA_GC_THRESHOLD, NOVA_STACK_LIMIT, NOVA_OVERFLOW_CHECKS, NOVA_WARNINGS, NOVA_PATH, NOVA_PLUGINS");
     // repl mode commands
     println!("\nREPL MODE COMMANDS");
     println!("\tshow           // show the current session");
@@ -430,11 +802,15 @@ fn print_help() {
     println!("\tnew            // start a new session");
     println!("\thelp           // display this menu");
     println!("\tsession [num]  // switch to a session");
-    println!("\tsave [file]    // save the current session to a file");
+    println!("\tsave [file]    // save the functions, types, lets and imports of the session as a script");
     println!("\tkeep [code]    // keep the current session");
     println!("\tbanner         // print a random banner");
     println!("\tast [code]     // print the ast of the code");
     println!("\tback           // go back to the previous session");
+    println!("\tset depth [num] // print values nested up to num deep, deeper ones as ...");
+    println!("\tset width [num] // print up to num characters of a value");
+    println!("\tprint full [code] // print the whole value of the code");
+    println!("\t               // commands can start with a colon, as in :save session.nv");
 
     // print size of vm data enum and heap
 
@@ -447,12 +823,56 @@ fn print_help() {
     println!("--------------------------------");
 }
 
-fn compile_file_or_exit(file: &Path) -> NovaCore {
+// command line flags that apply to every command that builds a file, the
+// settings that can also come from a config file live in Config
+struct Flags {
+    release: bool,
+    timings: bool,
+    trace: Option<String>,
+    args: Vec<String>,
+}
+
+// the project of the file being built, or of the working directory when
+// no file was given
+fn discover_project(file: Option<&Path>) -> Result<Option<Project>, NovaError> {
+    match file.and_then(Path::parent) {
+        Some(directory) if !directory.as_os_str().is_empty() => Project::discover(directory),
+        _ => Project::discover(&std::env::current_dir().unwrap_or_default()),
+    }
+}
+
+fn current_project_or_exit() -> Project {
+    match Project::discover(&std::env::current_dir().unwrap_or_default()) {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            eprintln!("No {} in this directory or any parent", project::MANIFEST);
+            exit(2);
+        }
+        Err(error) => {
+            error.show();
+            exit(error.exit_code());
+        }
+    }
+}
+
+fn compile_file_or_exit(file: &Path, flags: &Flags, config: &Config) -> NovaCore {
     match novacore::NovaCore::new(file) {
-        Ok(novacore) => novacore,
+        Ok(mut novacore) => {
+            novacore.set_release(flags.release);
+            novacore.set_timings(flags.timings);
+            novacore.set_args(flags.args.clone());
+            config.apply(&mut novacore);
+            if let Some(categories) = &flags.trace {
+                if let Err(msg) = novacore.set_trace(categories) {
+                    eprintln!("{}", msg);
+                    exit(2);
+                }
+            }
+            novacore
+        }
         Err(error) => {
             error.show();
-            exit(1);
+            exit(error.exit_code());
         }
     }
 }
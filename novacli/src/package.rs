@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use common::error::NovaError;
+use semver::{Version, VersionReq};
+use toml_edit::{DocumentMut, InlineTable, Item, Table};
+
+use crate::project::{Project, Source};
+
+// fetched packages live in NOVA_HOME/packages, ~/.nova/packages by default
+fn home() -> Result<PathBuf, NovaError> {
+    if let Some(home) = std::env::var_os("NOVA_HOME") {
+        return Ok(PathBuf::from(home));
+    }
+    match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        Some(home) => Ok(PathBuf::from(home).join(".nova")),
+        None => Err(NovaError::File {
+            msg: "Could not find a home directory for the package cache, set NOVA_HOME".into(),
+        }),
+    }
+}
+
+// one folder per name and source, so two revisions of a package can be
+// cached side by side
+pub fn cache_dir(name: &str, source: &Source) -> Result<PathBuf, NovaError> {
+    let key = match (&source.git, &source.version) {
+        (Some(git), Some(version)) => format!("git:{}@{}", git, version),
+        (Some(git), None) => format!("git:{}#{}", git, source.rev.as_deref().unwrap_or("")),
+        (None, _) => format!("url:{}", source.url.as_deref().unwrap_or("")),
+    };
+    Ok(home()?
+        .join("packages")
+        .join(format!("{}-{:016x}", name, fnv1a(&key))))
+}
+
+// stable across builds unlike the std hasher, the cache outlives the binary
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// downloads every git and url dependency that is not in the cache yet
+pub fn install(project: &Project) -> Result<(), NovaError> {
+    for (name, dependency) in project.manifest.dependencies.iter() {
+        let source = dependency.source();
+        source.check(name)?;
+        if source.path.is_some() {
+            continue;
+        }
+        let directory = cache_dir(name, &source)?;
+        if directory.is_dir() {
+            println!("{} is up to date", name);
+            continue;
+        }
+        let rev = match (&source.git, &source.version) {
+            (Some(git), Some(version)) => Some(resolve_version(git, version)?),
+            _ => source.rev.clone(),
+        };
+        fetch(&source, rev.as_deref(), &directory)?;
+        match (&source.version, &rev) {
+            (Some(_), Some(tag)) => {
+                println!("Installed {} {} into {}", name, tag, directory.display())
+            }
+            _ => println!("Installed {} into {}", name, directory.display()),
+        }
+    }
+    Ok(())
+}
+
+// records the dependency in nova.toml, keeping the rest of the file as written,
+// then installs it
+pub fn add(project: &Project, name: &str, source: Source) -> Result<(), NovaError> {
+    source.check(name)?;
+    for (what, value) in [
+        ("git url", &source.git),
+        ("rev", &source.rev),
+        ("url", &source.url),
+    ] {
+        if let Some(value) = value {
+            not_an_option(what, value)?;
+        }
+    }
+    let manifest = project.manifest_path();
+    let text = fs::read_to_string(&manifest).map_err(|error| NovaError::File {
+        msg: format!("Could not read {}: {}", manifest.display(), error).into(),
+    })?;
+    let mut document: DocumentMut = text.parse().map_err(|error| NovaError::File {
+        msg: format!("Invalid {}: {}", manifest.display(), error).into(),
+    })?;
+    let mut entry = InlineTable::new();
+    if let Some(path) = &source.path {
+        entry.insert("path", path.to_string_lossy().as_ref().into());
+    }
+    if let Some(git) = &source.git {
+        entry.insert("git", git.as_str().into());
+    }
+    if let Some(rev) = &source.rev {
+        entry.insert("rev", rev.as_str().into());
+    }
+    if let Some(version) = &source.version {
+        entry.insert("version", version.as_str().into());
+    }
+    if let Some(url) = &source.url {
+        entry.insert("url", url.as_str().into());
+    }
+    let dependencies = document
+        .entry("dependencies")
+        .or_insert(Item::Table(Table::new()));
+    let Some(dependencies) = dependencies.as_table_like_mut() else {
+        return Err(NovaError::File {
+            msg: format!("[dependencies] in {} is not a table", manifest.display()).into(),
+        });
+    };
+    dependencies.insert(name, Item::Value(entry.into()));
+    fs::write(&manifest, document.to_string()).map_err(|error| NovaError::File {
+        msg: format!("Could not write {}: {}", manifest.display(), error).into(),
+    })?;
+    install(&Project::load(&manifest)?)
+}
+
+// git, curl and tar read a value starting with - as one of their options, a
+// manifest could use that to run a command of its choosing
+fn not_an_option(what: &str, value: &str) -> Result<(), NovaError> {
+    if value.starts_with('-') {
+        return Err(NovaError::File {
+            msg: format!("The {} '{}' cannot start with -", what, value).into(),
+        });
+    }
+    Ok(())
+}
+
+// the newest tag of the repository matching the version requirement, tags
+// are read with or without a leading v
+fn resolve_version(git: &str, requirement: &str) -> Result<String, NovaError> {
+    not_an_option("git url", git)?;
+    let parsed = VersionReq::parse(requirement).map_err(|error| NovaError::File {
+        msg: format!("Invalid version '{}': {}", requirement, error).into(),
+    })?;
+    let output = Command::new("git")
+        .args(["ls-remote", "--tags", "--refs", "--", git])
+        .output()
+        .map_err(|error| NovaError::File {
+            msg: format!("Could not run git: {}", error).into(),
+        })?;
+    if !output.status.success() {
+        return Err(NovaError::File {
+            msg: format!("git ls-remote failed with {}", output.status).into(),
+        });
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once("refs/tags/").map(|(_, tag)| tag))
+        .filter_map(|tag| {
+            let version = Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()?;
+            parsed.matches(&version).then_some((version, tag))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag.to_string())
+        .ok_or_else(|| NovaError::File {
+            msg: format!("No tag of {} matches version {}", git, requirement).into(),
+        })
+}
+
+fn fetch(source: &Source, rev: Option<&str>, directory: &Path) -> Result<(), NovaError> {
+    // download next to the cache entry first, a failed fetch never looks installed
+    let partial = directory.with_extension("partial");
+    let _ = fs::remove_dir_all(&partial);
+    if let Some(parent) = partial.parent() {
+        fs::create_dir_all(parent).map_err(|error| NovaError::File {
+            msg: format!("Could not create {}: {}", parent.display(), error).into(),
+        })?;
+    }
+    if let Some(git) = &source.git {
+        not_an_option("git url", git)?;
+        if let Some(rev) = rev {
+            not_an_option("rev", rev)?;
+        }
+        run(Command::new("git")
+            .args(["clone", "--quiet", "--", git])
+            .arg(&partial))?;
+        if let Some(rev) = rev {
+            run(Command::new("git")
+                .arg("-C")
+                .arg(&partial)
+                .args(["checkout", "--quiet", rev, "--"]))?;
+        }
+    } else if let Some(url) = &source.url {
+        not_an_option("url", url)?;
+        let archive = directory.with_extension("tar.gz");
+        run(Command::new("curl")
+            .args(["-fsSL", "-o"])
+            .arg(&archive)
+            .arg(url))?;
+        fs::create_dir_all(&partial).map_err(|error| NovaError::File {
+            msg: format!("Could not create {}: {}", partial.display(), error).into(),
+        })?;
+        let extracted = run(Command::new("tar")
+            .arg("-xzf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&partial)
+            .arg("--strip-components=1"));
+        let _ = fs::remove_file(&archive);
+        extracted?;
+    }
+    fs::rename(&partial, directory).map_err(|error| NovaError::File {
+        msg: format!(
+            "Could not move package into {}: {}",
+            directory.display(),
+            error
+        )
+        .into(),
+    })
+}
+
+fn run(command: &mut Command) -> Result<(), NovaError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(NovaError::File {
+            msg: format!("{} failed with {}", program, status).into(),
+        }),
+        Err(error) => Err(NovaError::File {
+            msg: format!("Could not run {}: {}", program, error).into(),
+        }),
+    }
+}
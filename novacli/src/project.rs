@@ -0,0 +1,171 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use common::error::NovaError;
+use serde::Deserialize;
+
+use crate::package;
+
+pub const MANIFEST: &str = "nova.toml";
+
+// the nova.toml at the root of a project
+//
+//     [project]
+//     entry = "src/main.nv"
+//     sources = ["src", "lib"]
+//
+//     [dependencies]
+//     std = { path = "../nova-lang/std" }
+//     json = { git = "https://github.com/someone/nova-json", rev = "v1.2" }
+//     yaml = { git = "https://github.com/someone/nova-yaml", version = "0.3" }
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub project: Metadata,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Metadata {
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+    // directories imports are looked up in, relative to the manifest
+    #[serde(default)]
+    pub sources: Vec<PathBuf>,
+}
+
+// a folder of modules the project imports from, name = "dir" is short for
+// name = { path = "dir" }
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Dependency {
+    Path(PathBuf),
+    Table(Source),
+}
+
+// where a dependency comes from, exactly one of path, git and url is set
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Source {
+    pub path: Option<PathBuf>,
+    pub git: Option<String>,
+    // tag, branch or commit to check out, the default branch otherwise
+    pub rev: Option<String>,
+    // instead of rev, a requirement like "1.2" or "~1.2.3" that nova install
+    // resolves to the newest tag matching it
+    pub version: Option<String>,
+    // a .tar.gz archive holding a single top level folder, like a release download
+    pub url: Option<String>,
+}
+
+impl Dependency {
+    pub fn source(&self) -> Source {
+        match self {
+            Dependency::Path(path) => Source {
+                path: Some(path.clone()),
+                ..Source::default()
+            },
+            Dependency::Table(source) => source.clone(),
+        }
+    }
+
+    // the folder the dependency's modules are imported from
+    pub fn root(&self, project_root: &Path, name: &str) -> Result<PathBuf, NovaError> {
+        let source = self.source();
+        source.check(name)?;
+        if let Some(path) = &source.path {
+            return Ok(project_root.join(path));
+        }
+        let root = package::cache_dir(name, &source)?;
+        if root.is_dir() {
+            Ok(root)
+        } else {
+            Err(NovaError::File {
+                msg: format!("Dependency '{}' is not installed, run nova install", name).into(),
+            })
+        }
+    }
+}
+
+impl Source {
+    pub fn check(&self, name: &str) -> Result<(), NovaError> {
+        let error = |msg: String| Err(NovaError::File { msg: msg.into() });
+        match (&self.path, &self.git, &self.url) {
+            (Some(_), None, None) | (None, Some(_), None) | (None, None, Some(_)) => {}
+            _ => {
+                return error(format!(
+                    "Dependency '{}' needs exactly one of path, git or url",
+                    name
+                ))
+            }
+        }
+        if self.version.is_some() && (self.git.is_none() || self.rev.is_some()) {
+            return error(format!(
+                "Dependency '{}' can only have a version with git and without rev",
+                name
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn default_entry() -> PathBuf {
+    PathBuf::from("main.nv")
+}
+
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    // the closest nova.toml in the directory or any of its parents
+    pub fn discover(start: &Path) -> Result<Option<Project>, NovaError> {
+        let start = std::fs::canonicalize(start).unwrap_or_else(|_| start.to_path_buf());
+        for directory in start.ancestors() {
+            let manifest = directory.join(MANIFEST);
+            if manifest.is_file() {
+                return Project::load(&manifest).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn load(manifest: &Path) -> Result<Project, NovaError> {
+        let source = std::fs::read_to_string(manifest).map_err(|error| NovaError::File {
+            msg: format!("Could not read {}: {}", manifest.display(), error).into(),
+        })?;
+        let parsed = toml::from_str(&source).map_err(|error| NovaError::File {
+            msg: format!("Invalid {}: {}", manifest.display(), error.message()).into(),
+        })?;
+        Ok(Project {
+            root: manifest.parent().map(Path::to_path_buf).unwrap_or_default(),
+            manifest: parsed,
+        })
+    }
+
+    pub fn entry(&self) -> PathBuf {
+        self.root.join(&self.manifest.project.entry)
+    }
+
+    // source directories first, then every dependency in name order
+    pub fn search_paths(&self) -> Result<Vec<PathBuf>, NovaError> {
+        let mut paths: Vec<PathBuf> = self
+            .manifest
+            .project
+            .sources
+            .iter()
+            .map(|path| self.root.join(path))
+            .collect();
+        for (name, dependency) in self.manifest.dependencies.iter() {
+            paths.push(dependency.root(&self.root, name)?);
+        }
+        Ok(paths)
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join(MANIFEST)
+    }
+}
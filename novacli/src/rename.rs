@@ -0,0 +1,80 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::{
+    error::{apply_fixes, Fix, NovaError},
+    fileposition::FilePosition,
+};
+use novacore::NovaCore;
+
+use crate::{config::Config, fix::diff};
+
+// renames the symbol written at line:col of the file everywhere in the
+// program, with dry_run the files are left alone and the changes are printed
+// as a diff instead
+pub fn rename(
+    file: &Path,
+    at: &str,
+    new_name: &str,
+    dry_run: bool,
+    config: &Config,
+) -> Result<(), NovaError> {
+    let position = parse_position(file, at)?;
+    let mut novacore = NovaCore::new(file)?;
+    config.apply(&mut novacore);
+    let fixes = novacore.rename(&position, new_name)?;
+
+    let mut files: Vec<PathBuf> = fixes
+        .iter()
+        .filter_map(|fix| fix.start.filepath.as_deref().map(Path::to_path_buf))
+        .collect();
+    files.sort();
+    files.dedup();
+    for path in files.iter() {
+        let source = fs::read_to_string(path).map_err(|error| NovaError::File {
+            msg: format!("Could not read {}: {}", path.display(), error).into(),
+        })?;
+        let file_fixes: Vec<Fix> = fixes
+            .iter()
+            .filter(|fix| fix.start.filepath.as_deref() == Some(path.as_path()))
+            .cloned()
+            .collect();
+        let renamed = apply_fixes(&source, &file_fixes);
+        if dry_run {
+            print!("{}", diff(&source, &renamed, path));
+        } else {
+            fs::write(path, &renamed).map_err(|error| NovaError::File {
+                msg: format!("Could not write {}: {}", path.display(), error).into(),
+            })?;
+        }
+    }
+    let verb = if dry_run { "Would rename" } else { "Renamed" };
+    let plural = if files.len() == 1 { "" } else { "s" };
+    println!(
+        "{} {} places in {} file{}",
+        verb,
+        fixes.len(),
+        files.len(),
+        plural
+    );
+    Ok(())
+}
+
+// line:col, both counted from 1
+fn parse_position(file: &Path, at: &str) -> Result<FilePosition, NovaError> {
+    let parsed = at
+        .split_once(':')
+        .and_then(|(line, col)| Some((line.parse().ok()?, col.parse().ok()?)));
+    match parsed {
+        Some((line, col)) if line > 0 && col > 0 => Ok(FilePosition {
+            filepath: Some(file.into()),
+            line,
+            col,
+        }),
+        _ => Err(NovaError::File {
+            msg: format!("'{}' is not a position, write it as line:col", at).into(),
+        }),
+    }
+}
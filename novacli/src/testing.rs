@@ -0,0 +1,205 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use common::error::NovaError;
+use novacore::{BenchResult, NovaCore};
+
+use crate::{config::Config, fix::collect_files};
+
+// a file under the paths with functions named prefix something, its source
+// and those of the functions whose name has filter in it
+struct Found {
+    file: PathBuf,
+    source: String,
+    functions: Vec<Rc<str>>,
+}
+
+// the files with such functions, and how many files that could have them did
+// not build
+fn discover(
+    paths: &[PathBuf],
+    prefix: &str,
+    filter: &str,
+    config: &Config,
+) -> Result<(Vec<Found>, usize), NovaError> {
+    let mut files = vec![];
+    for path in paths {
+        collect_files(path, &mut files)?;
+    }
+    let mut found = vec![];
+    let mut broken = 0;
+    for file in files {
+        let source = fs::read_to_string(&file).map_err(|error| NovaError::File {
+            msg: format!("Could not read {}: {}", file.display(), error).into(),
+        })?;
+        // only files that could have them are built
+        if !source.contains(&format!("fn {}", prefix)) {
+            continue;
+        }
+        let novacore = prepare(&source, &file, config);
+        let functions = match prefix {
+            "bench_" => novacore.benches(),
+            _ => novacore.tests(),
+        };
+        let functions: Vec<_> = match functions {
+            Ok(functions) => functions
+                .into_iter()
+                .filter(|function| function.contains(filter))
+                .collect(),
+            Err(error) => {
+                error.show();
+                broken += 1;
+                continue;
+            }
+        };
+        if !functions.is_empty() {
+            found.push(Found {
+                file,
+                source,
+                functions,
+            });
+        }
+    }
+    Ok((found, broken))
+}
+
+fn prepare(source: &str, file: &Path, config: &Config) -> NovaCore {
+    let mut novacore = NovaCore::from_source(source, file);
+    config.apply(&mut novacore);
+    novacore
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    match count {
+        1 => format!("1 {}", one),
+        _ => format!("{} {}", count, many),
+    }
+}
+
+// the status is 0 when everything passed, 1 when something failed and 2 when
+// a file did not build
+fn status(failed: usize, broken: usize) -> i32 {
+    match (broken, failed) {
+        (0, 0) => 0,
+        (0, _) => 1,
+        _ => 2,
+    }
+}
+
+// runs the test functions of every .nv file under the paths whose name has
+// filter in it, each in a vm of its own
+pub fn test(paths: &[PathBuf], filter: &str, config: &Config) -> Result<i32, NovaError> {
+    let (found, broken) = discover(paths, "test_", filter, config)?;
+    let (mut passed, mut failed) = (0, 0);
+    for Found {
+        file,
+        source,
+        functions,
+    } in found
+    {
+        println!(
+            "running {} in {}",
+            plural(functions.len(), "test", "tests"),
+            file.display()
+        );
+        for test in functions {
+            match prepare(&source, &file, config).run_test(&test) {
+                Ok(0) => {
+                    println!("test {} ... ok", test);
+                    passed += 1;
+                }
+                Ok(status) => {
+                    println!("test {} ... FAILED, it exited with {}", test, status);
+                    failed += 1;
+                }
+                Err(error) => {
+                    println!("test {} ... FAILED", test);
+                    error.show();
+                    failed += 1;
+                }
+            }
+        }
+    }
+    let result = if failed + broken == 0 { "ok" } else { "FAILED" };
+    print!(
+        "test result: {}. {} passed, {} failed",
+        result, passed, failed
+    );
+    if broken > 0 {
+        print!(", {} did not build", plural(broken, "file", "files"));
+    }
+    println!();
+    Ok(status(failed, broken))
+}
+
+// times the bench functions of every .nv file under the paths whose name has
+// filter in it, each in a vm of its own
+pub fn bench(paths: &[PathBuf], filter: &str, config: &Config) -> Result<i32, NovaError> {
+    let (found, broken) = discover(paths, "bench_", filter, config)?;
+    let (mut measured, mut failed) = (0, 0);
+    for Found {
+        file,
+        source,
+        functions,
+    } in found
+    {
+        println!(
+            "running {} in {}",
+            plural(functions.len(), "bench", "benches"),
+            file.display()
+        );
+        let width = functions.iter().map(|bench| bench.len()).max().unwrap_or(0);
+        for bench in functions {
+            match prepare(&source, &file, config).run_bench(&bench) {
+                Ok(result) => {
+                    println!("bench {:<width$} ... {}", bench, report(&result));
+                    measured += 1;
+                }
+                Err(error) => {
+                    println!("bench {:<width$} ... FAILED", bench);
+                    error.show();
+                    failed += 1;
+                }
+            }
+        }
+    }
+    let result = if failed + broken == 0 { "ok" } else { "FAILED" };
+    print!(
+        "bench result: {}. {} measured, {} failed",
+        result, measured, failed
+    );
+    if broken > 0 {
+        print!(", {} did not build", plural(broken, "file", "files"));
+    }
+    println!();
+    Ok(status(failed, broken))
+}
+
+// 1,234 ns/iter (+/- 56, 4.5%) min 1,180 max 1,402 over 30000 iterations
+fn report(result: &BenchResult) -> String {
+    format!(
+        "{:>12} ns/iter (+/- {}, {:.1}%) min {} max {} over {} iterations",
+        grouped(result.mean),
+        grouped(result.deviation),
+        result.spread() * 100.0,
+        grouped(result.min),
+        grouped(result.max),
+        result.iterations
+    )
+}
+
+// nanoseconds rounded and with a comma every three digits
+fn grouped(nanos: f64) -> String {
+    let digits = format!("{}", nanos.round() as u64);
+    let mut out = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use common::error::NovaError;
+
+// calls made before anything is measured, so caches and the heap have
+// settled, and how long each measured batch of calls should take
+const WARMUP: Duration = Duration::from_millis(200);
+const BATCH: Duration = Duration::from_millis(20);
+const SAMPLES: usize = 30;
+
+// nanoseconds per call of a bench function, over the batches it was timed in
+#[derive(Debug, Clone, Copy)]
+pub struct BenchResult {
+    pub iterations: u64,
+    pub mean: f64,
+    pub deviation: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl BenchResult {
+    // deviation as a share of the mean, above a few percent the machine was
+    // busy with something else
+    pub fn spread(&self) -> f64 {
+        if self.mean == 0.0 {
+            0.0
+        } else {
+            self.deviation / self.mean
+        }
+    }
+}
+
+// warms call up, then times SAMPLES batches of as many calls as fit in BATCH
+pub(crate) fn measure(
+    mut call: impl FnMut() -> Result<(), NovaError>,
+) -> Result<BenchResult, NovaError> {
+    let start = Instant::now();
+    let mut calls: u64 = 0;
+    while calls == 0 || start.elapsed() < WARMUP {
+        call()?;
+        calls += 1;
+    }
+    let estimate = start.elapsed().as_nanos() as f64 / calls as f64;
+    let batch = (BATCH.as_nanos() as f64 / estimate.max(1.0)).max(1.0) as u64;
+
+    let mut samples = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        let start = Instant::now();
+        for _ in 0..batch {
+            call()?;
+        }
+        samples.push(start.elapsed().as_nanos() as f64 / batch as f64);
+    }
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    let variance = samples
+        .iter()
+        .map(|sample| (sample - mean).powi(2))
+        .sum::<f64>()
+        / (samples.len() - 1) as f64;
+    Ok(BenchResult {
+        iterations: batch * SAMPLES as u64,
+        mean,
+        deviation: variance.sqrt(),
+        min: samples.iter().copied().fold(f64::INFINITY, f64::min),
+        max: samples.iter().copied().fold(0.0, f64::max),
+    })
+}
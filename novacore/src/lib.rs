@@ -1,20 +1,89 @@
-use std::{path::Path, rc::Rc};
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use assembler::Assembler;
 use common::{
-    error::NovaError,
-    nodes::SymbolKind,
-    ttype::{generate_unique_string, TType},
+    error::{catch_panic, internal_error, Fix, NovaError},
+    nodes::{Atom, Expr, Statement, SymbolKind},
+    tokens::TokenValue,
+    ttype::{generate_unique_string, TType, Width},
 };
 use compiler::Compiler;
 use lexer::Lexer;
 use optimizer::Optimizer;
-use parser::Parser;
-use vm::{state::State, Vm};
+use parser::{doc::Docs, graph::Graph, Parser};
+use vm::{state::State, trace::Tracer, CallBack, Config, Hooks, InterruptHandle, Native, Vm};
+
+mod bench;
+pub mod plugin;
+mod rename;
+mod show;
+
+pub use bench::BenchResult;
+pub use show::ShowLimits;
+
+// functions, types, lets and imports, what a saved repl session keeps
+fn is_definition(statement: &Statement) -> bool {
+    match statement {
+        Statement::Expression { expr, .. } => matches!(expr, Expr::Let { .. }),
+        Statement::Block { filepath, .. } => filepath.is_some(),
+        Statement::Function { .. }
+        | Statement::Struct { .. }
+        | Statement::Enum { .. }
+        | Statement::ForwardDec { .. }
+        | Statement::Lazy { .. }
+        | Statement::Line { .. } => true,
+        _ => false,
+    }
+}
+
+// how long each phase of the last build took and how big its output was
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    pub phases: Vec<(&'static str, Duration)>,
+    pub tokens: usize,
+    pub nodes: usize,
+    pub instructions: usize,
+    pub bytes: usize,
+}
+
+impl Timings {
+    fn record(&mut self, phase: &'static str, start: Instant) {
+        self.phases.push((phase, start.elapsed()));
+    }
+
+    pub fn show(&self) {
+        eprintln!("Timings");
+        eprintln!("--------------------------------");
+        for (phase, duration) in self.phases.iter() {
+            eprintln!("{:<24}{:>8.3}ms", phase, duration.as_secs_f64() * 1000.0);
+        }
+        // listed so it is not mistaken for missing, the optimizer crate has no
+        // pass to run yet
+        eprintln!("{:<24}{:>10}", "optimization", "not run");
+        let total: Duration = self.phases.iter().map(|(_, duration)| *duration).sum();
+        eprintln!("{:<24}{:>8.3}ms", "total", total.as_secs_f64() * 1000.0);
+        eprintln!("--------------------------------");
+        eprintln!("{:<24}{:>10}", "tokens", self.tokens);
+        eprintln!("{:<24}{:>10}", "ast nodes", self.nodes);
+        eprintln!("{:<24}{:>10}", "instructions", self.instructions);
+        eprintln!("{:<24}{:>10}", "bytecode bytes", self.bytes);
+        eprintln!("--------------------------------");
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct NovaCore {
     pub current_repl: String,
+    // the repl lines that only define things, save writes them out
+    pub repl_definitions: String,
+    // top level statements of the lines kept so far
+    repl_statements: usize,
+    // how much of the value of an expression the repl prints
+    pub show_limits: ShowLimits,
     filepath: Option<Rc<Path>>,
     lexer: Lexer,
     pub parser: Parser,
@@ -22,6 +91,64 @@ pub struct NovaCore {
     _optimizer: Optimizer,
     assembler: Assembler,
     vm: Vm,
+    pub timings: Timings,
+    show_timings: bool,
+    search_paths: Vec<PathBuf>,
+    warnings: WarningLevel,
+    // the plugins whose functions every program this core builds gets
+    plugins: Vec<Rc<dyn plugin::Plugin>>,
+    // the test function run_test builds the program around
+    test: Option<Rc<str>>,
+}
+
+// what to do with the warnings the parser finds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WarningLevel {
+    #[default]
+    All,
+    None,
+    Error,
+}
+
+impl WarningLevel {
+    pub fn parse(level: &str) -> Option<WarningLevel> {
+        match level {
+            "all" => Some(WarningLevel::All),
+            "none" => Some(WarningLevel::None),
+            "error" => Some(WarningLevel::Error),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningLevel::All => "all",
+            WarningLevel::None => "none",
+            WarningLevel::Error => "error",
+        }
+    }
+}
+
+// what nova was doing when it crashed, see NovaCore::guard
+#[derive(Debug, Clone, Copy)]
+enum Phase {
+    Lexing,
+    Parsing,
+    Compiling,
+    Assembling,
+    Running,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Lexing => "lexing",
+            Phase::Parsing => "parsing",
+            Phase::Compiling => "compiling",
+            Phase::Assembling => "assembling",
+            Phase::Running => "running",
+        }
+    }
 }
 
 impl NovaCore {
@@ -33,8 +160,17 @@ impl NovaCore {
             compiler: compiler::new(),
             _optimizer: optimizer::new(),
             assembler: Assembler::empty(),
-            vm: vm::new(),
+            vm: Vm::new(Config::default()),
             current_repl: "".to_string(),
+            repl_definitions: String::new(),
+            repl_statements: 0,
+            show_limits: ShowLimits::default(),
+            timings: Timings::default(),
+            show_timings: false,
+            search_paths: vec![],
+            warnings: WarningLevel::All,
+            plugins: vec![],
+            test: None,
         }
     }
 
@@ -46,17 +182,180 @@ impl NovaCore {
             compiler: compiler::new(),
             _optimizer: optimizer::new(),
             assembler: Assembler::empty(),
-            vm: vm::new(),
+            vm: Vm::new(Config::default()),
             current_repl: String::new(),
+            repl_definitions: String::new(),
+            repl_statements: 0,
+            show_limits: ShowLimits::default(),
+            timings: Timings::default(),
+            show_timings: false,
+            search_paths: vec![],
+            warnings: WarningLevel::All,
+            plugins: vec![],
+            test: None,
         })
     }
 
+    // builds source the way the repl does, without a module declaration
+    pub fn from_script(source: &str) -> NovaCore {
+        NovaCore {
+            lexer: Lexer::new(source, None),
+            ..NovaCore::repl()
+        }
+    }
+
+    // builds source as if it had been read from path, imports are found
+    // next to path
+    pub fn from_source(source: &str, path: &Path) -> NovaCore {
+        NovaCore {
+            filepath: Some(path.into()),
+            lexer: Lexer::new(source, Some(path)),
+            parser: parser::new(path),
+            ..NovaCore::repl()
+        }
+    }
+
+    // the quick fixes for the program without building or running it: the one
+    // of the error that stopped parsing, or those of every warning. an error
+    // with no fix, or several to choose from, is returned as is
+    pub fn fixes(mut self) -> Result<Vec<Fix>, NovaError> {
+        self.initnova();
+        self.parser.input = self.lexer.tokenize()?;
+        self.parser.search_paths = self.search_paths.clone();
+        match self.guard(Phase::Parsing, |core| core.parser.parse()) {
+            Ok(()) => Ok(self
+                .parser
+                .warnings
+                .iter()
+                .flat_map(|warning| warning.fixes.iter().cloned())
+                .collect()),
+            Err(error) if error.fixes().len() == 1 => Ok(error.fixes().to_vec()),
+            Err(error) => Err(error),
+        }
+    }
+
+    // the import and call graph of the program, it is parsed but not built
+    pub fn graph(mut self) -> Result<Graph, NovaError> {
+        self.initnova();
+        self.parser.input = self.lexer.tokenize()?;
+        self.parser.search_paths = self.search_paths.clone();
+        self.guard(Phase::Parsing, |core| core.parser.parse())?;
+        let Some(filepath) = self.filepath.clone() else {
+            return Ok(Graph::default());
+        };
+        Ok(parser::graph::graph(&self.parser.ast.program, filepath))
+    }
+
+    // the pub functions, structs and enums of the module with their doc
+    // comments. the file is parsed the way a file importing it sees it, from
+    // a module of its own next to it
+    pub fn docs(mut self) -> Result<Docs, NovaError> {
+        let Some(filepath) = self.filepath.clone() else {
+            return Ok(Docs::default());
+        };
+        let file: Rc<Path> = std::fs::canonicalize(&filepath).map_or(filepath, Rc::from);
+        let root = file.with_file_name("___doc___.nv");
+        let import = TokenValue::StringLiteral(file.to_string_lossy().into()).source_text();
+        self.parser = parser::new(&root);
+        self.initnova();
+        self.parser
+            .overlay
+            .insert(file.to_path_buf(), self.lexer.source.clone());
+        self.parser.input =
+            Lexer::new(format!("module ___doc___\nimport {}", import), Some(&root)).tokenize()?;
+        self.parser.search_paths = self.search_paths.clone();
+        self.guard(Phase::Parsing, |core| core.parser.parse())?;
+        Ok(parser::doc::docs(&self.parser, &file))
+    }
+
+    // release builds strip requires and ensures checks from the bytecode
+    pub fn set_release(&mut self, release: bool) {
+        self.compiler.contracts = !release;
+    }
+
+    // print how long each compiler phase took once the program is built
+    pub fn set_timings(&mut self, show_timings: bool) {
+        self.show_timings = show_timings;
+    }
+
+    // print heap usage when the program exits
+    pub fn set_gc_stats(&mut self, gc_stats: bool) {
+        self.vm.config.gc_stats = gc_stats;
+    }
+
+    // heap size the garbage collector first runs at
+    pub fn set_gc_threshold(&mut self, threshold: usize) {
+        self.vm.config.gc_threshold = threshold;
+        self.vm.state.threshold = threshold;
+    }
+
+    // most calls that can be in progress at once before the script is stopped
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.vm.config.stack_limit = limit;
+    }
+
+    // stop the script when Int arithmetic overflows instead of wrapping around
+    pub fn set_overflow_checks(&mut self, overflow_checks: bool) {
+        self.vm.config.overflow_checks = overflow_checks;
+    }
+
+    // the nova Worker::spawn starts scripts with
+    pub fn set_nova_binary(&mut self, nova: PathBuf) {
+        self.vm.config.nova_binary = Some(nova.clone());
+        self.vm.state.nova_binary = Some(nova);
+    }
+
+    // show, hide or fail the build on warnings
+    pub fn set_warnings(&mut self, level: WarningLevel) {
+        self.warnings = level;
+    }
+
+    // the arguments args() returns, what came after the script path
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.vm.state.args = args.into_iter().map(Into::into).collect();
+    }
+
+    // stream chrome trace events for the given categories to stderr
+    pub fn set_trace(&mut self, categories: &str) -> Result<(), String> {
+        self.vm.state.trace = Some(Tracer::parse(categories)?);
+        Ok(())
+    }
+
+    // a directory imports are looked up in, searched in the order added and
+    // before the file's own folder
+    pub fn add_search_path(&mut self, directory: impl Into<PathBuf>) {
+        self.search_paths.push(directory.into());
+    }
+
+    // lets another thread stop the running script at its next loop or call
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.vm.interrupt_handle()
+    }
+
+    // tracing callbacks, see vm::Hooks
+    pub fn hooks_mut(&mut self) -> &mut Hooks {
+        &mut self.vm.hooks
+    }
+
+    // a plugin only this core uses, every program it builds from now on has
+    // the plugin's functions
+    pub fn add_plugin(&mut self, plugin: Rc<dyn plugin::Plugin>) {
+        self.plugins.push(plugin);
+    }
+
+    // lets programs write Module::function for the functions registered under it
+    pub fn add_module(&mut self, module: &str) {
+        if !self.parser.modules.has(&Rc::<str>::from(module)) {
+            self.parser.modules.insert(module.into());
+        }
+    }
+
     pub fn add_function(
         &mut self,
         function_id: &str,
         function_type: TType,
         function_kind: SymbolKind,
-        function_pointer: fn(&mut State) -> Result<(), NovaError>,
+        function: impl Fn(&mut State) -> Result<(), NovaError> + 'static,
     ) {
         match function_kind {
             SymbolKind::Function => {
@@ -79,9 +378,7 @@ impl NovaCore {
                 self.compiler
                     .native_functions_types
                     .insert(function_id.into(), function_type.clone());
-                self.vm
-                    .native_functions
-                    .insert(self.vm.native_functions.len(), function_pointer);
+                self.vm.native_functions.push(Native::new(function));
             }
             _ => {
                 self.parser.environment.insert_symbol(
@@ -96,9 +393,7 @@ impl NovaCore {
                 self.compiler
                     .native_functions_types
                     .insert(function_id, function_type);
-                self.vm
-                    .native_functions
-                    .insert(self.vm.native_functions.len(), function_pointer);
+                self.vm.native_functions.push(Native::new(function));
             }
         };
     }
@@ -109,6 +404,17 @@ impl NovaCore {
         self.parser.modules.insert("Cast".into());
         self.parser.modules.insert("Regex".into());
         self.parser.modules.insert("raylib".into());
+        self.parser.modules.insert("Sys".into());
+        self.parser.modules.insert("Lang".into());
+        self.parser.modules.insert("Timer".into());
+        self.parser.modules.insert("Worker".into());
+        self.parser.modules.insert("Json".into());
+        self.parser.modules.insert("Toml".into());
+        self.parser.modules.insert("Yaml".into());
+        self.parser.modules.insert("env".into());
+        self.parser.modules.insert("Random".into());
+        self.parser.modules.insert("Hash".into());
+        self.parser.modules.insert("time".into());
         // add remove for list
         self.add_function(
             "List::remove",
@@ -124,17 +430,36 @@ impl NovaCore {
             common::nodes::SymbolKind::GenericFunction,
             native::list::remove,
         );
-        // add regex captures function, takes two strings and returns a list of strings
-        // add printf function
         self.add_function(
-            "printf",
+            "List::insert",
             TType::Function {
-                parameters: vec![TType::String],
+                parameters: vec![
+                    TType::List {
+                        inner: Box::new(TType::Generic { name: "a".into() }),
+                    },
+                    TType::Int,
+                    TType::Generic { name: "a".into() },
+                ],
                 return_type: Box::new(TType::Void),
             },
-            common::nodes::SymbolKind::Function,
-            native::io::printf,
+            common::nodes::SymbolKind::GenericFunction,
+            native::list::insert,
         );
+        self.add_function(
+            "List::removeAt",
+            TType::Function {
+                parameters: vec![
+                    TType::List {
+                        inner: Box::new(TType::Generic { name: "a".into() }),
+                    },
+                    TType::Int,
+                ],
+                return_type: Box::new(TType::Generic { name: "a".into() }),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::list::remove_at,
+        );
+        // add regex captures function, takes two strings and returns a list of strings
         self.add_function(
             "Regex::captures",
             TType::Function {
@@ -169,6 +494,43 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::regex::regex_first,
         );
+        // the lexer and parser as seen from nova, tokens are (kind, text, line, col)
+        self.add_function(
+            "Lang::tokenize",
+            TType::Function {
+                parameters: vec![TType::String],
+                return_type: Box::new(TType::List {
+                    inner: Box::new(TType::Tuple {
+                        elements: vec![TType::String, TType::String, TType::Int, TType::Int],
+                    }),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::lang::tokenize,
+        );
+        // expression nodes are (kind, value, type, children) with the root first
+        self.add_function(
+            "Lang::parseExpr",
+            TType::Function {
+                parameters: vec![TType::String],
+                return_type: Box::new(TType::Option {
+                    inner: Box::new(TType::List {
+                        inner: Box::new(TType::Tuple {
+                            elements: vec![
+                                TType::String,
+                                TType::String,
+                                TType::String,
+                                TType::List {
+                                    inner: Box::new(TType::Int),
+                                },
+                            ],
+                        }),
+                    }),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::lang::parse_expr,
+        );
         // add printf function
         self.add_function(
             "printf",
@@ -199,6 +561,27 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::io::format,
         );
+        // the values after the format string of a call to printf or format
+        // come as a list of anything
+        for (name, return_type, function) in [
+            ("printf", TType::Void, native::io::printf as CallBack),
+            ("format", TType::String, native::io::format),
+        ] {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![
+                        TType::String,
+                        TType::List {
+                            inner: Box::new(TType::Any),
+                        },
+                    ],
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
         self.add_function(
             "terminal::args",
             TType::Function {
@@ -212,6 +595,17 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::terminal::retrieve_command_line_args,
         );
+        self.add_function(
+            "args",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::List {
+                    inner: Box::new(TType::String),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::terminal::args,
+        );
         self.add_function(
             "terminal::hideCursor",
             TType::Function {
@@ -230,6 +624,289 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::terminal::show_cursor,
         );
+        self.add_function(
+            "Sys::memStats",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::Tuple {
+                    elements: vec![TType::Int, TType::Int, TType::Int],
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::sys::mem_stats,
+        );
+        // file handles behind std/fs.nv
+        self.add_function(
+            "Sys::fileOpen",
+            TType::Function {
+                parameters: vec![TType::String],
+                return_type: Box::new(TType::Int),
+            },
+            common::nodes::SymbolKind::Function,
+            native::fs::open,
+        );
+        self.add_function(
+            "Sys::fileRead",
+            TType::Function {
+                parameters: vec![TType::Int],
+                return_type: Box::new(TType::String),
+            },
+            common::nodes::SymbolKind::Function,
+            native::fs::read,
+        );
+        self.add_function(
+            "Sys::fileWrite",
+            TType::Function {
+                parameters: vec![TType::Int, TType::String],
+                return_type: Box::new(TType::Void),
+            },
+            common::nodes::SymbolKind::Function,
+            native::fs::write,
+        );
+        self.add_function(
+            "Sys::fileClose",
+            TType::Function {
+                parameters: vec![TType::Int],
+                return_type: Box::new(TType::Void),
+            },
+            common::nodes::SymbolKind::Function,
+            native::fs::close,
+        );
+        self.add_function(
+            "Sys::listDir",
+            TType::Function {
+                parameters: vec![TType::String],
+                return_type: Box::new(TType::List {
+                    inner: Box::new(TType::Tuple {
+                        elements: vec![
+                            TType::String,
+                            TType::String,
+                            TType::Bool,
+                            TType::Int,
+                            TType::Int,
+                        ],
+                    }),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::fs::list_dir,
+        );
+        // environment variables
+        let natives: [(&str, Vec<TType>, TType, CallBack); 3] = [
+            (
+                "env::get",
+                vec![TType::String],
+                TType::Option {
+                    inner: Box::new(TType::String),
+                },
+                native::env::get,
+            ),
+            (
+                "env::set",
+                vec![TType::String, TType::String],
+                TType::Void,
+                native::env::set,
+            ),
+            (
+                "env::vars",
+                vec![TType::None],
+                TType::List {
+                    inner: Box::new(TType::Tuple {
+                        elements: vec![TType::String, TType::String],
+                    }),
+                },
+                native::env::vars,
+            ),
+        ];
+        for (name, parameters, return_type, function) in natives {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        // processes, std/process.nv wraps the handles in a Child
+        let natives: [(&str, Vec<TType>, TType, CallBack); 10] = [
+            (
+                "Sys::processRun",
+                vec![
+                    TType::String,
+                    TType::List {
+                        inner: Box::new(TType::String),
+                    },
+                ],
+                TType::Tuple {
+                    elements: vec![TType::Int, TType::String, TType::String],
+                },
+                native::process::run,
+            ),
+            (
+                "Sys::processSpawn",
+                vec![
+                    TType::String,
+                    TType::List {
+                        inner: Box::new(TType::String),
+                    },
+                ],
+                TType::Int,
+                native::process::spawn,
+            ),
+            (
+                "Sys::processWrite",
+                vec![TType::Int, TType::String],
+                TType::Void,
+                native::process::write,
+            ),
+            (
+                "Sys::processCloseInput",
+                vec![TType::Int],
+                TType::Void,
+                native::process::close_input,
+            ),
+            (
+                "Sys::processReadLine",
+                vec![TType::Int],
+                TType::Option {
+                    inner: Box::new(TType::String),
+                },
+                native::process::read_line,
+            ),
+            (
+                "Sys::processReadAll",
+                vec![TType::Int],
+                TType::String,
+                native::process::read_all,
+            ),
+            (
+                "Sys::processReadErr",
+                vec![TType::Int],
+                TType::String,
+                native::process::read_err,
+            ),
+            (
+                "Sys::processWait",
+                vec![TType::Int],
+                TType::Int,
+                native::process::wait,
+            ),
+            (
+                "Sys::processKill",
+                vec![TType::Int],
+                TType::Void,
+                native::process::kill,
+            ),
+            (
+                "Sys::processClose",
+                vec![TType::Int],
+                TType::Void,
+                native::process::close,
+            ),
+        ];
+        for (name, parameters, return_type, function) in natives {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        // sockets, std/net.nv wraps the handles
+        let natives: [(&str, Vec<TType>, TType, CallBack); 12] = [
+            (
+                "Sys::tcpConnect",
+                vec![TType::String],
+                TType::Int,
+                native::net::tcp_connect,
+            ),
+            (
+                "Sys::tcpListen",
+                vec![TType::String],
+                TType::Int,
+                native::net::tcp_listen,
+            ),
+            (
+                "Sys::tcpAccept",
+                vec![TType::Int],
+                TType::Int,
+                native::net::tcp_accept,
+            ),
+            (
+                "Sys::tcpSend",
+                vec![TType::Int, TType::String],
+                TType::Void,
+                native::net::tcp_send,
+            ),
+            (
+                "Sys::tcpRecv",
+                vec![TType::Int, TType::Int],
+                TType::String,
+                native::net::tcp_recv,
+            ),
+            (
+                "Sys::tcpRecvLine",
+                vec![TType::Int],
+                TType::Option {
+                    inner: Box::new(TType::String),
+                },
+                native::net::tcp_recv_line,
+            ),
+            (
+                "Sys::udpBind",
+                vec![TType::String],
+                TType::Int,
+                native::net::udp_bind,
+            ),
+            (
+                "Sys::udpSendTo",
+                vec![TType::Int, TType::String, TType::String],
+                TType::Void,
+                native::net::udp_send_to,
+            ),
+            (
+                "Sys::udpRecvFrom",
+                vec![TType::Int, TType::Int],
+                TType::Tuple {
+                    elements: vec![TType::String, TType::String],
+                },
+                native::net::udp_recv_from,
+            ),
+            (
+                "Sys::socketAddr",
+                vec![TType::Int],
+                TType::String,
+                native::net::socket_addr,
+            ),
+            (
+                "Sys::socketTimeout",
+                vec![TType::Int, TType::Int],
+                TType::Void,
+                native::net::socket_timeout,
+            ),
+            (
+                "Sys::socketClose",
+                vec![TType::Int],
+                TType::Void,
+                native::net::socket_close,
+            ),
+        ];
+        for (name, parameters, return_type, function) in natives {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
         self.add_function(
             "Cast::int",
             TType::Function {
@@ -250,6 +927,15 @@ impl NovaCore {
             common::nodes::SymbolKind::GenericFunction,
             native::str::to_string,
         );
+        self.add_function(
+            "Cast::hash",
+            TType::Function {
+                parameters: vec![TType::Generic { name: "a".into() }],
+                return_type: Box::new(TType::Int),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::binary::hash,
+        );
         // alias for toString
         // self.add_function(
         //     "toString",
@@ -291,8 +977,157 @@ impl NovaCore {
             common::nodes::SymbolKind::GenericFunction,
             native::time::sleep,
         );
+        // nanoseconds on a monotonic clock, for measuring how long things take
+        self.add_function(
+            "time::instant",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::Int),
+            },
+            common::nodes::SymbolKind::Function,
+            native::time::instant,
+        );
+        self.add_function(
+            "time::elapsed",
+            TType::Function {
+                parameters: vec![TType::Int],
+                return_type: Box::new(TType::Int),
+            },
+            common::nodes::SymbolKind::Function,
+            native::time::elapsed,
+        );
+        // callbacks run by the event loop once the program is done
+        let schedule: [(&str, CallBack); 2] = [
+            ("Timer::after", native::timer::after),
+            ("Timer::every", native::timer::every),
+        ];
+        for (name, function) in schedule {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![
+                        TType::Int,
+                        TType::Function {
+                            parameters: vec![TType::None],
+                            return_type: Box::new(TType::Void),
+                        },
+                    ],
+                    return_type: Box::new(TType::Int),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        self.add_function(
+            "Timer::cancel",
+            TType::Function {
+                parameters: vec![TType::Int],
+                return_type: Box::new(TType::Void),
+            },
+            common::nodes::SymbolKind::Function,
+            native::timer::cancel,
+        );
         self.add_function(
-            "terminal::rawmode",
+            "Timer::stop",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::Void),
+            },
+            common::nodes::SymbolKind::Function,
+            native::timer::stop,
+        );
+        // other nova processes, values go over in the binary format and the
+        // receiver names the type it expects, Worker::recv(id) @[a: Int]
+        self.add_function(
+            "Worker::spawn",
+            TType::Function {
+                parameters: vec![TType::String],
+                return_type: Box::new(TType::Int),
+            },
+            common::nodes::SymbolKind::Function,
+            native::worker::spawn,
+        );
+        self.add_function(
+            "Worker::send",
+            TType::Function {
+                parameters: vec![TType::Int, TType::Generic { name: "a".into() }],
+                return_type: Box::new(TType::Void),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::worker::send,
+        );
+        self.add_function(
+            "Worker::recv",
+            TType::Function {
+                parameters: vec![TType::Int],
+                return_type: Box::new(TType::Option {
+                    inner: Box::new(TType::Generic { name: "a".into() }),
+                }),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::worker::recv,
+        );
+        self.add_function(
+            "Worker::close",
+            TType::Function {
+                parameters: vec![TType::Int],
+                return_type: Box::new(TType::Void),
+            },
+            common::nodes::SymbolKind::Function,
+            native::worker::close,
+        );
+        self.add_function(
+            "Worker::isWorker",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::Bool),
+            },
+            common::nodes::SymbolKind::Function,
+            native::worker::is_worker,
+        );
+        // json text read into and written from any type without functions,
+        // the parser passes the shape of the type as a last argument
+        self.add_function(
+            "Json::decode",
+            TType::Function {
+                parameters: vec![TType::String],
+                return_type: Box::new(TType::Custom {
+                    name: "Result".into(),
+                    type_params: vec![TType::Generic { name: "a".into() }, TType::String],
+                }),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::json::decode,
+        );
+        // config files read the same way, only json can be written
+        for (name, function) in [
+            ("Toml::decode", native::config::decode_toml as CallBack),
+            ("Yaml::decode", native::config::decode_yaml),
+        ] {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![TType::String],
+                    return_type: Box::new(TType::Custom {
+                        name: "Result".into(),
+                        type_params: vec![TType::Generic { name: "a".into() }, TType::String],
+                    }),
+                },
+                common::nodes::SymbolKind::GenericFunction,
+                function,
+            );
+        }
+        self.add_function(
+            "Json::encode",
+            TType::Function {
+                parameters: vec![TType::Generic { name: "a".into() }],
+                return_type: Box::new(TType::String),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::json::encode,
+        );
+        self.add_function(
+            "terminal::rawMode",
             TType::Function {
                 parameters: vec![TType::Bool],
                 return_type: Box::new(TType::Void),
@@ -312,7 +1147,7 @@ impl NovaCore {
             native::terminal::getch,
         );
         self.add_function(
-            "terminal::rawread",
+            "terminal::rawRead",
             TType::Function {
                 parameters: vec![TType::Int],
                 return_type: Box::new(TType::Option {
@@ -331,6 +1166,17 @@ impl NovaCore {
             common::nodes::SymbolKind::GenericFunction,
             native::io::read_line,
         );
+        self.add_function(
+            "readLine",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::Option {
+                    inner: Box::new(TType::String),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::io::read_line_option,
+        );
         self.add_function(
             "terminal::clearScreen",
             TType::Function {
@@ -376,6 +1222,279 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::random::random_int,
         );
+        // generators by handle, 0 is the one seeded by the system. std/random.nv
+        // wraps them
+        let generators: [(&str, Vec<TType>, TType, CallBack); 3] = [
+            (
+                "Random::seeded",
+                vec![TType::Int],
+                TType::Int,
+                native::random::seeded,
+            ),
+            (
+                "Random::int",
+                vec![TType::Int, TType::Int, TType::Int],
+                TType::Int,
+                native::random::int,
+            ),
+            (
+                "Random::float",
+                vec![TType::Int],
+                TType::Float,
+                native::random::float,
+            ),
+        ];
+        for (name, parameters, return_type, function) in generators {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        // overflow aware arithmetic, checked returns None, saturating clamps and
+        // wrapping wraps around whether or not overflow checks are on
+        let checked: [(&str, CallBack); 3] = [
+            ("Int::checkedAdd", native::int::checked_add),
+            ("Int::checkedSub", native::int::checked_sub),
+            ("Int::checkedMul", native::int::checked_mul),
+        ];
+        for (name, function) in checked {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![TType::Int, TType::Int],
+                    return_type: Box::new(TType::Option {
+                        inner: Box::new(TType::Int),
+                    }),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        let saturating: [(&str, CallBack); 3] = [
+            ("Int::saturatingAdd", native::int::saturating_add),
+            ("Int::saturatingSub", native::int::saturating_sub),
+            ("Int::saturatingMul", native::int::saturating_mul),
+        ];
+        let wrapping: [(&str, CallBack); 3] = [
+            ("Int::wrappingAdd", native::int::wrapping_add),
+            ("Int::wrappingSub", native::int::wrapping_sub),
+            ("Int::wrappingMul", native::int::wrapping_mul),
+        ];
+        // | and & are found the way dunder methods of a struct are
+        let bitwise: [(&str, CallBack); 2] = [
+            ("Int::__bitor__", native::int::bit_or),
+            ("Int::__bitand__", native::int::bit_and),
+        ];
+        for (name, function) in saturating.into_iter().chain(wrapping).chain(bitwise) {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![TType::Int, TType::Int],
+                    return_type: Box::new(TType::Int),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        // numbers as text with a set base, width, separator or decimals
+        let numeric: [(&str, Vec<TType>, CallBack); 7] = [
+            (
+                "Int::toStringRadix",
+                vec![TType::Int, TType::Int],
+                native::int::to_string_radix,
+            ),
+            (
+                "Int::toString",
+                vec![TType::Int, TType::Int],
+                native::int::to_string_radix,
+            ),
+            (
+                "Int::toPadded",
+                vec![TType::Int, TType::Int],
+                native::int::to_padded,
+            ),
+            (
+                "Int::toGrouped",
+                vec![TType::Int, TType::String],
+                native::int::to_grouped,
+            ),
+            (
+                "Float::toFixed",
+                vec![TType::Float, TType::Int],
+                native::float::to_fixed,
+            ),
+            (
+                "Float::toGrouped",
+                vec![TType::Float, TType::Int, TType::String],
+                native::float::to_grouped,
+            ),
+            (
+                "Float::toExponent",
+                vec![TType::Float, TType::Int],
+                native::float::to_exponent,
+            ),
+        ];
+        for (name, parameters, function) in numeric {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(TType::String),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        self.add_function(
+            "Int::parse",
+            TType::Function {
+                parameters: vec![TType::String, TType::Int],
+                return_type: Box::new(TType::Option {
+                    inner: Box::new(TType::Int),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::int::parse,
+        );
+        let floats: [(&str, Vec<TType>, TType, CallBack); 6] = [
+            (
+                "Float::isNan",
+                vec![TType::Float],
+                TType::Bool,
+                native::float::is_nan,
+            ),
+            (
+                "Float::isInfinite",
+                vec![TType::Float],
+                TType::Bool,
+                native::float::is_infinite,
+            ),
+            (
+                "Float::isFinite",
+                vec![TType::Float],
+                TType::Bool,
+                native::float::is_finite,
+            ),
+            (
+                "Float::clamp",
+                vec![TType::Float, TType::Float, TType::Float],
+                TType::Float,
+                native::float::clamp,
+            ),
+            (
+                "Float::lerp",
+                vec![TType::Float, TType::Float, TType::Float],
+                TType::Float,
+                native::float::lerp,
+            ),
+            (
+                "Float::roundTo",
+                vec![TType::Float, TType::Int],
+                TType::Float,
+                native::float::round_to,
+            ),
+        ];
+        for (name, parameters, return_type, function) in floats {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        // BigInt operators are found the way dunder methods of a struct are
+        let operators: [(&str, CallBack, TType); 9] = [
+            ("BigInt::__add__", native::bigint::add, TType::BigInt),
+            ("BigInt::__sub__", native::bigint::sub, TType::BigInt),
+            ("BigInt::__mul__", native::bigint::mul, TType::BigInt),
+            ("BigInt::__div__", native::bigint::div, TType::BigInt),
+            ("BigInt::__mod__", native::bigint::rem, TType::BigInt),
+            ("BigInt::__lt__", native::bigint::lt, TType::Bool),
+            ("BigInt::__le__", native::bigint::le, TType::Bool),
+            ("BigInt::__gt__", native::bigint::gt, TType::Bool),
+            ("BigInt::__ge__", native::bigint::ge, TType::Bool),
+        ];
+        for (name, function, return_type) in operators {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![TType::BigInt, TType::BigInt],
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        let conversions: [(&str, CallBack, TType, TType); 3] = [
+            (
+                "BigInt::from",
+                native::bigint::from_int,
+                TType::Int,
+                TType::BigInt,
+            ),
+            (
+                "BigInt::parse",
+                native::bigint::parse,
+                TType::String,
+                TType::Option {
+                    inner: Box::new(TType::BigInt),
+                },
+            ),
+            (
+                "BigInt::toInt",
+                native::bigint::to_int,
+                TType::BigInt,
+                TType::Option {
+                    inner: Box::new(TType::Int),
+                },
+            ),
+        ];
+        for (name, function, parameter, return_type) in conversions {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters: vec![parameter],
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        self.add_function(
+            "BigInt::__neg__",
+            TType::Function {
+                parameters: vec![TType::BigInt],
+                return_type: Box::new(TType::BigInt),
+            },
+            common::nodes::SymbolKind::Function,
+            native::bigint::neg,
+        );
+        self.add_function(
+            "BigInt::pow",
+            TType::Function {
+                parameters: vec![TType::BigInt, TType::Int],
+                return_type: Box::new(TType::BigInt),
+            },
+            common::nodes::SymbolKind::Function,
+            native::bigint::pow,
+        );
+        self.add_function(
+            "BigInt::modPow",
+            TType::Function {
+                parameters: vec![TType::BigInt, TType::BigInt, TType::BigInt],
+                return_type: Box::new(TType::BigInt),
+            },
+            common::nodes::SymbolKind::Function,
+            native::bigint::mod_pow,
+        );
         self.add_function(
             "String::len",
             TType::Function {
@@ -396,6 +1515,150 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::str::str_to_chars,
         );
+        let bytes = || TType::List {
+            inner: Box::new(TType::Sized { width: Width::U8 }),
+        };
+        let text: [(&str, Vec<TType>, TType, CallBack); 15] = [
+            (
+                "String::byteLen",
+                vec![TType::String],
+                TType::Int,
+                native::str::byte_len,
+            ),
+            (
+                "String::slice",
+                vec![TType::String, TType::Int, TType::Int],
+                TType::String,
+                native::str::slice,
+            ),
+            (
+                "String::charAt",
+                vec![TType::String, TType::Int],
+                TType::Option {
+                    inner: Box::new(TType::Char),
+                },
+                native::str::char_at,
+            ),
+            (
+                "String::graphemes",
+                vec![TType::String],
+                TType::List {
+                    inner: Box::new(TType::String),
+                },
+                native::str::graphemes,
+            ),
+            (
+                "String::toUpper",
+                vec![TType::String],
+                TType::String,
+                native::str::to_upper,
+            ),
+            (
+                "String::toLower",
+                vec![TType::String],
+                TType::String,
+                native::str::to_lower,
+            ),
+            (
+                "String::trim",
+                vec![TType::String],
+                TType::String,
+                native::str::trim,
+            ),
+            (
+                "String::trimStart",
+                vec![TType::String],
+                TType::String,
+                native::str::trim_start,
+            ),
+            (
+                "String::trimEnd",
+                vec![TType::String],
+                TType::String,
+                native::str::trim_end,
+            ),
+            (
+                "String::toBytes",
+                vec![TType::String],
+                bytes(),
+                native::str::to_bytes,
+            ),
+            (
+                "String::fromBytes",
+                vec![bytes()],
+                TType::Option {
+                    inner: Box::new(TType::String),
+                },
+                native::str::from_bytes,
+            ),
+            (
+                "Char::isWhitespace",
+                vec![TType::Char],
+                TType::Bool,
+                native::char::is_whitespace,
+            ),
+            (
+                "Char::isUpper",
+                vec![TType::Char],
+                TType::Bool,
+                native::char::is_upper,
+            ),
+            (
+                "Char::isLower",
+                vec![TType::Char],
+                TType::Bool,
+                native::char::is_lower,
+            ),
+            (
+                "Char::isAlnum",
+                vec![TType::Char],
+                TType::Bool,
+                native::char::is_alnum,
+            ),
+        ];
+        for (name, parameters, return_type, function) in text {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+        // digests and signatures of bytes, std/hash.nv wraps them in Digest
+        let digests: [(&str, Vec<TType>, CallBack); 6] = [
+            ("Hash::sha256", vec![bytes()], native::digest::sha256),
+            ("Hash::sha1", vec![bytes()], native::digest::sha1),
+            ("Hash::md5", vec![bytes()], native::digest::md5),
+            (
+                "Hash::hmacSha256",
+                vec![bytes(), bytes()],
+                native::digest::hmac_sha256,
+            ),
+            (
+                "Hash::hmacSha1",
+                vec![bytes(), bytes()],
+                native::digest::hmac_sha1,
+            ),
+            (
+                "Hash::hmacMd5",
+                vec![bytes(), bytes()],
+                native::digest::hmac_md5,
+            ),
+        ];
+        for (name, parameters, function) in digests {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(bytes()),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
         self.add_function(
             "List::string",
             TType::Function {
@@ -407,6 +1670,55 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::str::chars_to_str,
         );
+        self.add_function(
+            "List::string",
+            TType::Function {
+                parameters: vec![TType::List {
+                    inner: Box::new(TType::String),
+                }],
+                return_type: Box::new(TType::String),
+            },
+            common::nodes::SymbolKind::Function,
+            native::str::strings_to_str,
+        );
+        self.add_function(
+            "List::join",
+            TType::Function {
+                parameters: vec![
+                    TType::List {
+                        inner: Box::new(TType::String),
+                    },
+                    TType::String,
+                ],
+                return_type: Box::new(TType::String),
+            },
+            common::nodes::SymbolKind::Function,
+            native::str::join,
+        );
+        self.add_function(
+            "String::repeat",
+            TType::Function {
+                parameters: vec![TType::String, TType::Int],
+                return_type: Box::new(TType::String),
+            },
+            common::nodes::SymbolKind::Function,
+            native::str::repeat,
+        );
+        self.add_function(
+            "List::concat",
+            TType::Function {
+                parameters: vec![TType::List {
+                    inner: Box::new(TType::List {
+                        inner: Box::new(TType::Generic { name: "a".into() }),
+                    }),
+                }],
+                return_type: Box::new(TType::List {
+                    inner: Box::new(TType::Generic { name: "a".into() }),
+                }),
+            },
+            common::nodes::SymbolKind::GenericFunction,
+            native::list::concat,
+        );
         self.add_function(
             "chr",
             TType::Function {
@@ -425,23 +1737,269 @@ impl NovaCore {
             common::nodes::SymbolKind::Function,
             native::io::read_file,
         );
+        #[cfg(feature = "canvas")]
+        self.init_canvas();
+        #[cfg(feature = "audio")]
+        self.init_audio();
+        #[cfg(feature = "input")]
+        self.init_input();
+        for plugin in self.plugins.clone() {
+            plugin.register(self);
+        }
+    }
+
+    // a window to draw into, built with the canvas feature
+    #[cfg(feature = "canvas")]
+    fn init_canvas(&mut self) {
+        self.parser.modules.insert("Canvas".into());
+        let function = |parameters: Vec<TType>, return_type: TType| TType::Function {
+            parameters,
+            return_type: Box::new(return_type),
+        };
+        let strings = TType::List {
+            inner: Box::new(TType::String),
+        };
+        let natives: [(&str, TType, CallBack); 14] = [
+            (
+                "Canvas::open",
+                function(vec![TType::String, TType::Int, TType::Int], TType::Void),
+                native::canvas::open,
+            ),
+            (
+                "Canvas::close",
+                function(vec![TType::None], TType::Void),
+                native::canvas::close,
+            ),
+            (
+                "Canvas::isOpen",
+                function(vec![TType::None], TType::Bool),
+                native::canvas::is_open,
+            ),
+            (
+                "Canvas::present",
+                function(vec![TType::None], TType::Void),
+                native::canvas::present,
+            ),
+            (
+                "Canvas::clear",
+                function(vec![TType::Int], TType::Void),
+                native::canvas::clear,
+            ),
+            (
+                "Canvas::pixel",
+                function(vec![TType::Int; 3], TType::Void),
+                native::canvas::pixel,
+            ),
+            (
+                "Canvas::rect",
+                function(vec![TType::Int; 5], TType::Void),
+                native::canvas::rect,
+            ),
+            (
+                "Canvas::text",
+                function(
+                    vec![TType::Int, TType::Int, TType::String, TType::Int],
+                    TType::Void,
+                ),
+                native::canvas::text,
+            ),
+            (
+                "Canvas::rgb",
+                function(vec![TType::Int; 3], TType::Int),
+                native::canvas::rgb,
+            ),
+            (
+                "Canvas::keyDown",
+                function(vec![TType::String], TType::Bool),
+                native::canvas::key_down,
+            ),
+            (
+                "Canvas::keysPressed",
+                function(vec![TType::None], strings),
+                native::canvas::keys_pressed,
+            ),
+            (
+                "Canvas::mouseX",
+                function(vec![TType::None], TType::Int),
+                native::canvas::mouse_x,
+            ),
+            (
+                "Canvas::mouseY",
+                function(vec![TType::None], TType::Int),
+                native::canvas::mouse_y,
+            ),
+            (
+                "Canvas::mouseDown",
+                function(vec![TType::None], TType::Bool),
+                native::canvas::mouse_down,
+            ),
+        ];
+        for (name, ttype, function) in natives {
+            self.add_function(name, ttype, common::nodes::SymbolKind::Function, function);
+        }
+    }
+
+    // sound output, built with the audio feature
+    #[cfg(feature = "audio")]
+    fn init_audio(&mut self) {
+        self.parser.modules.insert("Audio".into());
+        let natives: [(&str, Vec<TType>, CallBack); 6] = [
+            ("Audio::play", vec![TType::String], native::audio::play),
+            (
+                "Audio::tone",
+                vec![TType::Float, TType::Int],
+                native::audio::tone,
+            ),
+            ("Audio::beep", vec![TType::None], native::audio::beep),
+            (
+                "Audio::setVolume",
+                vec![TType::Float],
+                native::audio::set_volume,
+            ),
+            ("Audio::stop", vec![TType::None], native::audio::stop),
+            ("Audio::wait", vec![TType::None], native::audio::wait),
+        ];
+        for (name, parameters, function) in natives {
+            self.add_function(
+                name,
+                TType::Function {
+                    parameters,
+                    return_type: Box::new(TType::Void),
+                },
+                common::nodes::SymbolKind::Function,
+                function,
+            );
+        }
+    }
+
+    #[cfg(feature = "input")]
+    fn init_input(&mut self) {
+        self.parser.modules.insert("Input".into());
+        // std/input.nv turns the tuples into Event values
+        self.add_function(
+            "Input::poll",
+            TType::Function {
+                parameters: vec![TType::None],
+                return_type: Box::new(TType::Option {
+                    inner: Box::new(TType::Tuple {
+                        elements: vec![
+                            TType::String,
+                            TType::String,
+                            TType::Int,
+                            TType::Int,
+                            TType::Float,
+                        ],
+                    }),
+                }),
+            },
+            common::nodes::SymbolKind::Function,
+            native::input::poll,
+        );
+    }
+
+    // runs a phase, a panic inside it becomes an internal error saying where in
+    // the program the phase had got to
+    fn guard<T>(
+        &mut self,
+        phase: Phase,
+        run: impl FnOnce(&mut NovaCore) -> Result<T, NovaError>,
+    ) -> Result<T, NovaError> {
+        match catch_panic(|| run(self)) {
+            Ok(result) => result,
+            Err(msg) => {
+                let position = match phase {
+                    Phase::Lexing => Some(self.lexer.position()),
+                    Phase::Parsing => Some(self.parser.get_current_token_position()),
+                    Phase::Compiling | Phase::Assembling => None,
+                    Phase::Running => self.vm.current_position(),
+                };
+                Err(internal_error(phase.name(), msg, position))
+            }
+        }
+    }
+
+    // runs the built program and its timers, then the close functions of the
+    // with blocks it left open unless the vm crashed
+    fn execute(&mut self, debug: bool) -> Result<(), NovaError> {
+        let result = self.guard(Phase::Running, |core| {
+            match debug {
+                true => core.vm.run_debug(),
+                false => core.vm.run(),
+            }
+            .and_then(|_| core.vm.run_timers())
+        });
+        if !matches!(result, Err(NovaError::Internal { .. })) {
+            self.vm.unwind();
+        }
+        result
     }
 
     fn process(&mut self) -> Result<(), NovaError> {
+        self.timings = Timings::default();
+        let start = Instant::now();
         self.initnova();
-        let tokenlist = self.lexer.tokenize()?;
+        self.timings.record("initialize", start);
+
+        let start = Instant::now();
+        let tokenlist = self.guard(Phase::Lexing, |core| core.lexer.tokenize())?;
+        self.timings.tokens = tokenlist.len();
+        self.timings.record("lexing", start);
+
+        // typechecking happens while parsing so the two are timed together
+        let start = Instant::now();
         self.parser.input = tokenlist;
-        self.parser.parse()?;
-        let ast = self.parser.ast.clone();
+        self.parser.search_paths = self.search_paths.clone();
+        self.guard(Phase::Parsing, |core| core.parser.parse())?;
+        if self.warnings != WarningLevel::None {
+            for warning in self.parser.warnings.iter() {
+                warning.show();
+            }
+        }
+        if self.warnings == WarningLevel::Error && !self.parser.warnings.is_empty() {
+            return Err(NovaError::Compiler {
+                msg: format!(
+                    "Build failed with {} warning(s)",
+                    self.parser.warnings.len()
+                )
+                .into(),
+                note: "Warnings are treated as errors, set warnings to all to allow them".into(),
+            });
+        }
+        self.timings.nodes = self.parser.ast.node_count();
+        self.timings.record("parsing + typechecking", start);
+
+        let start = Instant::now();
+        let mut ast = self.parser.ast.clone();
+        if let Some(test) = &self.test {
+            Self::keep_test(&mut ast.program, test, self.filepath.clone());
+        }
         let filepath = self.filepath.clone();
         self.compiler.init();
-        let asm = self
-            .compiler
-            .compile_program(ast, filepath, true, true, false, false)?;
+        let asm = self.guard(Phase::Compiling, |core| {
+            core.compiler
+                .compile_program(ast, filepath, true, true, false, false)
+        })?;
+        self.timings.instructions = asm.len();
+        self.timings.record("codegen", start);
+
+        let start = Instant::now();
         self.assembler.input = asm;
-        self.assembler.assemble();
+        self.guard(Phase::Assembling, |core| {
+            core.assembler.assemble();
+            Ok(())
+        })?;
         self.vm.runtime_errors_table = self.assembler.runtime_error_table.clone();
+        self.vm.line_table = self.assembler.line_table.clone();
+        self.vm.function_names = self.assembler.function_names.clone();
+        self.vm.operators = self.compiler.operators.clone();
+        self.vm.equality = self.compiler.equality.clone();
         self.vm.state.program = self.assembler.output.clone();
+        self.timings.bytes = self.vm.state.program.len();
+        self.timings.record("assembly", start);
+
+        if self.show_timings {
+            self.timings.show();
+        }
         Ok(())
     }
 
@@ -454,92 +2012,263 @@ impl NovaCore {
         self.initnova();
 
         self.parser = parser::default();
-        self.parser.input = self.lexer.tokenize()?;
+        self.parser.input = self.guard(Phase::Lexing, |core| core.lexer.tokenize())?;
+        self.parser.search_paths = self.search_paths.clone();
         self.initnova();
 
-        self.parser.parse()?;
-        let ast = self.parser.ast.clone();
+        self.guard(Phase::Parsing, |core| core.parser.parse())?;
+        let mut ast = self.parser.ast.clone();
+        let statements = ast.program.len();
+        let added = &mut ast.program[self.repl_statements.min(statements)..];
+        let defines = added.iter().all(is_definition);
+        // the value of an expression on the line is left on the stack to be
+        // printed
+        let mut shown = None;
+        if let Some(Statement::Expression { ttype, expr }) = added
+            .iter_mut()
+            .rfind(|statement| !matches!(statement, Statement::Line { .. }))
+        {
+            // a trailing ; drops the value like it does in a file
+            if *ttype != TType::Void
+                && !matches!(expr, Expr::Let { .. })
+                && !line.trim_end().ends_with(';')
+            {
+                shown = Some(std::mem::replace(ttype, TType::Void));
+            }
+        }
 
         self.compiler = compiler::new();
         self.initnova();
         self.compiler.init();
-        let asm = self
-            .compiler
-            .compile_program(ast, None, true, true, false, false)?;
+        let asm = self.guard(Phase::Compiling, |core| {
+            core.compiler
+                .compile_program(ast, None, true, true, false, false)
+        })?;
 
         self.assembler = Assembler::empty();
         self.initnova();
         self.assembler.input = asm;
-        self.assembler.assemble();
+        self.guard(Phase::Assembling, |core| {
+            core.assembler.assemble();
+            Ok(())
+        })?;
 
-        self.vm = vm::new();
+        let interrupt = self.vm.interrupt_handle();
+        let hooks = self.vm.hooks.clone();
+        self.vm = Vm::new(self.vm.config.clone());
+        self.vm.interrupt = interrupt;
+        self.vm.hooks = hooks;
         self.initnova();
 
         self.vm.runtime_errors_table = self.assembler.runtime_error_table.clone();
+        self.vm.line_table = self.assembler.line_table.clone();
+        self.vm.function_names = self.assembler.function_names.clone();
+        self.vm.operators = self.compiler.operators.clone();
+        self.vm.equality = self.compiler.equality.clone();
         self.vm.state.program = self.assembler.output.clone();
 
-        self.vm.run()?;
+        let result = self.guard(Phase::Running, |core| {
+            core.vm.run()?;
+            if let Some(ttype) = &shown {
+                if let Some(value) = core.vm.state.stack.pop() {
+                    println!(
+                        "{}",
+                        show::show(
+                            &core.vm.state,
+                            &core.parser.environment,
+                            value,
+                            ttype,
+                            core.show_limits,
+                        )
+                    );
+                }
+            }
+            core.vm.run_timers()
+        });
+        if !matches!(result, Err(NovaError::Internal { .. })) {
+            self.vm.unwind();
+        }
+        result?;
         if !store && (line.contains("println") || line.contains("print")) {
             self.current_repl = oldrepl;
+        } else {
+            self.repl_statements = statements;
+            if defines {
+                self.repl_definitions.push_str(line);
+            }
+            // ended with ; so a next line starting with ( or [ is not read as
+            // a call or index of this value
+            if shown.is_some() {
+                self.current_repl.truncate(oldrepl.len());
+                self.current_repl.push_str(line.trim_end());
+                self.current_repl.push_str(";\n");
+            }
         }
 
         Ok(())
     }
 
-    pub fn run(mut self) -> Result<(), NovaError> {
+    // builds the program without running it, warnings are left in
+    // parser.warnings and run_built runs it afterwards
+    pub fn build(&mut self) -> Result<(), NovaError> {
+        self.process()
+    }
+
+    // the status the program passed to exit, 0 when it ran to the end
+    pub fn run(mut self) -> Result<i32, NovaError> {
         self.process()?;
-        self.vm.run()?;
-        Ok(())
+        self.run_built()
     }
 
-    pub fn check(mut self) -> Result<(), NovaError> {
-        let start = std::time::Instant::now();
-        self.initnova();
-        println!("OK | Initialize time: {}ms", start.elapsed().as_millis());
+    pub fn run_built(mut self) -> Result<i32, NovaError> {
+        let result = self.execute(false);
+        self.vm.show_gc_stats();
+        result.map(|_| self.vm.state.status)
+    }
 
-        let tokenlist = self.lexer.tokenize()?;
-        println!("OK | Lexing time: {}ms", start.elapsed().as_millis());
+    // the test functions of the file in the order they are written, those
+    // named test_ something that take nothing. the file is parsed, not run
+    pub fn tests(self) -> Result<Vec<Rc<str>>, NovaError> {
+        self.functions_named("test_")
+    }
 
-        self.parser.input = tokenlist;
-        self.parser.parse()?;
-        println!(
-            "OK | Parsing + Typechecking time: {}ms",
-            start.elapsed().as_millis()
-        );
+    // the same for bench functions, named bench_ something
+    pub fn benches(self) -> Result<Vec<Rc<str>>, NovaError> {
+        self.functions_named("bench_")
+    }
 
-        let ast = self.parser.ast;
-        self.compiler.init();
-        let asm = self
-            .compiler
-            .compile_program(ast, self.filepath, true, true, false, false)?;
-        println!("OK | Compile time: {}ms", start.elapsed().as_millis());
+    fn functions_named(mut self, prefix: &str) -> Result<Vec<Rc<str>>, NovaError> {
+        self.initnova();
+        self.parser.input = self.guard(Phase::Lexing, |core| core.lexer.tokenize())?;
+        self.parser.search_paths = self.search_paths.clone();
+        self.guard(Phase::Parsing, |core| core.parser.parse())?;
+        if self.warnings != WarningLevel::None {
+            for warning in self.parser.warnings.iter() {
+                warning.show();
+            }
+        }
+        Ok(self
+            .parser
+            .ast
+            .program
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Function {
+                    identifier,
+                    parameters,
+                    ..
+                } if parameters.is_empty() && identifier.starts_with(prefix) => {
+                    // named the way it was written, not by its signature
+                    let signature = generate_unique_string("", &[TType::None]);
+                    Some(
+                        identifier
+                            .strip_suffix(&signature)
+                            .unwrap_or(identifier)
+                            .into(),
+                    )
+                }
+                _ => None,
+            })
+            .collect())
+    }
 
-        self.assembler.input = asm;
-        self.assembler.assemble();
-        println!("OK | Assembler time: {}ms", start.elapsed().as_millis());
+    // runs one test of the file in a vm of its own. the definitions of the
+    // file are built but the rest of its top level is left out, so only the
+    // test and what it calls run. returns the status like run
+    pub fn run_test(mut self, test: &str) -> Result<i32, NovaError> {
+        self.test = Some(generate_unique_string(test, &[TType::None]).into());
+        // tests found them already
+        self.warnings = match self.warnings {
+            WarningLevel::Error => WarningLevel::Error,
+            _ => WarningLevel::None,
+        };
+        self.run()
+    }
 
-        self.vm.runtime_errors_table = self.assembler.runtime_error_table.clone();
-        self.vm.state.program = self.assembler.output;
-        Ok(())
+    // calls a bench function of the file over and over in one vm, after the
+    // definitions of the file are built and it has been called once the way
+    // run_test calls a test
+    pub fn run_bench(mut self, bench: &str) -> Result<BenchResult, NovaError> {
+        let id: Rc<str> = generate_unique_string(bench, &[TType::None]).into();
+        self.test = Some(id.clone());
+        self.warnings = match self.warnings {
+            WarningLevel::Error => WarningLevel::Error,
+            _ => WarningLevel::None,
+        };
+        self.process()?;
+        self.execute(false)?;
+        let Some(slot) = self.compiler.global.get_index(&id) else {
+            return Err(NovaError::Runtime {
+                msg: format!("No bench function {}", bench).into(),
+            });
+        };
+        self.guard(Phase::Running, |core| {
+            let function = core.vm.state.stack[slot];
+            bench::measure(|| core.vm.call_value(function, &[]).map(|_| ()))
+        })
+    }
+
+    // the definitions of the program followed by a call to the test
+    fn keep_test(program: &mut Vec<Statement>, test: &str, filepath: Option<Rc<Path>>) {
+        let call = program.iter().find_map(|statement| match statement {
+            Statement::Function {
+                ttype, identifier, ..
+            } if &**identifier == test => Some(Statement::Expression {
+                ttype: ttype.clone(),
+                expr: Expr::Literal {
+                    ttype: ttype.clone(),
+                    value: Atom::Call {
+                        name: identifier.clone(),
+                        arguments: vec![],
+                        position: common::fileposition::FilePosition {
+                            filepath: filepath.clone(),
+                            line: 0,
+                            col: 0,
+                        },
+                    },
+                },
+            }),
+            _ => None,
+        });
+        program.retain(is_definition);
+        program.extend(call);
     }
 
-    pub fn run_debug(mut self) -> Result<(), NovaError> {
+    pub fn check(mut self) -> Result<(), NovaError> {
+        let result = self.process();
+        let labels = [
+            "Initialize",
+            "Lexing",
+            "Parsing + Typechecking",
+            "Compile",
+            "Assembler",
+        ];
+        let mut elapsed = Duration::ZERO;
+        for (label, (_, duration)) in labels.iter().zip(self.timings.phases.iter()) {
+            elapsed += *duration;
+            println!("OK | {} time: {}ms", label, elapsed.as_millis());
+        }
+        result
+    }
+
+    pub fn run_debug(mut self) -> Result<i32, NovaError> {
         self.process()?;
-        self.vm.run_debug()?;
-        Ok(())
+        self.execute(true).map(|_| self.vm.state.status)
     }
 
     pub fn dis_file(mut self) -> Result<(), NovaError> {
         self.initnova();
         let tokenlist = self.lexer.tokenize()?;
         self.parser.input = tokenlist;
-        self.parser.parse()?;
+        self.parser.search_paths = self.search_paths.clone();
+        self.guard(Phase::Parsing, |core| core.parser.parse())?;
         let ast = self.parser.ast.clone();
         let filepath = self.filepath.clone();
         self.compiler.init();
-        let asm = self
-            .compiler
-            .compile_program(ast, filepath, true, true, false, false)?;
+        let asm = self.guard(Phase::Compiling, |core| {
+            core.compiler
+                .compile_program(ast, filepath, true, true, false, false)
+        })?;
         let mut dis = disassembler::new();
         dis.dis_asm(asm);
         Ok(())
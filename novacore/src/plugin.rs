@@ -0,0 +1,107 @@
+use std::{fmt, path::Path, rc::Rc};
+
+use common::error::NovaError;
+
+use crate::NovaCore;
+
+// a native extension. register adds its functions to every program a core
+// builds after it was given the plugin with NovaCore::add_plugin, through
+// add_module and add_function:
+//
+//     struct Sqlite;
+//
+//     impl Plugin for Sqlite {
+//         fn name(&self) -> &str {
+//             "sqlite"
+//         }
+//
+//         fn register(&self, core: &mut NovaCore) {
+//             core.add_module("sqlite");
+//             core.add_function("sqlite::open", ..., SymbolKind::Function, open);
+//         }
+//     }
+pub trait Plugin {
+    fn name(&self) -> &str;
+    fn register(&self, core: &mut NovaCore);
+}
+
+impl fmt::Debug for dyn Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Plugin({})", self.name())
+    }
+}
+
+// the symbol a plugin library exports with export_plugin!
+const ENTRY: &str = "nova_plugin";
+
+// exports the plugin from a cdylib so load can find it. the library has to be
+// built by the same compiler against the same version of nova, natives
+// exchange the vm's state with it directly
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:expr) => {
+        #[no_mangle]
+        pub extern "C" fn nova_plugin() -> *mut Box<dyn $crate::plugin::Plugin> {
+            let plugin: Box<dyn $crate::plugin::Plugin> = Box::new($plugin);
+            Box::into_raw(Box::new(plugin))
+        }
+    };
+}
+
+// opens a plugin library and returns the plugin it exports for add_plugin, the
+// library stays loaded for the rest of the process since its natives live in it
+#[cfg(unix)]
+pub fn load(path: &Path) -> Result<Rc<dyn Plugin>, NovaError> {
+    use std::{
+        ffi::{CStr, CString},
+        os::unix::ffi::OsStrExt,
+    };
+
+    let error = |reason: &str| NovaError::File {
+        msg: format!("Could not load plugin {}: {}", path.display(), reason).into(),
+    };
+    let last_error = || {
+        // SAFETY: dlerror returns null or a valid C string
+        let message = unsafe { libc::dlerror() };
+        if message.is_null() {
+            "unknown error".to_string()
+        } else {
+            // SAFETY: checked for null above
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        }
+    };
+    let filename = CString::new(path.as_os_str().as_bytes()).map_err(|_| error("bad path"))?;
+    // SAFETY: the filename is a valid C string, the library is never closed
+    let library = unsafe { libc::dlopen(filename.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if library.is_null() {
+        return Err(error(&last_error()));
+    }
+    let entry = CString::new(ENTRY).unwrap();
+    // SAFETY: the library handle is valid and the symbol name a valid C string
+    let symbol = unsafe { libc::dlsym(library, entry.as_ptr()) };
+    if symbol.is_null() {
+        return Err(error(&format!(
+            "it does not export {}, use novacore::export_plugin!",
+            ENTRY
+        )));
+    }
+    // SAFETY: export_plugin! declares the symbol with this signature
+    let plugin = unsafe {
+        let entry: extern "C" fn() -> *mut Box<dyn Plugin> = std::mem::transmute(symbol);
+        Box::from_raw(entry())
+    };
+    Ok(Rc::from(*plugin))
+}
+
+#[cfg(not(unix))]
+pub fn load(path: &Path) -> Result<Rc<dyn Plugin>, NovaError> {
+    Err(NovaError::File {
+        msg: format!(
+            "Could not load plugin {}: plugin libraries are only supported on unix",
+            path.display()
+        )
+        .into(),
+    })
+}
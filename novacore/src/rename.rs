@@ -0,0 +1,390 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use common::{
+    error::{apply_fixes, Fix, NovaError},
+    fileposition::FilePosition,
+    nodes::{Reference, SymbolRef},
+    tokens::{Token, TokenValue},
+};
+use lexer::Lexer;
+
+use crate::NovaCore;
+
+impl NovaCore {
+    // every name of the program with what it refers to, declarations refer to
+    // themselves. the program is parsed but not built
+    pub fn references(mut self) -> Result<Vec<Reference>, NovaError> {
+        self.initnova();
+        self.parser.input = self.lexer.tokenize()?;
+        self.parser.search_paths = self.search_paths.clone();
+        self.parser.parse()?;
+        let mut references = self.parser.references;
+        references.extend(
+            self.parser
+                .environment
+                .declarations
+                .into_iter()
+                .map(|position| Reference {
+                    position: position.clone(),
+                    symbol: SymbolRef::Declaration(position),
+                }),
+        );
+        Ok(references)
+    }
+
+    // the edits that rename the function, variable, field or variant written
+    // at position to new_name in every file of the program. the renamed program
+    // is parsed again and the edits are refused if it no longer builds or any
+    // of its names would refer to something else
+    pub fn rename(self, position: &FilePosition, new_name: &str) -> Result<Vec<Fix>, NovaError> {
+        check_name(new_name)?;
+        let mut sources = Sources::new(&self);
+        let references = self.clone().references()?;
+
+        let old_name = sources.identifier_at(position)?;
+        let symbol = references
+            .iter()
+            .find(|reference| reference.position == old_name.position)
+            .map(|reference| reference.symbol.clone())
+            .ok_or_else(|| {
+                error(
+                    format!("Cannot rename '{}'", name_of(&old_name)),
+                    "only functions, variables, parameters, fields and variants declared in nova code can be renamed",
+                    position.clone(),
+                )
+            })?;
+        let old_name = name_of(&old_name);
+        if *old_name == *new_name {
+            return Ok(vec![]);
+        }
+
+        let mut edits: Vec<FilePosition> = references
+            .iter()
+            .filter(|reference| reference.symbol == symbol)
+            .map(|reference| reference.position.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        edits.sort_by_key(|edit| (edit.filepath.clone(), edit.line, edit.col));
+        for edit in edits.iter() {
+            let declared_by_nova = edit
+                .filepath
+                .as_deref()
+                .is_none_or(|path| path == Path::new("prelude.nv"));
+            if declared_by_nova {
+                return Err(error(
+                    format!("Cannot rename '{}'", old_name),
+                    "it is part of the prelude or a builtin",
+                    position.clone(),
+                ));
+            }
+            match sources.token_at(edit)? {
+                Some(TokenValue::Identifier(name)) if *name == old_name => {}
+                _ => {
+                    return Err(error(
+                        format!("Cannot rename '{}'", old_name),
+                        "it is used here in a way nova rename cannot change",
+                        edit.clone(),
+                    ))
+                }
+            }
+        }
+        let fixes: Vec<Fix> = edits
+            .iter()
+            .map(|edit| Fix {
+                msg: format!("rename '{}' to '{}'", old_name, new_name).into(),
+                start: edit.clone(),
+                end: FilePosition {
+                    col: edit.col + old_name.chars().count(),
+                    ..edit.clone()
+                },
+                replacement: new_name.to_owned(),
+            })
+            .collect();
+
+        // build the renamed program from the edited sources
+        let mut renamed = self.clone();
+        let mut files: Vec<&Path> = edits.iter().filter_map(|e| e.filepath.as_deref()).collect();
+        files.dedup();
+        for file in files {
+            let file_fixes: Vec<Fix> = fixes
+                .iter()
+                .filter(|fix| fix.start.filepath.as_deref() == Some(file))
+                .cloned()
+                .collect();
+            let source = apply_fixes(&sources.source(file)?, &file_fixes);
+            if self.filepath.as_deref() == Some(file) {
+                renamed.lexer = Lexer::new(source, Some(file));
+            } else {
+                renamed
+                    .parser
+                    .overlay
+                    .insert(canonical_path(file), source.into());
+            }
+        }
+        let new_references = match renamed.references() {
+            Ok(references) => references,
+            Err(broken) => {
+                return Err(error(
+                    format!(
+                        "Renaming '{}' to '{}' would break the program",
+                        old_name, new_name
+                    ),
+                    describe(&broken),
+                    position.clone(),
+                ))
+            }
+        };
+
+        // every name has to refer to the same thing as before, with the
+        // columns after an edit on the same line moved along
+        let shift = Shift {
+            edits: &edits,
+            delta: new_name.chars().count() as isize - old_name.chars().count() as isize,
+        };
+        let renamed_symbol = |renamed: &SymbolRef| match renamed {
+            SymbolRef::Declaration(declared) => SymbolRef::Declaration(shift.forward(declared)),
+            SymbolRef::Member(owner, _) if *renamed == symbol => {
+                SymbolRef::Member(owner.clone(), new_name.into())
+            }
+            member => member.clone(),
+        };
+        let before: HashSet<Reference> = references
+            .iter()
+            .map(|reference| Reference {
+                position: shift.forward(&reference.position),
+                symbol: renamed_symbol(&reference.symbol),
+            })
+            .collect();
+        let after: HashSet<Reference> = new_references.into_iter().collect();
+        let mut changed: Vec<FilePosition> = before
+            .symmetric_difference(&after)
+            .map(|reference| shift.back(&reference.position))
+            .collect();
+        changed.sort_by_key(|changed| (changed.filepath.clone(), changed.line, changed.col));
+        if let Some(changed) = changed.into_iter().next() {
+            let name = sources
+                .identifier_at(&changed)
+                .map(|token| name_of(&token))
+                .unwrap_or_else(|_| new_name.into());
+            return Err(error(
+                format!(
+                    "Renaming '{}' to '{}' would change what a name refers to",
+                    old_name, new_name
+                ),
+                format!("'{}' here would refer to a different symbol", name),
+                changed,
+            ));
+        }
+        Ok(fixes)
+    }
+}
+
+// column changes of the edits, positions on other lines stay where they are
+struct Shift<'a> {
+    edits: &'a [FilePosition],
+    delta: isize,
+}
+
+impl Shift<'_> {
+    fn forward(&self, position: &FilePosition) -> FilePosition {
+        let before = self
+            .edits
+            .iter()
+            .filter(|edit| {
+                edit.filepath == position.filepath
+                    && edit.line == position.line
+                    && edit.col < position.col
+            })
+            .count() as isize;
+        FilePosition {
+            col: (position.col as isize + before * self.delta) as usize,
+            ..position.clone()
+        }
+    }
+
+    fn back(&self, position: &FilePosition) -> FilePosition {
+        let before = self
+            .edits
+            .iter()
+            .filter(|edit| {
+                edit.filepath == position.filepath
+                    && edit.line == position.line
+                    && self.forward(edit).col < position.col
+            })
+            .count() as isize;
+        FilePosition {
+            col: (position.col as isize - before * self.delta) as usize,
+            ..position.clone()
+        }
+    }
+}
+
+// the sources of the program as they are now, the file being built may not
+// be the one on disk
+struct Sources {
+    root: Option<(Rc<Path>, Rc<str>)>,
+    tokens: HashMap<PathBuf, Vec<Token>>,
+}
+
+impl Sources {
+    fn new(core: &NovaCore) -> Sources {
+        Sources {
+            root: core
+                .filepath
+                .clone()
+                .map(|filepath| (filepath, core.lexer.source.clone())),
+            tokens: HashMap::default(),
+        }
+    }
+
+    fn source(&self, file: &Path) -> Result<Rc<str>, NovaError> {
+        if let Some((root, source)) = &self.root {
+            if **root == *file {
+                return Ok(source.clone());
+            }
+        }
+        fs::read_to_string(file)
+            .map(Rc::from)
+            .map_err(|error| NovaError::File {
+                msg: format!("Could not read {}: {}", file.display(), error).into(),
+            })
+    }
+
+    fn tokens(&mut self, file: &Path) -> Result<&[Token], NovaError> {
+        if !self.tokens.contains_key(file) {
+            let tokens = Lexer::new(self.source(file)?, Some(file)).tokenize()?;
+            self.tokens.insert(file.to_path_buf(), tokens);
+        }
+        Ok(&self.tokens[file])
+    }
+
+    // the token that starts at position
+    fn token_at(&mut self, position: &FilePosition) -> Result<Option<&TokenValue>, NovaError> {
+        let Some(file) = position.filepath.clone() else {
+            return Ok(None);
+        };
+        Ok(self
+            .tokens(&file)?
+            .iter()
+            .find(|token| token.position == *position)
+            .map(|token| &token.value))
+    }
+
+    // the identifier written at or around position
+    fn identifier_at(&mut self, position: &FilePosition) -> Result<Token, NovaError> {
+        let Some(file) = position.filepath.clone() else {
+            return Err(nothing_at(position));
+        };
+        self.tokens(&file)?
+            .iter()
+            .find(|token| match &token.value {
+                TokenValue::Identifier(name) => {
+                    token.position.line == position.line
+                        && (token.position.col..token.position.col + name.chars().count())
+                            .contains(&position.col)
+                }
+                _ => false,
+            })
+            .cloned()
+            .ok_or_else(|| nothing_at(position))
+    }
+}
+
+fn nothing_at(position: &FilePosition) -> NovaError {
+    error(
+        "Nothing to rename here",
+        "put the position on the name of a function, variable, field or variant",
+        position.clone(),
+    )
+}
+
+fn name_of(token: &Token) -> Rc<str> {
+    match &token.value {
+        TokenValue::Identifier(name) => name.clone(),
+        _ => "".into(),
+    }
+}
+
+// the new name has to lex as a single identifier
+fn check_name(name: &str) -> Result<(), NovaError> {
+    let tokens = Lexer::new(name, None).tokenize();
+    match tokens.as_deref() {
+        Ok(
+            [Token {
+                value: TokenValue::Identifier(identifier),
+                ..
+            }],
+        ) if **identifier == *name => Ok(()),
+        _ => Err(NovaError::File {
+            msg: format!("'{}' is not a valid name", name).into(),
+        }),
+    }
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn error(
+    msg: impl Into<std::borrow::Cow<'static, str>>,
+    note: impl Into<std::borrow::Cow<'static, str>>,
+    position: FilePosition,
+) -> NovaError {
+    NovaError::Parsing {
+        msg: msg.into(),
+        note: note.into(),
+        position,
+        extra: None,
+        fixes: Box::default(),
+    }
+}
+
+// one line saying what went wrong in the renamed program and where
+fn describe(error: &NovaError) -> String {
+    let (msg, position) = match error {
+        NovaError::File { msg } | NovaError::Runtime { msg } => (msg.to_string(), None),
+        NovaError::Compiler { msg, .. } => (msg.to_string(), None),
+        NovaError::Lexing { msg, position, .. }
+        | NovaError::Parsing { msg, position, .. }
+        | NovaError::RuntimeWithPos { msg, position }
+        | NovaError::TypeError { msg, position, .. }
+        | NovaError::SimpleTypeError { msg, position } => (msg.to_string(), Some(position)),
+        NovaError::TypeMismatch {
+            expected,
+            found,
+            position,
+            ..
+        } => (
+            format!("expected {}, found {}", expected, found),
+            Some(position),
+        ),
+        NovaError::Interrupted => ("interrupted".to_string(), None),
+        NovaError::Internal {
+            phase,
+            msg,
+            position,
+        } => (
+            format!("nova crashed while {}: {}", phase, msg),
+            position.as_ref(),
+        ),
+    };
+    match position {
+        Some(position) => format!(
+            "afterwards {} at {}:{}:{}",
+            msg,
+            position
+                .filepath
+                .as_deref()
+                .map_or("".into(), |path| path.display().to_string()),
+            position.line,
+            position.col
+        ),
+        None => format!("afterwards {}", msg),
+    }
+}
@@ -0,0 +1,261 @@
+use std::rc::Rc;
+
+use common::{environment::Environment, ttype::TType, utilities::format_float};
+use parser::Parser;
+use vm::state::{Heap, State, VmData};
+
+// how much of a value the repl prints, lists, structs and tuples nested
+// deeper than depth and anything past width characters become ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShowLimits {
+    pub depth: usize,
+    pub width: usize,
+}
+
+impl ShowLimits {
+    pub const FULL: ShowLimits = ShowLimits {
+        depth: usize::MAX,
+        width: usize::MAX,
+    };
+}
+
+impl Default for ShowLimits {
+    fn default() -> Self {
+        ShowLimits {
+            depth: 4,
+            width: 100,
+        }
+    }
+}
+
+// a value written the way it would be in source, using its type to name the
+// fields of structs and the variants of enums
+pub fn show(
+    state: &State,
+    environment: &Environment,
+    value: VmData,
+    ttype: &TType,
+    limits: ShowLimits,
+) -> String {
+    let mut printer = Printer {
+        state,
+        environment,
+        limits,
+        out: String::new(),
+        len: 0,
+    };
+    printer.value(value, ttype, 0);
+    printer.out
+}
+
+struct Printer<'a> {
+    state: &'a State,
+    environment: &'a Environment,
+    limits: ShowLimits,
+    out: String,
+    // characters in out
+    len: usize,
+}
+
+impl Printer<'_> {
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.len += text.chars().count();
+    }
+
+    fn full(&self) -> bool {
+        self.len >= self.limits.width
+    }
+
+    fn items(&self, value: VmData) -> Option<Vec<VmData>> {
+        match value {
+            VmData::List(index) | VmData::Struct(index) => match self.state.get_ref(index) {
+                Heap::List(items) => Some(
+                    items
+                        .iter()
+                        .map(|item| self.state.to_vmdata(*item))
+                        .collect(),
+                ),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn text(&self, value: VmData) -> Option<Rc<str>> {
+        match value {
+            VmData::String(index) => match self.state.get_ref(index) {
+                Heap::String(text) => Some(text.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // the text is cut to what is left of the width
+    fn string(&mut self, text: &str) {
+        let quoted = format!("\"{}\"", text.escape_debug());
+        let room = self.limits.width.saturating_sub(self.len);
+        if quoted.chars().count() > room {
+            let cut: String = quoted.chars().take(room.saturating_sub(4)).collect();
+            self.write(&cut);
+            self.write("...\"");
+        } else {
+            self.write(&quoted);
+        }
+    }
+
+    // items between open and close, stopping with ... once the width is used up
+    fn sequence(&mut self, open: &str, close: &str, items: &[(VmData, TType)], depth: usize) {
+        self.write(open);
+        if depth >= self.limits.depth && !items.is_empty() {
+            self.write("...");
+            self.write(close);
+            return;
+        }
+        for (i, (item, ttype)) in items.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            if self.full() {
+                self.write(&format!("... {} more", items.len() - i));
+                break;
+            }
+            self.value(*item, ttype, depth + 1);
+        }
+        self.write(close);
+    }
+
+    fn value(&mut self, value: VmData, ttype: &TType, depth: usize) {
+        if let VmData::StackAddress(index) = value {
+            let value = self.state.stack[self.state.offset + index];
+            return self.value(value, ttype, depth);
+        }
+        match (ttype, value) {
+            (_, VmData::None(_)) => self.write("None"),
+            (TType::Option { inner }, value) => {
+                self.write("Some(");
+                self.value(value, inner, depth);
+                self.write(")");
+            }
+            (TType::Char, VmData::Char(c)) => self.write(&format!("{:?}", c)),
+            (TType::List { inner }, value) => match self.items(value) {
+                Some(items) => {
+                    let items: Vec<_> = items.into_iter().map(|v| (v, (**inner).clone())).collect();
+                    self.sequence("[", "]", &items, depth)
+                }
+                None => self.raw(value, depth),
+            },
+            (TType::Tuple { elements }, value) => match self.items(value) {
+                Some(items) if items.len() == elements.len() => {
+                    let items: Vec<_> = items.into_iter().zip(elements.iter().cloned()).collect();
+                    self.sequence("(", ")", &items, depth)
+                }
+                _ => self.raw(value, depth),
+            },
+            (TType::Custom { name, type_params }, value) => {
+                if !self.custom(value, name, type_params, depth) {
+                    self.raw(value, depth)
+                }
+            }
+            (TType::Function { .. }, _) => self.write(&format!("<{}>", ttype)),
+            (_, value) => self.raw(value, depth),
+        }
+    }
+
+    // a struct or enum, false when the value does not have its shape
+    fn custom(
+        &mut self,
+        value: VmData,
+        name: &Rc<str>,
+        type_params: &[TType],
+        depth: usize,
+    ) -> bool {
+        let (Some(fields), Some(items)) =
+            (self.environment.custom_types.get(name), self.items(value))
+        else {
+            return false;
+        };
+        let field_type = |ttype: &TType| match self.environment.generic_type_struct.get(name) {
+            Some(generics) if generics.len() == type_params.len() => {
+                Parser::replace_generic_types(ttype, generics, type_params)
+            }
+            _ => ttype.clone(),
+        };
+        if self.environment.enums.has(name) {
+            // [payload, variant, name]
+            let (3, Some(VmData::Int(variant))) = (items.len(), items.get(1).copied()) else {
+                return false;
+            };
+            let Some((variant, payload)) = fields.get(variant as usize) else {
+                return false;
+            };
+            let payload = field_type(payload);
+            self.write(&format!("{}::{}", name, variant));
+            if payload != TType::None {
+                self.write("(");
+                if depth >= self.limits.depth {
+                    self.write("...");
+                } else {
+                    self.value(items[0], &payload, depth + 1);
+                }
+                self.write(")");
+            }
+            return true;
+        }
+        // the fields then the name of the struct
+        if items.len() != fields.len() + 1 {
+            return false;
+        }
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|(field, ttype)| (field.clone(), field_type(ttype)))
+            .collect();
+        self.write(name);
+        if fields.is_empty() {
+            return true;
+        }
+        if depth >= self.limits.depth {
+            self.write(" {...}");
+            return true;
+        }
+        self.write(" { ");
+        for (i, ((field, ttype), item)) in fields.iter().zip(items).enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            if self.full() {
+                self.write("...");
+                break;
+            }
+            self.write(&format!("{}: ", field));
+            self.value(item, ttype, depth + 1);
+        }
+        self.write(" }");
+        true
+    }
+
+    // what the value looks like without a type to go by
+    fn raw(&mut self, value: VmData, depth: usize) {
+        if let Some(text) = self.text(value) {
+            return self.string(&text);
+        }
+        if let VmData::List(_) = value {
+            if let Some(items) = self.items(value) {
+                let items: Vec<_> = items.into_iter().map(|v| (v, TType::Any)).collect();
+                return self.sequence("[", "]", &items, depth);
+            }
+        }
+        let text = match value {
+            VmData::Int(v) => v.to_string(),
+            VmData::Sized(v, width) => width.value(v).to_string(),
+            VmData::Float(v) => format_float(v),
+            VmData::Bool(v) => v.to_string(),
+            VmData::Char(c) => format!("{:?}", c),
+            VmData::BigInt(index) => self.state.get_ref(index).to_string(),
+            VmData::Function(_) | VmData::Closure(_) => "<function>".to_string(),
+            _ => "?".to_string(),
+        };
+        self.write(&text);
+    }
+}
@@ -0,0 +1,287 @@
+use std::rc::Rc;
+
+use common::nodes::{Atom, Expr, Statement};
+
+// the names a function body uses without declaring them first, in the order
+// they are first used. a name declared in a block only counts as declared in
+// that block, and a nested closure uses the names it captures
+pub fn free_variables(parameters: &[Rc<str>], exprs: &[&Expr], body: &[Statement]) -> Vec<Rc<str>> {
+    let mut walk = FreeVariables {
+        scopes: vec![parameters.to_vec()],
+        free: vec![],
+    };
+    for expr in exprs {
+        walk.expr(expr);
+    }
+    walk.statements(body);
+    walk.free
+}
+
+struct FreeVariables {
+    scopes: Vec<Vec<Rc<str>>>,
+    free: Vec<Rc<str>>,
+}
+
+impl FreeVariables {
+    fn declare(&mut self, name: &Rc<str>) {
+        self.scopes.last_mut().unwrap().push(name.clone());
+    }
+
+    fn used(&mut self, name: &Rc<str>) {
+        let declared = self.scopes.iter().any(|scope| scope.contains(name));
+        if !declared && !self.free.contains(name) {
+            self.free.push(name.clone());
+        }
+    }
+
+    // statements in a block of their own, with name declared before them
+    fn block(&mut self, name: Option<&Rc<str>>, body: &[Statement]) {
+        self.scopes.push(name.into_iter().cloned().collect());
+        self.statements(body);
+        self.scopes.pop();
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Function {
+                    identifier,
+                    captures,
+                    ..
+                } => {
+                    for capture in captures {
+                        self.used(capture);
+                    }
+                    self.declare(identifier);
+                }
+                Statement::Lazy { captures, .. } => {
+                    for capture in captures {
+                        self.used(capture);
+                    }
+                }
+                Statement::Return { expr, .. } | Statement::Expression { expr, .. } => {
+                    self.expr(expr)
+                }
+                Statement::If {
+                    test,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.expr(test);
+                    self.block(None, body);
+                    if let Some(alternative) = alternative {
+                        self.block(None, alternative);
+                    }
+                }
+                Statement::Unwrap {
+                    identifier,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.used(identifier);
+                    self.block(Some(identifier), body);
+                    if let Some(alternative) = alternative {
+                        self.block(None, alternative);
+                    }
+                }
+                Statement::IfLet {
+                    identifier,
+                    expr,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.expr(expr);
+                    self.block(Some(identifier), body);
+                    if let Some(alternative) = alternative {
+                        self.block(None, alternative);
+                    }
+                }
+                Statement::While { test, body } => {
+                    self.expr(test);
+                    self.block(None, body);
+                }
+                Statement::WhileLet {
+                    identifier,
+                    expr,
+                    body,
+                } => {
+                    self.expr(expr);
+                    self.block(Some(identifier), body);
+                }
+                Statement::For {
+                    init,
+                    test,
+                    inc,
+                    body,
+                } => {
+                    self.scopes.push(vec![]);
+                    self.expr(init);
+                    self.expr(test);
+                    self.expr(inc);
+                    self.block(None, body);
+                    self.scopes.pop();
+                }
+                Statement::Foreach {
+                    identifier,
+                    expr,
+                    body,
+                    ..
+                } => {
+                    self.expr(expr);
+                    self.block(Some(identifier), body);
+                }
+                Statement::ForRange {
+                    identifier,
+                    start,
+                    end,
+                    step,
+                    body,
+                    ..
+                } => {
+                    self.expr(start);
+                    self.expr(end);
+                    if let Some(step) = step {
+                        self.expr(step);
+                    }
+                    self.block(Some(identifier), body);
+                }
+                Statement::Block { body, .. } => self.block(None, body),
+                Statement::With {
+                    identifier,
+                    expr,
+                    body,
+                    close,
+                } => {
+                    self.expr(expr);
+                    self.scopes.push(vec![identifier.clone()]);
+                    self.block(None, body);
+                    self.expr(close);
+                    self.scopes.pop();
+                }
+                Statement::Match {
+                    expr,
+                    arms,
+                    default,
+                    ..
+                } => {
+                    self.expr(expr);
+                    for (_, binding, body) in arms {
+                        self.block(binding.as_ref(), body);
+                    }
+                    if let Some(default) = default {
+                        self.block(None, default);
+                    }
+                }
+                Statement::Continue
+                | Statement::Break
+                | Statement::Pass
+                | Statement::Struct { .. }
+                | Statement::Enum { .. }
+                | Statement::ForwardDec { .. }
+                | Statement::Line { .. } => {}
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Let {
+                identifier, expr, ..
+            } => {
+                self.expr(expr);
+                self.declare(identifier);
+            }
+            Expr::Closure { captures, .. } => {
+                for capture in captures {
+                    self.used(capture);
+                }
+            }
+            Expr::Literal { value, .. } => match value {
+                Atom::Id { name } => self.used(name),
+                Atom::Call {
+                    name, arguments, ..
+                } => {
+                    self.used(name);
+                    for argument in arguments {
+                        self.expr(argument);
+                    }
+                }
+                _ => {}
+            },
+            Expr::ListConstructor { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::ListCompConstructor {
+                loops,
+                expr,
+                guards,
+                ..
+            } => {
+                self.scopes.push(vec![]);
+                for (identifier, source) in loops {
+                    self.expr(source);
+                    self.declare(identifier);
+                }
+                for expr in expr.iter().chain(guards) {
+                    self.expr(expr);
+                }
+                self.scopes.pop();
+            }
+            Expr::Field { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Propagate { expr, .. }
+            | Expr::Return { expr, .. } => self.expr(expr),
+            Expr::Indexed {
+                container, index, ..
+            } => {
+                self.expr(container);
+                self.expr(index);
+            }
+            Expr::Sliced {
+                container,
+                start,
+                end,
+                step,
+                ..
+            } => {
+                self.expr(container);
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            Expr::Call { function, args, .. } => {
+                self.expr(function);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Binop { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::StoreExpr {
+                name, expr, body, ..
+            } => {
+                self.expr(expr);
+                self.block(Some(name), body);
+            }
+            Expr::IfExpr {
+                test,
+                body,
+                alternative,
+                ..
+            } => {
+                self.expr(test);
+                self.expr(body);
+                self.expr(alternative);
+            }
+            Expr::Block { body, .. } | Expr::Try { body, .. } => self.block(None, body),
+            Expr::None | Expr::Void => {}
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::{fmt::Write, path::Path, rc::Rc};
+
+use common::{
+    nodes::{Arg, Field, Statement},
+    ttype::{generate_unique_string, TType},
+};
+
+use crate::{canonical_path, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Struct,
+    Enum,
+    Function,
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub kind: Kind,
+    // the name importers use, hash::sha256 or hash::Digest::hex
+    pub name: Rc<str>,
+    // the declaration with its types and without a body
+    pub signature: String,
+    pub doc: Option<Rc<str>>,
+}
+
+// the pub items of a module in the order they are declared
+#[derive(Debug, Clone, Default)]
+pub struct Docs {
+    pub module: Rc<str>,
+    pub items: Vec<Item>,
+}
+
+// the items the parser read from file, which it parsed as an import so that
+// the ones declared without pub are known
+pub fn docs(parser: &Parser, file: &Path) -> Docs {
+    let mut docs = Docs {
+        module: parser
+            .module_cache
+            .get(&canonical_path(file))
+            .cloned()
+            .unwrap_or_default(),
+        items: vec![],
+    };
+    collect(parser, file, &parser.ast.program, false, &mut docs.items);
+    docs
+}
+
+// an import is a block with the statements of its file, so is an impl block
+fn collect(
+    parser: &Parser,
+    file: &Path,
+    statements: &[Statement],
+    inside: bool,
+    items: &mut Vec<Item>,
+) {
+    for statement in statements {
+        match statement {
+            Statement::Block {
+                body,
+                filepath: Some(filepath),
+            } => collect(parser, file, body, &**filepath == file, items),
+            Statement::Function {
+                ttype,
+                identifier,
+                parameters,
+                doc,
+                ..
+            } if inside && !private(parser, identifier) => {
+                let name = function_name(identifier, parameters);
+                let signature = function_signature(&name, parameters, ttype);
+                // dunder methods are called through their operator, and generic
+                // functions can be listed once for every use
+                let method = name.rsplit("::").next().unwrap_or_default();
+                if method.starts_with("__") && method.ends_with("__")
+                    || items.iter().any(|item| item.signature == signature)
+                {
+                    continue;
+                }
+                items.push(Item {
+                    kind: Kind::Function,
+                    name,
+                    signature,
+                    doc: doc.clone(),
+                });
+            }
+            Statement::Struct {
+                identifier,
+                fields,
+                doc,
+                ..
+            } if inside && !private(parser, identifier) => items.push(Item {
+                kind: Kind::Struct,
+                name: identifier.clone(),
+                signature: type_signature(parser, "struct", identifier, fields),
+                doc: doc.clone(),
+            }),
+            Statement::Enum {
+                identifier,
+                fields,
+                doc,
+                ..
+            } if inside && !private(parser, identifier) => items.push(Item {
+                kind: Kind::Enum,
+                name: identifier.clone(),
+                signature: type_signature(parser, "enum", identifier, fields),
+                doc: doc.clone(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+// declared without pub, or a method of a type that was
+fn private(parser: &Parser, identifier: &str) -> bool {
+    parser.private.contains_key(identifier)
+        || identifier
+            .split_once("::")
+            .is_some_and(|(owner, _)| parser.private.contains_key(owner))
+}
+
+// the name as written, overloads have their parameter types in the identifier
+fn function_name(identifier: &Rc<str>, parameters: &[Arg]) -> Rc<str> {
+    let mut types: Vec<TType> = parameters.iter().map(|arg| arg.ttype.clone()).collect();
+    if types.is_empty() {
+        types.push(TType::None);
+    }
+    let signature = generate_unique_string("", &types);
+    identifier
+        .strip_suffix(signature.as_str())
+        .map_or(identifier.clone(), Rc::from)
+}
+
+// fn hash::sha256(text: String) -> Digest, a function returning nothing has no arrow
+fn function_signature(name: &str, parameters: &[Arg], output: &TType) -> String {
+    let parameters: Vec<String> = parameters
+        .iter()
+        .map(|arg| format!("{}: {}", arg.identifier, arg.ttype))
+        .collect();
+    let mut signature = format!("fn {}({})", name, parameters.join(", "));
+    if *output != TType::Void {
+        write!(signature, " -> {}", output).unwrap();
+    }
+    signature
+}
+
+// struct Pair(A, B) with a field per line, variants without a value are
+// written by their name alone
+fn type_signature(parser: &Parser, keyword: &str, name: &str, fields: &[Field]) -> String {
+    let mut signature = format!("{} {}", keyword, name);
+    if let Some(generics) = parser.environment.generic_type_struct.get(name) {
+        write!(signature, "({})", generics.join(", ")).unwrap();
+    }
+    signature.push_str(" {\n");
+    for field in fields {
+        if field.ttype == TType::None {
+            writeln!(signature, "    {},", field.identifier).unwrap();
+        } else {
+            writeln!(signature, "    {}: {},", field.identifier, field.ttype).unwrap();
+        }
+    }
+    signature.push('}');
+    signature
+}
+
+impl Docs {
+    pub fn markdown(&self) -> String {
+        let mut out = format!("# Module {}\n", self.module);
+        for (kind, heading) in HEADINGS {
+            let items: Vec<&Item> = self.items.iter().filter(|item| item.kind == kind).collect();
+            if items.is_empty() {
+                continue;
+            }
+            write!(out, "\n## {}\n", heading).unwrap();
+            for item in items {
+                write!(
+                    out,
+                    "\n### {}\n\n```nova\n{}\n```\n",
+                    item.name, item.signature
+                )
+                .unwrap();
+                if let Some(doc) = &item.doc {
+                    write!(out, "\n{}\n", doc).unwrap();
+                }
+            }
+        }
+        out
+    }
+
+    // a page of its own, doc comments are shown as paragraphs of plain text
+    pub fn html(&self) -> String {
+        let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        writeln!(out, "<title>Module {}</title>", escape(&self.module)).unwrap();
+        out.push_str("</head>\n<body>\n");
+        writeln!(out, "<h1>Module {}</h1>", escape(&self.module)).unwrap();
+        for (kind, heading) in HEADINGS {
+            let items: Vec<&Item> = self.items.iter().filter(|item| item.kind == kind).collect();
+            if items.is_empty() {
+                continue;
+            }
+            writeln!(out, "<h2>{}</h2>", heading).unwrap();
+            for item in items {
+                writeln!(
+                    out,
+                    "<h3>{}</h3>\n<pre><code>{}</code></pre>",
+                    escape(&item.name),
+                    escape(&item.signature)
+                )
+                .unwrap();
+                let Some(doc) = &item.doc else {
+                    continue;
+                };
+                for paragraph in doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                    writeln!(out, "<p>{}</p>", escape(paragraph.trim())).unwrap();
+                }
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+const HEADINGS: [(Kind, &str); 3] = [
+    (Kind::Struct, "Structs"),
+    (Kind::Enum, "Enums"),
+    (Kind::Function, "Functions"),
+];
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,423 @@
+use std::{collections::HashSet, fmt::Write, path::Path, rc::Rc};
+
+use common::{
+    nodes::{Atom, Expr, Statement},
+    ttype::{generate_unique_string, TType},
+};
+
+// where a call is made from, the top level code of a file or a function
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Caller {
+    Module(Rc<Path>),
+    Function(Rc<str>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    // the name calls use, overloads have their parameter types in it
+    pub name: Rc<str>,
+    // the name as written with its parameter types, add(Int, Int)
+    pub label: String,
+    pub file: Rc<Path>,
+}
+
+// the files of a program and what imports what, with the functions they
+// declare and which of them call or refer to each other. each edge is listed
+// once in the order it first appears
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    // the file that was built comes first
+    pub modules: Vec<Rc<Path>>,
+    pub imports: Vec<(Rc<Path>, Rc<Path>)>,
+    pub functions: Vec<Function>,
+    pub calls: Vec<(Caller, Rc<str>)>,
+}
+
+// the prelude is parsed in front of the program, its helpers are left out
+pub fn graph(program: &[Statement], file: Rc<Path>) -> Graph {
+    let program = match program.first() {
+        Some(Statement::Block { filepath, .. }) if filepath.as_ref() != Some(&file) => {
+            &program[1..]
+        }
+        _ => program,
+    };
+    let mut builder = Builder {
+        file: file.clone(),
+        caller: Caller::Module(file.clone()),
+        graph: Graph {
+            modules: vec![file],
+            ..Graph::default()
+        },
+        seen: HashSet::new(),
+        references: vec![],
+    };
+    builder.statements(program);
+
+    // only functions written in nova are part of the graph, natives and
+    // variables holding closures are not
+    let declared: HashSet<Rc<str>> = builder
+        .graph
+        .functions
+        .iter()
+        .map(|function| function.name.clone())
+        .collect();
+    let mut calls = HashSet::new();
+    for (caller, callee) in builder.references {
+        if declared.contains(&callee) && calls.insert((caller.clone(), callee.clone())) {
+            builder.graph.calls.push((caller, callee));
+        }
+    }
+    builder.graph
+}
+
+impl Graph {
+    // graphviz source, the functions of each file are grouped in a box and
+    // the top level code of a file is the node named after it
+    pub fn dot(&self, root: &Path) -> String {
+        let mut out = String::from("digraph nova {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (index, module) in self.modules.iter().enumerate() {
+            let name = display_path(module, root);
+            writeln!(out, "    subgraph cluster_{} {{", index).unwrap();
+            writeln!(out, "        label={};", quote(&name)).unwrap();
+            writeln!(
+                out,
+                "        {} [label={}, style=filled];",
+                quote(&module_node(module)),
+                quote("(top level)")
+            )
+            .unwrap();
+            for function in self.functions.iter().filter(|f| &f.file == module) {
+                writeln!(
+                    out,
+                    "        {} [label={}, shape=ellipse];",
+                    quote(&function.name),
+                    quote(&function.label)
+                )
+                .unwrap();
+            }
+            out.push_str("    }\n");
+        }
+        for (importer, imported) in self.imports.iter() {
+            writeln!(
+                out,
+                "    {} -> {} [style=bold, label=\"import\"];",
+                quote(&module_node(importer)),
+                quote(&module_node(imported))
+            )
+            .unwrap();
+        }
+        for (caller, callee) in self.calls.iter() {
+            let caller = match caller {
+                Caller::Module(module) => module_node(module),
+                Caller::Function(name) => name.to_string(),
+            };
+            writeln!(out, "    {} -> {};", quote(&caller), quote(callee)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    // one line per edge, imports first
+    pub fn text(&self, root: &Path) -> String {
+        let mut out = String::from("imports\n");
+        for (importer, imported) in self.imports.iter() {
+            writeln!(
+                out,
+                "    {} -> {}",
+                display_path(importer, root),
+                display_path(imported, root)
+            )
+            .unwrap();
+        }
+        out.push_str("calls\n");
+        for (caller, callee) in self.calls.iter() {
+            let caller = match caller {
+                Caller::Module(module) => display_path(module, root),
+                Caller::Function(name) => self.label(name).to_string(),
+            };
+            writeln!(out, "    {} -> {}", caller, self.label(callee)).unwrap();
+        }
+        out
+    }
+
+    fn label<'a>(&'a self, name: &'a str) -> &'a str {
+        self.functions
+            .iter()
+            .find(|function| &*function.name == name)
+            .map_or(name, |function| &function.label)
+    }
+}
+
+struct Builder {
+    file: Rc<Path>,
+    caller: Caller,
+    graph: Graph,
+    // edges already listed
+    seen: HashSet<(Rc<Path>, Rc<Path>)>,
+    // every name a caller calls or reads, kept once the functions are known
+    references: Vec<(Caller, Rc<str>)>,
+}
+
+impl Builder {
+    fn reference(&mut self, name: &Rc<str>) {
+        self.references.push((self.caller.clone(), name.clone()));
+    }
+
+    // an import is a block of another file's statements, a file imported
+    // before only leaves an empty block behind
+    fn block(&mut self, body: &[Statement], filepath: &Option<Rc<Path>>) {
+        let Some(filepath) = filepath.clone().filter(|filepath| *filepath != self.file) else {
+            self.statements(body);
+            return;
+        };
+        if self.seen.insert((self.file.clone(), filepath.clone())) {
+            self.graph
+                .imports
+                .push((self.file.clone(), filepath.clone()));
+        }
+        if !self.graph.modules.contains(&filepath) {
+            self.graph.modules.push(filepath.clone());
+        }
+        let file = std::mem::replace(&mut self.file, filepath.clone());
+        let caller = std::mem::replace(&mut self.caller, Caller::Module(filepath));
+        self.statements(body);
+        self.file = file;
+        self.caller = caller;
+    }
+
+    fn function(&mut self, name: &Rc<str>, types: Vec<TType>, body: &[Statement]) {
+        // generic functions are checked once for every type they are used with
+        if !self.graph.functions.iter().any(|f| &f.name == name) {
+            self.graph.functions.push(Function {
+                name: name.clone(),
+                label: label(name, &types),
+                file: self.file.clone(),
+            });
+        }
+        let caller = std::mem::replace(&mut self.caller, Caller::Function(name.clone()));
+        self.statements(body);
+        self.caller = caller;
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Function {
+                    identifier,
+                    parameters,
+                    body,
+                    ..
+                } => {
+                    let types = parameters.iter().map(|arg| arg.ttype.clone()).collect();
+                    self.function(identifier, types, body)
+                }
+                Statement::Block { body, filepath } => self.block(body, filepath),
+                Statement::Return { expr, .. }
+                | Statement::Expression { expr, .. }
+                | Statement::Lazy { expr, .. } => self.expr(expr),
+                Statement::If {
+                    test,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.expr(test);
+                    self.statements(body);
+                    self.statements(alternative.as_deref().unwrap_or_default());
+                }
+                Statement::Unwrap {
+                    body, alternative, ..
+                } => {
+                    self.statements(body);
+                    self.statements(alternative.as_deref().unwrap_or_default());
+                }
+                Statement::IfLet {
+                    expr,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.expr(expr);
+                    self.statements(body);
+                    self.statements(alternative.as_deref().unwrap_or_default());
+                }
+                Statement::While { test: expr, body }
+                | Statement::WhileLet { expr, body, .. }
+                | Statement::Foreach { expr, body, .. } => {
+                    self.expr(expr);
+                    self.statements(body);
+                }
+                Statement::For {
+                    init,
+                    test,
+                    inc,
+                    body,
+                } => {
+                    for expr in [init, test, inc] {
+                        self.expr(expr);
+                    }
+                    self.statements(body);
+                }
+                Statement::ForRange {
+                    start,
+                    end,
+                    step,
+                    body,
+                    ..
+                } => {
+                    for expr in [Some(start), Some(end), step.as_ref()]
+                        .into_iter()
+                        .flatten()
+                    {
+                        self.expr(expr);
+                    }
+                    self.statements(body);
+                }
+                Statement::With {
+                    expr, body, close, ..
+                } => {
+                    self.expr(expr);
+                    self.statements(body);
+                    self.expr(close);
+                }
+                Statement::Match {
+                    expr,
+                    arms,
+                    default,
+                    ..
+                } => {
+                    self.expr(expr);
+                    for (_, _, body) in arms {
+                        self.statements(body);
+                    }
+                    self.statements(default.as_deref().unwrap_or_default());
+                }
+                Statement::Continue
+                | Statement::Break
+                | Statement::Pass
+                | Statement::Struct { .. }
+                | Statement::Enum { .. }
+                | Statement::ForwardDec { .. }
+                | Statement::Line { .. } => {}
+            }
+        }
+    }
+
+    // calls inside a closure belong to the function the closure is written in
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal { value, .. } => match value {
+                Atom::Id { name } => self.reference(name),
+                Atom::Call {
+                    name, arguments, ..
+                } => {
+                    self.reference(name);
+                    for argument in arguments {
+                        self.expr(argument);
+                    }
+                }
+                _ => {}
+            },
+            Expr::Let { expr, .. }
+            | Expr::Field { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Propagate { expr, .. }
+            | Expr::Return { expr, .. } => self.expr(expr),
+            Expr::Binop { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Closure { body, .. } => self.statements(body),
+            Expr::ListConstructor { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::ListCompConstructor {
+                loops,
+                expr,
+                guards,
+                ..
+            } => {
+                for (_, source) in loops {
+                    self.expr(source);
+                }
+                for expr in expr.iter().chain(guards) {
+                    self.expr(expr);
+                }
+            }
+            Expr::Indexed {
+                container, index, ..
+            } => {
+                self.expr(container);
+                self.expr(index);
+            }
+            Expr::Sliced {
+                container,
+                start,
+                end,
+                step,
+                ..
+            } => {
+                self.expr(container);
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            Expr::Call { function, args, .. } => {
+                self.expr(function);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::StoreExpr { expr, body, .. } => {
+                self.expr(expr);
+                self.statements(body);
+            }
+            Expr::IfExpr {
+                test,
+                body,
+                alternative,
+                ..
+            } => {
+                self.expr(test);
+                self.expr(body);
+                self.expr(alternative);
+            }
+            Expr::Block { body, .. } | Expr::Try { body, .. } => self.statements(body),
+            Expr::None | Expr::Void => {}
+        }
+    }
+}
+
+// add_Int_Int declared with (Int, Int) reads add(Int, Int)
+fn label(name: &str, types: &[TType]) -> String {
+    let types = if types.is_empty() {
+        vec![TType::None]
+    } else {
+        types.to_vec()
+    };
+    let suffix = generate_unique_string("", &types);
+    let parameters: Vec<String> = types
+        .iter()
+        .filter(|ttype| **ttype != TType::None)
+        .map(|ttype| ttype.to_string())
+        .collect();
+    let name = name.strip_suffix(suffix.as_str()).unwrap_or(name);
+    format!("{}({})", name, parameters.join(", "))
+}
+
+fn module_node(module: &Path) -> String {
+    format!("file:{}", module.display())
+}
+
+// paths next to or below the file that was built are shown relative to it
+fn display_path(path: &Path, root: &Path) -> String {
+    let directory = root.parent().unwrap_or(Path::new(""));
+    let path = path.strip_prefix(directory).unwrap_or(path);
+    path.display().to_string()
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,17 +1,23 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
-    ops::Deref,
-    path::{Path, PathBuf},
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    ops::{Deref, Range},
+    path::{Component, Path, PathBuf},
     rc::Rc,
     str::FromStr,
 };
 
 use common::{
     environment::Environment,
-    error::NovaError,
+    error::{Fix, NovaError, Warning},
     fileposition::FilePosition,
-    nodes::{Arg, Ast, Atom, Expr, Field, Statement, Symbol, SymbolKind},
+    format::{self, Piece},
+    nodes::{
+        Arg, Ast, Atom, Bound, ConstError, Contract, ContractKind, Expr, Field, Pattern, Reference,
+        Statement, Symbol, SymbolKind, SymbolRef,
+    },
+    rewrite,
     table::{self, Table},
     tokens::{
         KeyWord, Operator,
@@ -20,11 +26,33 @@ use common::{
         TokenValue::{self, *},
         Unary,
     },
-    ttype::{generate_unique_string, TType},
+    ttype::{describe_type, generate_unique_string, TType, Width},
+    utilities::closest_match,
 };
 
+use indexmap::IndexMap;
 use lexer::Lexer;
 
+mod captures;
+pub mod doc;
+pub mod graph;
+mod lint;
+#[cfg(test)]
+mod tests;
+
+// what a where clause can ask of a generic type
+const BOUNDS: [&str; 3] = ["Comparable", "Hash", "Show"];
+
+// builtins that were renamed, the old name still works but warns with a fix
+// that nova fix applies
+const RENAMED: [(&str, &str); 2] = [
+    ("terminal::rawmode", "terminal::rawMode"),
+    ("terminal::rawread", "terminal::rawRead"),
+];
+
+// helpers every program can use without an import
+const PRELUDE: &str = include_str!("../../std/prelude.nv");
+
 #[derive(Debug, Clone)]
 pub struct Parser {
     filepath: Option<Rc<Path>>,
@@ -33,6 +61,61 @@ pub struct Parser {
     pub ast: Ast,
     pub environment: Environment,
     pub modules: table::Table<Rc<str>>,
+    // the file each imported module was declared in, builtin modules have none
+    pub module_files: HashMap<Rc<str>, Rc<Path>>,
+    // canonical path of every file parsed in this run and the module it declares,
+    // importing one again only makes the module visible
+    pub module_cache: HashMap<PathBuf, Rc<str>>,
+    // directories searched for imports before the importing file's own directory
+    pub search_paths: Vec<PathBuf>,
+    // modules a module makes available to its importers through pub import
+    pub reexports: HashMap<Rc<str>, Vec<Rc<str>>>,
+    // modules that can be named with Module:: in the file being parsed
+    visible_modules: table::Table<Rc<str>>,
+    module_name: Option<Rc<str>>,
+    // names each imported module declared at the top level, stored as Module::name
+    pub exports: HashMap<Rc<str>, Table<Rc<str>>>,
+    // modules whose names resolve without Module::, the current module and imports with { * }
+    open_modules: table::Table<Rc<str>>,
+    // set for imported files, their top level functions and lets are qualified
+    imported: bool,
+    // values of const declarations, inlined wherever the name is read
+    pub constants: HashMap<Rc<str>, Expr>,
+    // items declared without pub in an imported file, with their module and
+    // the name to report, functions are keyed by their overload
+    pub private: HashMap<Rc<str>, (Rc<str>, Rc<str>)>,
+    // set while parsing an item declared with pub
+    public: bool,
+    // the type whose impl block the next function is declared in
+    impl_type: Option<TType>,
+    pub warnings: Vec<Warning>,
+    // every name read or called and what it refers to, nova rename edits these
+    pub references: Vec<Reference>,
+    // sources to parse instead of the file on disk, keyed by canonical path
+    pub overlay: HashMap<PathBuf, Rc<str>>,
+    // the unused result warning of the statement just parsed, dropped again
+    // when the statement turns out to be the value of its block
+    unused_result: Option<usize>,
+    // functions the prelude declared that the program has not replaced yet
+    prelude: Table<Rc<str>>,
+    // _ written as a type so far, each one gets a generic name of its own
+    type_holes: usize,
+    // set while parsing the parameters of a |x| closure, where | ends the list
+    // instead of starting a union type
+    bar_parameters: bool,
+    // set while parsing the parts of a list comprehension, where | separates
+    // them instead of being an operator
+    comprehension: bool,
+    // where each name of the parameter list that ended last was written
+    parameter_positions: Vec<FilePosition>,
+    // the tokens of each argument of the argument list that ended last, with
+    // the index of its closing paren
+    last_arguments: Option<(usize, Vec<Range<usize>>)>,
+    // the /// comment of the item being parsed, pub and #[derive] in front of
+    // a declaration leave it for the declaration
+    doc: Option<Rc<str>>,
+    // lets without a type or a value that no assignment has given a type yet
+    auto_lets: Vec<(Rc<str>, FilePosition)>,
 }
 
 pub fn default() -> Parser {
@@ -44,6 +127,31 @@ pub fn default() -> Parser {
         index: 0,
         environment: env,
         modules: Table::new(),
+        module_files: HashMap::default(),
+        module_cache: HashMap::default(),
+        search_paths: vec![],
+        reexports: HashMap::default(),
+        visible_modules: Table::new(),
+        module_name: None,
+        exports: HashMap::default(),
+        open_modules: Table::new(),
+        imported: false,
+        constants: HashMap::default(),
+        private: HashMap::default(),
+        public: false,
+        impl_type: None,
+        warnings: vec![],
+        references: vec![],
+        overlay: HashMap::default(),
+        unused_result: None,
+        prelude: Table::new(),
+        type_holes: 0,
+        bar_parameters: false,
+        comprehension: false,
+        parameter_positions: vec![],
+        last_arguments: None,
+        doc: None,
+        auto_lets: vec![],
     }
 }
 
@@ -56,9 +164,39 @@ pub fn new(filepath: impl AsRef<Path>) -> Parser {
         index: 0,
         environment: env,
         modules: Table::new(),
+        module_files: HashMap::default(),
+        module_cache: HashMap::default(),
+        search_paths: vec![],
+        reexports: HashMap::default(),
+        visible_modules: Table::new(),
+        module_name: None,
+        exports: HashMap::default(),
+        open_modules: Table::new(),
+        imported: false,
+        constants: HashMap::default(),
+        private: HashMap::default(),
+        public: false,
+        impl_type: None,
+        warnings: vec![],
+        references: vec![],
+        overlay: HashMap::default(),
+        unused_result: None,
+        prelude: Table::new(),
+        type_holes: 0,
+        bar_parameters: false,
+        comprehension: false,
+        parameter_positions: vec![],
+        last_arguments: None,
+        doc: None,
+        auto_lets: vec![],
     }
 }
 
+// the same file reached through different relative paths shares one entry
+fn canonical_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
 fn create_environment() -> Environment {
     let mut env = Environment::new();
     env.insert_symbol(
@@ -88,10 +226,33 @@ fn create_environment() -> Environment {
         None,
         SymbolKind::GenericFunction,
     );
+    // assert(cond) and assert(cond, msg), the parser fills in the missing
+    // message type
+    env.insert_symbol(
+        "assert",
+        TType::Function {
+            parameters: vec![TType::Bool, TType::String],
+            return_type: Box::new(TType::Void),
+        },
+        None,
+        SymbolKind::GenericFunction,
+    );
+    env.insert_symbol(
+        "assert_eq",
+        TType::Function {
+            parameters: vec![
+                TType::Generic { name: "a".into() },
+                TType::Generic { name: "a".into() },
+            ],
+            return_type: Box::new(TType::Void),
+        },
+        None,
+        SymbolKind::GenericFunction,
+    );
     env.insert_symbol(
         "exit",
         TType::Function {
-            parameters: vec![TType::None],
+            parameters: vec![TType::Int],
             return_type: Box::new(TType::Void),
         },
         None,
@@ -155,6 +316,34 @@ fn create_environment() -> Environment {
         None,
         SymbolKind::GenericFunction,
     );
+    // U8(x) fails when x does not fit, U8::wrap keeps the low bits and
+    // U8::checked gives None
+    for width in Width::ALL {
+        let sized = TType::Sized { width };
+        let conversions = [
+            (width.to_string(), TType::Int, sized.clone()),
+            (format!("{}::wrap", width), TType::Int, sized.clone()),
+            (
+                format!("{}::checked", width),
+                TType::Int,
+                TType::Option {
+                    inner: Box::new(sized.clone()),
+                },
+            ),
+            (format!("{}::toInt", width), sized, TType::Int),
+        ];
+        for (name, parameter, return_type) in conversions {
+            env.insert_symbol(
+                &name,
+                TType::Function {
+                    parameters: vec![parameter],
+                    return_type: Box::new(return_type),
+                },
+                None,
+                SymbolKind::GenericFunction,
+            );
+        }
+    }
     env.insert_symbol(
         "clone",
         TType::Function {
@@ -168,6 +357,21 @@ fn create_environment() -> Environment {
 }
 
 impl Parser {
+    fn type_mismatch(&self, expected: &TType, found: &TType, position: FilePosition) -> NovaError {
+        NovaError::TypeMismatch {
+            expected: Box::new(expected.clone()),
+            found: Box::new(found.clone()),
+            expected_alias: self.environment.alias_of(expected),
+            found_alias: self.environment.alias_of(found),
+            position,
+        }
+    }
+
+    // a type as shown in diagnostics, naming the alias it was written as
+    fn describe_type(&self, ttype: &TType) -> String {
+        describe_type(ttype, self.environment.alias_of(ttype).as_deref())
+    }
+
     fn check_and_map_types(
         &self,
         type_list1: &[TType],
@@ -204,28 +408,34 @@ impl Parser {
                 ) => {
                     self.check_and_map_types(elements1, elements2, type_map, pos.clone())?;
                 }
+                (TType::Union { members }, TType::Union { members: found }) => {
+                    for member in found {
+                        self.check_union_member(t1, members, member, type_map, pos.clone())?;
+                    }
+                }
+                (TType::Union { members }, _) => {
+                    self.check_union_member(t1, members, t2, type_map, pos.clone())?;
+                }
+                (_, TType::Union { .. }) if !matches!(t1, TType::Generic { .. }) => {
+                    return Err(self.generate_error_with_pos(
+                        format!("Expected {}, found a value of type {}", t1, t2),
+                        format!(
+                            "Narrow the value with match or typeof before using it as {}",
+                            t1
+                        ),
+                        pos.clone(),
+                    ));
+                }
                 (TType::Generic { name: name1 }, _) => {
                     if t2 == &TType::None {
-                        return Err(NovaError::TypeMismatch {
-                            expected: t1.clone(),
-                            found: t2.clone(),
-                            position: pos.clone(),
-                        });
+                        return Err(self.type_mismatch(t1, t2, pos.clone()));
                     }
                     if t2 == &TType::Void {
-                        return Err(NovaError::TypeMismatch {
-                            expected: t1.clone(),
-                            found: t2.clone(),
-                            position: pos.clone(),
-                        });
+                        return Err(self.type_mismatch(t1, t2, pos.clone()));
                     }
                     if let Some(mapped_type) = type_map.get(name1) {
                         if mapped_type != t2 {
-                            return Err(NovaError::TypeMismatch {
-                                expected: mapped_type.clone(),
-                                found: t2.clone(),
-                                position: pos.clone(),
-                            });
+                            return Err(self.type_mismatch(mapped_type, t2, pos.clone()));
                         }
                     } else {
                         type_map.insert(name1.clone(), t2.clone());
@@ -259,11 +469,7 @@ impl Parser {
                     },
                 ) => {
                     if params1.len() != params2.len() {
-                        return Err(NovaError::TypeMismatch {
-                            expected: t1.clone(),
-                            found: t2.clone(),
-                            position: pos.clone(),
-                        });
+                        return Err(self.type_mismatch(t1, t2, pos.clone()));
                     }
 
                     self.check_and_map_types(params1, params2, type_map, pos.clone())?;
@@ -283,30 +489,46 @@ impl Parser {
                         name: custom2,
                         type_params: gen2,
                     },
-                ) => {
-                    if custom1 == custom2 {
-                        self.check_and_map_types(gen1, gen2, type_map, pos.clone())?;
-                    } else {
-                        return Err(NovaError::TypeMismatch {
-                            expected: t1.clone(),
-                            found: t2.clone(),
-                            position: pos.clone(),
-                        });
-                    }
+                ) if custom1 == custom2 => {
+                    self.check_and_map_types(gen1, gen2, type_map, pos.clone())?;
                 }
                 _ if t1 == t2 => continue,
                 _ => {
-                    return Err(NovaError::TypeMismatch {
-                        expected: t1.clone(),
-                        found: t2.clone(),
-                        position: pos.clone(),
-                    });
+                    return Err(self.type_mismatch(t1, t2, pos.clone()));
                 }
             }
         }
         Ok(())
     }
 
+    // a value of type found fits a union when it fits one of the members,
+    // generics are mapped by the first member that fits
+    fn check_union_member(
+        &self,
+        union: &TType,
+        members: &[TType],
+        found: &TType,
+        type_map: &mut HashMap<Rc<str>, TType>,
+        pos: FilePosition,
+    ) -> Result<(), NovaError> {
+        for member in members {
+            let mut attempt = type_map.clone();
+            if self
+                .check_and_map_types(
+                    std::slice::from_ref(member),
+                    std::slice::from_ref(found),
+                    &mut attempt,
+                    pos.clone(),
+                )
+                .is_ok()
+            {
+                *type_map = attempt;
+                return Ok(());
+            }
+        }
+        Err(self.type_mismatch(union, found, pos))
+    }
+
     pub fn get_output(
         &self,
         output: TType,
@@ -380,10 +602,128 @@ impl Parser {
                     type_params: mapped_type_params,
                 })
             }
+            TType::Union { members } => {
+                let mut mapped_members = Vec::new();
+                for member in members {
+                    mapped_members.push(self.get_output(member, type_map, pos.clone())?);
+                }
+                Ok(TType::union(mapped_members))
+            }
             _ => Ok(output.clone()),
         }
     }
 
+    // the annotation with its _ holes replaced by what the value's type has in
+    // their place, a hole is ambiguous when that part of the value is unknown
+    // itself or the value does not have that part at all, like a hole in [_]
+    // matched against Any
+    fn fill_type_holes(
+        &self,
+        annotation: &TType,
+        found: &TType,
+        pos: FilePosition,
+    ) -> Result<TType, NovaError> {
+        let mut candidates = vec![];
+        Self::hole_candidates(annotation, found, &mut candidates);
+        let mut type_map = HashMap::default();
+        if self
+            .check_and_map_types(
+                std::slice::from_ref(annotation),
+                std::slice::from_ref(found),
+                &mut type_map,
+                pos.clone(),
+            )
+            .is_err()
+        {
+            // the caller reports the mismatch against the annotation as written
+            return Ok(annotation.clone());
+        }
+        let holes = Self::collect_generics(std::slice::from_ref(annotation));
+        let unfilled = holes
+            .items
+            .iter()
+            .any(|hole| hole.starts_with('_') && !type_map.contains_key(hole));
+        if unfilled || !candidates.iter().all(|t| self.is_inferred(t)) {
+            let candidates: Vec<String> = candidates.iter().map(TType::to_string).collect();
+            let note = if candidates.is_empty() {
+                format!(
+                    "The value has type {}, write the type out instead of _",
+                    found
+                )
+            } else {
+                format!(
+                    "The value has type {}, so _ could only be {}, write the type out instead",
+                    found,
+                    candidates.join(" or ")
+                )
+            };
+            return Err(self.generate_error_with_pos(
+                format!("Cannot infer the type holes in {}", annotation),
+                note,
+                pos,
+            ));
+        }
+        self.get_output(annotation.clone(), &mut type_map, pos)
+    }
+
+    // the part of found that lines up with each hole of annotation
+    fn hole_candidates(annotation: &TType, found: &TType, candidates: &mut Vec<TType>) {
+        match (annotation, found) {
+            (hole, _) if hole.is_hole() => candidates.push(found.clone()),
+            (TType::List { inner: a }, TType::List { inner: b })
+            | (TType::Option { inner: a }, TType::Option { inner: b }) => {
+                Self::hole_candidates(a, b, candidates)
+            }
+            (TType::Tuple { elements: a }, TType::Tuple { elements: b })
+            | (TType::Custom { type_params: a, .. }, TType::Custom { type_params: b, .. }) => {
+                for (a, b) in a.iter().zip(b.iter()) {
+                    Self::hole_candidates(a, b, candidates);
+                }
+            }
+            (
+                TType::Function {
+                    parameters: a,
+                    return_type: ra,
+                },
+                TType::Function {
+                    parameters: b,
+                    return_type: rb,
+                },
+            ) => {
+                for (a, b) in a.iter().zip(b.iter()) {
+                    Self::hole_candidates(a, b, candidates);
+                }
+                Self::hole_candidates(ra, rb, candidates);
+            }
+            _ => {}
+        }
+    }
+
+    // whether a type is fully known, generics count when they are in scope
+    fn is_inferred(&self, ttype: &TType) -> bool {
+        match ttype {
+            TType::None | TType::Auto | TType::Void => false,
+            TType::Generic { name } => {
+                !ttype.is_hole() && self.environment.live_generics.last().unwrap().has(name)
+            }
+            TType::List { inner } | TType::Option { inner } => self.is_inferred(inner),
+            TType::Tuple { elements } | TType::Union { members: elements } => {
+                elements.iter().all(|t| self.is_inferred(t))
+            }
+            TType::Custom { type_params, .. } => type_params.iter().all(|t| self.is_inferred(t)),
+            TType::Function {
+                parameters,
+                return_type,
+            } => {
+                parameters
+                    .iter()
+                    .all(|t| *t == TType::None || self.is_inferred(t))
+                    && (**return_type == TType::Void || self.is_inferred(return_type))
+            }
+            _ => true,
+        }
+    }
+
     fn eof(&mut self) -> Result<(), NovaError> {
         if self.current_token().is_none() {
             // check if forward declarations are empty
@@ -395,12 +735,14 @@ impl Parser {
                         pos.clone(),
                     ));
                 }
+                forward_decl.sort_by_key(|(_, pos)| (pos.line, pos.col));
                 let pos = self.get_current_token_position();
                 return Err(NovaError::Parsing {
                     msg: "Reached end of file".into(),
                     note: "Make sure all forward declarations are resolved".into(),
                     position: pos,
                     extra: Some(forward_decl),
+                    fixes: Box::default(),
                 });
             }
             Ok(())
@@ -410,6 +752,7 @@ impl Parser {
                 note: "Make sure your statement ends with Semicolon.".into(),
                 position: self.get_current_token_position(),
                 extra: None,
+                fixes: Box::default(),
             })
         }
     }
@@ -428,6 +771,7 @@ impl Parser {
             note: note.into(),
             position: self.get_current_token_position(),
             extra: None,
+            fixes: Box::default(),
         }
     }
 
@@ -442,6 +786,7 @@ impl Parser {
             note: note.into(),
             position: pos,
             extra: None,
+            fixes: Box::default(),
         }
     }
 
@@ -452,14 +797,22 @@ impl Parser {
         (t.line(), t.col())
     }
 
-    fn get_current_token_position(&self) -> FilePosition {
+    pub fn get_current_token_position(&self) -> FilePosition {
         self.current_token()
             .map(|t| t.position())
             // unwrap or use previous token position
             .unwrap_or_else(|| {
-                self.input
-                    .get(self.index - 1)
-                    .map_or_else(FilePosition::default, |t| t.position())
+                self.index
+                    .checked_sub(1)
+                    .and_then(|index| self.input.get(index))
+                    .map_or_else(
+                        // a file without any tokens
+                        || FilePosition {
+                            filepath: self.filepath.clone(),
+                            ..FilePosition::default()
+                        },
+                        |t| t.position(),
+                    )
             })
     }
 
@@ -482,10 +835,27 @@ impl Parser {
                 self.advance();
                 Ok(())
             }
-            unexpected => Err(self.generate_error(
-                format!("unexpected symbol, got {unexpected:?}"),
-                format!("expected {:?}", sym),
-            )),
+            unexpected => {
+                let error = self.generate_error(
+                    format!("unexpected symbol, got {unexpected:?}"),
+                    format!("expected {:?}", sym),
+                );
+                // a missing ; belongs right after the token before it
+                if sym == Semicolon && self.index > 0 {
+                    let end = self.token_end(self.index - 1);
+                    return Err(error.with_fix(Fix::insert("insert ';'", end, ";")));
+                }
+                Err(error)
+            }
+        }
+    }
+
+    // where the source of the token at index ends
+    fn token_end(&self, index: usize) -> FilePosition {
+        let token = &self.input[index];
+        FilePosition {
+            col: token.col() + token.value.width(),
+            ..token.position()
         }
     }
 
@@ -498,7 +868,7 @@ impl Parser {
             }
             unexpected => Err(self.generate_error(
                 format!("unexpected keyword, got {unexpected:?}"),
-                format!("expected {kw:?}"),
+                format!("expected {}", kw.as_str()),
             )),
         }
     }
@@ -509,13 +879,16 @@ impl Parser {
                 self.advance();
                 Ok(())
             }
-            unexpected => Err(self.generate_error(
-                format!("unexpected identifier, got {unexpected:?}"),
-                match symbol {
-                    Some(s) => format!("expecting {s}"),
-                    None => "expecting an identifier".to_string(),
-                },
-            )),
+            unexpected => match (symbol, self.reserved_word_error()) {
+                (None, Some(error)) => Err(error),
+                _ => Err(self.generate_error(
+                    format!("unexpected identifier, got {unexpected:?}"),
+                    match symbol {
+                        Some(s) => format!("expecting {s}"),
+                        None => "expecting an identifier".to_string(),
+                    },
+                )),
+            },
         }
     }
 
@@ -595,12 +968,15 @@ impl Parser {
 
     fn argument_list(&mut self) -> Result<Vec<Expr>, NovaError> {
         let mut exprs = vec![];
+        let mut spans = vec![];
         self.consume_symbol(LeftParen)?;
         if !self
             .current_token()
             .is_some_and(|t| t.is_symbol(RightParen))
         {
+            let start = self.index;
             exprs.push(self.expr()?);
+            spans.push(start..self.index);
         }
         while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
             self.advance();
@@ -610,9 +986,12 @@ impl Parser {
             {
                 break;
             }
+            let start = self.index;
             exprs.push(self.expr()?);
+            spans.push(start..self.index);
         }
         self.consume_symbol(RightParen)?;
+        self.last_arguments = Some((self.index - 1, spans));
         Ok(exprs)
     }
 
@@ -622,9 +1001,9 @@ impl Parser {
         fields: Vec<(Rc<str>, TType)>,
         conpos: FilePosition,
     ) -> Result<Vec<Expr>, NovaError> {
-        let mut field_exprs = HashMap::default();
+        let mut field_exprs = IndexMap::default();
         self.consume_symbol(LeftBrace)?;
-        self.parse_field(&mut field_exprs)?;
+        self.parse_field(constructor, &mut field_exprs)?;
         while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
             self.advance();
             if self
@@ -633,16 +1012,28 @@ impl Parser {
             {
                 break;
             }
-            self.parse_field(&mut field_exprs)?;
+            self.parse_field(constructor, &mut field_exprs)?;
         }
         self.consume_symbol(RightBrace)?;
         self.validate_fields(constructor, &fields, conpos, &field_exprs)
     }
 
-    fn parse_field(&mut self, field_exprs: &mut HashMap<Rc<str>, Expr>) -> Result<(), NovaError> {
-        let (id, _) = self.get_identifier()?;
+    fn parse_field(
+        &mut self,
+        constructor: &str,
+        field_exprs: &mut IndexMap<Rc<str>, Expr>,
+    ) -> Result<(), NovaError> {
+        let (id, pos) = self.get_identifier()?;
+        self.reference_member(constructor, &id, pos.clone());
         self.consume_operator(Operator::Colon)?;
-        field_exprs.insert(id, self.expr()?);
+        let expr = self.expr()?;
+        if field_exprs.insert(id.clone(), expr).is_some() {
+            return Err(self.generate_error_with_pos(
+                format!("{} is given field {} twice", constructor, id),
+                "Remove one of them",
+                pos,
+            ));
+        }
         Ok(())
     }
 
@@ -651,16 +1042,26 @@ impl Parser {
         constructor: &str,
         fields: &[(impl AsRef<str>, TType)],
         conpos: FilePosition,
-        field_exprs: &HashMap<Rc<str>, Expr>,
+        field_exprs: &IndexMap<Rc<str>, Expr>,
     ) -> Result<Vec<Expr>, NovaError> {
         let mut validated_exprs = vec![];
-        for (field_name, field_type) in fields.iter() {
-            if field_name.as_ref() == "type" {
-                continue;
+        // the first unknown field in the order they were written is reported
+        for name in field_exprs.keys() {
+            if !fields
+                .iter()
+                .any(|(field, _)| field.as_ref() == name.as_ref())
+            {
+                return Err(self.generate_error_with_pos(
+                    format!("{} has no field {}", constructor, name),
+                    self.suggest_field(name, fields),
+                    conpos,
+                ));
             }
+        }
+        for (field_name, field_type) in fields.iter() {
             if let Some(expr) = field_exprs.get(field_name.as_ref()) {
                 self.check_and_map_types(
-                    &[field_type.clone()],
+                    std::slice::from_ref(field_type),
                     &[expr.get_type()],
                     &mut HashMap::default(),
                     conpos.clone(),
@@ -672,34 +1073,37 @@ impl Parser {
                     note: "".into(),
                     position: conpos,
                     extra: None,
+                    fixes: Box::default(),
                 });
             }
         }
-        if field_exprs.len() != fields.len() - 1 {
+        if field_exprs.len() != fields.len() {
             return Err(NovaError::Parsing {
                 msg: format!(
                     "{} has {} fields, you have {}",
                     constructor,
-                    fields.len() - 1,
+                    fields.len(),
                     field_exprs.len()
                 )
                 .into(),
                 note: "".into(),
                 position: conpos.clone(),
                 extra: None,
+                fixes: Box::default(),
             });
         }
-        if validated_exprs.len() != fields.len() - 1 {
+        if validated_exprs.len() != fields.len() {
             return Err(NovaError::Parsing {
                 msg: format!(
                     "{} has {} fields, not all of them are covered",
                     constructor,
-                    fields.len() - 1
+                    fields.len()
                 )
                 .into(),
                 note: "".into(),
                 position: conpos,
                 extra: None,
+                fixes: Box::default(),
             });
         }
         Ok(validated_exprs)
@@ -707,21 +1111,41 @@ impl Parser {
 
     fn method(
         &mut self,
-        mut identifier: Rc<str>,
+        identifier: Rc<str>,
         first_argument: Expr,
         pos: FilePosition,
     ) -> Result<Expr, NovaError> {
         let mut arguments = vec![first_argument];
-        arguments.extend(self.argument_list()?);
+        if !self
+            .current_token()
+            .is_some_and(|t| t.is_op(Operator::Colon))
+        {
+            arguments.extend(self.argument_list()?);
+        }
         let mut argument_types: Vec<TType> = arguments.iter().map(|t| t.get_type()).collect();
 
+        let receiver = argument_types
+            .first()
+            .filter(|ttype| match ttype {
+                TType::Custom { name, .. } => {
+                    self.environment.custom_types.contains_key(name.as_ref())
+                }
+                _ => true,
+            })
+            .and_then(Self::method_type);
+
         if self
             .current_token()
             .is_some_and(|t| t.is_op(Operator::Colon))
         {
             self.advance();
             // call get closure
-            let (typeinput, input, output, statement, captured) = self.bar_closure()?;
+            let mut names = vec![identifier.clone()];
+            if let Some(receiver) = &receiver {
+                names.insert(0, format!("{}::{}", receiver, identifier).into());
+            }
+            let implicit = self.implicit_parameters(&names, &argument_types)?;
+            let (typeinput, input, output, statement, captured) = self.bar_closure(implicit)?;
             let last_closure = Expr::Closure {
                 ttype: TType::Function {
                     parameters: typeinput,
@@ -738,72 +1162,186 @@ impl Parser {
         if argument_types.is_empty() {
             argument_types.push(TType::None)
         }
-        // used last time for stuff like random.println() but removed for now
-        // let old_identifier = identifier.clone();
-        identifier = if let Some(TType::Custom { name, .. }) = argument_types.first() {
-            if self.environment.custom_types.contains_key(name.as_ref()) {
-                format!("{}::{}", name, identifier).into()
-            } else {
-                identifier
+        let identifier = match receiver {
+            Some(receiver) => {
+                self.receiver_call(&receiver, identifier, &argument_types, &arguments)
             }
-        } else if let Some(ttype) = argument_types.first() {
-            match ttype {
-                TType::List { .. } => {
-                    format!("List::{}", identifier)
-                }
-                TType::Option { .. } => {
-                     format!("Option::{}", identifier)
-                }
-                TType::Function { parameters, .. } => {
-                    let repeated_elements: String = "(_)".repeat(parameters.len());
-                     format!("Function{}::{}",repeated_elements, identifier)
-                }
-                TType::Tuple { elements } => {
-                    let repeated_elements: String = "(_)".repeat(elements.len());
-                     format!("Tuple{}::{}",repeated_elements, identifier)
-                }
-                TType::Bool => {
-                     format!("Bool::{}", identifier)
-                }
-                TType::Int => {
-                     format!("Int::{}", identifier)
-                }
-                TType::Float => {
-                     format!("Float::{}", identifier)
-                }
-                TType::Char => {
-                     format!("Char::{}", identifier)
+            None if self.resolves(&identifier, &argument_types, &arguments) => identifier,
+            None => {
+                let ttype = argument_types.first().cloned().unwrap_or(TType::None);
+                return Err(self.generate_error_with_pos(
+                    format!("E1 Not a valid call: {}", identifier),
+                    format!(
+                        "No function signature '{}' with {} as arguments, Cant call method on type {}",
+                        identifier,
+                        argument_types
+                            .iter()
+                            .map(|t| self.describe_type(t))
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                        ttype,
+                    ),
+                    pos,
+                ));
+            }
+        };
+        self.varargs(&identifier, &mut argument_types, &mut arguments);
+        self.function_call(identifier, arguments, argument_types, pos.clone(), pos)
+    }
+
+    // the parameters of a closure written as a block after the :, taken from
+    // the last parameter of the function it is passed to once the arguments
+    // before it have mapped the generics. its one parameter is called it
+    fn implicit_parameters(
+        &mut self,
+        names: &[Rc<str>],
+        argument_types: &[TType],
+    ) -> Result<Option<Vec<TType>>, NovaError> {
+        if !self.current_token().is_some_and(|t| t.is_symbol(LeftBrace)) {
+            return Ok(None);
+        }
+        let pos = self.get_current_token_position();
+        let scope = self.environment.values.last().unwrap();
+        let mut expected = None;
+        'search: for name in names {
+            for symbol in scope.values() {
+                let TType::Function { parameters, .. } = &symbol.ttype else {
+                    continue;
+                };
+                if *symbol.id != **name && *symbol.id != generate_unique_string(name, parameters) {
+                    continue;
                 }
-                TType::String => {
-                     format!("String::{}", identifier)
+                let Some((
+                    TType::Function {
+                        parameters: closure,
+                        ..
+                    },
+                    before,
+                )) = parameters.split_last()
+                else {
+                    continue;
+                };
+                let mut type_map = HashMap::default();
+                if before.len() == argument_types.len()
+                    && self
+                        .check_and_map_types(before, argument_types, &mut type_map, pos.clone())
+                        .is_ok()
+                {
+                    expected = Some((closure.clone(), type_map));
+                    break 'search;
                 }
-                _ => {
+            }
+        }
+        let Some((closure, mut type_map)) = expected else {
+            return Err(self.generate_error_with_pos(
+                "Cannot tell what it is",
+                format!(
+                    "{} takes no closure after these arguments, name the parameter: |x: Type| ...",
+                    names
+                        .last()
+                        .map(|name| name.as_ref())
+                        .unwrap_or("the function")
+                ),
+                pos,
+            ));
+        };
+        let closure: Vec<TType> = closure.into_iter().filter(|t| *t != TType::None).collect();
+        if closure.len() > 1 {
+            return Err(self.generate_error_with_pos(
+                "it can only stand for a single parameter",
+                format!(
+                    "this closure takes {}, name them: |a: Type, b: Type| ...",
+                    closure.len()
+                ),
+                pos,
+            ));
+        }
+        let mut parameters = vec![];
+        for ttype in closure {
+            match self.get_output(ttype, &mut type_map, pos.clone()) {
+                Ok(ttype) => parameters.push(ttype),
+                Err(_) => {
                     return Err(self.generate_error_with_pos(
-                        format!("E1 Not a valid call: {}", identifier),
-                        format!(
-                            "No function signature '{}' with {} as arguments, Cant call method on type {}",
-                            identifier,
-                            argument_types
-                                .iter()
-                                .map(|t| t.to_string())
-                                .collect::<Vec<String>>()
-                                .join(", "),
-                            ttype,
-                        ),
+                        "Cannot infer the type of it",
+                        "the arguments before the closure do not fix it, name the parameter: |x: Type| ...",
                         pos,
                     ))
                 }
-            }.into()
+            }
+        }
+        Ok(Some(parameters))
+    }
+
+    // the name a type goes by in the methods extending it, Int::abs or
+    // Tuple(_)(_)::swap
+    fn method_type(ttype: &TType) -> Option<String> {
+        Some(match ttype {
+            TType::Custom { name, .. } => name.to_string(),
+            TType::List { .. } => "List".into(),
+            TType::Option { .. } => "Option".into(),
+            TType::Function { parameters, .. } => {
+                format!("Function{}", "(_)".repeat(parameters.len()))
+            }
+            TType::Tuple { elements } => format!("Tuple{}", "(_)".repeat(elements.len())),
+            TType::Bool => "Bool".into(),
+            TType::Int => "Int".into(),
+            TType::Sized { width } => width.to_string(),
+            TType::BigInt => "BigInt".into(),
+            TType::Float => "Float".into(),
+            TType::Char => "Char".into(),
+            TType::String => "String".into(),
+            _ => return None,
+        })
+    }
+
+    // uniform function call syntax, x.f(a) and T::f(x, a) both call the f
+    // extending T, x's type, and without one the free f(x, a)
+    fn receiver_call(
+        &mut self,
+        receiver: &str,
+        name: Rc<str>,
+        argument_types: &[TType],
+        arguments: &[Expr],
+    ) -> Rc<str> {
+        let method: Rc<str> = format!("{}::{}", receiver, name).into();
+        if !self.resolves(&method, argument_types, arguments)
+            && self.resolves(&name, argument_types, arguments)
+        {
+            name
         } else {
-            identifier
-        };
+            method
+        }
+    }
 
-        self.varargs(&identifier, &mut argument_types, &mut arguments);
+    // whether a call to the name would find a function, counting the
+    // arguments varargs would pack into a list
+    fn resolves(&mut self, identifier: &str, argument_types: &[TType], arguments: &[Expr]) -> bool {
+        let mut argument_types = argument_types.to_vec();
+        let mut arguments = arguments.to_vec();
+        self.varargs(identifier, &mut argument_types, &mut arguments);
+        self.environment
+            .get_function_type(identifier, &argument_types)
+            .is_some()
+            || self.environment.values.iter().any(|scope| {
+                scope
+                    .get(identifier)
+                    .is_some_and(|symbol| matches!(symbol.ttype, TType::Function { .. }))
+            })
+    }
 
+    fn function_call(
+        &mut self,
+        identifier: Rc<str>,
+        arguments: Vec<Expr>,
+        argument_types: Vec<TType>,
+        pos: FilePosition,
+        name_pos: FilePosition,
+    ) -> Result<Expr, NovaError> {
         if let Some((function_type, function_id, function_kind)) = self
             .environment
             .get_function_type(&identifier, &argument_types)
         {
+            self.reference(&function_id, name_pos);
             self.handle_function_call(
                 function_type,
                 function_id,
@@ -815,7 +1353,7 @@ impl Parser {
         } else if let Some((function_type, function_id, function_kind)) =
             self.environment.get_type_capture(&identifier)
         {
-            //println!("captured id {}", identifier);
+            self.reference(&identifier, name_pos);
             let pos = self.get_current_token_position();
             self.environment.captured.last_mut().unwrap().insert(
                 identifier.clone(),
@@ -824,6 +1362,7 @@ impl Parser {
                     ttype: function_type.clone(),
                     pos: Some(pos.clone()),
                     kind: SymbolKind::Captured,
+                    bounds: vec![],
                 },
             );
             self.handle_function_call(
@@ -835,19 +1374,7 @@ impl Parser {
                 pos,
             )
         } else {
-            Err(self.generate_error_with_pos(
-                format!("E1 Not a valid call: {}", identifier),
-                format!(
-                    "No function signature '{}' with {} as arguments",
-                    identifier,
-                    argument_types
-                        .iter()
-                        .map(|t| t.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                ),
-                pos,
-            ))
+            Err(self.no_signature_error(&identifier, &argument_types, pos))
         }
     }
 
@@ -856,7 +1383,7 @@ impl Parser {
         function_type: TType,
         mut function_id: Rc<str>,
         function_kind: SymbolKind,
-        arguments: Vec<Expr>,
+        mut arguments: Vec<Expr>,
         argument_types: Vec<TType>,
         pos: FilePosition,
     ) -> Result<Expr, NovaError> {
@@ -873,6 +1400,7 @@ impl Parser {
                 ))
             }
         };
+        self.require_accessible(&function_id, pos.clone())?;
 
         let mut generic_list = Self::collect_generics(&[*return_type.clone()]);
         generic_list.extend(Self::collect_generics(&parameters));
@@ -881,10 +1409,26 @@ impl Parser {
 
         if let SymbolKind::GenericFunction | SymbolKind::Constructor = function_kind {
             self.map_generic_types(&parameters, &argument_types, &mut type_map, pos.clone())?;
+            self.check_bounds(&function_id, &type_map, pos.clone())?;
         }
         // if current token is @ then parse [T: Type] and replace the generic type and inset that into the type_map
         self.modify_type_map(&mut type_map, pos.clone(), generic_list)?;
-        return_type = Box::new(self.get_output(*return_type, &mut type_map, pos.clone())?);
+        *return_type = self.get_output(*return_type, &mut type_map, pos.clone())?;
+
+        // json, toml and yaml are read and written in the shape of the type a
+        // stands for
+        if let (Some(ttype), "Json::decode" | "Json::encode" | "Toml::decode" | "Yaml::decode") =
+            (type_map.get("a").cloned(), function_id.deref())
+        {
+            let mut shape = String::new();
+            self.json_shape(&ttype, &mut shape, &mut HashSet::new(), &pos)?;
+            arguments.push(Expr::Literal {
+                ttype: TType::String,
+                value: Atom::String {
+                    value: shape.into(),
+                },
+            });
+        }
 
         if let Some(subtype) = self.environment.generic_type_map.get(&function_id) {
             function_id = subtype.clone();
@@ -900,13 +1444,86 @@ impl Parser {
         })
     }
 
-    fn modify_type_map(
-        &mut self,
-        type_map: &mut HashMap<Rc<str>, TType>,
-        pos: FilePosition,
-        generics_list: table::Table<Rc<str>>,
+    // the shape native::json reads and writes a type with, structs are written
+    // out once and referred to by name after that so they can contain themselves
+    fn json_shape(
+        &self,
+        ttype: &TType,
+        shape: &mut String,
+        seen: &mut HashSet<String>,
+        pos: &FilePosition,
     ) -> Result<(), NovaError> {
-        if !self.current_token().is_some_and(|t| t.is_symbol(At)) {
+        match ttype {
+            TType::Int => shape.push('i'),
+            TType::Float => shape.push('f'),
+            TType::Bool => shape.push('b'),
+            TType::String => shape.push('s'),
+            TType::Char => shape.push('c'),
+            TType::Option { inner } => {
+                shape.push('?');
+                self.json_shape(inner, shape, seen, pos)?;
+            }
+            TType::List { inner } => {
+                shape.push('[');
+                self.json_shape(inner, shape, seen, pos)?;
+                shape.push(']');
+            }
+            TType::Tuple { elements } => {
+                shape.push('(');
+                for element in elements {
+                    self.json_shape(element, shape, seen, pos)?;
+                }
+                shape.push(')');
+            }
+            // json::JsonValue, or JsonValue when std/json.nv is the file being run
+            TType::Custom { name, .. }
+                if name.deref() == "json::JsonValue"
+                    || name.deref() == "JsonValue"
+                        && self.module_name.as_deref() == Some("json") =>
+            {
+                shape.push('j')
+            }
+            TType::Custom { name, type_params }
+                if !self.environment.enums.has(name)
+                    && self.environment.custom_types.contains_key(name) =>
+            {
+                let key = ttype.to_string();
+                if !seen.insert(key.clone()) {
+                    write!(shape, "<{}>", key).unwrap();
+                    return Ok(());
+                }
+                write!(shape, "{{{}|", key).unwrap();
+                let generics = self.environment.generic_type_struct.get(name);
+                for (field, field_type) in &self.environment.custom_types[name] {
+                    let field_type = match generics {
+                        Some(generics) => {
+                            Self::replace_generic_types(field_type, generics, type_params)
+                        }
+                        None => field_type.clone(),
+                    };
+                    write!(shape, "{}:", field).unwrap();
+                    self.json_shape(&field_type, shape, seen, pos)?;
+                    shape.push(',');
+                }
+                shape.push('}');
+            }
+            _ => {
+                return Err(NovaError::SimpleTypeError {
+                    msg: format!("E1 {} cannot be read or written as JSON", ttype).into(),
+                    position: pos.clone(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn modify_type_map(
+        &mut self,
+        type_map: &mut HashMap<Rc<str>, TType>,
+        pos: FilePosition,
+        generics_list: table::Table<Rc<str>>,
+    ) -> Result<(), NovaError> {
+        if !self.current_token().is_some_and(|t| t.is_symbol(At)) {
             return Ok(());
         }
         self.advance();
@@ -921,7 +1538,7 @@ impl Parser {
         self.consume_operator(Operator::Colon)?;
         let ttype = self.ttype()?;
         // check to see if type is generic and then checkt to see if it is live and if it is not live, throw an error
-        let generic_list = Self::collect_generics(&[ttype.clone()]);
+        let generic_list = Self::collect_generics(std::slice::from_ref(&ttype));
         for generic in generic_list.items {
             if !self.environment.live_generics.last().unwrap().has(&generic) {
                 return Err(NovaError::SimpleTypeError {
@@ -953,7 +1570,7 @@ impl Parser {
             }
             self.consume_operator(Operator::Colon)?;
             let ttype = self.ttype()?;
-            let generic_list = Self::collect_generics(&[ttype.clone()]);
+            let generic_list = Self::collect_generics(std::slice::from_ref(&ttype));
             for generic in generic_list.items {
                 if !self.environment.live_generics.last().unwrap().has(&generic) {
                     return Err(NovaError::SimpleTypeError {
@@ -1080,16 +1697,91 @@ impl Parser {
         }
     }
 
+    // format and printf take values of any type after the format string, they
+    // are passed as one list. a literal format string is checked against them
+    fn format_arguments(
+        &self,
+        identifier: &str,
+        argument_types: &mut Vec<TType>,
+        arguments: &mut Vec<Expr>,
+        pos: &FilePosition,
+    ) -> Result<(), NovaError> {
+        if !matches!(identifier, "format" | "printf")
+            || argument_types.first() != Some(&TType::String)
+        {
+            return Ok(());
+        }
+        // the list of strings they used to take
+        let strings = TType::List {
+            inner: Box::new(TType::String),
+        };
+        if argument_types.len() == 2 && argument_types[1] == strings {
+            return Ok(());
+        }
+        let values = arguments.split_off(1);
+        if let Expr::Literal {
+            value: Atom::String { value },
+            ..
+        } = &arguments[0]
+        {
+            let pieces = format::parse(value).map_err(|reason| {
+                self.generate_error_with_pos("Invalid format string", reason, pos.clone())
+            })?;
+            let specs: Vec<_> = pieces
+                .iter()
+                .filter_map(|piece| match piece {
+                    Piece::Value(spec) => Some(spec),
+                    Piece::Text(_) => None,
+                })
+                .collect();
+            if specs.len() != values.len() {
+                return Err(self.generate_error_with_pos(
+                    format!(
+                        "The format string has {} placeholders but {} values were given",
+                        specs.len(),
+                        values.len()
+                    ),
+                    "Each {} takes one value, write {{ and }} for literal braces",
+                    pos.clone(),
+                ));
+            }
+            for (spec, value) in specs.iter().zip(values.iter()) {
+                spec.check(&value.get_type()).map_err(|reason| {
+                    self.generate_error_with_pos("Invalid format spec", reason, pos.clone())
+                })?;
+            }
+        }
+        let any = TType::List {
+            inner: Box::new(TType::Any),
+        };
+        arguments.push(Expr::ListConstructor {
+            ttype: any.clone(),
+            elements: values,
+        });
+        *argument_types = vec![TType::String, any];
+        Ok(())
+    }
+
     fn call(
         &mut self,
         identifier: Rc<str>,
         pos: FilePosition,
+        name_pos: FilePosition,
         first: Option<Expr>,
     ) -> Result<Expr, NovaError> {
         let mut arguments = self.get_field_arguments(&identifier, pos.clone())?;
         if let Some(first) = first {
             arguments.insert(0, first);
         }
+        // U8(200) is checked here, other values when the program runs
+        if let (Some(width), [argument]) = (Width::from_name(&identifier), arguments.as_slice()) {
+            if let Some(value) = Self::int_constant(argument) {
+                return self.sized_constant(value, width, pos);
+            }
+        }
+        if identifier.deref() == "exit" && arguments.is_empty() {
+            arguments.push(self.exit_without_status());
+        }
         let mut argument_types: Vec<TType> = arguments.iter().map(|t| t.get_type()).collect();
 
         if self
@@ -1098,7 +1790,18 @@ impl Parser {
         {
             self.advance();
             // call get closure
-            let (typeinput, input, output, statement, captured) = self.bar_closure()?;
+            let mut names = vec![identifier.clone()];
+            if let (Some((written, name)), Some(receiver)) = (
+                identifier.split_once("::"),
+                argument_types.first().and_then(Self::method_type),
+            ) {
+                if written == receiver || receiver.split('(').next() == Some(written) {
+                    names.push(format!("{}::{}", receiver, name).into());
+                    names.push(name.into());
+                }
+            }
+            let implicit = self.implicit_parameters(&names, &argument_types)?;
+            let (typeinput, input, output, statement, captured) = self.bar_closure(implicit)?;
             let last_closure = Expr::Closure {
                 ttype: TType::Function {
                     parameters: typeinput,
@@ -1115,58 +1818,36 @@ impl Parser {
         if argument_types.is_empty() {
             argument_types.push(TType::None)
         }
+        // the message of assert is optional, the compiler sees one argument
+        if identifier.deref() == "assert" && argument_types.len() == 1 {
+            argument_types.push(TType::String)
+        }
 
+        let identifier = self.type_call(identifier, &argument_types, &arguments);
+        self.format_arguments(&identifier, &mut argument_types, &mut arguments, &pos)?;
         self.varargs(&identifier, &mut argument_types, &mut arguments);
+        self.function_call(identifier, arguments, argument_types, pos, name_pos)
+    }
 
-        if let Some((function_type, function_id, function_kind)) = self
-            .environment
-            .get_function_type(&identifier, &argument_types)
-        {
-            self.handle_function_call(
-                function_type,
-                function_id,
-                function_kind,
-                arguments,
-                argument_types,
-                pos,
-            )
-        } else if let Some((function_type, function_id, function_kind)) =
-            self.environment.get_type_capture(&identifier)
-        {
-            //println!("captured id: call {}", identifier);
-            let pos = self.get_current_token_position();
-            self.environment.captured.last_mut().unwrap().insert(
-                identifier.clone(),
-                Symbol {
-                    id: identifier.clone(),
-                    ttype: function_type.clone(),
-                    pos: Some(pos.clone()),
-                    kind: SymbolKind::Captured,
-                },
-            );
-            self.handle_function_call(
-                function_type,
-                function_id,
-                function_kind,
-                arguments,
-                argument_types,
-                pos,
-            )
-        } else {
-            Err(self.generate_error_with_pos(
-                format!("E1 Not a valid call: {}", identifier),
-                format!(
-                    "No function signature '{}' with {} as arguments",
-                    identifier,
-                    argument_types
-                        .iter()
-                        .map(|t| t.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                ),
-                pos,
-            ))
+    // T::f(x, a) is x.f(a) when x is a T, Tuple and Function stand for a
+    // tuple or function of any length
+    fn type_call(
+        &mut self,
+        identifier: Rc<str>,
+        argument_types: &[TType],
+        arguments: &[Expr],
+    ) -> Rc<str> {
+        let Some((written, name)) = identifier.split_once("::") else {
+            return identifier;
+        };
+        let Some(receiver) = argument_types.first().and_then(Self::method_type) else {
+            return identifier;
+        };
+        if written != receiver && receiver.split('(').next() != Some(written) {
+            return identifier;
         }
+        let name: Rc<str> = name.into();
+        self.receiver_call(&receiver, name, argument_types, arguments)
     }
 
     fn get_field_arguments(
@@ -1185,7 +1866,121 @@ impl Parser {
         }
     }
 
-    fn replace_generic_types(ttype: &TType, x: &[impl AsRef<str>], type_params: &[TType]) -> TType {
+    fn no_signature_error(
+        &self,
+        identifier: &str,
+        argument_types: &[TType],
+        pos: FilePosition,
+    ) -> NovaError {
+        let error = self.generate_error_with_pos(
+            format!("E1 Not a valid call: {}", identifier),
+            format!(
+                "No function signature '{}' with {} as arguments",
+                identifier,
+                argument_types
+                    .iter()
+                    .map(|t| self.describe_type(t))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            pos,
+        );
+        self.call_fixes(identifier, argument_types)
+            .into_iter()
+            .fold(error, NovaError::with_fix)
+    }
+
+    // fixes for a call that fits no signature of the function, extra arguments
+    // are removed and arguments given in the wrong order are put in order
+    fn call_fixes(&self, identifier: &str, argument_types: &[TType]) -> Vec<Fix> {
+        // only when the argument list just parsed is the whole call
+        let Some((close, spans)) = &self.last_arguments else {
+            return vec![];
+        };
+        if *close + 1 != self.index || spans.is_empty() || spans.len() != argument_types.len() {
+            return vec![];
+        }
+        let mut signatures: Vec<&[TType]> = self
+            .environment
+            .values
+            .last()
+            .unwrap()
+            .values()
+            .filter(|symbol| symbol.kind == SymbolKind::Function)
+            .filter_map(|symbol| match &symbol.ttype {
+                TType::Function { parameters, .. }
+                    if *generate_unique_string(identifier, parameters) == *symbol.id =>
+                {
+                    Some(parameters.as_slice())
+                }
+                _ => None,
+            })
+            .collect();
+        signatures.sort_by_key(|parameters| parameters.len());
+
+        let start = |index: usize| self.input[spans[index].start].position();
+        let end = |index: usize| self.token_end(spans[index].end - 1);
+        let last = spans.len() - 1;
+        let mut fixes = vec![];
+        for parameters in signatures {
+            let parameters = match parameters {
+                [TType::None] => &[],
+                parameters => parameters,
+            };
+            let kept = parameters.len();
+            if kept < argument_types.len() && argument_types[..kept] == *parameters {
+                let extra = argument_types.len() - kept;
+                fixes.push(Fix {
+                    msg: match extra {
+                        1 => "remove the extra argument".into(),
+                        _ => format!("remove the {} extra arguments", extra).into(),
+                    },
+                    start: if kept == 0 { start(0) } else { end(kept - 1) },
+                    end: end(last),
+                    replacement: String::new(),
+                });
+            } else if kept == argument_types.len() {
+                // the argument each parameter would take, first of its type
+                let mut order: Vec<usize> = vec![];
+                for parameter in parameters {
+                    match (0..argument_types.len())
+                        .find(|i| !order.contains(i) && argument_types[*i] == *parameter)
+                    {
+                        Some(i) => order.push(i),
+                        None => break,
+                    }
+                }
+                if order.len() == kept && order.iter().enumerate().any(|(i, j)| i != *j) {
+                    let arguments: Vec<String> = order
+                        .iter()
+                        .map(|i| rewrite::source_text(&self.input[spans[*i].clone()]))
+                        .collect();
+                    fixes.push(Fix {
+                        msg: format!(
+                            "reorder the arguments to match {}({})",
+                            identifier,
+                            parameters
+                                .iter()
+                                .map(|t| self.describe_type(t))
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        )
+                        .into(),
+                        start: start(0),
+                        end: end(last),
+                        replacement: arguments.join(", "),
+                    });
+                }
+            }
+        }
+        fixes
+    }
+
+    pub fn replace_generic_types(
+        ttype: &TType,
+        x: &[impl AsRef<str>],
+        type_params: &[TType],
+    ) -> TType {
         match ttype {
             TType::Generic { name: n } => {
                 if let Some(index) = x.iter().position(|x| x.as_ref() == n.deref()) {
@@ -1197,6 +1992,8 @@ impl Parser {
             TType::None
             | TType::Any
             | TType::Int
+            | TType::Sized { .. }
+            | TType::BigInt
             | TType::Float
             | TType::Bool
             | TType::String
@@ -1244,6 +2041,11 @@ impl Parser {
                     elements: new_elements,
                 }
             }
+            TType::Union { members } => TType::union(
+                members
+                    .iter()
+                    .map(|member| Self::replace_generic_types(member, x, type_params)),
+            ),
         }
     }
 
@@ -1271,6 +2073,8 @@ impl Parser {
                         fields.clone()
                     };
                 if let Some((index, field_type)) = self.find_field(&identifier, &new_fields) {
+                    let type_name = type_name.to_owned();
+                    self.reference_member(&type_name, &identifier, pos.clone());
                     lhs = Expr::Field {
                         ttype: field_type.clone(),
                         name: type_name.into(),
@@ -1317,13 +2121,29 @@ impl Parser {
         type_name: &str,
         pos: FilePosition,
     ) -> Result<Expr, NovaError> {
+        let note = match self.environment.custom_types.get(type_name) {
+            Some(fields) => self.suggest_field(identifier, fields),
+            None => "cannot retrieve field".to_string(),
+        };
         Err(self.generate_error_with_pos(
             format!("No field '{}' found for {}", identifier, type_name),
-            "cannot retrieve field".to_string(),
+            note,
             pos,
         ))
     }
 
+    // note for an unknown field or variant, naming the closest one that exists
+    fn suggest_field(&self, name: &str, fields: &[(impl AsRef<str>, TType)]) -> String {
+        let candidates = fields.iter().map(|(field, _)| field.as_ref());
+        match closest_match(name, candidates) {
+            Some(closest) => format!("did you mean '{}'?", closest),
+            None => {
+                let names: Vec<&str> = fields.iter().map(|(field, _)| field.as_ref()).collect();
+                format!("expected one of {}", names.join(", "))
+            }
+        }
+    }
+
     fn chain(&mut self, mut lhs: Expr) -> Result<Expr, NovaError> {
         let (identifier, pos) = self.get_identifier()?;
         match self.current_token_value() {
@@ -1377,8 +2197,7 @@ impl Parser {
                         &mut type_map,
                         pos.clone(),
                     )?;
-                    return_type =
-                        Box::new(self.get_output(*return_type.clone(), &mut type_map, pos)?);
+                    *return_type = self.get_output(*return_type.clone(), &mut type_map, pos)?;
                     lhs = Expr::Call {
                         ttype: *return_type,
                         name: "anon".into(),
@@ -1396,6 +2215,15 @@ impl Parser {
             Some(StructuralSymbol(LeftParen)) => {
                 lhs = self.method(identifier, lhs, pos)?;
             }
+            // xs.map: { it * 2 } leaves out the empty argument list
+            Some(Operator(Operator::Colon))
+                if matches!(
+                    self.peek_offset_value(1),
+                    Some(StructuralSymbol(LeftBrace | Pipe) | Operator(Operator::Or))
+                ) =>
+            {
+                lhs = self.method(identifier, lhs, pos)?;
+            }
             Some(StructuralSymbol(LeftSquareBracket)) => {
                 lhs = self.field(identifier.clone(), lhs, pos)?;
                 lhs = self.index(identifier.clone(), lhs.clone(), lhs.get_type())?;
@@ -1629,9 +2457,34 @@ impl Parser {
         ))
     }
 
-    fn anchor(&mut self, identifier: Rc<str>, pos: FilePosition) -> Result<Expr, NovaError> {
+    fn anchor(
+        &mut self,
+        identifier: Rc<str>,
+        pos: FilePosition,
+        name_pos: FilePosition,
+    ) -> Result<Expr, NovaError> {
+        self.require_accessible(&identifier, pos.clone())?;
+        if self.environment.is_constant(&identifier) {
+            if let Some(value) = self.constants.get(&identifier).cloned() {
+                self.reference(&identifier, name_pos);
+                return Ok(value);
+            }
+        }
+        if self.environment.is_uninitialized(&identifier) {
+            let note = if self.environment.get_type(&identifier) == Some(TType::Auto) {
+                format!("the type of '{identifier}' is inferred from its first assignment, assign it on every path before use or annotate it")
+            } else {
+                format!("assign '{identifier}' on every path before reading it")
+            };
+            return Err(self.generate_error_with_pos(
+                format!("Variable '{}' is used before being initialized", identifier),
+                note,
+                pos,
+            ));
+        }
         let anchor = match self.current_token_value() {
             Some(Operator(Operator::RightArrow)) => {
+                self.reference(&identifier, name_pos);
                 self.consume_operator(Operator::RightArrow)?;
                 let (field, field_position) = self.get_identifier()?;
                 if let Some(identifier_type) = self.environment.get_type(&identifier) {
@@ -1665,8 +2518,7 @@ impl Parser {
                             &mut type_map,
                             field_position.clone(),
                         )?;
-                        return_type =
-                            Box::new(self.get_output(*return_type.clone(), &mut type_map, pos)?);
+                        *return_type = self.get_output(*return_type.clone(), &mut type_map, pos)?;
                         // dbg!(arguments.clone(), return_type.clone(), left_expr.clone());
 
                         Expr::Call {
@@ -1691,15 +2543,19 @@ impl Parser {
                 }
             }
             Some(StructuralSymbol(LeftSquareBracket)) => {
+                self.reference(&identifier, name_pos);
                 self.handle_indexing(identifier.clone(), pos.clone())?
             }
-            Some(StructuralSymbol(LeftParen)) => self.call(identifier.clone(), pos, None)?,
+            Some(StructuralSymbol(LeftParen)) => {
+                self.call(identifier.clone(), pos, name_pos, None)?
+            }
             _ => {
                 if self.current_token().is_some_and(|t| t.is_symbol(LeftBrace))
                     && self.environment.custom_types.contains_key(&identifier)
                 {
-                    self.call(identifier.clone(), pos.clone(), None)?
+                    self.call(identifier.clone(), pos.clone(), name_pos, None)?
                 } else {
+                    self.reference(&identifier, name_pos);
                     self.handle_literal_or_capture(identifier.clone(), pos.clone())?
                 }
             }
@@ -1720,13 +2576,17 @@ impl Parser {
         identifier: Rc<str>,
         position: FilePosition,
     ) -> Result<Expr, NovaError> {
-        if let Some(ttype) = self.environment.get_type(&identifier) {
+        if let Some(expr) = self.lazy_read(&identifier, position.clone()) {
+            let ttype = expr.get_type();
+            self.index(identifier.clone(), expr, ttype)
+        } else if let Some(ttype) = self.environment.get_type(&identifier) {
             self.index(
                 identifier.clone(),
                 self.create_literal_expr(identifier.clone(), ttype.clone()),
                 ttype.clone(),
             )
         } else if let Some((ttype, _, kind)) = self.environment.get_type_capture(&identifier) {
+            let declared = self.environment.declaration(&identifier);
             self.environment.captured.last_mut().unwrap().insert(
                 identifier.clone(),
                 Symbol {
@@ -1734,12 +2594,14 @@ impl Parser {
                     ttype: ttype.clone(),
                     pos: Some(position.clone()),
                     kind: SymbolKind::Captured,
+                    bounds: vec![],
                 },
             );
+            // the copy in the closure's scope keeps where it was declared
             self.environment.insert_symbol(
                 &identifier,
                 ttype.clone(),
-                Some(position.clone()),
+                declared.or(Some(position.clone())),
                 kind,
             );
             self.index(
@@ -1761,7 +2623,9 @@ impl Parser {
         identifier: Rc<str>,
         position: FilePosition,
     ) -> Result<Expr, NovaError> {
-        if let Some(ttype) = self.environment.get_type(&identifier) {
+        if let Some(expr) = self.lazy_read(&identifier, position.clone()) {
+            Ok(expr)
+        } else if let Some(ttype) = self.environment.get_type(&identifier) {
             //println!("identifier hloc-not-capture {}", identifier);
             Ok(self.create_literal_expr(identifier.clone(), ttype.clone()))
         } else if let Some((ttype, _, kind)) = self.environment.get_type_capture(&identifier) {
@@ -1770,6 +2634,7 @@ impl Parser {
             //     "environment {:?}",
             //     self.environment.captured.last().unwrap()
             // );
+            let declared = self.environment.declaration(&identifier);
             self.environment.captured.last_mut().unwrap().insert(
                 identifier.clone(),
                 Symbol {
@@ -1777,12 +2642,14 @@ impl Parser {
                     ttype: ttype.clone(),
                     pos: Some(position.clone()),
                     kind: SymbolKind::Captured,
+                    bounds: vec![],
                 },
             );
+            // the copy in the closure's scope keeps where it was declared
             self.environment.insert_symbol(
                 &identifier,
                 ttype.clone(),
-                Some(position.clone()),
+                declared.or(Some(position.clone())),
                 kind,
             );
             Ok(self.create_literal_expr(identifier.clone(), ttype.clone()))
@@ -1814,6 +2681,16 @@ impl Parser {
                         op: sign,
                     });
                 }
+            } else if sign == Unary::Negative && factor.get_type() == TType::BigInt {
+                let name = generate_unique_string("BigInt::__neg__", &[TType::BigInt]);
+                return Ok(Expr::Literal {
+                    ttype: TType::BigInt,
+                    value: Atom::Call {
+                        name: name.into(),
+                        arguments: vec![factor],
+                        position: self.get_current_token_position(),
+                    },
+                });
             } else {
                 return Ok(Expr::Unary {
                     ttype: factor.get_type(),
@@ -1827,7 +2704,7 @@ impl Parser {
                 left = self.block_expr()?;
             }
             // if expression if test {} else {}, both branches must return the same type
-            Some(Identifier(id)) if "if" == id.deref() => {
+            Some(Keyword(KeyWord::If)) => {
                 let pos = self.get_current_token_position();
                 self.advance();
 
@@ -1839,9 +2716,15 @@ impl Parser {
                         format!("Got {}", condition.get_type()),
                     ));
                 }
+                let before = self.environment.initialization_state();
                 let if_branch = self.block_expr()?;
-                self.consume_identifier(Some("else"))?;
+                let if_state = self.environment.initialization_state();
+                self.environment.restore_initialization(before);
+                self.consume_keyword(KeyWord::Else)?;
                 let else_branch = self.block_expr()?;
+                let else_state = self.environment.initialization_state();
+                self.environment
+                    .merge_initialization(vec![if_state, else_state]);
                 let if_type = if if_branch.get_type() == else_branch.get_type() {
                     if_branch.get_type()
                 } else {
@@ -1862,7 +2745,33 @@ impl Parser {
                     alternative: Box::new(else_branch),
                 };
             }
-            Some(Identifier(id)) if "return" == id.deref() => {
+            // try { ... } is a Result, Err with the message of an error raised
+            // anywhere in the body. a body without a value gives Ok(true)
+            Some(Identifier(id))
+                if "try" == id.deref()
+                    && matches!(self.peek_offset_value(1), Some(StructuralSymbol(LeftBrace))) =>
+            {
+                self.advance();
+                *self.environment.open_tries.last_mut().unwrap() += 1;
+                let body = self.block_expr()?;
+                *self.environment.open_tries.last_mut().unwrap() -= 1;
+                let Expr::Block { ttype, body } = body else {
+                    unreachable!()
+                };
+                let ttype = if ttype == TType::Void {
+                    TType::Bool
+                } else {
+                    ttype
+                };
+                left = Expr::Try {
+                    ttype: TType::Custom {
+                        name: "Result".into(),
+                        type_params: vec![ttype, TType::String],
+                    },
+                    body,
+                };
+            }
+            Some(Keyword(KeyWord::Return)) => {
                 self.advance();
                 let ret = self.expr()?;
                 left = Expr::Return {
@@ -1871,6 +2780,7 @@ impl Parser {
                 };
             }
             Some(Identifier(id)) if "None" == id.deref() => {
+                let position = self.get_current_token_position();
                 self.advance();
                 self.consume_symbol(LeftParen)?;
                 let option_type = self.ttype()?;
@@ -1878,7 +2788,11 @@ impl Parser {
                     ttype: TType::Option {
                         inner: Box::new(option_type),
                     },
-                    value: Atom::None,
+                    value: Atom::Call {
+                        name: "None".into(),
+                        arguments: vec![],
+                        position,
+                    },
                 };
                 self.consume_symbol(RightParen)?;
             }
@@ -1895,6 +2809,7 @@ impl Parser {
                 // get parameters
                 self.consume_symbol(LeftParen)?;
                 let parameters = self.parameter_list()?;
+                let positions = std::mem::take(&mut self.parameter_positions);
                 self.consume_symbol(RightParen)?;
                 // get output type
                 let mut output = TType::Void;
@@ -1958,7 +2873,7 @@ impl Parser {
                 self.environment.push_scope();
 
                 // insert params into scope
-                for (ttype, id) in parameters.iter() {
+                for ((ttype, id), pos) in parameters.iter().zip(positions.iter()) {
                     match ttype.clone() {
                         TType::Function {
                             parameters: paraminput,
@@ -1985,57 +2900,8 @@ impl Parser {
 
                 let mut statements = self.block()?;
 
-                let mut captured: Vec<_> = self
-                    .environment
-                    .captured
-                    .last()
-                    .unwrap()
-                    .iter()
-                    .map(|v| v.0.clone())
-                    .collect();
-
-                self.environment.pop_scope();
-
-                for c in captured.iter() {
-                    if let Some(mc) = self.environment.get_type_capture(&c.clone()) {
-                        let pos = self.get_current_token_position();
-                        self.environment.captured.last_mut().unwrap().insert(
-                            c.clone(),
-                            Symbol {
-                                id: mc.1,
-                                ttype: mc.0,
-                                pos: Some(pos),
-                                kind: mc.2,
-                            },
-                        );
-                    }
-                }
-
-                captured = self
-                    .environment
-                    .captured
-                    .last()
-                    .unwrap()
-                    .iter()
-                    .map(|v| v.0.clone())
-                    .collect();
-
-                for arg in parameters.iter() {
-                    let name = arg.1.clone();
-                    // check if name is in captured
-                    if captured.contains(&name) {
-                        captured.retain(|x| x != &name);
-                    }
-                }
-
-                // for dc in captured.iter() {
-                //     if let Some(v) = self.environment.values.last().unwrap().get(dc) {
-                //         if let SymbolKind::Captured = v.kind {
-                //         } else {
-                //             self.environment.captured.last_mut().unwrap().remove(dc);
-                //         }
-                //     }
-                // }
+                let names: Vec<Rc<str>> = parameters.iter().map(|(_, id)| id.clone()).collect();
+                let captured = self.end_captures(&names, &[], &statements);
 
                 // check return types
 
@@ -2072,7 +2938,7 @@ impl Parser {
                 };
             }
             Some(StructuralSymbol(Pipe) | Operator(Operator::Or)) => {
-                let (typeinput, input, output, statement, captured) = self.bar_closure()?;
+                let (typeinput, input, output, statement, captured) = self.bar_closure(None)?;
 
                 left = Expr::Closure {
                     ttype: TType::Function {
@@ -2096,7 +2962,7 @@ impl Parser {
                         // first get ident, then in keyword, then expr, and then any guards
                         let (ident, mut pos) = self.get_identifier()?;
                         self.consume_keyword(KeyWord::In)?;
-                        let listexpr = self.expr()?;
+                        let listexpr = self.comprehension_expr()?;
 
                         if let TType::List { inner } = listexpr.get_type() {
                             self.environment.insert_symbol(
@@ -2117,15 +2983,15 @@ impl Parser {
                         // while comma is present, get ident, in keyword, expr
                         while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
                             self.consume_symbol(Comma)?;
-                            let (ident, _) = self.get_identifier()?;
+                            let (ident, ident_pos) = self.get_identifier()?;
                             self.consume_keyword(KeyWord::In)?;
-                            let listexpr = self.expr()?;
+                            let listexpr = self.comprehension_expr()?;
                             // insert identifer into scope for typechecking
                             if let TType::List { inner } = listexpr.get_type() {
                                 self.environment.insert_symbol(
                                     &ident,
                                     *inner.clone(),
-                                    Some(pos.clone()),
+                                    Some(ident_pos),
                                     SymbolKind::Variable,
                                 );
                             } else {
@@ -2140,11 +3006,11 @@ impl Parser {
                         self.consume_symbol(Pipe)?;
 
                         self.environment.push_block();
-                        let mut outexpr = vec![self.expr()?];
+                        let mut outexpr = vec![self.comprehension_expr()?];
                         // continue parsing expr if there is a comma after the outexpr
                         if self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
                             self.advance();
-                            outexpr.push(self.expr()?);
+                            outexpr.push(self.comprehension_expr()?);
                         }
                         // typecheck taht outexpr is not void
                         if outexpr.last().unwrap().get_type() == TType::Void {
@@ -2160,7 +3026,7 @@ impl Parser {
                         while self.current_token().is_some_and(|t| t.is_symbol(Pipe)) {
                             pos = self.get_current_token_position();
                             self.consume_symbol(Pipe)?;
-                            guards.push(self.expr()?);
+                            guards.push(self.comprehension_expr()?);
                         }
 
                         // check that all the guard types are bool
@@ -2192,13 +3058,42 @@ impl Parser {
                         };
                     }
                     _ => {
-                        let expr_list = self.expr_list()?;
+                        let mut expr_list = self.expr_list()?;
                         let mut ttype = TType::None;
                         if !expr_list.is_empty() {
                             ttype = expr_list[0].get_type()
                         }
+                        let annotation = if self
+                            .current_token()
+                            .is_some_and(|t| t.is_op(Operator::Colon))
+                        {
+                            self.consume_operator(Operator::Colon)?;
+                            Some(self.ttype()?)
+                        } else {
+                            None
+                        };
+                        // [1, "a"]: Int | String mixes the members of a union
+                        if let Some(union @ TType::Union { .. }) = annotation.clone() {
+                            for elem in expr_list.iter() {
+                                self.check_and_map_types(
+                                    std::slice::from_ref(&union),
+                                    &[elem.get_type()],
+                                    &mut HashMap::default(),
+                                    pos.clone(),
+                                )?;
+                            }
+                            ttype = union;
+                        }
+                        // [1, 2, 3]: U8 gives the Int constants the sized type
+                        if let Some(sized @ TType::Sized { .. }) = &annotation {
+                            expr_list = expr_list
+                                .into_iter()
+                                .map(|elem| self.sized_literal(elem, sized, pos.clone()))
+                                .collect::<Result<_, _>>()?;
+                            ttype = sized.clone();
+                        }
                         for elem in expr_list.clone() {
-                            if elem.get_type() != ttype {
+                            if elem.get_type() != ttype && !ttype.is_union() {
                                 return Err(NovaError::TypeError {
                                     msg: "List must contain same type".into(),
                                     expected: ttype.to_string().into(),
@@ -2208,12 +3103,8 @@ impl Parser {
                             }
                         }
 
-                        if self
-                            .current_token()
-                            .is_some_and(|t| t.is_op(Operator::Colon))
-                        {
-                            self.consume_operator(Operator::Colon)?;
-                            ttype = self.ttype()?;
+                        if let Some(annotation) = annotation.filter(|t| !t.is_union()) {
+                            ttype = annotation;
                             if !expr_list.is_empty() && ttype != expr_list[0].get_type() {
                                 return Err(NovaError::TypeError {
                                     msg: "List must contain same type".into(),
@@ -2262,7 +3153,7 @@ impl Parser {
                         "Add more elements to the tuple",
                     ));
                 } else {
-                    let expr = self.expr()?;
+                    let expr = self.nested_expr()?;
                     if expr.get_type() == TType::None {
                         return Err(self.generate_error(
                             "Tuple must not contain None",
@@ -2281,7 +3172,7 @@ impl Parser {
                             {
                                 break;
                             }
-                            let expr = self.expr()?;
+                            let expr = self.nested_expr()?;
                             if expr.get_type() == TType::None {
                                 return Err(self.generate_error(
                                     "Tuple must not contain None",
@@ -2304,28 +3195,47 @@ impl Parser {
             }
             Some(Identifier(_)) => {
                 let (mut identifier, pos) = self.get_identifier()?;
+                // where the last part of a Module::name was written
+                let mut name_pos = pos.clone();
+                identifier = self.resolve_open(identifier);
+                if !identifier.contains("::") {
+                    identifier = self.resolve_type(identifier, pos.clone())?;
+                }
                 identifier = match self.current_token_value() {
                     Some(Operator(Operator::DoubleColon))
                         if matches!(
                             identifier.as_ref(),
-                            "Int" | "String" | "Float" | "Bool" | "List" | "Char" | "Option"
-                        ) =>
+                            "Int"
+                                | "String"
+                                | "Float"
+                                | "Bool"
+                                | "List"
+                                | "Char"
+                                | "Option"
+                                | "BigInt"
+                                | "Tuple"
+                                | "Function"
+                        ) || Width::from_name(&identifier).is_some() =>
                     {
                         self.advance();
-                        let (name, _) = self.get_identifier()?;
+                        let (name, at) = self.get_identifier()?;
+                        name_pos = at;
                         format!("{}::{}", identifier, name).into()
                     }
                     Some(Operator(Operator::DoubleColon))
                         if self.environment.custom_types.contains_key(&identifier) =>
                     {
                         self.advance();
-                        let (name, _) = self.get_identifier()?;
+                        let (name, at) = self.get_identifier()?;
+                        name_pos = at;
                         format!("{}::{}", identifier, name).into()
                     }
                     Some(Operator(Operator::DoubleColon)) if self.modules.has(&identifier) => {
+                        self.require_module_visible(&identifier, pos.clone())?;
                         self.advance();
-                        let (name, _) = self.get_identifier()?;
-                        format!("{}::{}", identifier, name).into()
+                        let (name, at) = self.get_identifier()?;
+                        name_pos = at.clone();
+                        self.module_member(&identifier, &name, at)
                     }
                     Some(Operator(Operator::DoubleColon)) => identifier,
                     Some(StructuralSymbol(At)) => {
@@ -2349,24 +3259,37 @@ impl Parser {
                     Some(Operator(Operator::DoubleColon))
                         if matches!(
                             identifier.as_ref(),
-                            "Int" | "String" | "Float" | "Bool" | "List" | "Char" | "Option"
-                        ) =>
+                            "Int"
+                                | "String"
+                                | "Float"
+                                | "Bool"
+                                | "List"
+                                | "Char"
+                                | "Option"
+                                | "BigInt"
+                                | "Tuple"
+                                | "Function"
+                        ) || Width::from_name(&identifier).is_some() =>
                     {
                         self.advance();
-                        let (name, _) = self.get_identifier()?;
+                        let (name, at) = self.get_identifier()?;
+                        name_pos = at;
                         format!("{}::{}", identifier, name).into()
                     }
                     Some(Operator(Operator::DoubleColon))
                         if self.environment.custom_types.contains_key(&identifier) =>
                     {
                         self.advance();
-                        let (name, _) = self.get_identifier()?;
+                        let (name, at) = self.get_identifier()?;
+                        name_pos = at;
                         format!("{}::{}", identifier, name).into()
                     }
                     Some(Operator(Operator::DoubleColon)) if self.modules.has(&identifier) => {
+                        self.require_module_visible(&identifier, pos.clone())?;
                         self.advance();
-                        let (name, _) = self.get_identifier()?;
-                        format!("{}::{}", identifier, name).into()
+                        let (name, at) = self.get_identifier()?;
+                        name_pos = at.clone();
+                        self.module_member(&identifier, &name, at)
                     }
                     Some(Operator(Operator::DoubleColon)) => identifier,
                     Some(StructuralSymbol(At)) => {
@@ -2386,7 +3309,7 @@ impl Parser {
                     _ => identifier,
                 };
 
-                let leftt = self.anchor(identifier.clone(), pos)?;
+                let leftt = self.anchor(identifier.clone(), pos, name_pos)?;
                 left = leftt;
 
                 // dbg!(self.current_token(), identifier.clone());
@@ -2444,31 +3367,49 @@ impl Parser {
                 Some(StructuralSymbol(LeftSquareBracket)) => {
                     left = self.handle_chain_indexint(left)?;
                 }
+                Some(StructuralSymbol(QuestionMark)) => {
+                    left = self.propagate(left)?;
+                }
                 Some(Operator(Operator::PipeArrow)) => {
                     self.consume_operator(Operator::PipeArrow)?;
                     let (mut identifier, pos) = self.get_identifier()?;
+                    let mut name_pos = pos.clone();
+                    identifier = self.resolve_open(identifier);
                     identifier = match self.current_token_value() {
                         Some(Operator(Operator::DoubleColon))
                             if matches!(
                                 identifier.as_ref(),
-                                "Int" | "String" | "Float" | "Bool" | "List" | "Char" | "Option"
-                            ) =>
+                                "Int"
+                                    | "String"
+                                    | "Float"
+                                    | "Bool"
+                                    | "List"
+                                    | "Char"
+                                    | "Option"
+                                    | "BigInt"
+                                    | "Tuple"
+                                    | "Function"
+                            ) || Width::from_name(&identifier).is_some() =>
                         {
                             self.advance();
-                            let (name, _) = self.get_identifier()?;
+                            let (name, at) = self.get_identifier()?;
+                            name_pos = at;
                             format!("{}::{}", identifier, name).into()
                         }
                         Some(Operator(Operator::DoubleColon))
                             if self.environment.custom_types.contains_key(&identifier) =>
                         {
                             self.advance();
-                            let (name, _) = self.get_identifier()?;
+                            let (name, at) = self.get_identifier()?;
+                            name_pos = at;
                             format!("{}::{}", identifier, name).into()
                         }
                         Some(Operator(Operator::DoubleColon)) if self.modules.has(&identifier) => {
+                            self.require_module_visible(&identifier, pos.clone())?;
                             self.advance();
-                            let (name, _) = self.get_identifier()?;
-                            format!("{}::{}", identifier, name).into()
+                            let (name, at) = self.get_identifier()?;
+                            name_pos = at.clone();
+                            self.module_member(&identifier, &name, at)
                         }
                         Some(Operator(Operator::DoubleColon)) => identifier,
                         Some(StructuralSymbol(At)) => {
@@ -2487,7 +3428,7 @@ impl Parser {
                         }
                         _ => identifier,
                     };
-                    left = self.call(identifier, pos, Some(left))?;
+                    left = self.call(identifier, pos, name_pos, Some(left))?;
                 }
                 _ => {
                     break;
@@ -2498,22 +3439,67 @@ impl Parser {
         Ok(left)
     }
 
+    // expr? hands a None or an Err to the enclosing try block
+    fn propagate(&mut self, expr: Expr) -> Result<Expr, NovaError> {
+        let position = self.get_current_token_position();
+        self.consume_symbol(QuestionMark)?;
+        if self.environment.open_tries.last() == Some(&0) {
+            return Err(self.generate_error_with_pos(
+                "? can only be used inside a try block",
+                "Wrap the code in try { ... } to get a Result, or match on the value",
+                position,
+            ));
+        }
+        let ttype = match expr.get_type() {
+            TType::Option { inner } => *inner,
+            TType::Custom { name, type_params } if name.as_ref() == "Result" => {
+                type_params[0].clone()
+            }
+            other => {
+                return Err(self.generate_error_with_pos(
+                    format!("Cannot use ? on {}", self.describe_type(&other)),
+                    "Only Option and Result values can be propagated",
+                    position,
+                ))
+            }
+        };
+        Ok(Expr::Propagate {
+            ttype,
+            expr: Box::new(expr),
+            position,
+        })
+    }
+
     #[allow(clippy::type_complexity)]
+    // |x: Int| x * 2, or with the parameters of the function it is passed to
+    // a block alone, { it * 2 }
     fn bar_closure(
         &mut self,
+        implicit: Option<Vec<TType>>,
     ) -> Result<(Vec<TType>, Vec<Arg>, TType, Vec<Statement>, Vec<Rc<str>>), NovaError> {
         let pos = self.get_current_token_position();
-        let parameters = match self.consume_symbol(Pipe) {
-            Ok(_) => {
-                let p = self.parameter_list()?;
-                self.consume_symbol(Pipe)?;
-                p
-            }
-            Err(_) => {
-                self.consume_operator(Operator::Or)?;
-                vec![]
+        let parameters = match implicit {
+            Some(implicit) => {
+                self.parameter_positions = vec![pos.clone(); implicit.len()];
+                implicit.into_iter().map(|t| (t, "it".into())).collect()
             }
+            None => match self.consume_symbol(Pipe) {
+                Ok(_) => {
+                    self.bar_parameters = true;
+                    let p = self.parameter_list();
+                    self.bar_parameters = false;
+                    let p = p?;
+                    self.consume_symbol(Pipe)?;
+                    p
+                }
+                Err(_) => {
+                    self.consume_operator(Operator::Or)?;
+                    self.parameter_positions.clear();
+                    vec![]
+                }
+            },
         };
+        let positions = std::mem::take(&mut self.parameter_positions);
         let mut typeinput = vec![];
         for arg in parameters.iter() {
             typeinput.push(arg.0.clone())
@@ -2555,7 +3541,7 @@ impl Parser {
         let generic_list = Self::collect_generics(&typeinput);
         self.environment.live_generics.push(generic_list.clone());
         self.environment.push_scope();
-        for (ttype, id) in parameters.iter() {
+        for ((ttype, id), pos) in parameters.iter().zip(positions.iter()) {
             match ttype.clone() {
                 TType::Function {
                     parameters: paraminput,
@@ -2599,61 +3585,33 @@ impl Parser {
             }];
             statement
         };
-        let mut captured: Vec<_> = self
-            .environment
-            .captured
-            .last()
-            .unwrap()
-            .iter()
-            .map(|v| v.0.clone())
-            .collect();
-
-        self.environment.pop_scope();
+        let names: Vec<Rc<str>> = parameters.iter().map(|(_, id)| id.clone()).collect();
+        let captured = self.end_captures(&names, &[], &statement);
         self.environment.live_generics.pop();
-        for c in captured.iter() {
-            if let Some(mc) = self.environment.get_type_capture(&c.clone()) {
-                let pos = self.get_current_token_position();
 
-                self.environment.captured.last_mut().unwrap().insert(
-                    c.clone(),
-                    Symbol {
-                        id: mc.1,
-                        ttype: mc.0,
-                        pos: Some(pos),
-                        kind: mc.2,
-                    },
-                );
-            }
-        }
-        captured = self
-            .environment
-            .captured
-            .last()
-            .unwrap()
-            .iter()
-            .map(|v| v.0.clone())
-            .collect();
+        Ok((typeinput, input, output, statement, captured))
+    }
 
-        for arg in parameters.iter() {
-            let name = arg.1.clone();
-            // check if name is in captured
-            if captured.contains(&name) {
-                // remove from captured
-                // remove from captured variable
-                captured.retain(|x| x != &name);
-            }
+    // leaves the scope of a function body and returns what it captures: the
+    // names it uses without declaring them that were found in an enclosing
+    // function while parsing it. the enclosing function captures those in
+    // turn when they are not its own
+    fn end_captures(
+        &mut self,
+        parameters: &[Rc<str>],
+        exprs: &[&Expr],
+        body: &[Statement],
+    ) -> Vec<Rc<str>> {
+        let outside = self.environment.captured.last().unwrap().clone();
+        let captures: Vec<Rc<str>> = captures::free_variables(parameters, exprs, body)
+            .into_iter()
+            .filter(|name| outside.contains_key(name))
+            .collect();
+        self.environment.pop_scope();
+        for capture in captures.iter() {
+            self.environment.get_type_capture(capture);
         }
-
-        // for dc in captured.iter() {
-        //     if let Some(v) = self.environment.values.last().unwrap().get(dc) {
-        //         if let SymbolKind::Captured = v.kind {
-        //         } else {
-        //             self.environment.captured.last_mut().unwrap().remove(dc);
-        //         }
-        //     }
-        // }
-
-        Ok((typeinput, input, output, statement, captured))
+        captures
     }
 
     fn handle_inner_function_call(&mut self, left: Expr) -> Result<Expr, NovaError> {
@@ -2711,7 +3669,7 @@ impl Parser {
             }
             let mut type_map = HashMap::new();
             self.check_and_map_types(&parameters, &input_types, &mut type_map, pos.clone())?;
-            return_type = Box::new(self.get_output(*return_type.clone(), &mut type_map, pos)?);
+            *return_type = self.get_output(*return_type.clone(), &mut type_map, pos)?;
             Ok(Expr::Call {
                 ttype: *return_type,
                 name: function_name,
@@ -2734,7 +3692,29 @@ impl Parser {
             if let Some(operation) = self.current_token().and_then(|t| t.get_operator()) {
                 self.advance();
                 let right_expr = self.factor()?;
+                let (lhs, right_expr) =
+                    self.sized_operands(left_expr, right_expr, current_pos.clone())?;
+                left_expr = lhs;
                 match (left_expr.clone().get_type(), right_expr.clone().get_type()) {
+                    (TType::Sized { width }, TType::Sized { width: other }) if width == other => {
+                        left_expr = self.create_binop_expr(
+                            left_expr.clone(),
+                            right_expr,
+                            operation,
+                            left_expr.get_type(),
+                        );
+                    }
+                    (TType::Sized { .. }, TType::Sized { .. } | TType::Int)
+                    | (TType::Int, TType::Sized { .. })
+                    | (TType::BigInt, TType::Int)
+                    | (TType::Int, TType::BigInt) => {
+                        return Err(self.create_type_error(
+                            left_expr.clone(),
+                            right_expr.clone(),
+                            operation,
+                            current_pos.clone(),
+                        ));
+                    }
                     (TType::Int, TType::Int) | (TType::Float, TType::Float) => {
                         // if module only works with int
                         if operation == Operator::Modulo {
@@ -2964,9 +3944,50 @@ impl Parser {
 
     fn expr(&mut self) -> Result<Expr, NovaError> {
         match self.current_token_value() {
-            Some(Identifier(id)) if "let" == id.deref() => {
+            Some(Keyword(KeyWord::Let)) => {
                 return self.let_expr();
             }
+            Some(Identifier(id))
+                if "_" == id.deref()
+                    && self
+                        .peek_offset(1)
+                        .is_some_and(|t| t.is_op(Operator::Assignment)) =>
+            {
+                return self.discard();
+            }
+            Some(Identifier(id))
+                if self.environment.is_uninitialized(id)
+                    && self
+                        .peek_offset(1)
+                        .is_some_and(|t| t.is_op(Operator::Assignment)) =>
+            {
+                let (identifier, pos) = self.get_identifier()?;
+                return self.initialize(identifier, pos);
+            }
+            // lazy reads become calls, so catch assignments before the name disappears
+            Some(Identifier(id))
+                if self.environment.is_lazy(&self.resolve_open(id.clone()))
+                    && self.peek_offset(1).is_some_and(|t| t.is_assign()) =>
+            {
+                let (identifier, pos) = self.get_identifier()?;
+                return Err(self.generate_error_with_pos(
+                    format!("Cannot assign to lazy value '{}'", identifier),
+                    "Lazy values are computed once and cannot be changed",
+                    pos,
+                ));
+            }
+            // const reads are inlined, so catch assignments before the name disappears
+            Some(Identifier(id))
+                if self.environment.is_constant(&self.resolve_open(id.clone()))
+                    && self.peek_offset(1).is_some_and(|t| t.is_assign()) =>
+            {
+                let (identifier, pos) = self.get_identifier()?;
+                return Err(self.generate_error_with_pos(
+                    format!("Cannot assign to constant '{}'", identifier),
+                    "Constants cannot be changed after they are declared",
+                    pos,
+                ));
+            }
             _ => {}
         }
         let mut left_expr = self.logical_top_expr()?;
@@ -2975,6 +3996,10 @@ impl Parser {
             if let Some(operation) = self.current_token().and_then(|t| t.get_operator()) {
                 self.advance();
                 let right_expr = self.logical_top_expr()?;
+                let right_expr =
+                    self.sized_literal(right_expr, &left_expr.get_type(), current_pos.clone())?;
+                let (operation, right_expr) =
+                    self.dunder_assign(operation, &left_expr, right_expr, current_pos.clone());
                 match left_expr.clone() {
                     Expr::ListConstructor { .. }
                     | Expr::Binop { .. }
@@ -2989,6 +4014,20 @@ impl Parser {
                         ));
                     }
                     Expr::Literal { value: v, .. } => match v {
+                        Atom::Id { name } if self.environment.is_constant(&name) => {
+                            return Err(self.generate_error_with_pos(
+                                format!("Cannot assign to constant '{}'", name),
+                                "Constants cannot be changed after they are declared",
+                                current_pos.clone(),
+                            ));
+                        }
+                        Atom::Id { name } if self.environment.is_immutable(&name) => {
+                            return Err(self.generate_error_with_pos(
+                                format!("Cannot assign twice to immutable variable '{}'", name),
+                                format!("Declare it with let mut {} to allow reassignment", name),
+                                current_pos.clone(),
+                            ));
+                        }
                         Atom::Id { .. } => {
                             self.check_and_map_types(
                                 &[left_expr.get_type()],
@@ -3067,6 +4106,7 @@ impl Parser {
                         self.environment.pop_block();
 
                         if let Some(Statement::Expression { ttype, .. }) = expr_block.last() {
+                            self.keep_block_value();
                             left_expr = Expr::StoreExpr {
                                 ttype: ttype.clone(),
                                 name: identifier.clone(),
@@ -3097,12 +4137,15 @@ impl Parser {
     }
 
     fn top_expr(&mut self) -> Result<Expr, NovaError> {
-        let mut left_expr = self.mid_expr()?;
+        let mut left_expr = self.bit_expr()?;
         let current_pos = self.get_current_token_position();
         while self.current_token().is_some_and(|t| t.is_relop()) {
             if let Some(operation) = self.current_token().and_then(|t| t.get_operator()) {
                 self.advance();
-                let right_expr = self.mid_expr()?;
+                let right_expr = self.bit_expr()?;
+                let (lhs, right_expr) =
+                    self.sized_operands(left_expr, right_expr, current_pos.clone())?;
+                left_expr = lhs;
                 // check if void
                 if left_expr.get_type() == TType::Void || right_expr.get_type() == TType::Void {
                     return Err(self.generate_error_with_pos(
@@ -3125,6 +4168,45 @@ impl Parser {
                                     TType::Bool,
                                 );
                             }
+                            (TType::Sized { width }, TType::Sized { width: other })
+                                if width == other =>
+                            {
+                                left_expr = self.create_binop_expr(
+                                    left_expr,
+                                    right_expr,
+                                    operation,
+                                    TType::Bool,
+                                );
+                            }
+                            (TType::Sized { .. }, TType::Sized { .. } | TType::Int)
+                            | (TType::Int, TType::Sized { .. })
+                            | (TType::BigInt, TType::Int)
+                            | (TType::Int, TType::BigInt) => {
+                                return Err(self.create_type_error(
+                                    left_expr.clone(),
+                                    right_expr.clone(),
+                                    operation,
+                                    current_pos.clone(),
+                                ));
+                            }
+                            // compared by their runtime type
+                            (TType::Generic { name: a }, TType::Generic { name: b })
+                                if a == b && self.environment.has_bound(&a, "Comparable") =>
+                            {
+                                left_expr = self.create_binop_expr(
+                                    left_expr,
+                                    right_expr,
+                                    operation,
+                                    TType::Bool,
+                                );
+                            }
+                            (TType::Generic { name }, TType::Generic { .. }) => {
+                                return Err(self.generate_error_with_pos(
+                                    format!("Cannot compare values of generic type ${}", name),
+                                    format!("Add where {}: Comparable after the return type", name),
+                                    current_pos.clone(),
+                                ));
+                            }
                             (TType::Float, TType::Float) => {
                                 left_expr = self.create_binop_expr(
                                     left_expr,
@@ -3692,6 +4774,93 @@ impl Parser {
         Ok(left_expr)
     }
 
+    // | between two Ints, or a call of __bitor__, binding looser than &
+    fn bit_expr(&mut self) -> Result<Expr, NovaError> {
+        let mut left_expr = self.bit_and_expr()?;
+        while !self.comprehension && self.current_token().is_some_and(|t| t.is_symbol(Pipe)) {
+            let pos = self.get_current_token_position();
+            self.advance();
+            let right_expr = self.bit_and_expr()?;
+            left_expr = self.bit_call("__bitor__", left_expr, right_expr, pos)?;
+        }
+        Ok(left_expr)
+    }
+
+    fn bit_and_expr(&mut self) -> Result<Expr, NovaError> {
+        let mut left_expr = self.mid_expr()?;
+        while self.current_token().is_some_and(|t| t.is_symbol(Ampersand)) {
+            let pos = self.get_current_token_position();
+            self.advance();
+            let right_expr = self.mid_expr()?;
+            left_expr = self.bit_call("__bitand__", left_expr, right_expr, pos)?;
+        }
+        Ok(left_expr)
+    }
+
+    // Int has both methods as natives, so a type only needs to define them
+    fn bit_call(
+        &mut self,
+        method: &str,
+        left_expr: Expr,
+        right_expr: Expr,
+        pos: FilePosition,
+    ) -> Result<Expr, NovaError> {
+        let left_type = left_expr.get_type();
+        let Some(custom) = left_type.custom_to_string() else {
+            return Err(self.generate_error_with_pos(
+                "Operation not supported",
+                format!("{} has no {method}", left_type),
+                pos,
+            ));
+        };
+        let function_id = format!("{custom}::{method}");
+        let unique =
+            generate_unique_string(&function_id, &[left_type.clone(), right_expr.get_type()]);
+        let (name, overload) = if let Some(overload) = self.environment.get(&unique) {
+            (unique, overload)
+        } else if let Some(overload) = self.environment.get(&function_id) {
+            (function_id, overload)
+        } else {
+            return Err(self.generate_error_with_pos(
+                "Operation not supported",
+                format!("Try implementing the method {}", function_id),
+                pos,
+            ));
+        };
+        let TType::Function { return_type, .. } = overload.ttype else {
+            return Err(self.generate_error_with_pos(
+                "Expected function",
+                "Make sure function is defined",
+                pos,
+            ));
+        };
+        Ok(Expr::Literal {
+            ttype: *return_type,
+            value: Atom::Call {
+                name: name.into(),
+                arguments: vec![left_expr, right_expr],
+                position: pos,
+            },
+        })
+    }
+
+    // an expression in a list comprehension, ended by a | that is not in
+    // parentheses
+    fn comprehension_expr(&mut self) -> Result<Expr, NovaError> {
+        let comprehension = std::mem::replace(&mut self.comprehension, true);
+        let expr = self.expr();
+        self.comprehension = comprehension;
+        expr
+    }
+
+    // an expression inside parentheses, where | is an operator again
+    fn nested_expr(&mut self) -> Result<Expr, NovaError> {
+        let comprehension = std::mem::take(&mut self.comprehension);
+        let expr = self.expr();
+        self.comprehension = comprehension;
+        expr
+    }
+
     fn mid_expr(&mut self) -> Result<Expr, NovaError> {
         let mut left_expr = self.term()?;
         let current_pos = self.get_current_token_position();
@@ -3699,8 +4868,30 @@ impl Parser {
             if let Some(operation) = self.current_token().and_then(|t| t.get_operator()) {
                 self.advance();
                 let right_expr = self.term()?;
+                let (lhs, right_expr) =
+                    self.sized_operands(left_expr, right_expr, current_pos.clone())?;
+                left_expr = lhs;
 
                 match (left_expr.get_type(), right_expr.get_type()) {
+                    (TType::Sized { width }, TType::Sized { width: other }) if width == other => {
+                        left_expr = self.create_binop_expr(
+                            left_expr.clone(),
+                            right_expr,
+                            operation,
+                            left_expr.get_type(),
+                        );
+                    }
+                    (TType::Sized { .. }, TType::Sized { .. } | TType::Int)
+                    | (TType::Int, TType::Sized { .. })
+                    | (TType::BigInt, TType::Int)
+                    | (TType::Int, TType::BigInt) => {
+                        return Err(self.create_type_error(
+                            left_expr.clone(),
+                            right_expr.clone(),
+                            operation,
+                            current_pos.clone(),
+                        ));
+                    }
                     (TType::Int, TType::Int)
                     | (TType::Float, TType::Float)
                     | (TType::String, TType::String) => {
@@ -3848,6 +5039,122 @@ impl Parser {
         }
     }
 
+    // the value of an Int expression known at compile time
+    fn int_constant(expr: &Expr) -> Option<i64> {
+        match expr.const_value()? {
+            Expr::Literal {
+                ttype: TType::Int,
+                value: Atom::Integer { value },
+            } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn sized_constant(
+        &self,
+        value: i64,
+        width: Width,
+        pos: FilePosition,
+    ) -> Result<Expr, NovaError> {
+        if !width.fits(value as i128) {
+            return Err(self.generate_error_with_pos(
+                format!("{} does not fit in {}", value, width),
+                format!(
+                    "{} holds values from {} to {}",
+                    width,
+                    width.min(),
+                    width.max()
+                ),
+                pos,
+            ));
+        }
+        Ok(Expr::Literal {
+            ttype: TType::Sized { width },
+            value: Atom::Integer { value },
+        })
+    }
+
+    // an Int constant written where a sized integer is expected takes that
+    // type when it fits, a list of them when every element does, and so on
+    // for lists of lists
+    fn sized_literal(
+        &self,
+        expr: Expr,
+        expected: &TType,
+        pos: FilePosition,
+    ) -> Result<Expr, NovaError> {
+        match (expected, expr) {
+            (TType::Sized { width }, expr) => match Self::int_constant(&expr) {
+                Some(value) => self.sized_constant(value, *width, pos),
+                None => Ok(expr),
+            },
+            (
+                TType::List { inner },
+                Expr::ListConstructor {
+                    ttype: TType::List { inner: found },
+                    elements,
+                },
+            ) if Self::holds_sized(inner) && Self::holds_sized_literals(&found) => {
+                let converted = elements
+                    .iter()
+                    .map(|element| self.sized_literal(element.clone(), inner, pos.clone()))
+                    .collect::<Result<Vec<Expr>, NovaError>>()?;
+                if converted
+                    .iter()
+                    .any(|element| element.get_type() != **inner)
+                {
+                    return Ok(Expr::ListConstructor {
+                        ttype: TType::List { inner: found },
+                        elements,
+                    });
+                }
+                Ok(Expr::ListConstructor {
+                    ttype: expected.clone(),
+                    elements: converted,
+                })
+            }
+            (_, expr) => Ok(expr),
+        }
+    }
+
+    // a sized integer, or a list of them at any depth
+    fn holds_sized(ttype: &TType) -> bool {
+        match ttype {
+            TType::Sized { .. } => true,
+            TType::List { inner } => Self::holds_sized(inner),
+            _ => false,
+        }
+    }
+
+    // what a list literal is typed as before its Int constants become sized
+    fn holds_sized_literals(ttype: &TType) -> bool {
+        match ttype {
+            TType::Int => true,
+            TType::List { inner } => Self::holds_sized_literals(inner),
+            _ => false,
+        }
+    }
+
+    // in b + 1 with b a sized integer the constant takes the type of b
+    fn sized_operands(
+        &self,
+        lhs: Expr,
+        rhs: Expr,
+        pos: FilePosition,
+    ) -> Result<(Expr, Expr), NovaError> {
+        match (lhs.get_type(), rhs.get_type()) {
+            (sized @ TType::Sized { .. }, TType::Int) => {
+                let rhs = self.sized_literal(rhs, &sized, pos)?;
+                Ok((lhs, rhs))
+            }
+            (TType::Int, sized @ TType::Sized { .. }) => {
+                let lhs = self.sized_literal(lhs, &sized, pos)?;
+                Ok((lhs, rhs))
+            }
+            _ => Ok((lhs, rhs)),
+        }
+    }
+
     fn create_type_error(
         &self,
         left_expr: Expr,
@@ -3869,6 +5176,89 @@ impl Parser {
     }
 
     fn ttype(&mut self) -> Result<TType, NovaError> {
+        let pos = self.get_current_token_position();
+        let first = self.type_member()?;
+        if self.bar_parameters || !self.current_token().is_some_and(|t| t.is_symbol(Pipe)) {
+            return Ok(first);
+        }
+        let mut members = vec![first];
+        while self.current_token().is_some_and(|t| t.is_symbol(Pipe)) {
+            self.consume_symbol(Pipe)?;
+            members.push(self.type_member()?);
+        }
+        let union = TType::union(members);
+        if let TType::Union { members } = &union {
+            self.check_union(members, pos)?;
+        }
+        Ok(union)
+    }
+
+    // a type inside brackets, where | starts a union even in closure parameters
+    fn nested_type(&mut self) -> Result<TType, NovaError> {
+        let bar_parameters = std::mem::take(&mut self.bar_parameters);
+        let ttype = self.ttype();
+        self.bar_parameters = bar_parameters;
+        ttype
+    }
+
+    // the vm has to tell which member a value is, by its kind or for structs
+    // and enums by the type name stored with them
+    fn check_union(&self, members: &[TType], pos: FilePosition) -> Result<(), NovaError> {
+        let union = TType::Union {
+            members: members.to_vec(),
+        };
+        for member in members {
+            let note = match member {
+                TType::Option { inner } => format!(
+                    "Make the whole union optional instead, Option({})",
+                    TType::union(members.iter().map(|t| match t {
+                        TType::Option { .. } if t == member => *inner.clone(),
+                        t => t.clone(),
+                    }))
+                ),
+                TType::Any | TType::Void | TType::None | TType::Auto => {
+                    format!("{} already stands for any value", member)
+                }
+                TType::Generic { .. } => {
+                    "A generic could be any of the other members, name the types instead".into()
+                }
+                _ => continue,
+            };
+            return Err(self.generate_error_with_pos(
+                format!("{} cannot be a member of a union", member),
+                note,
+                pos,
+            ));
+        }
+        let list_like: Vec<&TType> = members.iter().filter(|t| t.is_list_like()).collect();
+        let functions: Vec<&TType> = members.iter().filter(|t| t.is_function()).collect();
+        // list-like members are only told apart by the name structs and enums carry
+        let mut clash = None;
+        for (i, a) in list_like.iter().enumerate() {
+            for b in list_like[i + 1..].iter() {
+                let named = matches!(
+                    (a, b),
+                    (TType::Custom { name: x, .. }, TType::Custom { name: y, .. }) if x != y
+                );
+                if !named {
+                    clash = clash.or(Some((*a, *b)));
+                }
+            }
+        }
+        if functions.len() > 1 {
+            clash = clash.or(Some((functions[0], functions[1])));
+        }
+        if let Some((a, b)) = clash {
+            return Err(self.generate_error_with_pos(
+                format!("Cannot tell {} and {} apart in {}", a, b, union),
+                "A union can hold one function and either one list, tuple or struct, or several structs and enums with different names",
+                pos,
+            ));
+        }
+        Ok(())
+    }
+
+    fn type_member(&mut self) -> Result<TType, NovaError> {
         match self.current_token_value() {
             Some(StructuralSymbol(LeftParen)) => {
                 let mut typelist = vec![];
@@ -3883,7 +5273,7 @@ impl Parser {
                         "Add more elements to the tuple",
                     ));
                 }
-                typelist.push(self.ttype()?);
+                typelist.push(self.nested_type()?);
                 while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
                     self.consume_symbol(Comma)?;
                     // if (5,) single element tuple
@@ -3894,7 +5284,7 @@ impl Parser {
                         self.consume_symbol(RightParen)?;
                         return Ok(TType::Tuple { elements: typelist });
                     }
-                    typelist.push(self.ttype()?);
+                    typelist.push(self.nested_type()?);
                 }
                 self.consume_symbol(RightParen)?;
                 // if there is only one type in the tuple, return that type
@@ -3911,11 +5301,11 @@ impl Parser {
                     .current_token()
                     .is_some_and(|t| t.is_symbol(RightParen))
                 {
-                    let inner = self.ttype()?;
+                    let inner = self.nested_type()?;
                     input.push(inner);
                     while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
                         self.consume_symbol(Comma)?;
-                        let inner = self.ttype()?;
+                        let inner = self.nested_type()?;
                         input.push(inner);
                     }
                     self.consume_symbol(RightParen)?;
@@ -3952,10 +5342,17 @@ impl Parser {
                 let (generictype, _) = self.get_identifier()?;
                 Ok(TType::Generic { name: generictype })
             }
+            Some(Identifier(id)) if "_" == id.deref() => {
+                self.advance();
+                self.type_holes += 1;
+                Ok(TType::Generic {
+                    name: format!("_{}", self.type_holes).into(),
+                })
+            }
             Some(Identifier(id)) if "Option" == id.deref() => {
                 self.advance();
                 self.consume_symbol(LeftParen)?;
-                let ttype = self.ttype()?;
+                let ttype = self.nested_type()?;
                 self.consume_symbol(RightParen)?;
                 if let TType::Option { .. } = ttype {
                     return Err(self.generate_error(
@@ -3974,7 +5371,7 @@ impl Parser {
                     .current_token()
                     .is_some_and(|t| t.is_symbol(RightSquareBracket))
                 {
-                    inner = self.ttype()?;
+                    inner = self.nested_type()?;
                 }
                 self.consume_symbol(RightSquareBracket)?;
                 Ok(TType::List {
@@ -3982,7 +5379,17 @@ impl Parser {
                 })
             }
             Some(Identifier(_)) => {
-                let (identifier, pos) = self.get_identifier()?;
+                let (mut identifier, pos) = self.get_identifier()?;
+                if self.modules.has(&identifier)
+                    && self
+                        .current_token()
+                        .is_some_and(|t| t.is_op(Operator::DoubleColon))
+                {
+                    self.require_module_visible(&identifier, pos.clone())?;
+                    self.advance();
+                    let (name, _) = self.get_identifier()?;
+                    identifier = format!("{}::{}", identifier, name).into();
+                }
 
                 let builtin = 'builtin: {
                     Some(match identifier.as_ref() {
@@ -3992,21 +5399,28 @@ impl Parser {
                         "String" => TType::String,
                         "Any" => TType::Any,
                         "Char" => TType::Char,
-                        _ => break 'builtin None,
+                        "BigInt" => TType::BigInt,
+                        name => match Width::from_name(name) {
+                            Some(width) => TType::Sized { width },
+                            None => break 'builtin None,
+                        },
                     })
                 };
                 if let Some(builtin) = builtin {
-                    Ok(builtin)
-                } else if self.environment.custom_types.contains_key(&identifier) {
+                    return Ok(builtin);
+                }
+                let identifier = self.resolve_type(identifier, pos.clone())?;
+                if self.environment.custom_types.contains_key(&identifier) {
+                    self.require_accessible(&identifier, pos.clone())?;
                     let mut type_annotation = vec![];
                     if let Some(StructuralSymbol(LeftParen)) = self.current_token_value() {
                         self.consume_symbol(LeftParen)?;
 
-                        let ta = self.ttype()?;
+                        let ta = self.nested_type()?;
                         type_annotation.push(ta);
                         while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
                             self.advance();
-                            let ta = self.ttype()?;
+                            let ta = self.nested_type()?;
                             type_annotation.push(ta);
                         }
                         self.consume_symbol(RightParen)?;
@@ -4044,10 +5458,29 @@ impl Parser {
         }
     }
 
+    // a keyword, true or false written where a name was expected
+    fn reserved_word_error(&self) -> Option<NovaError> {
+        let word = match self.current_token_value()? {
+            Keyword(keyword) => keyword.as_str(),
+            Bool(true) => "true",
+            Bool(false) => "false",
+            _ => return None,
+        };
+        let mut reserved: Vec<&str> = lexer::KEYWORDS.keys().copied().collect();
+        reserved.sort();
+        Some(self.generate_error(
+            format!("'{}' is a reserved word and cannot be used as a name", word),
+            format!("Reserved words are {}", reserved.join(", ")),
+        ))
+    }
+
     fn get_identifier(&mut self) -> Result<(Rc<str>, FilePosition), NovaError> {
         let identifier = match self.current_token_value() {
             Some(Identifier(id)) => id.clone(),
             _ => {
+                if let Some(error) = self.reserved_word_error() {
+                    return Err(error);
+                }
                 return Err(self.generate_error(
                     "Expected identifier",
                     format!("Cannot assign a value to {:?}", self.current_token(),),
@@ -4069,8 +5502,13 @@ impl Parser {
     fn parameter_list(&mut self) -> Result<Vec<(TType, Rc<str>)>, NovaError> {
         let mut parameters: Table<Rc<str>> = Table::new();
         let mut arguments = vec![];
+        let mut positions = vec![];
 
-        while self.current_token().is_some_and(|t| t.is_identifier()) {
+        // keywords are let through so using one as a name gets a clear error
+        while self
+            .current_token()
+            .is_some_and(|t| t.is_identifier() || matches!(t.value, Keyword(_)))
+        {
             let (identifier, pos) = self.get_identifier()?;
             if parameters.has(&identifier) {
                 return Err(self.generate_error_with_pos(
@@ -4083,6 +5521,7 @@ impl Parser {
             self.consume_operator(Operator::Colon)?;
             let ttype = self.ttype()?;
             arguments.push((ttype, identifier));
+            positions.push(pos);
 
             if !self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
                 break;
@@ -4090,14 +5529,54 @@ impl Parser {
             self.advance();
         }
 
+        self.parameter_positions = positions;
         Ok(arguments)
     }
 
+    // parameters of a function in an impl block, a leading self may leave out its type
+    fn receiver_parameter_list(
+        &mut self,
+        target: &TType,
+    ) -> Result<Vec<(TType, Rc<str>)>, NovaError> {
+        let shorthand = self.current_token().is_some_and(|t| t.is_id("self"))
+            && !self
+                .peek_offset(1)
+                .is_some_and(|t| t.is_op(Operator::Colon));
+        if !shorthand {
+            return self.parameter_list();
+        }
+        let receiver = self.get_current_token_position();
+        self.advance();
+        let mut parameters = vec![(target.clone(), Rc::from("self"))];
+        self.parameter_positions = vec![receiver.clone()];
+        if self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+            self.advance();
+            let rest = self.parameter_list()?;
+            self.parameter_positions.insert(0, receiver);
+            for (ttype, identifier) in rest {
+                if &*identifier == "self" {
+                    return Err(self.generate_error_with_pos(
+                        "parameter identifier already defined",
+                        "try using another name",
+                        self.get_current_token_position(),
+                    ));
+                }
+                parameters.push((ttype, identifier));
+            }
+        }
+        Ok(parameters)
+    }
+
     fn enum_list(&mut self) -> Result<Vec<(TType, Rc<str>)>, NovaError> {
         let mut parameters = Table::new();
         let mut arguments = vec![];
+        let mut positions = vec![];
 
-        while self.current_token().is_some_and(|t| t.is_identifier()) {
+        // keywords are let through so using one as a name gets a clear error
+        while self
+            .current_token()
+            .is_some_and(|t| t.is_identifier() || matches!(t.value, Keyword(_)))
+        {
             let (identifier, pos) = self.get_identifier()?;
             if parameters.has(&identifier) {
                 return Err(self.generate_error_with_pos(
@@ -4107,6 +5586,7 @@ impl Parser {
                 ));
             }
             parameters.insert(identifier.clone());
+            positions.push(pos);
             // if no colon, then its a unit variant
             if !self
                 .current_token()
@@ -4129,32 +5609,49 @@ impl Parser {
             self.advance();
         }
 
+        self.parameter_positions = positions;
         Ok(arguments)
     }
 
-    fn alternative(&mut self) -> Result<Vec<Statement>, NovaError> {
-        let test = self.top_expr()?;
-        let pos = self.get_current_token_position();
-        if test.get_type() != TType::Bool {
-            return Err(self.generate_error_with_pos(
-                "If statement expression must return a bool",
-                format!("got {}", test.get_type()),
-                pos,
-            ));
-        }
-        self.environment.push_block();
-        let statements = self.block()?;
-        self.environment.pop_block();
-        let mut alternative: Option<Vec<Statement>> = None;
+    // parses the elif/else tail of an if, afterwards a variable only counts as
+    // initialized if every branch that falls through assigned it
+    fn else_branch(&mut self, before: Table<Rc<str>>) -> Result<Option<Vec<Statement>>, NovaError> {
+        let body_state = self.environment.initialization_state();
+        self.environment.restore_initialization(before);
+        let mut alternative = None;
         if self.current_token().is_some_and(|t| t.is_id("elif")) {
             self.advance();
             alternative = Some(self.alternative()?);
-        } else if self.current_token().is_some_and(|t| t.is_id("else")) {
+        } else if self
+            .current_token()
+            .is_some_and(|t| t.is_keyword(KeyWord::Else))
+        {
             self.advance();
             self.environment.push_block();
             alternative = Some(self.block()?);
             self.environment.pop_block();
-        };
+        }
+        let alternative_state = self.environment.initialization_state();
+        self.environment
+            .merge_initialization(vec![body_state, alternative_state]);
+        Ok(alternative)
+    }
+
+    fn alternative(&mut self) -> Result<Vec<Statement>, NovaError> {
+        let test = self.top_expr()?;
+        let pos = self.get_current_token_position();
+        if test.get_type() != TType::Bool {
+            return Err(self.generate_error_with_pos(
+                "If statement expression must return a bool",
+                format!("got {}", test.get_type()),
+                pos,
+            ));
+        }
+        let before = self.environment.initialization_state();
+        self.environment.push_block();
+        let statements = self.block()?;
+        self.environment.pop_block();
+        let alternative = self.else_branch(before)?;
         Ok(vec![Statement::If {
             ttype: TType::Void,
             test,
@@ -4163,7 +5660,137 @@ impl Parser {
         }])
     }
 
-    fn import_file(&mut self) -> Result<Option<Statement>, NovaError> {
+    // top level functions, lets and types of an imported file are declared as
+    // Module::name
+    fn qualify_declaration(&mut self, identifier: Rc<str>) -> Rc<str> {
+        let Some(module) = self.module_name.clone() else {
+            return identifier;
+        };
+        if !self.imported || self.environment.values.len() != 1 {
+            return identifier;
+        }
+        self.exports
+            .entry(module.clone())
+            .or_default()
+            .insert(identifier.clone());
+        format!("{}::{}", module, identifier).into()
+    }
+
+    // types are qualified like the other declarations, except the prelude's
+    // which every file sees
+    fn qualify_type(&mut self, identifier: Rc<str>) -> Rc<str> {
+        if self.filepath.as_deref() == Some(Path::new("prelude.nv")) {
+            return identifier;
+        }
+        self.qualify_declaration(identifier)
+    }
+
+    // an unqualified name falls back to Module::name for the current module and
+    // modules imported with { * }, unless a variable shadows it
+    fn resolve_open(&self, identifier: Rc<str>) -> Rc<str> {
+        let shadowed = self.environment.values.iter().any(|scope| {
+            scope.get(&identifier).is_some_and(|s| {
+                !matches!(s.kind, SymbolKind::Function | SymbolKind::GenericFunction)
+            })
+        });
+        if shadowed {
+            return identifier;
+        }
+        for module in self.open_modules.items.iter() {
+            if self
+                .exports
+                .get(module)
+                .is_some_and(|names| names.has(&identifier))
+            {
+                return format!("{}::{}", module, identifier).into();
+            }
+        }
+        identifier
+    }
+
+    // a type of an imported file can be written without its module there and
+    // in the files importing it, unless two of those modules declare the name
+    fn resolve_type(&self, identifier: Rc<str>, pos: FilePosition) -> Result<Rc<str>, NovaError> {
+        let declared = |name: &Rc<str>| {
+            self.environment.custom_types.contains_key(name)
+                || self.environment.type_alias.contains_key(name)
+        };
+        if declared(&identifier) {
+            return Ok(identifier);
+        }
+        let qualified =
+            |module: &Rc<str>| -> Rc<str> { format!("{}::{}", module, identifier).into() };
+        if let Some(own) = self.module_name.as_ref().map(qualified).filter(declared) {
+            return Ok(own);
+        }
+        let found: Vec<Rc<str>> = self
+            .visible_modules
+            .items
+            .iter()
+            .map(qualified)
+            .filter(declared)
+            .collect();
+        match found.as_slice() {
+            [] => Ok(identifier),
+            [only] => Ok(only.clone()),
+            [first, second, ..] => Err(self.generate_error_with_pos(
+                format!(
+                    "Type '{}' is declared in more than one imported module",
+                    identifier
+                ),
+                format!("Write {} or {} to say which one", first, second),
+                pos,
+            )),
+        }
+    }
+
+    // items of an imported file stay private to its module unless declared pub
+    fn record_visibility(&mut self, key: Rc<str>, name: Rc<str>) {
+        if !self.imported || self.public || self.environment.values.len() != 1 {
+            return;
+        }
+        if let Some(module) = self.module_name.clone() {
+            self.private.insert(key, (module, name));
+        }
+    }
+
+    // private items, and methods of private types, only resolve inside their module
+    fn require_accessible(&self, name: &str, pos: FilePosition) -> Result<(), NovaError> {
+        let owner = self.private.get(name).or_else(|| {
+            name.split_once("::")
+                .and_then(|(prefix, _)| self.private.get(prefix))
+        });
+        match owner {
+            Some((owner, item)) if self.module_name.as_ref() != Some(owner) => Err(self
+                .generate_error_with_pos(
+                    format!("'{}' is private to module {}", item, owner),
+                    format!(
+                        "Declare it with pub in {} to use it from other modules",
+                        owner
+                    ),
+                    pos,
+                )),
+            _ => Ok(()),
+        }
+    }
+
+    // modules that come from a file can only be named where they are imported,
+    // directly or through a pub import, builtin modules are visible everywhere
+    fn require_module_visible(&self, module: &Rc<str>, pos: FilePosition) -> Result<(), NovaError> {
+        if !self.module_files.contains_key(module) || self.visible_modules.has(module) {
+            return Ok(());
+        }
+        Err(self.generate_error_with_pos(
+            format!("Module '{}' is not imported in this file", module),
+            format!(
+                "Import {} here, or re-export it with pub import {}",
+                module, module
+            ),
+            pos,
+        ))
+    }
+
+    fn import_file(&mut self, public: bool) -> Result<Option<Statement>, NovaError> {
         self.consume_identifier(Some("import"))?;
         let pos = self.get_current_token_position();
         let import_filepath: PathBuf = match self.current_token_value() {
@@ -4190,20 +5817,29 @@ impl Parser {
             }
             _ => panic!(),
         };
-        let resolved_filepath = match self
-            .filepath
-            .as_ref()
-            .and_then(|p| p.parent())
-            .map(|p| p.to_path_buf())
-        {
-            Some(mut current_dir) => {
-                current_dir.push(import_filepath);
-                current_dir
-            }
-            None => import_filepath,
+        // import name { * } makes the module's names usable without name::
+        let flat = self.current_token().is_some_and(|t| t.is_symbol(LeftBrace))
+            && self
+                .peek_offset(1)
+                .is_some_and(|t| t.is_op(Operator::Multiplication));
+        if flat {
+            self.consume_symbol(LeftBrace)?;
+            self.consume_operator(Operator::Multiplication)?;
+            self.consume_symbol(RightBrace)?;
+        }
+        let resolved_filepath: Rc<Path> = self.resolve_import(import_filepath).into();
+        if let Some(imported) = self.module_cache.get(&canonical_path(&resolved_filepath)) {
+            // its items are already in the environment and its code in the program
+            self.expose_module(imported.clone(), flat, public);
+            return Ok(Some(Statement::Block {
+                body: vec![],
+                filepath: Some(resolved_filepath),
+            }));
+        }
+        let tokens = match self.overlay.get(&canonical_path(&resolved_filepath)) {
+            Some(source) => Ok(Lexer::new(source.clone(), Some(&resolved_filepath))),
+            None => Lexer::read_file(&resolved_filepath),
         };
-        let resolved_filepath: Rc<Path> = resolved_filepath.into();
-        let tokens = Lexer::read_file(&resolved_filepath);
         let tokens = match tokens {
             Ok(tokens) => tokens,
             Err(_) => {
@@ -4219,19 +5855,140 @@ impl Parser {
         parser.index = 0;
         parser.filepath = Some(resolved_filepath.clone());
         parser.input = tokens;
+        parser.visible_modules = Table::new();
+        parser.module_name = None;
+        parser.open_modules = Table::new();
+        parser.imported = true;
+        parser.auto_lets = vec![];
         parser.parse()?;
-        self.environment = parser.environment.clone();
-        self.modules = parser.modules.clone();
+        self.adopt(&parser);
+        if let Some(imported) = parser.module_name {
+            self.expose_module(imported, flat, public);
+        }
         Ok(Some(Statement::Block {
             body: parser.ast.program.clone(),
             filepath: Some(resolved_filepath),
         }))
     }
 
+    // takes over what a file parsed by a child parser added to the program
+    fn adopt(&mut self, parser: &Parser) {
+        self.environment = parser.environment.clone();
+        self.modules = parser.modules.clone();
+        self.module_files = parser.module_files.clone();
+        self.module_cache = parser.module_cache.clone();
+        self.reexports = parser.reexports.clone();
+        self.exports = parser.exports.clone();
+        self.constants = parser.constants.clone();
+        self.private = parser.private.clone();
+        self.warnings = parser.warnings.clone();
+        self.references = parser.references.clone();
+        self.prelude = parser.prelude.clone();
+    }
+
+    // the name at position refers to the symbol visible under key, Enum::Variant
+    // keys refer to the variant
+    fn reference(&mut self, key: &str, position: FilePosition) {
+        if let Some((owner, member)) = key.split_once("::") {
+            if self.environment.enums.has(&Rc::<str>::from(owner)) {
+                self.reference_member(owner, member, position);
+                return;
+            }
+        }
+        if let Some(declared) = self.environment.declaration(key) {
+            self.references.push(Reference {
+                position,
+                symbol: SymbolRef::Declaration(declared),
+            });
+        }
+    }
+
+    // the name at position is a field or variant of the type
+    fn reference_member(&mut self, owner: &str, member: &str, position: FilePosition) {
+        self.references.push(Reference {
+            position,
+            symbol: SymbolRef::Member(owner.into(), member.into()),
+        });
+    }
+
+    // parsed in front of the program like an import of std/prelude.nv
+    fn load_prelude(&mut self) -> Result<Statement, NovaError> {
+        let filepath: Rc<Path> = Path::new("prelude.nv").into();
+        let mut parser = self.clone();
+        parser.index = 0;
+        parser.filepath = Some(filepath.clone());
+        parser.input = Lexer::new(PRELUDE, Some(&filepath)).tokenize()?;
+        parser.visible_modules = Table::new();
+        parser.module_name = None;
+        parser.open_modules = Table::new();
+        parser.imported = true;
+        parser.parse()?;
+        for statement in parser.ast.program.iter() {
+            if let Statement::Function { identifier, .. } = statement {
+                parser.prelude.insert(identifier.clone());
+            }
+        }
+        self.adopt(&parser);
+        if let Some(prelude) = parser.module_name {
+            self.expose_module(prelude, true, false);
+        }
+        Ok(Statement::Block {
+            body: parser.ast.program,
+            filepath: Some(filepath),
+        })
+    }
+
+    // library directories win over the importing file's directory, paths that
+    // climb out with super are always relative to the file
+    fn resolve_import(&self, import_filepath: PathBuf) -> PathBuf {
+        let climbs = import_filepath
+            .components()
+            .any(|component| component == Component::ParentDir);
+        if !climbs && import_filepath.is_relative() {
+            for directory in self.search_paths.iter() {
+                let candidate = directory.join(&import_filepath);
+                if candidate.is_file() {
+                    return candidate;
+                }
+            }
+        }
+        match self.filepath.as_ref().and_then(|p| p.parent()) {
+            Some(current_dir) => current_dir.join(import_filepath),
+            None => import_filepath,
+        }
+    }
+
+    // lets the importing file name the module and whatever it re-exports
+    fn expose_module(&mut self, imported: Rc<str>, flat: bool, public: bool) {
+        if flat {
+            self.open_modules.insert(imported.clone());
+        }
+        let mut exported = vec![imported.clone()];
+        exported.extend(self.reexports.get(&imported).cloned().unwrap_or_default());
+        for module in exported.iter() {
+            self.visible_modules.insert(module.clone());
+        }
+        if let (true, Some(current)) = (public, self.module_name.clone()) {
+            let reexports = self.reexports.entry(current).or_default();
+            for module in exported {
+                if !reexports.contains(&module) {
+                    reexports.push(module);
+                }
+            }
+        }
+    }
+
     fn match_statement(&mut self) -> Result<Option<Statement>, NovaError> {
         self.consume_identifier(Some("match"))?;
         let expr = self.expr()?;
 
+        if matches!(expr.get_type(), TType::Int | TType::Char) {
+            return self.value_match(expr);
+        }
+        if let TType::Union { members } = expr.get_type() {
+            return self.union_match(expr, members);
+        }
+
         if expr.get_type().custom_to_string().is_some() {
         } else {
             return Err(self.generate_error_with_pos(
@@ -4245,11 +6002,30 @@ impl Parser {
         let mut branches = vec![];
         self.consume_symbol(LeftBrace)?;
         let mut default_branch = None;
+        let before = self.environment.initialization_state();
+        let mut arm_states = vec![];
         while !self
             .current_token()
             .is_some_and(|t| t.is_symbol(RightBrace))
         {
-            let (variant, pos) = self.get_identifier()?;
+            self.environment.restore_initialization(before.clone());
+            let (mut variant, mut pos) = self.get_identifier()?;
+            // arms may name the enum, Shape::Rect
+            if self
+                .current_token()
+                .is_some_and(|t| t.is_op(Operator::DoubleColon))
+            {
+                let enum_name = expr.get_type().custom_to_string().unwrap().to_string();
+                if *variant != *enum_name {
+                    return Err(self.generate_error_with_pos(
+                        format!("Expected a variant of {}, got {}", enum_name, variant),
+                        format!("the matched value has type {}", expr.get_type()),
+                        pos,
+                    ));
+                }
+                self.advance();
+                (variant, pos) = self.get_identifier()?;
+            }
             if &*variant == "_" {
                 // check to see if default branch is already defined
                 if default_branch.is_some() {
@@ -4269,17 +6045,30 @@ impl Parser {
                         expr: body,
                     }])
                 };
+                arm_states.push(self.environment.initialization_state());
                 continue;
             }
             // collect identifiers
             let mut enum_id = None;
+            let mut binding = None;
+            let mut pattern = None;
             if self.current_token().is_some_and(|t| t.is_symbol(LeftParen)) {
                 self.consume_symbol(LeftParen)?;
                 if !self
                     .current_token()
                     .is_some_and(|t| t.is_symbol(RightParen))
                 {
-                    enum_id = Some(self.get_identifier()?.0);
+                    match self.pattern()? {
+                        Pattern::Bind(id, pos) => {
+                            enum_id = Some(id);
+                            binding = Some(pos);
+                        }
+                        // the payload is bound to a hidden variable and destructured from there
+                        nested => {
+                            enum_id = Some(format!("__payload__{}_{}", pos.line, pos.col).into());
+                            pattern = Some(nested);
+                        }
+                    }
                 }
                 self.consume_symbol(RightParen)?;
             }
@@ -4337,45 +6126,46 @@ impl Parser {
                 if !found {
                     return Err(self.generate_error_with_pos(
                         format!("variant '{}' not found in type", variant),
-                        "make sure the variant is in the type",
+                        self.suggest_field(&variant, &new_fields),
                         pos,
                     ));
                 }
+                let enum_name = expr.get_type().custom_to_string().unwrap().to_owned();
+                self.reference_member(&enum_name, &variant, pos.clone());
 
                 self.environment.push_block();
                 self.environment.insert_symbol(
                     enum_id.as_deref().unwrap_or_default(),
-                    vtype,
-                    None,
+                    vtype.clone(),
+                    binding,
                     SymbolKind::Variable,
                 );
+                let mut bindings = vec![];
+                if let (Some(pattern), Some(payload)) = (pattern, &enum_id) {
+                    let source = self.create_literal_expr(payload.clone(), vtype);
+                    self.bind_pattern(pattern, source, &mut bindings)?;
+                }
                 // get expression if no { }
-
-                //let enum_id = enum_id.unwrap_or_default();
                 if self.current_token().is_some_and(|t| t.is_symbol(LeftBrace)) {
-                    let body = self.block()?;
-                    branches.push((tag, enum_id.clone(), body.clone()));
-                    body.clone()
+                    bindings.extend(self.block()?);
                 } else {
                     let body = self.expr()?;
-                    branches.push((
-                        tag,
-                        enum_id.clone(),
-                        vec![Statement::Expression {
-                            ttype: body.clone().get_type(),
-                            expr: body.clone(),
-                        }],
-                    ));
-                    vec![Statement::Expression {
-                        ttype: body.clone().get_type(),
+                    bindings.push(Statement::Expression {
+                        ttype: body.get_type(),
                         expr: body,
-                    }]
+                    });
                 };
+                branches.push((tag, enum_id.clone(), bindings));
 
                 self.environment.pop_block();
+                arm_states.push(self.environment.initialization_state());
             }
         }
         self.consume_symbol(RightBrace)?;
+        if arm_states.is_empty() {
+            arm_states.push(before);
+        }
+        self.environment.merge_initialization(arm_states);
 
         if default_branch.is_none() {
             // check to see if all variants are covered
@@ -4411,7 +6201,7 @@ impl Parser {
                     fields.clone()
                 };
                 for (i, field) in new_fields.iter().enumerate() {
-                    if field.0.deref() != "type" && !covered.contains(&i) {
+                    if !covered.contains(&i) {
                         return Err(self.generate_error_with_pos(
                             format!("variant '{}' is not covered", field.0),
                             "make sure all variants are covered",
@@ -4431,81 +6221,1208 @@ impl Parser {
         }))
     }
 
-    // new statement for making type aliases
-    // alias identifer = <type>
-    fn type_alias(&mut self) -> Result<Option<Statement>, NovaError> {
-        self.consume_identifier(Some("type"))?;
-        let (alias, _) = self.get_identifier()?;
-        if self.environment.custom_types.contains_key(&alias) {
-            return Err(self.generate_error_with_pos(
-                format!("type '{}' already defined", alias),
-                "try using another name",
-                self.get_current_token_position(),
-            ));
-        }
-        self.consume_operator(Operator::Assignment)?;
-        let ttype = self.ttype()?;
-        self.environment.type_alias.insert(alias, ttype.clone());
-        Ok(None)
-    }
-
-    fn statement(&mut self) -> Result<Option<Statement>, NovaError> {
-        match self.current_token_value() {
-            Some(Identifier(id)) => match id.as_ref() {
-                "match" => self.match_statement(),
-                "type" => self.type_alias(),
-                "import" => self.import_file(),
-                "pass" => self.pass_statement(),
-                "struct" => self.struct_declaration(),
-                "if" => self.if_statement(),
-                "while" => self.while_statement(),
-                "return" => self.return_statement(),
-                "fn" => self.function_declaration(),
-                "enum" => self.enum_declaration(),
-                "for" => self.for_statement(),
-                "break" => {
-                    self.consume_identifier(Some("break"))?;
-                    Ok(Some(Statement::Break))
+    // match over an Int or Char, arms are values or ranges and the whole match
+    // becomes an if chain over a hidden copy of the matched value
+    fn value_match(&mut self, expr: Expr) -> Result<Option<Statement>, NovaError> {
+        let ttype = expr.get_type();
+        let pos = self.get_current_token_position();
+        let scrutinee = self.create_literal_expr(
+            format!("__match__{}_{}", pos.line, pos.col).into(),
+            ttype.clone(),
+        );
+        self.consume_symbol(LeftBrace)?;
+        let before = self.environment.initialization_state();
+        let mut arm_states = vec![];
+        let mut arms = vec![];
+        let mut covered: Vec<(i64, i64, FilePosition)> = vec![];
+        let mut default_branch = None;
+        while !self
+            .current_token()
+            .is_some_and(|t| t.is_symbol(RightBrace))
+        {
+            self.environment.restore_initialization(before.clone());
+            let arm_pos = self.get_current_token_position();
+            if self.current_token().is_some_and(|t| t.is_id("_")) {
+                self.advance();
+                if default_branch.is_some() {
+                    return Err(self.generate_error_with_pos(
+                        "default branch already defined",
+                        "make sure only one default branch is defined",
+                        arm_pos,
+                    ));
                 }
-                "continue" => {
-                    self.consume_identifier(Some("continue"))?;
-                    Ok(Some(Statement::Continue))
+                self.consume_operator(Operator::FatArrow)?;
+                default_branch = Some(self.arm_body()?);
+                arm_states.push(self.environment.initialization_state());
+                continue;
+            }
+            let (start, start_value) = self.pattern_value(&ttype)?;
+            let (test, end_value) = match self.current_token_value() {
+                Some(Operator(op @ (Operator::InclusiveRange | Operator::ExclusiveRange))) => {
+                    let inclusive = *op == Operator::InclusiveRange;
+                    self.advance();
+                    let (end, end_value) = self.pattern_value(&ttype)?;
+                    let upper = if inclusive {
+                        Operator::LessOrEqual
+                    } else {
+                        Operator::Less
+                    };
+                    let test = Expr::Binop {
+                        ttype: TType::Bool,
+                        op: Operator::And,
+                        lhs: Box::new(Expr::Binop {
+                            ttype: TType::Bool,
+                            op: Operator::GreaterOrEqual,
+                            lhs: Box::new(scrutinee.clone()),
+                            rhs: Box::new(start),
+                        }),
+                        rhs: Box::new(Expr::Binop {
+                            ttype: TType::Bool,
+                            op: upper,
+                            lhs: Box::new(scrutinee.clone()),
+                            rhs: Box::new(end),
+                        }),
+                    };
+                    (test, if inclusive { end_value } else { end_value - 1 })
                 }
-                _ => self.expression_statement(),
-            },
-            None => Ok(None),
-            _ => self.expression_statement(),
+                _ => {
+                    let test = Expr::Binop {
+                        ttype: TType::Bool,
+                        op: Operator::Equal,
+                        lhs: Box::new(scrutinee.clone()),
+                        rhs: Box::new(start),
+                    };
+                    (test, start_value)
+                }
+            };
+            if start_value > end_value {
+                return Err(self.generate_error_with_pos(
+                    "Range pattern is empty",
+                    "the start of the range must not be past its end",
+                    arm_pos,
+                ));
+            }
+            if let Some((_, _, other)) = covered
+                .iter()
+                .find(|(low, high, _)| start_value <= *high && *low <= end_value)
+            {
+                return Err(self.generate_error_with_pos(
+                    "Pattern overlaps an earlier arm",
+                    format!(
+                        "the arm on line {} already matches some of these values",
+                        other.line
+                    ),
+                    arm_pos,
+                ));
+            }
+            covered.push((start_value, end_value, arm_pos));
+            self.consume_operator(Operator::FatArrow)?;
+            arms.push((test, self.arm_body()?));
+            arm_states.push(self.environment.initialization_state());
         }
-    }
+        self.consume_symbol(RightBrace)?;
+        if default_branch.is_none() {
+            arm_states.push(before);
+        }
+        self.environment.merge_initialization(arm_states);
 
-    fn pass_statement(&mut self) -> Result<Option<Statement>, NovaError> {
-        self.consume_identifier(Some("pass"))?;
-        Ok(Some(Statement::Pass))
+        let mut chain = default_branch;
+        for (test, body) in arms.into_iter().rev() {
+            chain = Some(vec![Statement::If {
+                ttype: TType::Void,
+                test,
+                body,
+                alternative: chain,
+            }]);
+        }
+        let Expr::Literal {
+            value: Atom::Id { name: identifier },
+            ..
+        } = scrutinee
+        else {
+            unreachable!()
+        };
+        let mut body = vec![Statement::Expression {
+            ttype: TType::Void,
+            expr: Expr::Let {
+                ttype: TType::Void,
+                identifier,
+                expr: Box::new(expr),
+                global: false,
+            },
+        }];
+        body.extend(chain.unwrap_or_default());
+        Ok(Some(Statement::Block {
+            body,
+            filepath: self.filepath.clone(),
+        }))
     }
 
-    fn get_id_list(&mut self) -> Result<Vec<Rc<str>>, NovaError> {
-        let mut idlist = vec![];
-        self.consume_symbol(LeftParen)?;
-        if !self
+    // match over a union, each arm names a member, n: Int => ..., and binds the
+    // value narrowed to it. like value_match this becomes an if chain, over
+    // the runtime type name of a hidden copy of the matched value
+    fn union_match(
+        &mut self,
+        expr: Expr,
+        members: Vec<TType>,
+    ) -> Result<Option<Statement>, NovaError> {
+        let union = expr.get_type();
+        let pos = self.get_current_token_position();
+        let hidden: Rc<str> = format!("__match__{}_{}", pos.line, pos.col).into();
+        let kind_name: Rc<str> = format!("__kind__{}_{}", pos.line, pos.col).into();
+        let scrutinee = self.create_literal_expr(hidden.clone(), union.clone());
+        let kind = self.create_literal_expr(kind_name.clone(), TType::String);
+        self.consume_symbol(LeftBrace)?;
+        let before = self.environment.initialization_state();
+        let mut arm_states = vec![];
+        let mut arms = vec![];
+        let mut covered: Vec<(TType, FilePosition)> = vec![];
+        let mut default_branch = None;
+        while !self
             .current_token()
-            .is_some_and(|t| t.is_symbol(RightParen))
+            .is_some_and(|t| t.is_symbol(RightBrace))
         {
-            idlist.push(self.get_identifier()?.0);
-        }
-        while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
-            self.advance();
-            if self
-                .current_token()
-                .is_some_and(|t| t.is_symbol(RightParen))
+            self.environment.restore_initialization(before.clone());
+            let (name, arm_pos) = self.get_identifier()?;
+            if &*name == "_"
+                && self
+                    .current_token()
+                    .is_some_and(|t| t.is_op(Operator::FatArrow))
             {
-                break;
-            }
-            idlist.push(self.get_identifier()?.0);
-        }
-        self.consume_symbol(RightParen)?;
-        Ok(idlist)
-    }
-
+                if default_branch.is_some() {
+                    return Err(self.generate_error_with_pos(
+                        "default branch already defined",
+                        "make sure only one default branch is defined",
+                        arm_pos,
+                    ));
+                }
+                self.consume_operator(Operator::FatArrow)?;
+                default_branch = Some(self.arm_body()?);
+                arm_states.push(self.environment.initialization_state());
+                continue;
+            }
+            self.consume_operator(Operator::Colon)?;
+            let member = self.ttype()?;
+            if !members.contains(&member) {
+                return Err(self.generate_error_with_pos(
+                    format!("{} is not a member of {}", member, union),
+                    format!("Arms of this match name one of {}", union),
+                    arm_pos,
+                ));
+            }
+            if let Some((_, other)) = covered.iter().find(|(t, _)| *t == member) {
+                return Err(self.generate_error_with_pos(
+                    format!("{} is already matched", member),
+                    format!("the arm on line {} matches it first", other.line),
+                    arm_pos,
+                ));
+            }
+            covered.push((member.clone(), arm_pos.clone()));
+            self.consume_operator(Operator::FatArrow)?;
+
+            let test = match member.union_tag(&members) {
+                Some(tag) => self.kind_test(&kind, Operator::Equal, tag),
+                // the one list-like member is the value when no other member is
+                None => members
+                    .iter()
+                    .filter_map(|other| other.union_tag(&members))
+                    .map(|tag| self.kind_test(&kind, Operator::NotEqual, tag))
+                    .reduce(|lhs, rhs| Expr::Binop {
+                        ttype: TType::Bool,
+                        op: Operator::And,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                    })
+                    .unwrap_or(Expr::Literal {
+                        ttype: TType::Bool,
+                        value: Atom::Bool { value: true },
+                    }),
+            };
+            self.environment.push_block();
+            let mut body = vec![];
+            if &*name != "_" {
+                self.environment.insert_symbol(
+                    &name,
+                    member.clone(),
+                    Some(arm_pos.clone()),
+                    SymbolKind::Variable,
+                );
+                // unused bindings are reported on the arm
+                body.push(Statement::Line { position: arm_pos });
+                body.push(Statement::Expression {
+                    ttype: TType::Void,
+                    expr: Expr::Let {
+                        ttype: TType::Void,
+                        identifier: name,
+                        expr: Box::new(self.create_literal_expr(hidden.clone(), member)),
+                        global: false,
+                    },
+                });
+            }
+            body.extend(self.arm_body()?);
+            self.environment.pop_block();
+            arms.push((test, body));
+            arm_states.push(self.environment.initialization_state());
+        }
+        self.consume_symbol(RightBrace)?;
+        if default_branch.is_none() {
+            if let Some(missing) = members
+                .iter()
+                .find(|member| !covered.iter().any(|(t, _)| t == *member))
+            {
+                return Err(self.generate_error_with_pos(
+                    format!("{} is not covered", missing),
+                    format!("add an arm for every member of {} or a _ arm", union),
+                    pos,
+                ));
+            }
+            arm_states.push(before);
+        }
+        self.environment.merge_initialization(arm_states);
+
+        let mut chain = default_branch;
+        for (test, body) in arms.into_iter().rev() {
+            chain = Some(vec![Statement::If {
+                ttype: TType::Void,
+                test,
+                body,
+                alternative: chain,
+            }]);
+        }
+        let kind_of = Expr::Literal {
+            ttype: TType::String,
+            value: Atom::Call {
+                name: "__typename".into(),
+                arguments: vec![scrutinee.clone()],
+                position: pos.clone(),
+            },
+        };
+        let mut body = vec![];
+        for (identifier, value) in [(hidden, expr), (kind_name, kind_of)] {
+            body.push(Statement::Expression {
+                ttype: TType::Void,
+                expr: Expr::Let {
+                    ttype: TType::Void,
+                    identifier,
+                    expr: Box::new(value),
+                    global: false,
+                },
+            });
+        }
+        body.extend(chain.unwrap_or_default());
+        Ok(Some(Statement::Block {
+            body,
+            filepath: self.filepath.clone(),
+        }))
+    }
+
+    // kind == "Int" or kind != "Int" for the runtime type name of a union value
+    fn kind_test(&self, kind: &Expr, op: Operator, tag: &str) -> Expr {
+        Expr::Binop {
+            ttype: TType::Bool,
+            op,
+            lhs: Box::new(kind.clone()),
+            rhs: Box::new(Expr::Literal {
+                ttype: TType::String,
+                value: Atom::String { value: tag.into() },
+            }),
+        }
+    }
+
+    // a literal Int or Char in a pattern, with its value for overlap checks
+    // -value in a pattern, an error for the one Int whose negation does not fit
+    fn negate_pattern(
+        &self,
+        value: i64,
+        negative: bool,
+        pos: FilePosition,
+    ) -> Result<i64, NovaError> {
+        if !negative {
+            return Ok(value);
+        }
+        value.checked_neg().ok_or_else(|| {
+            self.generate_error_with_pos(
+                format!("Negating {} overflows Int", value),
+                format!("an Int is from {} to {}", i64::MIN, i64::MAX),
+                pos,
+            )
+        })
+    }
+
+    fn pattern_value(&mut self, ttype: &TType) -> Result<(Expr, i64), NovaError> {
+        let pos = self.get_current_token_position();
+        let negative = self
+            .current_token()
+            .is_some_and(|t| t.is_op(Operator::Subtraction));
+        if negative {
+            self.advance();
+        }
+        match (self.current_token_value(), ttype) {
+            (Some(&Integer(value)), TType::Int) => {
+                self.advance();
+                let value = self.negate_pattern(value, negative, pos)?;
+                Ok((
+                    Expr::Literal {
+                        ttype: TType::Int,
+                        value: Atom::Integer { value },
+                    },
+                    value,
+                ))
+            }
+            (Some(&Char(value)), TType::Char) if !negative => {
+                self.advance();
+                Ok((
+                    Expr::Literal {
+                        ttype: TType::Char,
+                        value: Atom::Char { value },
+                    },
+                    value as i64,
+                ))
+            }
+            (Some(Identifier(_)), _) => {
+                let (mut name, _) = self.get_identifier()?;
+                if self.modules.has(&name)
+                    && self
+                        .current_token()
+                        .is_some_and(|t| t.is_op(Operator::DoubleColon))
+                {
+                    self.require_module_visible(&name, pos.clone())?;
+                    self.advance();
+                    let (field, _) = self.get_identifier()?;
+                    name = format!("{}::{}", name, field).into();
+                } else {
+                    name = self.resolve_open(name);
+                }
+                let value = match self.constants.get(&name) {
+                    Some(Expr::Literal {
+                        value: Atom::Integer { value },
+                        ..
+                    }) if *ttype == TType::Int => self.negate_pattern(*value, negative, pos)?,
+                    Some(Expr::Literal {
+                        value: Atom::Char { value },
+                        ..
+                    }) if *ttype == TType::Char && !negative => *value as i64,
+                    _ => {
+                        return Err(self.generate_error_with_pos(
+                            format!("Expected a {} constant in pattern, got {}", ttype, name),
+                            "patterns can name constants declared with const",
+                            pos,
+                        ))
+                    }
+                };
+                let literal = match ttype {
+                    TType::Char => Atom::Char {
+                        value: char::from_u32(value as u32).unwrap_or_default(),
+                    },
+                    _ => Atom::Integer { value },
+                };
+                Ok((
+                    Expr::Literal {
+                        ttype: ttype.clone(),
+                        value: literal,
+                    },
+                    value,
+                ))
+            }
+            _ => Err(self.generate_error_with_pos(
+                format!("Expected a {} literal in pattern", ttype),
+                "patterns over Int and Char are literals or ranges like 1..=9",
+                pos,
+            )),
+        }
+    }
+
+    // body of a match arm, a block or a single expression
+    fn arm_body(&mut self) -> Result<Vec<Statement>, NovaError> {
+        self.environment.push_block();
+        let body = if self.current_token().is_some_and(|t| t.is_symbol(LeftBrace)) {
+            self.block()?
+        } else {
+            let body = self.expr()?;
+            vec![Statement::Expression {
+                ttype: body.get_type(),
+                expr: body,
+            }]
+        };
+        self.environment.pop_block();
+        Ok(body)
+    }
+
+    // name, tuple (a, b) or struct {x, y: pattern}, nesting freely
+    fn pattern(&mut self) -> Result<Pattern, NovaError> {
+        let pos = self.get_current_token_position();
+        if self.current_token().is_some_and(|t| t.is_symbol(LeftParen)) {
+            self.consume_symbol(LeftParen)?;
+            let mut elements = vec![self.pattern()?];
+            while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+                self.advance();
+                elements.push(self.pattern()?);
+            }
+            self.consume_symbol(RightParen)?;
+            Ok(Pattern::Tuple(elements, pos))
+        } else if self.current_token().is_some_and(|t| t.is_symbol(LeftBrace)) {
+            self.consume_symbol(LeftBrace)?;
+            let mut fields = vec![];
+            loop {
+                let (field, field_pos) = self.get_identifier()?;
+                if self
+                    .current_token()
+                    .is_some_and(|t| t.is_op(Operator::Colon))
+                {
+                    self.advance();
+                    fields.push((field, self.pattern()?));
+                } else {
+                    fields.push((field.clone(), Pattern::Bind(field, field_pos)));
+                }
+                if !self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+                    break;
+                }
+                self.advance();
+            }
+            self.consume_symbol(RightBrace)?;
+            Ok(Pattern::Struct(fields, pos))
+        } else {
+            let (identifier, pos) = self.get_identifier()?;
+            Ok(Pattern::Bind(identifier, pos))
+        }
+    }
+
+    // declares every name in the pattern and emits a let for each, reading it out of source
+    fn bind_pattern(
+        &mut self,
+        pattern: Pattern,
+        source: Expr,
+        bindings: &mut Vec<Statement>,
+    ) -> Result<(), NovaError> {
+        match pattern {
+            Pattern::Bind(identifier, pos) => {
+                if identifier.deref() == "_" {
+                    return Ok(());
+                }
+                let bound_twice = bindings.iter().any(|binding| {
+                    matches!(binding, Statement::Expression {
+                        expr: Expr::Let { identifier: id, .. },
+                        ..
+                    } if *id == identifier)
+                });
+                if bound_twice {
+                    return Err(self.generate_error_with_pos(
+                        format!(
+                            "'{}' is bound more than once in the same pattern",
+                            identifier
+                        ),
+                        "Use a different name for each binding",
+                        pos,
+                    ));
+                }
+                self.environment.insert_symbol(
+                    &identifier,
+                    source.get_type(),
+                    Some(pos),
+                    SymbolKind::Variable,
+                );
+                bindings.push(Statement::Expression {
+                    ttype: TType::Void,
+                    expr: Expr::Let {
+                        ttype: TType::Void,
+                        identifier,
+                        expr: Box::new(source),
+                        global: false,
+                    },
+                });
+            }
+            Pattern::Tuple(elements, pos) => {
+                let TType::Tuple {
+                    elements: element_types,
+                } = source.get_type()
+                else {
+                    return Err(self.generate_error_with_pos(
+                        format!("Cannot destructure {} as a tuple", source.get_type()),
+                        "Tuple patterns only match tuple values",
+                        pos,
+                    ));
+                };
+                if element_types.len() != elements.len() {
+                    return Err(self.generate_error_with_pos(
+                        format!(
+                            "Tuple pattern has {} elements but the value has {}",
+                            elements.len(),
+                            element_types.len()
+                        ),
+                        format!("the value has type {}", source.get_type()),
+                        pos,
+                    ));
+                }
+                for (index, (element, ttype)) in elements.into_iter().zip(element_types).enumerate()
+                {
+                    let element_source = Expr::Indexed {
+                        ttype,
+                        name: "tuple".into(),
+                        container: Box::new(source.clone()),
+                        index: Box::new(Expr::Literal {
+                            ttype: TType::Int,
+                            value: Atom::Integer {
+                                value: index as i64,
+                            },
+                        }),
+                        position: pos.clone(),
+                    };
+                    self.bind_pattern(element, element_source, bindings)?;
+                }
+            }
+            Pattern::Struct(fields, pos) => {
+                let is_struct = source
+                    .get_type()
+                    .custom_to_string()
+                    .is_some_and(|name| !self.environment.enums.has(&Rc::<str>::from(name)));
+                if !is_struct {
+                    return Err(self.generate_error_with_pos(
+                        format!("Cannot destructure {} as a struct", source.get_type()),
+                        "Struct patterns only match struct values",
+                        pos,
+                    ));
+                }
+                for (field, pattern) in fields {
+                    let field_source = self.field(field, source.clone(), pos.clone())?;
+                    self.bind_pattern(pattern, field_source, bindings)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // new statement for making type aliases
+    // alias identifer = <type>
+    fn type_alias(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("type"))?;
+        let (alias, _) = self.get_identifier()?;
+        let alias = self.qualify_type(alias);
+        if self.environment.custom_types.contains_key(&alias) {
+            return Err(self.generate_error_with_pos(
+                format!("type '{}' already defined", alias),
+                "try using another name",
+                self.get_current_token_position(),
+            ));
+        }
+        self.consume_operator(Operator::Assignment)?;
+        let ttype = match self.ttype() {
+            // the alias is not known yet while its own type is read
+            Err(_) if self.index > 0 && self.input[self.index - 1].is_id(&alias) => {
+                return Err(self.generate_error_with_pos(
+                    format!("Type alias '{}' cannot refer to itself", alias),
+                    format!(
+                        "An alias is replaced by its type, so it never ends. Declare a struct instead, like struct {} {{ items: [{}] }}",
+                        alias, alias
+                    ),
+                    self.input[self.index - 1].position(),
+                ));
+            }
+            ttype => ttype?,
+        };
+        self.environment.type_alias.insert(alias, ttype.clone());
+        Ok(None)
+    }
+
+    // const NAME = expr, folded while parsing and inlined wherever NAME is read
+    fn const_declaration(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("const"))?;
+        let (name, pos) = self.get_identifier()?;
+        if self.environment.values.len() != 1 {
+            return Err(self.generate_error_with_pos(
+                format!("Constant '{}' must be declared at the top level", name),
+                "Move the const out of the function or block, or use let",
+                pos,
+            ));
+        }
+        let annotation = if self
+            .current_token()
+            .is_some_and(|t| t.is_op(Operator::Colon))
+        {
+            self.consume_operator(Operator::Colon)?;
+            Some(self.ttype()?)
+        } else {
+            None
+        };
+        self.consume_operator(Operator::Assignment)?;
+        let mut expr = self.expr()?;
+        if let Some(ttype) = &annotation {
+            expr = self.sized_literal(expr, ttype, pos.clone())?;
+        }
+        match expr.const_error() {
+            Some(ConstError::Overflow) => {
+                return Err(self.generate_error_with_pos(
+                    format!("Constant '{}' overflows Int", name),
+                    format!("an Int is from {} to {}", i64::MIN, i64::MAX),
+                    pos,
+                ))
+            }
+            Some(ConstError::DivisionByZero) => {
+                return Err(self.generate_error_with_pos(
+                    format!("Constant '{}' divides by zero", name),
+                    "Integer division by zero",
+                    pos,
+                ))
+            }
+            None => {}
+        }
+        let Some(value) = expr.const_value() else {
+            return Err(self.generate_error_with_pos(
+                format!("Constant '{}' is not known at compile time", name),
+                "Constants can only use literals, other constants and operators on them",
+                pos,
+            ));
+        };
+        if let Some(mut ttype) = annotation {
+            if ttype.has_hole() {
+                ttype = self.fill_type_holes(&ttype, &value.get_type(), pos.clone())?;
+            }
+            if ttype != value.get_type() {
+                return Err(self.generate_error_with_pos(
+                    format!(
+                        "Cannot assign {} to {}",
+                        self.describe_type(&value.get_type()),
+                        self.describe_type(&ttype)
+                    ),
+                    "Make sure the expression returns the givin type",
+                    pos,
+                ));
+            }
+        }
+        let identifier = self.qualify_declaration(name);
+        self.record_visibility(identifier.clone(), identifier.clone());
+        if self.environment.has(&identifier) {
+            return Err(self.generate_error_with_pos(
+                format!("Symbol '{}' is already instantiated", identifier),
+                "Cannot reinstantiate the same symbol in the same scope",
+                pos,
+            ));
+        }
+        self.environment.insert_symbol(
+            &identifier,
+            value.get_type(),
+            Some(pos),
+            SymbolKind::Constant,
+        );
+        self.constants.insert(identifier, value);
+        Ok(None)
+    }
+
+    // lazy let x = expr, expr runs the first time x is read instead of where it
+    // is declared, later reads get the same value
+    fn lazy_declaration(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("lazy"))?;
+        if !self
+            .current_token()
+            .is_some_and(|t| t.is_keyword(KeyWord::Let))
+        {
+            return Err(
+                self.generate_error("Expected let after lazy", "Write lazy let name = value")
+            );
+        }
+        self.consume_keyword(KeyWord::Let)?;
+        if self.current_token().is_some_and(|t| t.is_id("mut")) {
+            return Err(self.generate_error(
+                "Lazy values cannot be mutable",
+                "A lazy value is computed once, use let mut for a value that changes",
+            ));
+        }
+        let (name, pos) = self.get_identifier()?;
+        if self.environment.values.len() != 1 {
+            return Err(self.generate_error_with_pos(
+                format!("Lazy value '{}' must be declared at the top level", name),
+                "Move the lazy let out of the function or block, or use let",
+                pos,
+            ));
+        }
+        let annotation = if self
+            .current_token()
+            .is_some_and(|t| t.is_op(Operator::Colon))
+        {
+            self.consume_operator(Operator::Colon)?;
+            Some(self.ttype()?)
+        } else {
+            None
+        };
+        self.consume_operator(Operator::Assignment)?;
+
+        // the initializer runs in a frame of its own, like a function body
+        self.environment.push_scope();
+        let expr = self.expr()?;
+        let captures = self.end_captures(&[], &[&expr], &[]);
+
+        if expr.get_type() == TType::Void {
+            return Err(self.generate_error_with_pos(
+                format!("Lazy value '{}' cannot be assinged to void", name),
+                "Make sure the expression returns a value",
+                pos,
+            ));
+        }
+        let ttype = match annotation {
+            Some(mut ttype) => {
+                if ttype.has_hole() {
+                    ttype = self.fill_type_holes(&ttype, &expr.get_type(), pos.clone())?;
+                }
+                let mut type_map = HashMap::default();
+                if self
+                    .check_and_map_types(
+                        std::slice::from_ref(&ttype),
+                        &[expr.get_type()],
+                        &mut type_map,
+                        pos.clone(),
+                    )
+                    .is_err()
+                {
+                    return Err(self.generate_error_with_pos(
+                        format!(
+                            "Cannot assign {} to {}",
+                            self.describe_type(&expr.get_type()),
+                            self.describe_type(&ttype)
+                        ),
+                        "Make sure the expression returns the givin type",
+                        pos,
+                    ));
+                }
+                ttype
+            }
+            None => expr.get_type(),
+        };
+
+        let identifier = self.qualify_declaration(name);
+        self.record_visibility(identifier.clone(), identifier.clone());
+        if self.environment.has(&identifier) {
+            return Err(self.generate_error_with_pos(
+                format!("Symbol '{}' is already instantiated", identifier),
+                "Cannot reinstantiate the same symbol in the same scope",
+                pos,
+            ));
+        }
+        self.environment.insert_symbol(
+            &identifier,
+            ttype.clone(),
+            Some(pos.clone()),
+            SymbolKind::Lazy,
+        );
+        Ok(Some(Statement::Lazy {
+            ttype,
+            identifier: Self::lazy_function(&identifier),
+            expr,
+            captures,
+            position: pos,
+        }))
+    }
+
+    // the hidden function that computes and caches a lazy value
+    fn lazy_function(identifier: &str) -> Rc<str> {
+        format!("__lazy__{}", identifier).into()
+    }
+
+    // reading a lazy value calls its function, which runs the initializer once
+    fn lazy_read(&self, identifier: &str, position: FilePosition) -> Option<Expr> {
+        let symbol = self
+            .environment
+            .values
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(identifier))?;
+        if symbol.kind != SymbolKind::Lazy {
+            return None;
+        }
+        Some(Expr::Literal {
+            ttype: symbol.ttype.clone(),
+            value: Atom::Call {
+                name: Self::lazy_function(identifier),
+                arguments: vec![],
+                position,
+            },
+        })
+    }
+
+    fn statement(&mut self) -> Result<Option<Statement>, NovaError> {
+        if let Some(doc) = self.current_token().and_then(|t| t.doc.clone()) {
+            self.doc = Some(doc);
+        }
+        let statement = self.statement_kind();
+        // a /// comment above anything but a declaration documents nothing
+        self.doc = None;
+        statement
+    }
+
+    fn statement_kind(&mut self) -> Result<Option<Statement>, NovaError> {
+        match self.current_token_value() {
+            Some(Identifier(id)) => match id.as_ref() {
+                "match" => self.match_statement(),
+                "type" => self.type_alias(),
+                "const" => self.const_declaration(),
+                "lazy" => self.lazy_declaration(),
+                "import" => self.import_file(false),
+                "pub" => {
+                    self.consume_identifier(Some("pub"))?;
+                    if self.current_token().is_some_and(|t| t.is_id("import")) {
+                        return self.import_file(true);
+                    }
+                    let item = self.current_token().is_some_and(|t| {
+                        t.is_keyword(KeyWord::Let)
+                            || ["fn", "struct", "enum", "const", "lazy"]
+                                .iter()
+                                .any(|keyword| t.is_id(keyword))
+                    });
+                    if !item {
+                        return Err(self.generate_error(
+                            "Expected an item after pub",
+                            "Only imports, functions, structs, enums, lets, lazy lets and consts can be marked pub",
+                        ));
+                    }
+                    if self.environment.values.len() != 1 {
+                        return Err(self.generate_error(
+                            "pub items must be declared at the top level",
+                            "Remove pub, items inside functions and blocks are always local",
+                        ));
+                    }
+                    self.public = true;
+                    let item = self.statement();
+                    self.public = false;
+                    item
+                }
+                "pass" => self.pass_statement(),
+                "struct" => self.struct_declaration(),
+                "impl" => self.impl_block(),
+                "with" => self.with_statement(),
+                "while" => self.loop_statement(Self::while_statement),
+                "fn" => self.function_declaration(),
+                "enum" => self.enum_declaration(),
+                "for" => self.loop_statement(Self::for_statement),
+                "break" => {
+                    self.consume_identifier(Some("break"))?;
+                    self.environment.mark_unreachable();
+                    Ok(Some(Statement::Break))
+                }
+                "continue" => {
+                    self.consume_identifier(Some("continue"))?;
+                    self.environment.mark_unreachable();
+                    Ok(Some(Statement::Continue))
+                }
+                _ => self.expression_statement(),
+            },
+            Some(Keyword(KeyWord::If)) => self.if_statement(),
+            Some(Keyword(KeyWord::Return)) => self.return_statement(),
+            Some(StructuralSymbol(Pound)) => self.derive_attribute(),
+            None => Ok(None),
+            _ => self.expression_statement(),
+        }
+    }
+
+    // a loop body may run zero times, so nothing it assigns counts as initialized after it
+    fn loop_statement(
+        &mut self,
+        statement: fn(&mut Self) -> Result<Option<Statement>, NovaError>,
+    ) -> Result<Option<Statement>, NovaError> {
+        let before = self.environment.initialization_state();
+        let statement = statement(self)?;
+        self.environment.restore_initialization(before);
+        Ok(statement)
+    }
+
+    // #[derive(Ord, Hash)] in front of a struct or enum. the dunders are
+    // written as nova source after the declaration and parsed next, their
+    // tokens all sit on the attribute so errors in them point there
+    fn derive_attribute(&mut self) -> Result<Option<Statement>, NovaError> {
+        let position = self.get_current_token_position();
+        self.consume_symbol(Pound)?;
+        self.consume_symbol(LeftSquareBracket)?;
+        self.consume_identifier(Some("derive"))?;
+        let traits = self.get_id_list()?;
+        self.consume_symbol(RightSquareBracket)?;
+        for name in traits.iter() {
+            if !matches!(name.deref(), "Ord" | "Hash") {
+                return Err(self.generate_error_with_pos(
+                    format!("Cannot derive {}", name),
+                    "Ord and Hash can be derived",
+                    position,
+                ));
+            }
+        }
+        let public = self.current_token().is_some_and(|t| t.is_id("pub"));
+        let offset = public as usize;
+        let name = match (self.peek_offset(offset), self.peek_offset_value(offset + 1)) {
+            (Some(keyword), Some(Identifier(name)))
+                if keyword.is_id("struct") || keyword.is_id("enum") =>
+            {
+                name.clone()
+            }
+            _ => {
+                return Err(self.generate_error_with_pos(
+                    "#[derive] has to be followed by a struct or enum",
+                    "Put the attribute on the line before the declaration",
+                    position,
+                ))
+            }
+        };
+        if self.environment.values.len() != 1 {
+            return Err(self.generate_error_with_pos(
+                "#[derive] can only be used at the top level",
+                "Declare the type outside of functions and blocks",
+                position,
+            ));
+        }
+        let declaration = self.statement()?;
+        // both need values that are equal field by field to be ==
+        let mut source = String::new();
+        self.derived_source(&name, "Eq", public, &position, &mut source)?;
+        for derived in traits.iter() {
+            self.derived_source(&name, derived, public, &position, &mut source)?;
+        }
+        let mut tokens = Lexer::new(source, position.filepath.as_deref()).tokenize()?;
+        for token in tokens.iter_mut() {
+            token.position = position.clone();
+        }
+        self.input.splice(self.index..self.index, tokens);
+        Ok(declaration)
+    }
+
+    // Eq writes __eq__ and __ne__ comparing the fields with ==. Ord writes
+    // __lt__ comparing the fields in order, or the variants in the order they
+    // are declared and then their values, and __gt__, __le__ and __ge__ in
+    // terms of it. Hash writes __hash__ mixing the hashes of the fields
+    fn derived_source(
+        &self,
+        name: &str,
+        derived: &str,
+        public: bool,
+        position: &FilePosition,
+        source: &mut String,
+    ) -> Result<(), NovaError> {
+        let bound = match derived {
+            "Ord" => "Comparable",
+            "Hash" => "Hash",
+            _ => "",
+        };
+        let fields: Vec<(Rc<str>, TType)> = self.environment.custom_types[name].clone();
+        for (field, ttype) in fields.iter() {
+            if !bound.is_empty()
+                && !matches!(ttype, TType::Generic { .. } | TType::None)
+                && !self.satisfies_bound(ttype, bound)
+            {
+                return Err(self.generate_error_with_pos(
+                    format!(
+                        "Cannot derive {} for {}, {} is {} which is not {}",
+                        derived,
+                        name,
+                        field,
+                        self.describe_type(ttype),
+                        bound
+                    ),
+                    match bound {
+                        "Hash" => "Hash types are Int, the sized integers, Bool, Char, String, tuples of them and types that define __hash__",
+                        _ => "Comparable types are Int, the sized integers, BigInt, Float, Char, String and types that define __lt__",
+                    },
+                    position.clone(),
+                ));
+            }
+        }
+        let generics = self.environment.generic_type_struct.get(name);
+        let (ttype, bounds) = match generics {
+            Some(generics) if bound.is_empty() => (
+                format!(
+                    "{}({})",
+                    name,
+                    generics
+                        .iter()
+                        .map(|generic| format!("${}", generic))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                String::new(),
+            ),
+            Some(generics) => (
+                format!(
+                    "{}({})",
+                    name,
+                    generics
+                        .iter()
+                        .map(|generic| format!("${}", generic))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    " where {}",
+                    generics
+                        .iter()
+                        .map(|generic| format!("{}: {}", generic, bound))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+            None => (name.to_string(), String::new()),
+        };
+        let public = if public { "pub " } else { "" };
+        // the value a field or variant adds to a hash
+        let hash = |value: &str, ttype: &TType| match ttype {
+            TType::Custom { .. } => format!("{}.__hash__()", value),
+            _ => format!("Cast::hash({})", value),
+        };
+        let mix = |hash: String| {
+            format!(
+                "hash = Int::wrappingAdd(Int::wrappingMul(hash, 31), {})\n",
+                hash
+            )
+        };
+        let enumeration = self.environment.enums.has(&Rc::from(name));
+        let variant = |field: &str, ttype: &TType, binding: &str| match ttype {
+            TType::None => format!("{}::{}()", name, field),
+            _ => format!("{}::{}({})", name, field, binding),
+        };
+        if derived == "Eq" {
+            writeln!(
+                source,
+                "{public}fn extends __eq__(self: {ttype}, other: {ttype}) -> Bool {{"
+            )
+            .unwrap();
+            if enumeration {
+                source.push_str("match self {\n");
+                for (field, ttype) in fields.iter() {
+                    let equal = if *ttype == TType::None {
+                        "true"
+                    } else {
+                        "a == b"
+                    };
+                    writeln!(
+                        source,
+                        "{} => {{ match other {{ {} => {{ return {} }} _ => {{}} }} }}",
+                        variant(field, ttype, "a"),
+                        variant(field, ttype, "b"),
+                        equal
+                    )
+                    .unwrap();
+                }
+                source.push_str("}\nreturn false\n}\n");
+            } else {
+                for (field, _) in fields.iter() {
+                    writeln!(
+                        source,
+                        "if self.{field} != other.{field} {{ return false }}"
+                    )
+                    .unwrap();
+                }
+                source.push_str("return true\n}\n");
+            }
+            writeln!(
+                source,
+                "{public}fn extends __ne__(self: {ttype}, other: {ttype}) -> Bool {{ return !(self == other) }}"
+            )
+            .unwrap();
+        } else if derived == "Ord" {
+            writeln!(
+                source,
+                "{public}fn extends __lt__(self: {ttype}, other: {ttype}) -> Bool{bounds} {{"
+            )
+            .unwrap();
+            if enumeration {
+                for side in ["self", "other"] {
+                    writeln!(source, "let mut {}Rank = 0\nmatch {} {{", side, side).unwrap();
+                    for (rank, (field, ttype)) in fields.iter().enumerate() {
+                        let pattern = variant(field, ttype, "_");
+                        writeln!(source, "{} => {{ {}Rank = {} }}", pattern, side, rank).unwrap();
+                    }
+                    source.push_str("}\n");
+                }
+                source.push_str(
+                    "if selfRank != otherRank { return selfRank < otherRank }\nmatch self {\n",
+                );
+                for (field, ttype) in fields.iter() {
+                    if *ttype == TType::None {
+                        writeln!(source, "{} => {{}}", variant(field, ttype, "")).unwrap();
+                        continue;
+                    }
+                    writeln!(
+                        source,
+                        "{} => {{ match other {{ {} => {{ return a < b }} _ => {{}} }} }}",
+                        variant(field, ttype, "a"),
+                        variant(field, ttype, "b")
+                    )
+                    .unwrap();
+                }
+                source.push_str("}\n");
+            } else {
+                for (field, _) in fields.iter() {
+                    writeln!(
+                        source,
+                        "if self.{field} < other.{field} {{ return true }}\nif other.{field} < self.{field} {{ return false }}"
+                    )
+                    .unwrap();
+                }
+            }
+            source.push_str("return false\n}\n");
+            for (dunder, body) in [
+                ("__gt__", "other < self"),
+                ("__le__", "!(other < self)"),
+                ("__ge__", "!(self < other)"),
+            ] {
+                writeln!(
+                    source,
+                    "{public}fn extends {dunder}(self: {ttype}, other: {ttype}) -> Bool{bounds} {{ return {body} }}"
+                )
+                .unwrap();
+            }
+        } else {
+            writeln!(
+                source,
+                "{public}fn extends __hash__(self: {ttype}) -> Int{bounds} {{\nlet mut hash = Cast::hash(\"{name}\")"
+            )
+            .unwrap();
+            if enumeration {
+                source.push_str("match self {\n");
+                for (field, ttype) in fields.iter() {
+                    let mut body = mix(format!("Cast::hash(\"{}\")", field));
+                    if *ttype != TType::None {
+                        body.push_str(&mix(hash("value", ttype)));
+                    }
+                    writeln!(
+                        source,
+                        "{} => {{\n{}}}",
+                        variant(field, ttype, "value"),
+                        body
+                    )
+                    .unwrap();
+                }
+                source.push_str("}\n");
+            } else {
+                for (field, ttype) in fields.iter() {
+                    source.push_str(&mix(hash(&format!("self.{}", field), ttype)));
+                }
+            }
+            source.push_str("return hash\n}\n");
+        }
+        Ok(())
+    }
+
+    fn pass_statement(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("pass"))?;
+        Ok(Some(Statement::Pass))
+    }
+
+    fn get_id_list(&mut self) -> Result<Vec<Rc<str>>, NovaError> {
+        let mut idlist = vec![];
+        self.consume_symbol(LeftParen)?;
+        if !self
+            .current_token()
+            .is_some_and(|t| t.is_symbol(RightParen))
+        {
+            idlist.push(self.get_identifier()?.0);
+        }
+        while self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+            self.advance();
+            if self
+                .current_token()
+                .is_some_and(|t| t.is_symbol(RightParen))
+            {
+                break;
+            }
+            idlist.push(self.get_identifier()?.0);
+        }
+        self.consume_symbol(RightParen)?;
+        Ok(idlist)
+    }
+
     fn collect_generics(input: &[TType]) -> Table<Rc<str>> {
         let mut contracts = Table::new();
         for t in input {
@@ -4527,7 +7444,7 @@ impl Parser {
                 TType::Custom { type_params, .. } => {
                     contracts.extend(Self::collect_generics(&type_params.clone()))
                 }
-                TType::Tuple { elements } => {
+                TType::Tuple { elements } | TType::Union { members: elements } => {
                     contracts.extend(Self::collect_generics(&elements.clone()))
                 }
                 _ => {}
@@ -4536,9 +7453,134 @@ impl Parser {
         contracts
     }
 
+    // whether a value of ttype holds a target value in place, following struct
+    // fields and tuple elements. lists, options, functions and enums with
+    // other variants can be built without one, so they end the search
+    fn contains_directly(&self, target: &str, ttype: &TType, path: &mut Vec<String>) -> bool {
+        match ttype {
+            TType::Tuple { elements } => elements
+                .iter()
+                .any(|element| self.contains_directly(target, element, path)),
+            TType::Custom { name, .. } if name.deref() == target => true,
+            TType::Custom { name, type_params } if !self.environment.enums.has(name) => {
+                let Some(fields) = self.environment.custom_types.get(name) else {
+                    return false;
+                };
+                let generics = self
+                    .environment
+                    .generic_type_struct
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_default();
+                for (field, field_type) in fields.iter() {
+                    let field_type =
+                        Self::replace_generic_types(field_type, &generics, type_params);
+                    path.push(format!("{}.{}", name, field));
+                    if self.contains_directly(target, &field_type, path) {
+                        return true;
+                    }
+                    path.pop();
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    // enum flags Name { A, B } is a struct holding an Int with a bit for each
+    // variant, Name::A is 1 and Name::B is 2. The methods are written as
+    // source and parsed after the struct, like #[derive] does
+    fn flags_declaration(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("flags"))?;
+        let (name, position) = self.get_identifier()?;
+        self.consume_symbol(LeftBrace)?;
+        let mut flags: Vec<Rc<str>> = vec![];
+        while let Some(Identifier(_)) = self.current_token_value() {
+            let (flag, pos) = self.get_identifier()?;
+            if flags.contains(&flag) {
+                return Err(self.generate_error_with_pos(
+                    format!("{} is declared twice in {}", flag, name),
+                    "Each flag needs a name of its own",
+                    pos,
+                ));
+            }
+            flags.push(flag);
+            if !self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+                break;
+            }
+            self.consume_symbol(Comma)?;
+        }
+        self.consume_symbol(RightBrace)?;
+        if flags.len() > 63 {
+            return Err(self.generate_error_with_pos(
+                format!("{} has {} flags", name, flags.len()),
+                "An Int has room for 63 flags",
+                position,
+            ));
+        }
+        if self.environment.values.len() != 1 {
+            return Err(self.generate_error_with_pos(
+                "enum flags can only be declared at the top level",
+                "Declare the type outside of functions and blocks",
+                position,
+            ));
+        }
+        let public = if self.public { "pub " } else { "" };
+        let mut source = format!("{public}struct {name} {{ bits: Int }}\nimpl {name} {{\n");
+        for (bit, flag) in flags.iter().enumerate() {
+            writeln!(source, "{public}let {flag} = {name}({})", 1i64 << bit).unwrap();
+        }
+        source.push_str("}\n");
+        for (method, op) in [("__bitor__", "|"), ("__bitand__", "&")] {
+            writeln!(
+                source,
+                "{public}fn extends {method}(self: {name}, other: {name}) -> {name} {{ return {name}(self.bits {op} other.bits) }}"
+            )
+            .unwrap();
+        }
+        writeln!(
+            source,
+            "{public}fn extends has(self: {name}, other: {name}) -> Bool {{ return self.bits & other.bits == other.bits }}\n\
+             {public}fn extends __eq__(self: {name}, other: {name}) -> Bool {{ return self.bits == other.bits }}\n\
+             {public}fn extends __ne__(self: {name}, other: {name}) -> Bool {{ return self.bits != other.bits }}\n\
+             {public}fn extends toString(self: {name}) -> String {{\n\
+             let mut text = \"\"\nlet mut separator = \"\""
+        )
+        .unwrap();
+        for (bit, flag) in flags.iter().enumerate() {
+            // the last one leaving separator unread would be warned about
+            let separator = if bit + 1 < flags.len() {
+                "\nseparator = \" | \""
+            } else {
+                ""
+            };
+            writeln!(
+                source,
+                "if self.bits & {} != 0 {{ text = text + separator + \"{flag}\"{separator} }}",
+                1i64 << bit
+            )
+            .unwrap();
+        }
+        source.push_str("if text == \"\" { return \"none\" }\nreturn text\n}\n");
+        let mut tokens = Lexer::new(source, position.filepath.as_deref()).tokenize()?;
+        for token in tokens.iter_mut() {
+            token.position = position.clone();
+        }
+        self.input.splice(self.index..self.index, tokens);
+        self.statement()
+    }
+
     fn enum_declaration(&mut self) -> Result<Option<Statement>, NovaError> {
+        let doc = self.doc.take();
         self.consume_identifier(Some("enum"))?;
+        if self.current_token().is_some_and(|t| t.is_id("flags"))
+            && matches!(self.peek_offset_value(1), Some(Identifier(_)))
+        {
+            return self.flags_declaration();
+        }
         let (enum_name, position) = self.get_identifier()?;
+        let enum_name = self.qualify_type(enum_name);
+        self.record_visibility(enum_name.clone(), enum_name.clone());
 
         // Initialize the struct in the environment for recursive types
         self.environment
@@ -4557,18 +7599,33 @@ impl Parser {
 
         self.consume_symbol(LeftBrace)?;
         let parameter_list = self.enum_list()?;
+        for ((_, variant), position) in parameter_list
+            .iter()
+            .zip(std::mem::take(&mut self.parameter_positions))
+        {
+            self.reference_member(&enum_name, variant, position);
+        }
         self.consume_symbol(RightBrace)?;
         let mut fields = vec![];
         let mut type_parameters = vec![];
         let mut generics_table = Table::new();
 
         for (field_type, field_name) in parameter_list.clone() {
-            generics_table.extend(Self::collect_generics(&[field_type.clone()]));
+            generics_table.extend(Self::collect_generics(std::slice::from_ref(&field_type)));
             type_parameters.push(field_type.clone());
             fields.push((field_name, field_type));
         }
-        fields.push(("type".into(), TType::String));
 
+        if parameter_list
+            .iter()
+            .any(|(field_type, _)| field_type.has_hole())
+        {
+            return Err(self.generate_error_with_pos(
+                format!("Type holes cannot be used in the fields of '{}'", enum_name),
+                "Write the field type out, or use a generic type like $T",
+                position.clone(),
+            ));
+        }
         for generic_type in generics_table.items.iter() {
             if !generic_field_names.contains(generic_type) {
                 return Err(self.generate_error_with_pos(
@@ -4633,25 +7690,171 @@ impl Parser {
             self.environment.no_override.insert(enum_name.clone());
         } else {
             return Err(self.generate_error_with_pos(
-                format!("Enum '{}' is already instantiated", enum_name),
-                "Cannot reinstantiate the same type",
-                position.clone(),
+                format!("Enum '{}' is already instantiated", enum_name),
+                "Cannot reinstantiate the same type",
+                position.clone(),
+            ));
+        }
+
+        Ok(Some(Statement::Enum {
+            ttype: TType::Custom {
+                name: enum_name.clone(),
+                type_params: vec![],
+            },
+            identifier: enum_name,
+            fields: field_definitions,
+            doc,
+        }))
+    }
+
+    // impl Type { fn name(self, ...) {...} } declares every method as if it
+    // were written fn extends name(self: Type, ...), functions without self and
+    // let constants are namespaced as Type::name
+    fn impl_block(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("impl"))?;
+        // a generic struct can be named without its type parameters
+        let generics = match self.current_token_value() {
+            Some(Identifier(name))
+                if !self
+                    .peek_offset(1)
+                    .is_some_and(|t| t.is_symbol(LeftParen) || t.is_op(Operator::DoubleColon)) =>
+            {
+                let name = self.resolve_type(name.clone(), self.get_current_token_position())?;
+                self.environment
+                    .generic_type_struct
+                    .get(&name)
+                    .map(|generics| (name, generics.clone()))
+            }
+            _ => None,
+        };
+        let target = match generics {
+            Some((name, generics)) => {
+                self.advance();
+                TType::Custom {
+                    name,
+                    type_params: generics
+                        .into_iter()
+                        .map(|name| TType::Generic { name })
+                        .collect(),
+                }
+            }
+            None => self.ttype()?,
+        };
+        self.consume_symbol(LeftBrace)?;
+        let mut body = vec![];
+        loop {
+            self.doc = self.current_token().and_then(|t| t.doc.clone());
+            self.public = self.current_token().is_some_and(|t| t.is_id("pub"));
+            if self.public {
+                self.advance();
+            }
+            if self
+                .current_token()
+                .is_some_and(|t| t.is_keyword(KeyWord::Let))
+            {
+                let constant = self.impl_constant(&target);
+                self.public = false;
+                body.push(constant?);
+                continue;
+            }
+            if !self.current_token().is_some_and(|t| t.is_id("fn")) {
+                self.public = false;
+                break;
+            }
+            self.impl_type = Some(target.clone());
+            let function = self.function_declaration();
+            self.impl_type = None;
+            self.public = false;
+            if let Some(function) = function? {
+                body.push(function);
+            }
+        }
+        self.consume_symbol(RightBrace)?;
+        Ok(Some(Statement::Block {
+            body,
+            filepath: self.filepath.clone(),
+        }))
+    }
+
+    fn impl_constant(&mut self, target: &TType) -> Result<Statement, NovaError> {
+        self.consume_keyword(KeyWord::Let)?;
+        let (name, pos) = self.get_identifier()?;
+        let identifier: Rc<str> = format!(
+            "{}::{}",
+            target.custom_to_string().unwrap_or_default(),
+            name
+        )
+        .into();
+        let annotation = if self
+            .current_token()
+            .is_some_and(|t| t.is_op(Operator::Colon))
+        {
+            self.consume_operator(Operator::Colon)?;
+            Some(self.ttype()?)
+        } else {
+            None
+        };
+        self.consume_operator(Operator::Assignment)?;
+        let expr = self.expr()?;
+        if expr.get_type() == TType::Void {
+            return Err(self.generate_error_with_pos(
+                format!("Constant '{}' cannot be assinged to void", identifier),
+                "Make sure the expression returns a value",
+                pos,
+            ));
+        }
+        let ttype = match annotation {
+            Some(ttype) => {
+                if self
+                    .check_and_map_types(
+                        std::slice::from_ref(&ttype),
+                        &[expr.get_type()],
+                        &mut HashMap::default(),
+                        pos.clone(),
+                    )
+                    .is_err()
+                {
+                    return Err(self.generate_error_with_pos(
+                        format!(
+                            "Cannot assign {} to {}",
+                            self.describe_type(&expr.get_type()),
+                            self.describe_type(&ttype)
+                        ),
+                        "Make sure the expression returns the givin type",
+                        pos,
+                    ));
+                }
+                ttype
+            }
+            None => expr.get_type(),
+        };
+        if self.environment.has(&identifier) {
+            return Err(self.generate_error_with_pos(
+                format!("Symbol '{}' is already instantiated", identifier),
+                "Cannot reinstantiate the same symbol in the same scope",
+                pos,
             ));
         }
-
-        Ok(Some(Statement::Enum {
-            ttype: TType::Custom {
-                name: enum_name.clone(),
-                type_params: vec![],
+        self.record_visibility(identifier.clone(), identifier.clone());
+        self.environment
+            .insert_symbol(&identifier, ttype, Some(pos), SymbolKind::Constant);
+        Ok(Statement::Expression {
+            ttype: TType::Void,
+            expr: Expr::Let {
+                ttype: TType::Void,
+                identifier,
+                expr: Box::new(expr),
+                global: false,
             },
-            identifier: enum_name,
-            fields: field_definitions,
-        }))
+        })
     }
 
     fn struct_declaration(&mut self) -> Result<Option<Statement>, NovaError> {
+        let doc = self.doc.take();
         self.consume_identifier(Some("struct"))?;
         let (struct_name, position) = self.get_identifier()?;
+        let struct_name = self.qualify_type(struct_name);
+        self.record_visibility(struct_name.clone(), struct_name.clone());
 
         // Initialize the struct in the environment for recursive types
         self.environment
@@ -4668,6 +7871,12 @@ impl Parser {
 
         self.consume_symbol(LeftBrace)?;
         let parameter_list = self.parameter_list()?;
+        for ((_, field), position) in parameter_list
+            .iter()
+            .zip(std::mem::take(&mut self.parameter_positions))
+        {
+            self.reference_member(&struct_name, field, position);
+        }
         self.consume_symbol(RightBrace)?;
 
         let mut fields = vec![];
@@ -4675,12 +7884,24 @@ impl Parser {
         let mut generics_table = Table::new();
 
         for (field_type, field_name) in parameter_list.clone() {
-            generics_table.extend(Self::collect_generics(&[field_type.clone()]));
+            generics_table.extend(Self::collect_generics(std::slice::from_ref(&field_type)));
             type_parameters.push(field_type.clone());
             fields.push((field_name, field_type));
         }
-        fields.push(("type".into(), TType::String));
 
+        if parameter_list
+            .iter()
+            .any(|(field_type, _)| field_type.has_hole())
+        {
+            return Err(self.generate_error_with_pos(
+                format!(
+                    "Type holes cannot be used in the fields of '{}'",
+                    struct_name
+                ),
+                "Write the field type out, or use a generic type like $T",
+                position.clone(),
+            ));
+        }
         for generic_type in generics_table.items.iter() {
             if !generic_field_names.contains(generic_type) {
                 return Err(self.generate_error_with_pos(
@@ -4694,6 +7915,28 @@ impl Parser {
             }
         }
 
+        // a struct may refer to itself behind an Option or a List, but holding
+        // itself directly would make every value infinitely large
+        for (field_type, field_name) in parameter_list.iter() {
+            let mut path = vec![format!("{}.{}", struct_name, field_name)];
+            if self.contains_directly(&struct_name, field_type, &mut path) {
+                return Err(self.generate_error_with_pos(
+                    format!(
+                        "Struct '{}' contains itself through field '{}'",
+                        struct_name, field_name
+                    ),
+                    format!(
+                        "{} -> {} never ends, use Option({}) or [{}] to break the cycle",
+                        path.join(" -> "),
+                        struct_name,
+                        struct_name,
+                        struct_name
+                    ),
+                    position.clone(),
+                ));
+            }
+        }
+
         let mut field_definitions = vec![];
         for (field_name, field_type) in fields.clone() {
             field_definitions.push(Field {
@@ -4754,12 +7997,20 @@ impl Parser {
             },
             identifier: struct_name,
             fields: field_definitions,
+            doc,
         }))
     }
 
     fn for_statement(&mut self) -> Result<Option<Statement>, NovaError> {
         self.consume_identifier(Some("for"))?;
 
+        if self
+            .current_token()
+            .is_some_and(|t| t.is_symbol(LeftParen) || t.is_symbol(LeftBrace))
+        {
+            return self.foreach_pattern();
+        }
+
         if let Some(Keyword(KeyWord::In)) = self.peek_offset_value(1) {
             // Handle foreach statement
 
@@ -4852,6 +8103,10 @@ impl Parser {
             // Handle regular for statement
             self.environment.push_block();
             let init = self.expr()?;
+            // the loop counter is reassigned by the increment
+            if let Expr::Let { identifier, .. } = &init {
+                self.environment.mark_mutable(identifier);
+            }
             self.consume_symbol(Semicolon)?;
             let testpos = self.get_current_token_position();
             let test = self.expr()?;
@@ -4875,10 +8130,58 @@ impl Parser {
         }
     }
 
+    // for (k, v) in pairs, each element is bound to a hidden loop variable and
+    // taken apart at the top of the body
+    fn foreach_pattern(&mut self) -> Result<Option<Statement>, NovaError> {
+        let pos = self.get_current_token_position();
+        let pattern = self.pattern()?;
+        for (identifier, pos) in pattern.names() {
+            if self.environment.has(&identifier) {
+                return Err(self.generate_error_with_pos(
+                    "identifier already used",
+                    format!("identifier '{identifier}' is already used within this scope"),
+                    pos,
+                ));
+            }
+        }
+        self.consume_keyword(KeyWord::In)?;
+        let arraypos = self.get_current_token_position();
+        let array = self.expr()?;
+        let TType::List { inner } = array.get_type() else {
+            return Err(self.generate_error_with_pos(
+                "foreach can only iterate over arrays",
+                format!("got {}", array.get_type()),
+                arraypos,
+            ));
+        };
+        let identifier: Rc<str> = format!("__item__{}_{}", pos.line, pos.col).into();
+        self.environment.push_block();
+        self.environment.insert_symbol(
+            &identifier,
+            *inner.clone(),
+            Some(pos),
+            SymbolKind::Variable,
+        );
+        let mut body = vec![];
+        let source = self.create_literal_expr(identifier.clone(), *inner);
+        self.bind_pattern(pattern, source, &mut body)?;
+        body.extend(self.block()?);
+        self.environment.pop_block();
+        Ok(Some(Statement::Foreach {
+            identifier,
+            expr: array,
+            body,
+            position: arraypos,
+        }))
+    }
+
     fn while_statement(&mut self) -> Result<Option<Statement>, NovaError> {
         self.consume_identifier(Some("while"))?;
         // check for let keyword
-        if self.current_token().is_some_and(|t| t.is_id("let")) {
+        if self
+            .current_token()
+            .is_some_and(|t| t.is_keyword(KeyWord::Let))
+        {
             self.advance();
             let (identifier, pos) = self.get_identifier()?;
             self.consume_operator(Operator::Assignment)?;
@@ -4939,9 +8242,12 @@ impl Parser {
     }
 
     fn if_statement(&mut self) -> Result<Option<Statement>, NovaError> {
-        self.consume_identifier(Some("if"))?;
+        self.consume_keyword(KeyWord::If)?;
 
-        if self.current_token().is_some_and(|t| t.is_id("let")) {
+        if self
+            .current_token()
+            .is_some_and(|t| t.is_keyword(KeyWord::Let))
+        {
             // Handle if let statement
             self.advance(); // consume 'let'
             let mut global = false;
@@ -4970,6 +8276,7 @@ impl Parser {
                     pos.clone(),
                 ))
             } else {
+                let before = self.environment.initialization_state();
                 self.environment.push_block();
                 self.environment.insert_symbol(
                     &identifier,
@@ -4979,17 +8286,7 @@ impl Parser {
                 );
                 let body = self.block()?;
                 self.environment.pop_block();
-
-                let mut alternative: Option<Vec<Statement>> = None;
-                if self.current_token().is_some_and(|t| t.is_id("elif")) {
-                    self.advance();
-                    alternative = Some(self.alternative()?);
-                } else if self.current_token().is_some_and(|t| t.is_id("else")) {
-                    self.advance();
-                    self.environment.push_block();
-                    alternative = Some(self.block()?);
-                    self.environment.pop_block();
-                };
+                let alternative = self.else_branch(before)?;
 
                 Ok(Some(Statement::IfLet {
                     ttype: expr.get_type(),
@@ -5011,20 +8308,17 @@ impl Parser {
                     testpos.clone(),
                 ));
             }
+            let narrowed = self.typeof_narrowing(&test, testpos)?;
+            let before = self.environment.initialization_state();
             self.environment.push_block();
+            if let Some((name, member)) = narrowed {
+                if let Some(symbol) = self.environment.values.last_mut().unwrap().get_mut(&name) {
+                    symbol.ttype = member;
+                }
+            }
             let body = self.block()?;
             self.environment.pop_block();
-            let mut alternative: Option<Vec<Statement>> = None;
-
-            if self.current_token().is_some_and(|t| t.is_id("elif")) {
-                self.advance();
-                alternative = Some(self.alternative()?);
-            } else if self.current_token().is_some_and(|t| t.is_id("else")) {
-                self.advance();
-                self.environment.push_block();
-                alternative = Some(self.block()?);
-                self.environment.pop_block();
-            };
+            let alternative = self.else_branch(before)?;
 
             Ok(Some(Statement::If {
                 ttype: TType::Void,
@@ -5035,9 +8329,228 @@ impl Parser {
         }
     }
 
+    // x += y on a type whose __add__ gives back the same type is x = x + y,
+    // and -= likewise with __sub__
+    fn dunder_assign(
+        &mut self,
+        operation: Operator,
+        target: &Expr,
+        value: Expr,
+        position: FilePosition,
+    ) -> (Operator, Expr) {
+        let method = match operation {
+            Operator::AddAssign => "__add__",
+            Operator::SubAssign => "__sub__",
+            _ => return (operation, value),
+        };
+        let ttype = target.get_type();
+        let custom = match &ttype {
+            TType::Int
+            | TType::Sized { .. }
+            | TType::Float
+            | TType::String
+            | TType::List { .. } => None,
+            ttype => ttype.custom_to_string(),
+        };
+        let Some(custom) = custom else {
+            return (operation, value);
+        };
+        let name = generate_unique_string(
+            &format!("{}::{}", custom, method),
+            &[ttype.clone(), value.get_type()],
+        );
+        match self.environment.get(&name).map(|symbol| symbol.ttype) {
+            Some(TType::Function { return_type, .. }) if *return_type == ttype => (
+                Operator::Assignment,
+                Expr::Literal {
+                    ttype,
+                    value: Atom::Call {
+                        name: name.into(),
+                        arguments: vec![target.clone(), value],
+                        position,
+                    },
+                },
+            ),
+            _ => (operation, value),
+        }
+    }
+
+    // let xs: [Int] = [] can take the element type of the empty list from
+    // the annotation
+    fn empty_list_fix(&self, error: NovaError, start: usize, annotation: &TType) -> NovaError {
+        let TType::List { inner } = annotation else {
+            return error;
+        };
+        let empty = self
+            .input
+            .get(start)
+            .is_some_and(|t| t.is_symbol(LeftSquareBracket))
+            && self
+                .input
+                .get(start + 1)
+                .is_some_and(|t| t.is_symbol(RightSquareBracket));
+        let untyped = matches!(
+            &error,
+            NovaError::Parsing { msg, position, .. }
+                if msg == "List must have a type" && *position == self.input[start].position()
+        );
+        // Void and None are not written in source, a fix naming them would not parse
+        let text = inner.to_string();
+        if !empty || !untyped || inner.has_hole() || text.contains("Void") || text.contains("None")
+        {
+            return error;
+        }
+        let end = self.token_end(start + 1);
+        error.with_fix(Fix::insert(
+            format!("annotate the list as []: {}", text),
+            end,
+            &format!(": {}", text),
+        ))
+    }
+
+    // if typeof(x) == "Int" { ... } narrows a variable x of a union type to the
+    // member named by the string inside the block
+    fn typeof_narrowing(
+        &self,
+        test: &Expr,
+        pos: FilePosition,
+    ) -> Result<Option<(Rc<str>, TType)>, NovaError> {
+        let Expr::Binop {
+            op: Operator::Equal,
+            lhs,
+            rhs,
+            ..
+        } = test
+        else {
+            return Ok(None);
+        };
+        let (call, member) = match (&**lhs, &**rhs) {
+            (
+                call,
+                Expr::Literal {
+                    value: Atom::String { value },
+                    ..
+                },
+            )
+            | (
+                Expr::Literal {
+                    value: Atom::String { value },
+                    ..
+                },
+                call,
+            ) => (call, value),
+            _ => return Ok(None),
+        };
+        let Expr::Literal {
+            value: Atom::Call {
+                name, arguments, ..
+            },
+            ..
+        } = call
+        else {
+            return Ok(None);
+        };
+        let [Expr::Literal {
+            ttype: union @ TType::Union { members },
+            value: Atom::Id { name: variable },
+        }] = arguments.as_slice()
+        else {
+            return Ok(None);
+        };
+        if &**name != "typeof" {
+            return Ok(None);
+        }
+        match members.iter().find(|t| t.to_string() == **member) {
+            Some(member) => Ok(Some((variable.clone(), member.clone()))),
+            None => Err(self.generate_error_with_pos(
+                format!("'{}' is not a member of {}", member, union),
+                format!(
+                    "typeof gives one of {}",
+                    members
+                        .iter()
+                        .map(|t| format!("\"{}\"", t))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                pos,
+            )),
+        }
+    }
+
+    // with expr as name { body }, the close or __exit__ method of the value's
+    // type runs when the body is left, whether it ends, returns, breaks or fails
+    fn with_statement(&mut self) -> Result<Option<Statement>, NovaError> {
+        self.consume_identifier(Some("with"))?;
+        let pos = self.get_current_token_position();
+        let expr = self.expr()?;
+        self.consume_identifier(Some("as"))?;
+        let (identifier, id_pos) = self.get_identifier()?;
+        let ttype = expr.get_type();
+        let close = match &ttype {
+            TType::Custom { name, .. } => ["__exit__", "close"].iter().find_map(|method| {
+                self.environment.get_function_type(
+                    &format!("{}::{}", name, method),
+                    std::slice::from_ref(&ttype),
+                )
+            }),
+            _ => None,
+        };
+        let Some((function_type, function_id, function_kind)) = close else {
+            return Err(self.generate_error_with_pos(
+                format!("Cannot use {} in a with block", self.describe_type(&ttype)),
+                "The type needs a close or __exit__ method that takes only the value",
+                pos,
+            ));
+        };
+        let close = self.handle_function_call(
+            function_type,
+            function_id,
+            function_kind,
+            vec![self.create_literal_expr(identifier.clone(), ttype.clone())],
+            vec![ttype.clone()],
+            pos,
+        )?;
+        if self.environment.has(&identifier) {
+            return Err(self.generate_error_with_pos(
+                format!("Symbol '{}' is already instantiated", identifier),
+                "Cannot reinstantiate the same symbol in the same scope",
+                id_pos,
+            ));
+        }
+        self.environment.push_block();
+        self.environment
+            .insert_symbol(&identifier, ttype, Some(id_pos), SymbolKind::Variable);
+        let body = self.block()?;
+        self.environment.pop_block();
+        Ok(Some(Statement::With {
+            identifier,
+            expr,
+            body,
+            close,
+        }))
+    }
+
+    fn consume_mut(&mut self) -> bool {
+        let mutable = self.current_token().is_some_and(|t| t.is_id("mut"));
+        if mutable {
+            self.advance();
+        }
+        mutable
+    }
+
     fn let_expr(&mut self) -> Result<Expr, NovaError> {
-        self.consume_identifier(Some("let"))?;
+        self.consume_keyword(KeyWord::Let)?;
+        if self.current_token().is_some_and(|t| t.is_id("_")) {
+            return self.discard();
+        }
+        if self
+            .current_token()
+            .is_some_and(|t| t.is_symbol(LeftParen) || t.is_symbol(LeftBrace))
+        {
+            return self.destructuring_let();
+        }
         let mut global = false;
+        let mut mutable = self.consume_mut();
         // refactor out into two parsing ways for ident. one with module and one without
         let (mut identifier, mut pos) = self.get_identifier()?;
         if self.modules.has(&identifier) {
@@ -5049,10 +8562,18 @@ impl Parser {
             ));
         }
         if identifier.deref() == "global" {
+            mutable |= self.consume_mut();
             (identifier, pos) = self.get_identifier()?;
             global = true
         }
-        let ttype;
+        let identifier = self.qualify_declaration(identifier);
+        self.record_visibility(identifier.clone(), identifier.clone());
+        let kind = if mutable {
+            SymbolKind::Mutable
+        } else {
+            SymbolKind::Variable
+        };
+        let mut ttype;
         let expr;
         if self
             .current_token()
@@ -5060,31 +8581,56 @@ impl Parser {
         {
             self.consume_operator(Operator::Colon)?;
             ttype = self.ttype()?;
+            if !self
+                .current_token()
+                .is_some_and(|t| t.is_op(Operator::Assignment))
+            {
+                return self.deferred_let(identifier, ttype, pos, global, kind);
+            }
             self.consume_operator(Operator::Assignment)?;
-            expr = self.expr()?;
+            let start = self.index;
+            let value = self
+                .expr()
+                .map_err(|error| self.empty_list_fix(error, start, &ttype))?;
+            expr = self.sized_literal(value, &ttype, pos.clone())?;
+            if ttype.has_hole() {
+                ttype = self.fill_type_holes(&ttype, &expr.get_type(), pos.clone())?;
+            }
             match (
                 self.check_and_map_types(
-                    &[ttype.clone()],
+                    std::slice::from_ref(&ttype),
                     &[expr.get_type()],
                     &mut HashMap::default(),
                     pos.clone(),
                 ),
                 self.check_and_map_types(
                     &[expr.get_type()],
-                    &[ttype.clone()],
+                    std::slice::from_ref(&ttype),
                     &mut HashMap::default(),
                     pos.clone(),
                 ),
             ) {
                 (Ok(_), Ok(_)) => {}
+                // the value only has to fit one of the members
+                (Ok(_), Err(_)) if ttype.has_union() => {}
                 _ => {
                     return Err(self.generate_error_with_pos(
-                        format!("Cannot assign {} to {}", expr.get_type(), ttype),
+                        format!(
+                            "Cannot assign {} to {}",
+                            self.describe_type(&expr.get_type()),
+                            self.describe_type(&ttype)
+                        ),
                         "Make sure the expression returns the givin type",
                         pos.clone(),
                     ));
                 }
             }
+        } else if !self
+            .current_token()
+            .is_some_and(|t| t.is_op(Operator::Assignment))
+        {
+            // no type and no value, the type comes from the first assignment
+            return self.deferred_let(identifier, TType::Auto, pos, global, kind);
         } else {
             self.consume_operator(Operator::Assignment)?;
             expr = self.expr()?;
@@ -5107,12 +8653,8 @@ impl Parser {
                 pos.clone(),
             ))
         } else {
-            self.environment.insert_symbol(
-                &identifier,
-                ttype.clone(),
-                Some(pos.clone()),
-                SymbolKind::Variable,
-            );
+            self.environment
+                .insert_symbol(&identifier, ttype.clone(), Some(pos.clone()), kind);
             Ok(Expr::Let {
                 ttype: TType::Void,
                 identifier,
@@ -5122,9 +8664,181 @@ impl Parser {
         }
     }
 
+    // _ = expr evaluates expr and drops the value, _ is never bound
+    fn discard(&mut self) -> Result<Expr, NovaError> {
+        let pos = self.get_current_token_position();
+        self.consume_identifier(Some("_"))?;
+        self.consume_operator(Operator::Assignment)?;
+        let expr = self.expr()?;
+        if expr.get_type() == TType::Void {
+            return Err(self.generate_error_with_pos(
+                "Nothing to discard",
+                "The expression does not return a value",
+                pos,
+            ));
+        }
+        Ok(Expr::Let {
+            ttype: TType::Void,
+            identifier: "_".into(),
+            expr: Box::new(expr),
+            global: false,
+        })
+    }
+
+    // let (a, b) = pair or let {x, y} = point, the value is stored once in a
+    // hidden variable and each name is read out of it
+    fn destructuring_let(&mut self) -> Result<Expr, NovaError> {
+        let pos = self.get_current_token_position();
+        let pattern = self.pattern()?;
+        self.consume_operator(Operator::Assignment)?;
+        let expr = self.expr()?;
+        if expr.get_type() == TType::Void {
+            return Err(self.generate_error_with_pos(
+                "Cannot destructure void",
+                "Make sure the expression returns a value",
+                pos,
+            ));
+        }
+        for (identifier, pos) in pattern.names() {
+            if self.environment.has(&identifier) {
+                return Err(self.generate_error_with_pos(
+                    format!("Symbol '{}' is already instantiated", identifier),
+                    "Cannot reinstantiate the same symbol in the same scope",
+                    pos,
+                ));
+            }
+        }
+        let hidden: Rc<str> = format!("__destructure__{}_{}", pos.line, pos.col).into();
+        let source = self.create_literal_expr(hidden.clone(), expr.get_type());
+        let mut body = vec![Statement::Expression {
+            ttype: TType::Void,
+            expr: Expr::Let {
+                ttype: TType::Void,
+                identifier: hidden,
+                expr: Box::new(expr),
+                global: false,
+            },
+        }];
+        self.bind_pattern(pattern, source, &mut body)?;
+        Ok(Expr::Block {
+            ttype: TType::Void,
+            body,
+        })
+    }
+
+    // let without a value, the variable must be assigned on every path before it is read
+    fn deferred_let(
+        &mut self,
+        identifier: Rc<str>,
+        ttype: TType,
+        pos: FilePosition,
+        global: bool,
+        kind: SymbolKind,
+    ) -> Result<Expr, NovaError> {
+        if self.environment.has(&identifier) {
+            return Err(self.generate_error_with_pos(
+                format!("Symbol '{}' is already instantiated", identifier),
+                "Cannot reinstantiate the same symbol in the same scope",
+                pos,
+            ));
+        }
+        if ttype == TType::Auto {
+            self.auto_lets.push((identifier.clone(), pos.clone()));
+        }
+        self.environment
+            .insert_symbol(&identifier, ttype, Some(pos), kind);
+        self.environment.declare_uninitialized(identifier.clone());
+        Ok(Expr::Let {
+            ttype: TType::Void,
+            identifier,
+            expr: Box::new(Expr::None),
+            global,
+        })
+    }
+
+    // a let without a type has to get one from an assignment somewhere in
+    // its scope, otherwise nothing says what it holds
+    fn check_auto_lets(&self) -> Result<(), NovaError> {
+        match self.auto_lets.first() {
+            Some((identifier, pos)) => Err(self.generate_error_with_pos(
+                format!("Cannot infer the type of '{}'", identifier),
+                format!("'{identifier}' is never assigned, annotate it with let {identifier}: Type or give it a value"),
+                pos.clone(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // first assignment to a variable declared without a value
+    fn initialize(&mut self, identifier: Rc<str>, pos: FilePosition) -> Result<Expr, NovaError> {
+        self.consume_operator(Operator::Assignment)?;
+        let value_pos = self.get_current_token_position();
+        let right_expr = self.logical_top_expr()?;
+        let value_type = right_expr.get_type();
+        if value_type == TType::Void {
+            return Err(self.generate_error_with_pos(
+                format!("Variable '{}' cannot be assinged to void", identifier),
+                "Make sure the expression returns a value",
+                value_pos,
+            ));
+        }
+        let ttype = match self.environment.get_type(&identifier) {
+            Some(TType::Auto) => {
+                let declared = self
+                    .environment
+                    .values
+                    .last()
+                    .and_then(|scope| scope.get(&identifier))
+                    .and_then(|symbol| symbol.pos.clone());
+                self.auto_lets
+                    .retain(|(_, pos)| Some(pos) != declared.as_ref());
+                self.environment
+                    .resolve_auto(&identifier, value_type.clone());
+                value_type
+            }
+            Some(mut ttype) => {
+                if ttype.has_hole() {
+                    ttype = self.fill_type_holes(&ttype, &value_type, value_pos.clone())?;
+                }
+                if self
+                    .check_and_map_types(
+                        std::slice::from_ref(&ttype),
+                        std::slice::from_ref(&value_type),
+                        &mut HashMap::default(),
+                        value_pos.clone(),
+                    )
+                    .is_err()
+                {
+                    return Err(self.generate_error_with_pos(
+                        format!("cannot assign {} to {}", value_type, ttype),
+                        format!("'{}' has type {}", identifier, ttype),
+                        value_pos,
+                    ));
+                }
+                self.environment.resolve_auto(&identifier, ttype.clone());
+                ttype
+            }
+            None => {
+                return Err(self.generate_error_with_pos(
+                    format!("Variable '{}' is not defined", identifier),
+                    "Declare the variable with let before assigning it",
+                    pos,
+                ))
+            }
+        };
+        self.environment.mark_initialized(&identifier);
+        Ok(Expr::Binop {
+            ttype: TType::Void,
+            op: Operator::Assignment,
+            lhs: Box::new(self.create_literal_expr(identifier, ttype)),
+            rhs: Box::new(right_expr),
+        })
+    }
+
     fn return_statement(&mut self) -> Result<Option<Statement>, NovaError> {
-        self.consume_identifier(Some("return"))?;
+        self.consume_keyword(KeyWord::Return)?;
         let expr = self.expr()?;
+        self.environment.mark_unreachable();
         Ok(Some(Statement::Return {
             ttype: expr.get_type(),
             expr,
@@ -5138,30 +8852,24 @@ impl Parser {
                 TType::Function {
                     parameters,
                     return_type,
-                } => {
-                    if Self::is_generic(parameters) || Self::is_generic(&[*return_type.clone()]) {
-                        return true;
-                    }
+                } if (Self::is_generic(parameters)
+                    || Self::is_generic(&[*return_type.clone()])) =>
+                {
+                    return true;
                 }
-                TType::List { inner } => {
-                    if Self::is_generic(&[*inner.clone()]) {
-                        return true;
-                    }
+                TType::List { inner } if Self::is_generic(&[*inner.clone()]) => {
+                    return true;
                 }
-                TType::Option { inner } => {
-                    if Self::is_generic(&[*inner.clone()]) {
-                        return true;
-                    }
+                TType::Option { inner } if Self::is_generic(&[*inner.clone()]) => {
+                    return true;
                 }
-                TType::Custom { type_params, .. } => {
-                    if Self::is_generic(type_params) {
-                        return true;
-                    }
+                TType::Custom { type_params, .. } if Self::is_generic(type_params) => {
+                    return true;
                 }
-                TType::Tuple { elements } => {
-                    if Self::is_generic(elements) {
-                        return true;
-                    }
+                TType::Tuple { elements } | TType::Union { members: elements }
+                    if Self::is_generic(elements) =>
+                {
+                    return true;
                 }
                 _ => {}
             }
@@ -5169,8 +8877,154 @@ impl Parser {
         false
     }
 
+    // where K: Hash, V: Show + Comparable after the return type of a generic function
+    fn where_clause(&mut self, generics: &Table<Rc<str>>) -> Result<Vec<Bound>, NovaError> {
+        let mut bounds = vec![];
+        if !self.current_token().is_some_and(|t| t.is_id("where")) {
+            return Ok(bounds);
+        }
+        self.advance();
+        loop {
+            if self
+                .current_token()
+                .is_some_and(|t| t.is_symbol(DollarSign))
+            {
+                self.advance();
+            }
+            let (generic, pos) = self.get_identifier()?;
+            if !generics.has(&generic) {
+                return Err(self.generate_error_with_pos(
+                    format!("'{}' is not a generic type of this function", generic),
+                    "Only generics used in the parameters or return type can be bounded",
+                    pos,
+                ));
+            }
+            self.consume_operator(Operator::Colon)?;
+            loop {
+                let (bound, pos) = self.get_identifier()?;
+                if !BOUNDS.contains(&&*bound) {
+                    let note = match closest_match(&bound, BOUNDS.iter().copied()) {
+                        Some(suggestion) => format!("did you mean '{}'?", suggestion),
+                        None => format!("expected one of {}", BOUNDS.join(", ")),
+                    };
+                    return Err(self.generate_error_with_pos(
+                        format!("Unknown bound '{}'", bound),
+                        note,
+                        pos,
+                    ));
+                }
+                bounds.push((generic.clone(), bound));
+                if !self
+                    .current_token()
+                    .is_some_and(|t| t.is_op(Operator::Addition))
+                {
+                    break;
+                }
+                self.advance();
+            }
+            if !self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+                return Ok(bounds);
+            }
+            self.advance();
+        }
+    }
+
+    fn satisfies_bound(&self, ttype: &TType, bound: &str) -> bool {
+        match (bound, ttype) {
+            (
+                "Comparable",
+                TType::Int
+                | TType::Sized { .. }
+                | TType::BigInt
+                | TType::Float
+                | TType::Char
+                | TType::String,
+            ) => true,
+            ("Comparable", TType::Custom { name, .. }) => self.defines_method(name, "__lt__"),
+            (
+                "Hash",
+                TType::Int | TType::Sized { .. } | TType::Bool | TType::Char | TType::String,
+            ) => true,
+            ("Hash", TType::Tuple { elements }) => {
+                elements.iter().all(|t| self.satisfies_bound(t, bound))
+            }
+            ("Hash", TType::Custom { name, .. }) => self.defines_method(name, "__hash__"),
+            (
+                "Show",
+                TType::Int
+                | TType::Sized { .. }
+                | TType::BigInt
+                | TType::Float
+                | TType::Bool
+                | TType::Char
+                | TType::String,
+            ) => true,
+            ("Show", TType::List { inner } | TType::Option { inner }) => {
+                self.satisfies_bound(inner, bound)
+            }
+            ("Show", TType::Tuple { elements }) => {
+                elements.iter().all(|t| self.satisfies_bound(t, bound))
+            }
+            ("Show", TType::Custom { name, .. }) => self.defines_method(name, "toString"),
+            (_, TType::Generic { name }) => self.environment.has_bound(name, bound),
+            _ => false,
+        }
+    }
+
+    // whether some overload of name::method exists
+    fn defines_method(&self, name: &str, method: &str) -> bool {
+        let method = format!("{}::{}", name, method);
+        self.environment.values[0].keys().any(|key| {
+            key.strip_prefix(method.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('_'))
+        })
+    }
+
+    // the types a call binds to bounded generics have to meet the bounds
+    fn check_bounds(
+        &self,
+        function_id: &str,
+        type_map: &HashMap<Rc<str>, TType>,
+        pos: FilePosition,
+    ) -> Result<(), NovaError> {
+        let Some(symbol) = self
+            .environment
+            .values
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(function_id))
+        else {
+            return Ok(());
+        };
+        for (generic, bound) in symbol.bounds.iter() {
+            let Some(ttype) = type_map.get(generic) else {
+                continue;
+            };
+            if !self.satisfies_bound(ttype, bound) {
+                return Err(self.generate_error_with_pos(
+                    format!(
+                        "{} does not satisfy the bound {}: {} of {}",
+                        self.describe_type(ttype),
+                        generic,
+                        bound,
+                        function_id
+                    ),
+                    match bound.as_ref() {
+                        "Hash" => "Hash types are Int, the sized integers, Bool, Char, String, tuples of them and types that define __hash__",
+                        "Show" => "Show types are Int, the sized integers, BigInt, Float, Bool, Char, String, lists, options and tuples of them and types that define toString",
+                        _ => "Comparable types are Int, the sized integers, BigInt, Float, Char, String and types that define __lt__",
+                    },
+                    pos,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn function_declaration(&mut self) -> Result<Option<Statement>, NovaError> {
+        let doc = self.doc.take();
         self.consume_identifier(Some("fn"))?;
+        let impl_type = self.impl_type.take();
         let builtin_types = [
             "List", "Option", "Function", "Tuple", "Bool", "Int", "Float", "String", "Char",
         ];
@@ -5180,12 +9034,28 @@ impl Parser {
         // check if dunder method
         // check to see if next is the extends keyword with a custom type name and get the custom type name
         let mut custom_type = Rc::default();
-        if self.current_token().is_some_and(|t| t.is_id("extends")) {
+        if let Some(target) = &impl_type {
+            if self
+                .current_token()
+                .is_some_and(|t| t.is_keyword(KeyWord::Extends) || t.is_keyword(KeyWord::Mod))
+            {
+                return Err(self.generate_error_with_pos(
+                    format!("Functions in an impl block already extend {}", target),
+                    "Remove extends or mod from this function",
+                    self.get_current_token_position(),
+                ));
+            }
+        }
+        if self
+            .current_token()
+            .is_some_and(|t| t.is_keyword(KeyWord::Extends))
+        {
             self.advance();
             // if current token is ( then get the custom type name , otherwise extend from first argument
             if self.current_token().is_some_and(|t| t.is_symbol(LeftParen)) {
                 self.consume_symbol(LeftParen)?;
-                (custom_type, _) = self.get_identifier()?;
+                let (name, pos) = self.get_identifier()?;
+                custom_type = self.resolve_type(name, pos)?;
                 // check to see if its a valid custom type
                 if !self.environment.custom_types.contains_key(&custom_type)
                     && !builtin_types.contains(&&*custom_type)
@@ -5201,7 +9071,10 @@ impl Parser {
             } else {
                 get_first = true;
             }
-        } else if self.current_token().is_some_and(|t| t.is_id("mod")) {
+        } else if self
+            .current_token()
+            .is_some_and(|t| t.is_keyword(KeyWord::Mod))
+        {
             self.advance();
             self.consume_symbol(LeftParen)?;
             (custom_type, _) = self.get_identifier()?;
@@ -5221,29 +9094,63 @@ impl Parser {
 
         // get parameters
         self.consume_symbol(LeftParen)?;
-        let parameters = self.parameter_list()?;
+        let parameters = match &impl_type {
+            Some(target) => self.receiver_parameter_list(target)?,
+            None => self.parameter_list()?,
+        };
+        let positions = std::mem::take(&mut self.parameter_positions);
         self.consume_symbol(RightParen)?;
+        if let Some(target) = &impl_type {
+            let has_receiver = parameters
+                .first()
+                .is_some_and(|(_, name)| &**name == "self");
+            if has_receiver {
+                get_first = true;
+            } else {
+                // associated function, called as Type::name without an instance
+                custom_type = target.custom_to_string().unwrap_or_default().into();
+                is_extended = true;
+            }
+        }
         // get output type
 
         let mut output = TType::Void;
-        if self.current_token().is_some_and(|t| t.is_symbol(LeftBrace)) {
+        if self.current_token().is_some_and(|t| {
+            t.is_symbol(LeftBrace) || t.is_id("requires") || t.is_id("ensures") || t.is_id("where")
+        }) {
         } else {
+            let output_pos = self.get_current_token_position();
             self.consume_operator(Operator::RightArrow)?;
             output = self.ttype()?;
+            // a hole in a parameter is an unnamed generic, but nothing fills one
+            // that only appears in the return type
+            if output.has_hole() {
+                return Err(self.generate_error_with_pos(
+                    format!("Cannot infer the type holes in return type {}", output),
+                    "Return types are not inferred from the function body, write the type out",
+                    output_pos,
+                ));
+            }
         }
         // retrieve types for input
         let mut typeinput = vec![];
         for arg in parameters.iter() {
             typeinput.push(arg.0.clone())
         }
-        // is function using generics?
-        let generic = Self::is_generic(&typeinput);
+        let mut declared_generics = Self::collect_generics(&typeinput);
+        declared_generics.extend(Self::collect_generics(std::slice::from_ref(&output)));
+        let bounds = self.where_clause(&declared_generics)?;
+        // is function using generics? a function taking a union cannot be
+        // overloaded either, its calls are checked like a generic function's
+        let generic = Self::is_generic(&typeinput) || typeinput.iter().any(TType::has_union);
 
         // check if dunder method
         match identifier.as_ref() {
             id @ "__add__"
             | id @ "__and__"
             | id @ "__or__"
+            | id @ "__bitand__"
+            | id @ "__bitor__"
             | id @ "__sub__"
             | id @ "__mul__"
             | id @ "__div__"
@@ -5298,52 +9205,21 @@ impl Parser {
             identifier = format!("{}::{}", custom_type, identifier).into();
         }
 
+        if !is_extended && !is_mod && !get_first {
+            identifier = self.qualify_declaration(identifier);
+        }
+
         if !is_extended && get_first {
             //println!("{} {}", identifier, parameters.len());
             if let Some((ttype, _)) = parameters.first() {
-                identifier = match ttype {
-                    TType::Custom { name, .. } => {
-                        format!("{}::{}", name, identifier)
-                    }
-                    TType::List { .. } => {
-                        format!("List::{}", identifier)
-                    }
-                    TType::Option { .. } => {
-                        format!("Option::{}", identifier)
-                    }
-                    TType::Function { parameters, .. } => {
-                        let repeated_elements: String = "(_)".repeat(parameters.len());
-                        format!("Function{}::{}", repeated_elements, identifier)
-                    }
-                    TType::Tuple { elements } => {
-                        let repeated_elements: String = "(_)".repeat(elements.len());
-                        format!("Tuple{}::{}", repeated_elements, identifier)
-                    }
-                    TType::Bool => {
-                        format!("Bool::{}", identifier)
-                    }
-                    TType::Int => {
-                        format!("Int::{}", identifier)
-                    }
-                    TType::Float => {
-                        format!("Float::{}", identifier)
-                    }
-                    TType::String => {
-                        format!("String::{}", identifier)
-                    }
-                    TType::Char => {
-                        format!("Char::{}", identifier)
-                    }
-                    _ => {
-                        // error
-                        return Err(self.generate_error_with_pos(
-                            "Cannot extend from type",
-                            "Cannot extend from this type",
-                            pos.clone(),
-                        ));
-                    }
-                }
-                .into();
+                let Some(receiver) = Self::method_type(ttype) else {
+                    return Err(self.generate_error_with_pos(
+                        "Cannot extend from type",
+                        "Cannot extend from this type",
+                        pos.clone(),
+                    ));
+                };
+                identifier = format!("{}::{}", receiver, identifier).into();
             }
         }
         //dbg!(identifier.clone());
@@ -5384,7 +9260,22 @@ impl Parser {
             typeinput.push(TType::None)
         }
 
+        // a program's own definition replaces the prelude's
+        let key: Rc<str> = if generic {
+            identifier.clone()
+        } else {
+            generate_unique_string(&identifier, &typeinput).into()
+        };
+        if self.prelude.has(&key) {
+            self.prelude.remove(&key);
+            if self.environment.no_override.has(&key) {
+                self.environment.no_override.remove(&key);
+            }
+            self.environment.values.last_mut().unwrap().remove(&key);
+        }
+
         // insert function into environment
+        let name = identifier.clone();
         if !generic {
             // check if normal function exist
             if self
@@ -5435,11 +9326,21 @@ impl Parser {
                 SymbolKind::GenericFunction,
             );
         }
+        if let Some(symbol) = self
+            .environment
+            .values
+            .last_mut()
+            .unwrap()
+            .get_mut(&identifier)
+        {
+            symbol.bounds = bounds.clone();
+        }
+        self.record_visibility(identifier.clone(), name);
         //println!("{} {}", identifier, parameters.len());
         // check for no rightbrace
         if self
             .current_token()
-            .is_some_and(|t| !t.is_symbol(LeftBrace))
+            .is_some_and(|t| !t.is_symbol(LeftBrace) && !t.is_id("requires") && !t.is_id("ensures"))
         {
             //dbg!(&identifier);
             self.environment.forward_declarations.insert(
@@ -5454,10 +9355,11 @@ impl Parser {
         let mut generic_list = Self::collect_generics(&typeinput);
         generic_list.extend(Self::collect_generics(&[output.clone()]));
         self.environment.live_generics.push(generic_list.clone());
+        self.environment.live_bounds.push(bounds);
         // parse body with scope
         self.environment.push_scope();
         // insert params into scope
-        for (ttype, id) in parameters.iter() {
+        for ((ttype, id), pos) in parameters.iter().zip(positions.iter()) {
             match ttype {
                 TType::Function {
                     parameters,
@@ -5482,64 +9384,14 @@ impl Parser {
             }
         }
 
+        let contracts = self.contract_list(&output)?;
         let mut statements = self.block()?;
 
-        // capture variables -----------------------------------
-        let mut captured: Vec<Rc<str>> = self
-            .environment
-            .captured
-            .last()
-            .unwrap()
-            .iter()
-            .map(|v| v.0.clone())
-            .collect();
-
-        self.environment.pop_scope();
+        let names: Vec<Rc<str>> = parameters.iter().map(|(_, id)| id.clone()).collect();
+        let tests: Vec<&Expr> = contracts.iter().map(|contract| &contract.test).collect();
+        let captured = self.end_captures(&names, &tests, &statements);
         self.environment.live_generics.pop();
-        for c in captured.iter() {
-            if let Some(mc) = self.environment.get_type_capture(&c.clone()) {
-                let pos = self.get_current_token_position();
-
-                self.environment.captured.last_mut().unwrap().insert(
-                    c.clone(),
-                    Symbol {
-                        id: mc.1,
-                        ttype: mc.0,
-                        pos: Some(pos),
-                        kind: mc.2,
-                    },
-                );
-            }
-        }
-
-        captured = self
-            .environment
-            .captured
-            .last()
-            .unwrap()
-            .iter()
-            .map(|v| v.0.clone())
-            .collect();
-        for arg in parameters.iter() {
-            let name = arg.1.clone();
-            // check if name is in captured
-            if captured.contains(&name) {
-                // remove from captured
-                // remove from captured variable
-                captured.retain(|x| x != &name);
-            }
-        }
-
-        for dc in captured.iter() {
-            if let Some(v) = self.environment.values.last().unwrap().get(dc) {
-                if let SymbolKind::Captured = v.kind {
-                } else {
-                    self.environment.captured.last_mut().unwrap().remove(dc);
-                }
-            }
-        }
-
-        // done capturing variables ----------------------------
+        self.environment.live_bounds.pop();
 
         // if output void, insert return as last statement if one wasnt added
         if output == TType::Void {
@@ -5572,9 +9424,60 @@ impl Parser {
             parameters: input,
             body: statements,
             captures: captured,
+            contracts,
+            doc,
         }))
     }
 
+    // parses the requires and ensures clauses between a function signature and its body,
+    // ensures clauses can refer to the returned value as result
+    fn contract_list(&mut self, output: &TType) -> Result<Vec<Contract>, NovaError> {
+        let mut contracts = vec![];
+        loop {
+            let kind = if self.current_token().is_some_and(|t| t.is_id("requires")) {
+                ContractKind::Requires
+            } else if self.current_token().is_some_and(|t| t.is_id("ensures")) {
+                ContractKind::Ensures
+            } else {
+                break;
+            };
+            self.advance();
+            // a clause can hold several conditions separated by commas
+            loop {
+                let position = self.get_current_token_position();
+                self.environment.push_block();
+                if kind == ContractKind::Ensures && output != &TType::Void {
+                    self.environment.insert_symbol(
+                        "result",
+                        output.clone(),
+                        None,
+                        SymbolKind::Variable,
+                    );
+                }
+                let test = self.expr()?;
+                self.environment.pop_block();
+                if test.get_type() != TType::Bool {
+                    return Err(self.generate_error_with_pos(
+                        "Contract expression must return a bool",
+                        format!("got {}", test.get_type()),
+                        position,
+                    ));
+                }
+                contracts.push(Contract {
+                    kind: kind.clone(),
+                    test,
+                    position,
+                });
+                if self.current_token().is_some_and(|t| t.is_symbol(Comma)) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+        Ok(contracts)
+    }
+
     // function to see if all returns are valid and to see if stuff like if statements have returns, match statements have returns ect
     fn will_return(
         &self,
@@ -5586,8 +9489,8 @@ impl Parser {
             match statement {
                 Statement::Return { ttype, .. } => {
                     match self.check_and_map_types(
-                        &[ttype.clone()],
-                        &[return_type.clone()],
+                        std::slice::from_ref(ttype),
+                        std::slice::from_ref(&return_type),
                         &mut HashMap::default(),
                         pos.clone(),
                     ) {
@@ -5620,7 +9523,7 @@ impl Parser {
                     if let Expr::Return { expr, ttype: _ } = expr {
                         match self.check_and_map_types(
                             &[expr.get_type()],
-                            &[return_type.clone()],
+                            std::slice::from_ref(&return_type),
                             &mut HashMap::default(),
                             pos.clone(),
                         ) {
@@ -5754,8 +9657,10 @@ impl Parser {
                     }
                     self.will_return(body, return_type.clone(), pos.clone())?;
                 }
-                Statement::Block { body, .. } => {
-                    self.will_return(body, return_type.clone(), pos.clone())?;
+                Statement::Block { body, .. } | Statement::With { body, .. } => {
+                    if self.will_return(body, return_type.clone(), pos.clone())? {
+                        return Ok(true);
+                    }
                 }
                 Statement::Match {
                     expr,
@@ -5776,7 +9681,7 @@ impl Parser {
                     for arm in arms.iter() {
                         for statement in arm.2.iter() {
                             arms_return.push(self.will_return(
-                                &[statement.clone()],
+                                std::slice::from_ref(statement),
                                 return_type.clone(),
                                 pos.clone(),
                             )?);
@@ -5786,7 +9691,7 @@ impl Parser {
                     if let Some(default) = default {
                         for statement in default.iter() {
                             arms_return.push(self.will_return(
-                                &[statement.clone()],
+                                std::slice::from_ref(statement),
                                 return_type.clone(),
                                 pos.clone(),
                             )?);
@@ -5803,6 +9708,8 @@ impl Parser {
                 Statement::Struct { .. } => {}
                 Statement::Function { .. } => {}
                 Statement::ForwardDec { .. } => {}
+                Statement::Lazy { .. } => {}
+                Statement::Line { .. } => {}
                 Statement::Continue => {}
                 Statement::Break => {}
                 Statement::Unwrap { .. } => {}
@@ -5824,13 +9731,116 @@ impl Parser {
     }
 
     fn expression_statement(&mut self) -> Result<Option<Statement>, NovaError> {
-        // check for return statement
-        self.expr().map(|expr| {
-            Some(Statement::Expression {
-                ttype: expr.get_type(),
-                expr,
-            })
-        })
+        let pos = self.get_current_token_position();
+        let expr = self.expr()?;
+        // a trailing ; drops the value on purpose
+        let discarded = self.current_token().is_some_and(|t| t.is_symbol(Semicolon));
+        self.unused_result = None;
+        if !discarded && !matches!(expr.get_type(), TType::Void | TType::None) {
+            let index = self.warnings.len();
+            self.warn(
+                format!(
+                    "Unused result of type {}",
+                    self.describe_type(&expr.get_type())
+                ),
+                "Assign it to _ or end the statement with ; to discard the value on purpose",
+                pos,
+            );
+            // the repl gets no warnings
+            if self.warnings.len() > index {
+                self.unused_result = Some(index);
+            }
+        }
+        Ok(Some(Statement::Expression {
+            ttype: expr.get_type(),
+            expr,
+        }))
+    }
+
+    // the last statement of a block is its value, so it is not unused
+    fn keep_block_value(&mut self) {
+        if let Some(index) = self.unused_result.take() {
+            self.warnings.remove(index);
+        }
+    }
+
+    fn warn(
+        &mut self,
+        msg: impl Into<Cow<'static, str>>,
+        note: impl Into<Cow<'static, str>>,
+        position: FilePosition,
+    ) {
+        self.warn_with_fixes(msg, note, position, vec![]);
+    }
+
+    fn warn_with_fixes(
+        &mut self,
+        msg: impl Into<Cow<'static, str>>,
+        note: impl Into<Cow<'static, str>>,
+        position: FilePosition,
+        fixes: Vec<Fix>,
+    ) {
+        // the repl has nothing to point at and shows every value anyway
+        if self.filepath.is_some() {
+            self.warnings.push(Warning {
+                msg: msg.into(),
+                note: note.into(),
+                position,
+                fixes,
+            });
+        }
+    }
+
+    // the full name of module::name, a renamed builtin resolves to its new
+    // name and warns with a fix that rewrites the call
+    fn module_member(&mut self, module: &str, name: &str, position: FilePosition) -> Rc<str> {
+        let full = format!("{}::{}", module, name);
+        let Some((_, renamed)) = RENAMED.iter().find(|(old, _)| *old == full) else {
+            return full.into();
+        };
+        let new_name = renamed.rsplit("::").next().unwrap_or(renamed);
+        let end = FilePosition {
+            col: position.col + name.chars().count(),
+            ..position.clone()
+        };
+        let fix = Fix {
+            msg: format!("rename to {}", new_name).into(),
+            start: position.clone(),
+            end,
+            replacement: new_name.to_owned(),
+        };
+        self.warn_with_fixes(
+            format!("{} is deprecated", full),
+            format!("It was renamed to {}, nova fix updates it", renamed),
+            position,
+            vec![fix],
+        );
+        (*renamed).into()
+    }
+
+    // exit() from before exit took a status still stops with 0, and warns
+    // with a fix that writes the 0 between the parentheses
+    fn exit_without_status(&mut self) -> Expr {
+        let position = self
+            .input
+            .get(self.index.saturating_sub(1))
+            .map_or_else(FilePosition::default, |t| t.position());
+        let fix = Fix {
+            msg: "exit with status 0".into(),
+            start: position.clone(),
+            end: position.clone(),
+            replacement: "0".to_owned(),
+        };
+        self.warn_with_fixes(
+            "exit needs a status",
+            "0 is success, anything up to 255 a failure, nova fix adds the 0",
+            position,
+            vec![fix],
+        );
+        Expr::Literal {
+            ttype: TType::Int,
+            value: Atom::Integer { value: 0 },
+        }
     }
 
     fn block(&mut self) -> Result<Vec<Statement>, NovaError> {
@@ -5851,6 +9861,7 @@ impl Parser {
         self.consume_symbol(LeftBrace)?;
         self.environment.push_block();
         let statements = self.compound_statement()?;
+        self.keep_block_value();
         self.environment.pop_block();
         self.consume_symbol(RightBrace)?;
         // check if last statement is a statement expression
@@ -5868,11 +9879,24 @@ impl Parser {
         })
     }
 
+    // every statement is preceded by a line marker so the vm can report line events
+    fn line_statement(&mut self, statements: &mut Vec<Statement>) -> Result<(), NovaError> {
+        let position = self.get_current_token_position();
+        self.unused_result = None;
+        let statement = self.statement()?;
+        if !matches!(statement, Some(Statement::Expression { .. })) {
+            self.unused_result = None;
+        }
+        if let Some(statement) = statement {
+            statements.push(Statement::Line { position });
+            statements.push(statement);
+        }
+        Ok(())
+    }
+
     fn compound_statement(&mut self) -> Result<Vec<Statement>, NovaError> {
         let mut initial_statements = vec![];
-        if let Some(statement) = self.statement()? {
-            initial_statements.push(statement)
-        };
+        self.line_statement(&mut initial_statements)?;
         let statements = {
             let mut statements = initial_statements;
 
@@ -5889,9 +9913,7 @@ impl Parser {
                 {
                     break;
                 }
-                if let Some(statement) = self.statement()? {
-                    statements.push(statement);
-                }
+                self.line_statement(&mut statements)?;
                 if self.index == index_change {
                     return Err(self.generate_error("Expected statement", "Expected statement"));
                 }
@@ -5901,20 +9923,38 @@ impl Parser {
         Ok(statements)
     }
 
+    // parses the input as a single expression, used by Lang::parseExpr
+    pub fn parse_expr(&mut self) -> Result<Expr, NovaError> {
+        let expr = self.expr()?;
+        self.eof()?;
+        Ok(expr)
+    }
+
     pub fn parse(&mut self) -> Result<(), NovaError> {
         // if repl mode no need to parse module
         if self.filepath.is_none() {
+            let prelude = self.load_prelude()?;
             self.ast.program = self.compound_statement()?;
-            return self.eof();
+            self.ast.program.insert(0, prelude);
+            self.eof()?;
+            return self.check_auto_lets();
         }
 
         if self.current_token().is_some_and(|t| t.is_id("module")) {
             self.consume_identifier(Some("module"))?;
             let (module_name, _) = self.get_identifier()?;
+            self.module_name = Some(module_name.clone());
+            self.visible_modules.insert(module_name.clone());
+            self.open_modules.insert(module_name.clone());
             if self.modules.has(&module_name) {
                 return Ok(());
             }
-            self.modules.insert(module_name);
+            self.modules.insert(module_name.clone());
+            if let Some(filepath) = &self.filepath {
+                self.module_cache
+                    .insert(canonical_path(filepath), module_name.clone());
+                self.module_files.insert(module_name, filepath.clone());
+            }
         } else {
             return Err(self.generate_error(
                 "Expected module declaration",
@@ -5922,7 +9962,19 @@ impl Parser {
             ));
         }
 
+        let prelude = (!self.imported).then(|| self.load_prelude()).transpose()?;
         self.ast.program = self.compound_statement()?;
-        self.eof()
+        self.eof()?;
+        self.check_auto_lets()?;
+        self.ast.program.splice(0..0, prelude);
+        // imported files are part of the program, checked once it is complete
+        if !self.imported {
+            self.warnings.extend(lint::dead_stores(&self.ast.program));
+            self.warnings.sort_by_key(|warning| {
+                let position = &warning.position;
+                (position.filepath.clone(), position.line, position.col)
+            });
+        }
+        Ok(())
     }
 }
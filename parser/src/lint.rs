@@ -0,0 +1,419 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use common::{
+    error::Warning,
+    fileposition::FilePosition,
+    nodes::{Atom, Expr, Statement},
+    tokens::Operator,
+};
+
+// finds values stored into a local variable that nothing reads afterwards.
+// the walk follows the source order of a function body, branches and loops
+// get a region of their own so a store is only called dead when a later store
+// in the same or an enclosing region overwrites it on every path
+pub fn dead_stores(program: &[Statement]) -> Vec<Warning> {
+    let mut lint = DeadStores::new(None, false);
+    lint.statements(program);
+    let mut warnings: Vec<Warning> = lint.warnings.into_iter().map(|(_, w)| w).collect();
+    warnings.sort_by_key(|warning| {
+        (
+            warning.position.filepath.clone(),
+            warning.position.line,
+            warning.position.col,
+            warning.msg.clone(),
+        )
+    });
+    // generic functions are checked once for every type they are used with
+    warnings.dedup();
+    warnings
+}
+
+struct DeadStores {
+    // false at the top level, globals can be read by any function
+    tracking: bool,
+    // where each local was declared
+    locals: HashMap<Rc<str>, FilePosition>,
+    read: HashSet<Rc<str>>,
+    // stores not read yet, with the regions they were made in
+    pending: HashMap<Rc<str>, Vec<(FilePosition, Vec<usize>)>>,
+    regions: Vec<usize>,
+    next_region: usize,
+    line: Option<FilePosition>,
+    // the variable each warning is about, the function is done once every
+    // warning about a variable that is never read is replaced by a single one
+    warnings: Vec<(Option<Rc<str>>, Warning)>,
+}
+
+impl DeadStores {
+    fn new(line: Option<FilePosition>, tracking: bool) -> Self {
+        DeadStores {
+            tracking,
+            locals: HashMap::new(),
+            read: HashSet::new(),
+            pending: HashMap::new(),
+            regions: vec![0],
+            next_region: 1,
+            line,
+            warnings: vec![],
+        }
+    }
+
+    fn function(&mut self, body: &[Statement]) {
+        let mut lint = DeadStores::new(self.line.clone(), true);
+        lint.statements(body);
+        for (name, stores) in std::mem::take(&mut lint.pending) {
+            for (position, _) in stores {
+                lint.warn(
+                    Some(name.clone()),
+                    format!("Value assigned to '{}' is never read", name),
+                    "Nothing reads the variable after this assignment",
+                    position,
+                );
+            }
+        }
+        let read = std::mem::take(&mut lint.read);
+        lint.warnings
+            .retain(|(name, _)| name.as_ref().is_none_or(|name| read.contains(name)));
+        for (name, position) in std::mem::take(&mut lint.locals) {
+            if !read.contains(&name) && !name.starts_with('_') {
+                lint.warn(
+                    None,
+                    format!("Variable '{}' is never read", name),
+                    format!("Rename it to _{} if this is on purpose", name),
+                    position,
+                );
+            }
+        }
+        self.warnings.extend(lint.warnings);
+    }
+
+    fn warn(
+        &mut self,
+        name: Option<Rc<str>>,
+        msg: impl Into<Cow<'static, str>>,
+        note: impl Into<Cow<'static, str>>,
+        position: FilePosition,
+    ) {
+        let warning = Warning {
+            msg: msg.into(),
+            note: note.into(),
+            position,
+            fixes: vec![],
+        };
+        self.warnings.push((name, warning));
+    }
+
+    fn read(&mut self, name: &Rc<str>) {
+        self.read.insert(name.clone());
+        self.pending.remove(name);
+    }
+
+    fn store(&mut self, name: &Rc<str>) {
+        if !self.tracking || name.starts_with('_') || !self.locals.contains_key(name) {
+            return;
+        }
+        let Some(line) = self.line.clone() else {
+            return;
+        };
+        let stores = self.pending.remove(name).unwrap_or_default();
+        let (overwritten, live): (Vec<_>, Vec<_>) = stores
+            .into_iter()
+            .partition(|(_, regions)| regions.starts_with(&self.regions));
+        for (position, _) in overwritten {
+            self.warn(
+                Some(name.clone()),
+                format!("Value assigned to '{}' is never read", name),
+                "It is overwritten before anything reads it",
+                position,
+            );
+        }
+        let mut stores = live;
+        stores.push((line, self.regions.clone()));
+        self.pending.insert(name.clone(), stores);
+    }
+
+    fn region(&mut self, body: &[Statement]) {
+        self.regions.push(self.next_region);
+        self.next_region += 1;
+        self.statements(body);
+        self.regions.pop();
+    }
+
+    // a store late in a loop body can be read by an earlier statement on the
+    // next iteration, so anything read in the loop stays alive
+    fn repeat(&mut self, tests: &[&Expr], body: &[Statement]) {
+        let mut names = HashSet::new();
+        for test in tests {
+            expr_reads(test, &mut names);
+        }
+        statement_reads(body, &mut names);
+        let region = self.next_region;
+        self.next_region += 1;
+        self.regions.push(region);
+        for test in tests {
+            self.expr(test);
+        }
+        self.statements(body);
+        self.regions.pop();
+        for name in names {
+            if let Some(stores) = self.pending.get_mut(&name) {
+                stores.retain(|(_, regions)| !regions.contains(&region));
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Line { position } => self.line = Some(position.clone()),
+                Statement::Function { body, .. } => self.function(body),
+                Statement::Return { expr, .. } | Statement::Expression { expr, .. } => {
+                    self.expr(expr)
+                }
+                Statement::If {
+                    test,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.expr(test);
+                    self.region(body);
+                    if let Some(alternative) = alternative {
+                        self.region(alternative);
+                    }
+                }
+                Statement::Unwrap {
+                    identifier,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.read(identifier);
+                    self.region(body);
+                    if let Some(alternative) = alternative {
+                        self.region(alternative);
+                    }
+                }
+                Statement::IfLet {
+                    expr,
+                    body,
+                    alternative,
+                    ..
+                } => {
+                    self.expr(expr);
+                    self.region(body);
+                    if let Some(alternative) = alternative {
+                        self.region(alternative);
+                    }
+                }
+                Statement::While { test, body } => self.repeat(&[test], body),
+                Statement::WhileLet { expr, body, .. } => self.repeat(&[expr], body),
+                Statement::For {
+                    init,
+                    test,
+                    inc,
+                    body,
+                } => {
+                    self.expr(init);
+                    self.repeat(&[test, inc], body);
+                }
+                Statement::Foreach { expr, body, .. } => {
+                    self.expr(expr);
+                    self.repeat(&[], body);
+                }
+                Statement::ForRange {
+                    start,
+                    end,
+                    step,
+                    body,
+                    ..
+                } => {
+                    self.expr(start);
+                    self.expr(end);
+                    if let Some(step) = step {
+                        self.expr(step);
+                    }
+                    self.repeat(&[], body);
+                }
+                Statement::Lazy { expr, .. } => self.expr(expr),
+                Statement::Block { body, .. } => self.statements(body),
+                Statement::With {
+                    expr, body, close, ..
+                } => {
+                    self.expr(expr);
+                    self.statements(body);
+                    self.expr(close);
+                }
+                Statement::Match {
+                    expr,
+                    arms,
+                    default,
+                    ..
+                } => {
+                    self.expr(expr);
+                    for (_, _, body) in arms {
+                        self.region(body);
+                    }
+                    if let Some(default) = default {
+                        self.region(default);
+                    }
+                }
+                Statement::Continue
+                | Statement::Break
+                | Statement::Pass
+                | Statement::Struct { .. }
+                | Statement::Enum { .. }
+                | Statement::ForwardDec { .. } => {}
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Let {
+                identifier,
+                expr,
+                global,
+                ..
+            } => {
+                self.expr(expr);
+                if !global && self.tracking {
+                    if let Some(line) = self.line.clone() {
+                        self.locals.entry(identifier.clone()).or_insert(line);
+                    }
+                    if !matches!(**expr, Expr::None) {
+                        self.store(identifier);
+                    }
+                }
+            }
+            Expr::Binop { op, lhs, rhs, .. } => match (op, &**lhs) {
+                (
+                    Operator::Assignment,
+                    Expr::Literal {
+                        value: Atom::Id { name },
+                        ..
+                    },
+                ) => {
+                    self.expr(rhs);
+                    self.store(name);
+                }
+                (
+                    Operator::AddAssign | Operator::SubAssign,
+                    Expr::Literal {
+                        value: Atom::Id { name },
+                        ..
+                    },
+                ) => {
+                    self.expr(rhs);
+                    self.read(name);
+                    self.store(name);
+                }
+                _ => {
+                    self.expr(lhs);
+                    self.expr(rhs);
+                }
+            },
+            Expr::Literal { value, .. } => match value {
+                Atom::Id { name } => self.read(name),
+                Atom::Call {
+                    name, arguments, ..
+                } => {
+                    self.read(name);
+                    for argument in arguments {
+                        self.expr(argument);
+                    }
+                }
+                _ => {}
+            },
+            Expr::Closure { body, captures, .. } => {
+                for capture in captures {
+                    self.read(capture);
+                }
+                self.function(body);
+            }
+            Expr::ListConstructor { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::ListCompConstructor {
+                loops,
+                expr,
+                guards,
+                ..
+            } => {
+                for (_, source) in loops {
+                    self.expr(source);
+                }
+                for expr in expr.iter().chain(guards) {
+                    self.expr(expr);
+                }
+            }
+            Expr::Field { expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Propagate { expr, .. }
+            | Expr::Return { expr, .. } => self.expr(expr),
+            Expr::Indexed {
+                container, index, ..
+            } => {
+                self.expr(container);
+                self.expr(index);
+            }
+            Expr::Sliced {
+                container,
+                start,
+                end,
+                step,
+                ..
+            } => {
+                self.expr(container);
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            Expr::Call { function, args, .. } => {
+                self.expr(function);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::StoreExpr { expr, body, .. } => {
+                self.expr(expr);
+                self.statements(body);
+            }
+            Expr::IfExpr {
+                test,
+                body,
+                alternative,
+                ..
+            } => {
+                self.expr(test);
+                for branch in [body, alternative] {
+                    self.regions.push(self.next_region);
+                    self.next_region += 1;
+                    self.expr(branch);
+                    self.regions.pop();
+                }
+            }
+            Expr::Block { body, .. } | Expr::Try { body, .. } => self.statements(body),
+            Expr::None | Expr::Void => {}
+        }
+    }
+}
+
+// every name read anywhere in the statements
+fn statement_reads(statements: &[Statement], names: &mut HashSet<Rc<str>>) {
+    let mut lint = DeadStores::new(None, false);
+    lint.statements(statements);
+    names.extend(lint.read);
+}
+
+fn expr_reads(expr: &Expr, names: &mut HashSet<Rc<str>>) {
+    let mut lint = DeadStores::new(None, false);
+    lint.expr(expr);
+    names.extend(lint.read);
+}
@@ -0,0 +1,298 @@
+use super::*;
+use pretty_assertions::assert_eq;
+
+// the captures of every closure in the program, outer closures before the
+// ones inside them
+#[track_caller]
+fn assert_captures(input: &str, output: &[&[&str]]) {
+    let mut parser = default();
+    parser.input = Lexer::new(input, None)
+        .tokenize()
+        .expect("Lexing failed unexpectedly");
+    let program = parser
+        .compound_statement()
+        .expect("Parsing failed unexpectedly");
+    let mut captures = vec![];
+    closures(&program, &mut captures);
+    let output: Vec<Vec<Rc<str>>> = output
+        .iter()
+        .map(|names| names.iter().map(|name| Rc::from(*name)).collect())
+        .collect();
+    assert_eq!(captures, output)
+}
+
+fn closures(statements: &[Statement], captures: &mut Vec<Vec<Rc<str>>>) {
+    for statement in statements {
+        match statement {
+            Statement::Expression {
+                expr: Expr::Let { expr, .. },
+                ..
+            } => closure(expr, captures),
+            Statement::Expression { expr, .. } | Statement::Return { expr, .. } => {
+                closure(expr, captures)
+            }
+            Statement::If {
+                body, alternative, ..
+            } => {
+                closures(body, captures);
+                closures(alternative.as_deref().unwrap_or_default(), captures);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn closure(expr: &Expr, captures: &mut Vec<Vec<Rc<str>>>) {
+    if let Expr::Closure {
+        body,
+        captures: names,
+        ..
+    } = expr
+    {
+        captures.push(names.clone());
+        closures(body, captures);
+    }
+}
+
+#[test]
+fn closure_captures_what_it_uses() {
+    assert_captures(
+        "let a = 1
+        let b = 2
+        let f = fn() -> Int { return b }",
+        &[&["b"]],
+    );
+}
+
+#[test]
+fn parameters_are_not_captured() {
+    assert_captures(
+        "let x = 1
+        let f = fn(x: Int) -> Int { return x }
+        let g = |x: Int| x + 1",
+        &[&[], &[]],
+    );
+}
+
+#[test]
+fn nested_closures_capture_through_the_outer_one() {
+    assert_captures(
+        "let a = 1
+        let f = fn(x: Int) -> fn() -> Int {
+            let y = x + 1
+            return fn() -> Int { return a + x + y }
+        }",
+        &[&["a"], &["a", "x", "y"]],
+    );
+}
+
+#[test]
+fn outer_closure_does_not_capture_its_own_locals() {
+    assert_captures(
+        "let f = fn() -> Int {
+            let local = 2
+            let g = || local * 2
+            return g()
+        }",
+        &[&[], &["local"]],
+    );
+}
+
+#[test]
+fn later_closures_do_not_inherit_captures() {
+    assert_captures(
+        "let a = 1
+        let b = 2
+        let f = || a
+        let g = || b",
+        &[&["a"], &["b"]],
+    );
+}
+
+#[test]
+fn sibling_closures_capture_separately() {
+    assert_captures(
+        "let a = 1
+        let b = 2
+        let f = fn() -> Int {
+            let g = || a
+            let h = || b
+            return g() + h()
+        }",
+        &[&["a", "b"], &["a"], &["b"]],
+    );
+}
+
+#[test]
+fn shadowed_names_are_not_captured() {
+    assert_captures(
+        "let x = 1
+        let f = fn() -> Int {
+            let x = 5
+            let g = |y: Int| y + x
+            return g(x)
+        }",
+        &[&[], &["x"]],
+    );
+}
+
+#[test]
+fn names_declared_in_a_block_stay_in_it() {
+    assert_captures(
+        "let x = 1
+        let f = fn(flag: Bool) -> Int {
+            if flag {
+                let x = 2
+                return x
+            }
+            return x
+        }",
+        &[&["x"]],
+    );
+}
+
+// the doc comment of every function, struct and enum in the program
+fn docs(input: &str) -> Vec<(Rc<str>, Option<Rc<str>>)> {
+    let mut parser = default();
+    parser.input = Lexer::new(input, None)
+        .tokenize()
+        .expect("Lexing failed unexpectedly");
+    let program = parser
+        .compound_statement()
+        .expect("Parsing failed unexpectedly");
+    program
+        .into_iter()
+        .filter_map(|statement| match statement {
+            Statement::Function {
+                identifier, doc, ..
+            }
+            | Statement::Struct {
+                identifier, doc, ..
+            }
+            | Statement::Enum {
+                identifier, doc, ..
+            } => Some((identifier, doc)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn doc_comments_belong_to_the_declaration_below() {
+    let found = docs(
+        "/// a point
+        pub struct Point { x: Int }
+        /// documents nothing
+        let x = 1
+        fn f() {}
+        /// kinds
+        pub enum Kind { A, B }",
+    );
+    let expected: Vec<(Rc<str>, Option<Rc<str>>)> = vec![
+        ("Point".into(), Some("a point".into())),
+        ("f_None".into(), None),
+        ("Kind".into(), Some("kinds".into())),
+    ];
+    assert_eq!(found, expected);
+}
+
+// parses main.nv with the other files only in memory. it is read like an
+// import so that the prelude, which needs the natives of novacore, is left out
+fn parse_files(files: &[(&str, &str)]) -> Result<Parser, NovaError> {
+    let mut parser = default();
+    parser.imported = true;
+    for (path, source) in files {
+        parser
+            .overlay
+            .insert(PathBuf::from(path), Rc::from(*source));
+    }
+    let main = Path::new("main.nv");
+    parser.input = Lexer::new(parser.overlay[main].clone(), Some(main)).tokenize()?;
+    parser.filepath = Some(main.into());
+    parser.parse()?;
+    Ok(parser)
+}
+
+#[test]
+fn types_of_two_modules_can_share_a_name() {
+    let parser = parse_files(&[
+        ("a.nv", "module a\npub struct Point { x: Int }"),
+        ("b.nv", "module b\npub struct Point { y: Float }"),
+        (
+            "main.nv",
+            "module main\nimport a\nimport b\nlet p = a::Point(1)\nlet q: b::Point = b::Point(1.5)",
+        ),
+    ])
+    .expect("Parsing failed unexpectedly");
+    assert!(parser.environment.custom_types.contains_key("a::Point"));
+    assert!(parser.environment.custom_types.contains_key("b::Point"));
+}
+
+#[test]
+fn an_imported_type_can_be_written_without_its_module() {
+    parse_files(&[
+        ("a.nv", "module a\npub struct Point { x: Int }"),
+        (
+            "main.nv",
+            "module main\nimport a\nlet p: Point = Point(1)\nlet x: Int = p.x",
+        ),
+    ])
+    .expect("Parsing failed unexpectedly");
+}
+
+#[test]
+fn a_type_two_imports_declare_needs_its_module() {
+    let error = parse_files(&[
+        ("a.nv", "module a\npub struct Point { x: Int }"),
+        ("b.nv", "module b\npub struct Point { y: Float }"),
+        (
+            "main.nv",
+            "module main\nimport a\nimport b\nlet p: Point = a::Point(1)",
+        ),
+    ])
+    .expect_err("Parsing succeeded unexpectedly");
+    assert!(matches!(
+        error,
+        NovaError::Parsing { msg, .. } if msg.contains("more than one imported module")
+    ));
+}
+
+#[test]
+fn a_constant_that_overflows_says_so() {
+    let error = parse_files(&[(
+        "main.nv",
+        "module main\nconst BIG = 9223372036854775807 + 1",
+    )])
+    .expect_err("Parsing succeeded unexpectedly");
+    assert!(matches!(
+        error,
+        NovaError::Parsing { msg, .. } if msg == "Constant 'BIG' overflows Int"
+    ));
+}
+
+#[test]
+fn negating_the_smallest_int_in_a_pattern_is_an_error() {
+    let error = parse_files(&[(
+        "main.nv",
+        "module main\nconst MIN = -9223372036854775807 - 1\nmatch 3 {\n    -MIN => {}\n    _ => {}\n}",
+    )])
+    .expect_err("Parsing succeeded unexpectedly");
+    assert!(matches!(
+        error,
+        NovaError::Parsing { msg, .. } if msg.contains("overflows Int")
+    ));
+}
+
+#[test]
+fn a_let_without_a_type_that_is_never_assigned_is_an_error() {
+    let error = parse_files(&[(
+        "main.nv",
+        "module main\nfn f() {\n    let w\n    println(\"no\")\n}",
+    )])
+    .expect_err("Parsing succeeded unexpectedly");
+    assert!(matches!(
+        error,
+        NovaError::Parsing { msg, position, .. }
+            if msg == "Cannot infer the type of 'w'" && position.line == 3
+    ));
+}
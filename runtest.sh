@@ -12,6 +12,25 @@ $nova run demo/function.nv
 $nova run demo/structs.nv
 $nova run demo/option_type.nv
 $nova run demo/fib.nv
+$nova run demo/ufcs.nv
+$nova run demo/json.nv
+$nova run demo/config.nv
+$nova run demo/derive.nv
+$nova run demo/flags.nv
+$nova run demo/files.nv
+$nova run demo/environment.nv
+$nova run demo/lists.nv
+$nova run demo/arguments.nv one "two three" --release
+$nova run demo/strings.nv
+$nova run demo/processes.nv
+$nova run demo/numbers.nv
+$nova run demo/sockets.nv
+$nova run demo/randoms.nv
+$nova run demo/hashing.nv
+$nova test demo/testing.nv
+$nova doc std/hash.nv
+$nova run demo/timing.nv
+printf 'one\ntwo\r\n\nlast' | $nova run demo/stdin.nv
 $nova run Aoc2024/nvaoc1.nv
 $nova run Aoc2024/nvaoc2.nv
 $nova run Aoc2024/nvaoc3.nv
@@ -26,4 +45,7 @@ $nova run std/hashmap.nv
 $nova run std/io.nv
 $nova run std/tui.nv
 $nova run std/tuple.nv
+$nova run std/json.nv
+$nova run std/toml.nv
+$nova run std/yaml.nv
 
@@ -1,43 +1,495 @@
 pub mod state;
+pub mod trace;
+// a native written as a plain function, Native::new wraps it for the vm
 pub type CallBack = fn(state: &mut state::State) -> Result<(), NovaError>;
 
 use std::{
     collections::HashMap,
+    fmt,
     io::{self, Write},
-    process::exit,
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use common::{
-    code::{byte_to_string, Code},
+    code::{byte_to_string, Code, Overflow},
     error::NovaError,
     fileposition::FilePosition,
+    ttype::Width,
+    utilities::format_float,
 };
 
 use modulo::Mod;
-use state::{Heap, State};
+use state::{Heap, Provenance, State};
 
 use crate::state::VmData;
 
+// return address of a call made by the host, run returns to it instead of jumping
+const HOST_RETURN: usize = usize::MAX;
+
+// settings for one vm. a vm owns all of its state, so any number of them can
+// run side by side in the same host process
+#[derive(Debug, Clone)]
+pub struct Config {
+    // print heap usage when the program ends
+    pub gc_stats: bool,
+    // heap size the garbage collector first runs at
+    pub gc_threshold: usize,
+    // most calls that can be in progress at once before the script is stopped
+    pub stack_limit: usize,
+    // stop the script when Int arithmetic overflows instead of wrapping around
+    pub overflow_checks: bool,
+    // the nova Worker::spawn runs scripts with, NOVA_BIN or nova on the PATH
+    // when it is not set
+    pub nova_binary: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            gc_stats: false,
+            gc_threshold: 999999999,
+            stack_limit: 1_000_000,
+            overflow_checks: false,
+            nova_binary: None,
+        }
+    }
+}
+
+// token a host thread can use to stop a running script. the vm checks it at
+// backward jumps and calls, so every loop and recursion hits a safepoint
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    // clears the request so the vm can run again after being stopped
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
+
+pub type CallHook = Rc<dyn Fn(&State, usize, Option<&FilePosition>)>;
+pub type ReturnHook = Rc<dyn Fn(&State)>;
+pub type LineHook = Rc<dyn Fn(&State, &FilePosition)>;
+
+// a function the NATIVE opcode calls, a CallBack or a closure from the host
+#[derive(Clone)]
+pub struct Native(Rc<NativeFn>);
+
+type NativeFn = dyn Fn(&mut State) -> Result<(), NovaError>;
+
+impl Native {
+    pub fn new(function: impl Fn(&mut State) -> Result<(), NovaError> + 'static) -> Native {
+        Native(Rc::new(function))
+    }
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Native")
+    }
+}
+
+// callbacks an embedder can register to trace execution. on_call gets the
+// address of the callee and the call site when it is known, on_return runs
+// before the frame is dropped and on_line runs as each statement starts
+#[derive(Clone, Default)]
+pub struct Hooks {
+    pub on_call: Option<CallHook>,
+    pub on_return: Option<ReturnHook>,
+    pub on_line: Option<LineHook>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_call", &self.on_call.is_some())
+            .field("on_return", &self.on_return.is_some())
+            .field("on_line", &self.on_line.is_some())
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Vm {
     pub runtime_errors_table: HashMap<usize, FilePosition>,
-    pub native_functions: Vec<CallBack>,
+    pub line_table: HashMap<usize, Vec<FilePosition>>,
+    pub function_names: HashMap<usize, Rc<str>>,
+    // global slot of the __lt__ of each type that defines one
+    pub operators: HashMap<Rc<str>, u32>,
+    // and of the __eq__
+    pub equality: HashMap<Rc<str>, u32>,
+    pub native_functions: Vec<Native>,
     pub state: state::State,
+    pub config: Config,
+    pub interrupt: InterruptHandle,
+    pub hooks: Hooks,
 }
 
 pub fn new() -> Vm {
-    Vm {
-        native_functions: vec![],
-        state: state::new(),
-        runtime_errors_table: HashMap::default(),
-    }
+    Vm::new(Config::default())
 }
 
 impl Vm {
+    pub fn new(config: Config) -> Vm {
+        let mut state = state::new();
+        state.threshold = config.gc_threshold;
+        state.nova_binary = config.nova_binary.clone();
+        Vm {
+            native_functions: vec![],
+            state,
+            runtime_errors_table: HashMap::default(),
+            line_table: HashMap::default(),
+            function_names: HashMap::default(),
+            operators: HashMap::default(),
+            equality: HashMap::default(),
+            config,
+            interrupt: InterruptHandle::default(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    pub fn on_call(&mut self, hook: impl Fn(&State, usize, Option<&FilePosition>) + 'static) {
+        self.hooks.on_call = Some(Rc::new(hook));
+    }
+
+    pub fn on_return(&mut self, hook: impl Fn(&State) + 'static) {
+        self.hooks.on_return = Some(Rc::new(hook));
+    }
+
+    pub fn on_line(&mut self, hook: impl Fn(&State, &FilePosition) + 'static) {
+        self.hooks.on_line = Some(Rc::new(hook));
+    }
+
+    // runs after the jump into the callee, so the callstack holds the return address
+    #[inline(always)]
+    fn call_hook(&self) {
+        let trace = self.state.trace.as_ref().filter(|t| t.calls);
+        if self.hooks.on_call.is_none() && trace.is_none() {
+            return;
+        }
+        let target = self.state.current_instruction;
+        let callsite = self
+            .state
+            .callstack
+            .last()
+            .and_then(|caller| self.runtime_errors_table.get(caller));
+        if let Some(trace) = trace {
+            match self.function_names.get(&target) {
+                Some(name) => trace.call(name, callsite),
+                None => trace.call(&format!("fn@{}", target), callsite),
+            }
+        }
+        if let Some(hook) = &self.hooks.on_call {
+            hook(&self.state, target, callsite);
+        }
+    }
+
     #[inline(always)]
+    fn return_hook(&self) {
+        if let Some(trace) = self.state.trace.as_ref().filter(|t| t.calls) {
+            trace.ret();
+        }
+        if let Some(hook) = &self.hooks.on_return {
+            hook(&self.state);
+        }
+    }
+
+    #[inline(always)]
+    fn line_hook(&self) {
+        if let Some(hook) = &self.hooks.on_line {
+            if let Some(positions) = self.line_table.get(&self.state.current_instruction) {
+                for position in positions {
+                    hook(&self.state, position);
+                }
+            }
+        }
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    #[inline(always)]
+    fn safepoint(&self) -> Result<(), NovaError> {
+        if self.interrupt.take() {
+            return Err(NovaError::Interrupted);
+        }
+        if self.state.callstack.len() > self.config.stack_limit {
+            return Err(NovaError::Runtime {
+                msg: format!(
+                    "Stack overflow, more than {} calls in progress",
+                    self.config.stack_limit
+                )
+                .into(),
+            });
+        }
+        Ok(())
+    }
+
+    // builds the error for a failed requires or ensures check, preconditions
+    // are reported at the call site that broke them when it is known
+    fn contract_error(&self) -> NovaError {
+        let clause = self.runtime_errors_table[&self.state.current_instruction].clone();
+        if self.state.program[self.state.current_instruction - 1] == Code::ENSURES {
+            return NovaError::RuntimeWithPos {
+                msg: "Postcondition failed, ensures clause does not hold".into(),
+                position: clause,
+            };
+        }
+        match self
+            .state
+            .callstack
+            .last()
+            .and_then(|caller| self.runtime_errors_table.get(caller))
+        {
+            Some(caller) => NovaError::RuntimeWithPos {
+                msg: format!(
+                    "Precondition failed, requires clause on line {} does not hold",
+                    clause.line
+                )
+                .into(),
+                position: caller.clone(),
+            },
+            None => NovaError::RuntimeWithPos {
+                msg: "Precondition failed, requires clause does not hold".into(),
+                position: clause,
+            },
+        }
+    }
+
+    // negative indices have the list length added by the compiler before they
+    // get here, so a value still below zero started out below -len
+    fn check_index(&self, index: i64, len: usize) -> Result<usize, NovaError> {
+        if index >= 0 && (index as usize) < len {
+            return Ok(index as usize);
+        }
+        let attempted = if index < 0 { index - len as i64 } else { index };
+        let msg = format!(
+            "Index out of bounds, index {} is not valid for a list of length {}",
+            attempted, len
+        )
+        .into();
+        match self
+            .runtime_errors_table
+            .get(&self.state.current_instruction)
+        {
+            Some(pos) => Err(NovaError::RuntimeWithPos {
+                msg,
+                position: pos.clone(),
+            }),
+            None => Err(NovaError::Runtime { msg }),
+        }
+    }
+
+    // Int arithmetic wraps around at 64 bits like the sized integers do, with
+    // overflow checks on the script stops at the operation that overflowed
+    #[inline(always)]
+    fn int_result(
+        &self,
+        checked: Option<i64>,
+        wrapped: i64,
+        describe: impl FnOnce() -> String,
+    ) -> Result<i64, NovaError> {
+        match checked {
+            Some(result) => Ok(result),
+            None if self.config.overflow_checks => {
+                let msg = format!("Integer overflow, {} does not fit in an Int", describe()).into();
+                match self
+                    .runtime_errors_table
+                    .get(&self.state.current_instruction)
+                {
+                    Some(pos) => Err(NovaError::RuntimeWithPos {
+                        msg,
+                        position: pos.clone(),
+                    }),
+                    None => Err(NovaError::Runtime { msg }),
+                }
+            }
+            None => Ok(wrapped),
+        }
+    }
+
+    // see Asm::TYPENAME, a list of strings ending in a name looks like a
+    // struct, the parser only asks where that cannot happen
+    fn runtime_type_name(&self, value: VmData) -> Rc<str> {
+        let name = match value {
+            VmData::Int(_) => "Int",
+            VmData::Sized(_, width) => width.name(),
+            VmData::Float(_) => "Float",
+            VmData::Bool(_) => "Bool",
+            VmData::Char(_) => "Char",
+            VmData::String(_) => "String",
+            VmData::BigInt(_) => "BigInt",
+            VmData::Function(_) | VmData::Closure(_) => "Function",
+            VmData::None(_) => "None",
+            VmData::Struct(_) | VmData::StackAddress(_) => "Struct",
+            VmData::List(index) => return self.type_name(index).unwrap_or("List".into()),
+        };
+        name.into()
+    }
+
+    // unwrapping None is reported where the unwrap happened, and in debug mode
+    // also where the None was written
+    fn unwrap_error(&self, origin: Provenance) -> NovaError {
+        let mut msg = String::from("Tried to Unwrap a None value");
+        if let Some(created) = self.runtime_errors_table.get(&(origin.0 as usize)) {
+            match &created.filepath {
+                Some(path) => msg.push_str(&format!(
+                    ", the None was created at {}:{}:{}",
+                    path.display(),
+                    created.line,
+                    created.col
+                )),
+                None => msg.push_str(&format!(
+                    ", the None was created at line {}, col {}",
+                    created.line, created.col
+                )),
+            }
+        }
+        match self
+            .runtime_errors_table
+            .get(&self.state.current_instruction)
+        {
+            Some(pos) => NovaError::RuntimeWithPos {
+                msg: msg.into(),
+                position: pos.clone(),
+            },
+            None => NovaError::Runtime { msg: msg.into() },
+        }
+    }
+
+    // summary of heap usage printed when the program ends, enabled by --gc-stats
+    pub fn show_gc_stats(&self) {
+        if !self.config.gc_stats {
+            return;
+        }
+        eprintln!("GC Stats");
+        eprintln!("--------------------------------");
+        eprintln!("{:<24}{:>10}", "heap size", self.state.heap.len());
+        eprintln!("{:<24}{:>10}", "live objects", self.state.live_objects());
+        eprintln!("{:<24}{:>10}", "collections", self.state.gc_count);
+        eprintln!(
+            "{:<24}{:>10}",
+            "objects collected", self.state.garbage_collected
+        );
+        eprintln!("--------------------------------");
+    }
+
+    // the position of the last instruction at or before the current one that
+    // has one, to tell where the vm was when it crashed
+    pub fn current_position(&self) -> Option<FilePosition> {
+        let at = self.state.current_instruction;
+        self.runtime_errors_table
+            .iter()
+            .map(|(instruction, position)| (*instruction, Some(position)))
+            .chain(
+                self.line_table
+                    .iter()
+                    .map(|(instruction, positions)| (*instruction, positions.last())),
+            )
+            .filter(|(instruction, _)| *instruction <= at)
+            .max_by_key(|(instruction, _)| *instruction)
+            .and_then(|(_, position)| position.cloned())
+    }
+
+    // runs until the program ends. an error raised inside a try block opened
+    // by this run goes to its handler instead of ending it
     pub fn run(&mut self) -> Result<(), NovaError> {
+        let base = self.state.handlers.len();
+        loop {
+            match self.execute() {
+                Err(error) if self.catches(&error, base) => self.recover(error),
+                result => return result,
+            }
+        }
+    }
+
+    pub fn run_debug(&mut self) -> Result<(), NovaError> {
+        let base = self.state.handlers.len();
+        loop {
+            match self.execute_debug() {
+                Err(error) if self.catches(&error, base) => self.recover(error),
+                result => return result,
+            }
+        }
+    }
+
+    // interrupts are never caught, the host asked for the program to stop
+    fn catches(&self, error: &NovaError, base: usize) -> bool {
+        self.state.handlers.len() > base
+            && matches!(
+                error,
+                NovaError::Runtime { .. } | NovaError::RuntimeWithPos { .. }
+            )
+    }
+
+    // unwinds to the innermost try block, closing the with blocks opened
+    // inside it, and jumps to its handler with the error message
+    fn recover(&mut self, error: NovaError) {
+        let handler = self.state.handlers.pop().unwrap();
+        self.state.gclock = false;
+        while self.state.deferred.len() > handler.deferred {
+            let (close, resource) = self.state.deferred.pop().unwrap();
+            let _ = self.call_value(close, &[resource]);
+        }
+        self.state.stack.truncate(handler.stack);
+        self.state.callstack.truncate(handler.callstack);
+        self.state.window.truncate(handler.window);
+        self.state.offset = handler.offset;
+        let message = match error {
+            NovaError::Runtime { msg } | NovaError::RuntimeWithPos { msg, .. } => msg,
+            _ => unreachable!(),
+        };
+        let index = self.state.allocate_string(message.as_ref().into());
+        self.state.stack.push(VmData::String(index));
+        self.state.goto(handler.address);
+    }
+
+    // the error of a RAISE, its message is the string on top of the stack
+    fn raise(&mut self) -> NovaError {
+        let position = self.runtime_errors_table[&self.state.current_instruction].clone();
+        let msg = match self.state.stack.pop() {
+            Some(VmData::String(index)) => self.state.get_ref(index).get_string().to_string(),
+            _ => "Error".to_string(),
+        };
+        NovaError::RuntimeWithPos {
+            msg: msg.into(),
+            position,
+        }
+    }
+
+    fn open_try(&mut self) {
+        let jump = u32::from_le_bytes(self.state.next_arr()) as usize;
+        self.state.handlers.push(state::Handler {
+            address: self.state.current_instruction + jump,
+            stack: self.state.stack.len(),
+            callstack: self.state.callstack.len(),
+            window: self.state.window.len(),
+            offset: self.state.offset,
+            deferred: self.state.deferred.len(),
+        });
+    }
+
+    #[inline(always)]
+    fn execute(&mut self) -> Result<(), NovaError> {
         loop {
             // /dbg!(&self.state.stack, &self.state.program[self.state.current_instruction]);
+            self.line_hook();
             match self.state.next_instruction() {
                 Code::ERROR => {
                     return Err(NovaError::RuntimeWithPos {
@@ -46,7 +498,85 @@ impl Vm {
                             .clone(),
                     });
                 }
-                Code::EXIT => exit(0),
+                // stop this program only, never the host process, which can
+                // read the status from the state
+                Code::EXIT => {
+                    let status = match self.state.stack.pop() {
+                        Some(VmData::Int(status)) => status,
+                        _ => {
+                            return Err(NovaError::Runtime {
+                                msg: "exit expects an Int status".into(),
+                            })
+                        }
+                    };
+                    self.state.status = match u8::try_from(status) {
+                        Ok(status) => status.into(),
+                        Err(_) => {
+                            return Err(NovaError::RuntimeWithPos {
+                                msg: format!("Exit status must be from 0 to 255, got {}", status)
+                                    .into(),
+                                position: self.runtime_errors_table
+                                    [&self.state.current_instruction]
+                                    .clone(),
+                            })
+                        }
+                    };
+                    self.state.timers.clear();
+                    return Ok(());
+                }
+                Code::LAZY => self.lazy()?,
+                Code::DEFER => {
+                    let close = self.state.stack.pop().unwrap();
+                    let resource = self.state.stack.pop().unwrap();
+                    self.state.deferred.push((close, resource));
+                }
+                Code::UNDEFER => {
+                    self.state.deferred.pop();
+                }
+                Code::TRY => self.open_try(),
+                Code::ENDTRY => {
+                    self.state.handlers.pop();
+                }
+                Code::RAISE => return Err(self.raise()),
+                Code::TYPENAME => {
+                    let value = self.state.stack.pop().unwrap();
+                    let name = self.runtime_type_name(value);
+                    let index = self.state.allocate_string(name);
+                    self.state.stack.push(VmData::String(index));
+                }
+                Code::SIZED => {
+                    let width = Width::from_byte(self.state.next_instruction()).unwrap();
+                    let value = i64::from_le_bytes(self.state.next_arr());
+                    self.state.stack.push(VmData::Sized(value, width));
+                }
+                Code::CONVERT => self.convert()?,
+                Code::IADD
+                | Code::ISUB
+                | Code::IMUL
+                | Code::IDIV
+                | Code::IMODULO
+                | Code::ILSS
+                | Code::IGTR
+                    if self.sized_operands() =>
+                {
+                    let code = self.state.program[self.state.current_instruction - 1];
+                    self.sized_arithmetic(code)?
+                }
+                Code::LAZYSET => {
+                    let index = u32::from_le_bytes(self.state.next_arr()) as usize;
+                    self.state.stack[index] = *self.state.stack.last().unwrap();
+                    self.state.lazy.insert(index, true);
+                }
+                Code::REQUIRES | Code::ENSURES => {
+                    let Some(VmData::Bool(passed)) = self.state.stack.pop() else {
+                        return Err(NovaError::Runtime {
+                            msg: "Contract expects a bool".into(),
+                        });
+                    };
+                    if !passed {
+                        return Err(self.contract_error());
+                    }
+                }
                 Code::CONCAT => match (self.state.stack.pop(), self.state.stack.pop()) {
                     (Some(VmData::String(s1)), Some(VmData::String(s2))) => {
                         match (self.state.get_ref(s1), self.state.get_ref(s2)) {
@@ -87,26 +617,13 @@ impl Vm {
                     }
                 },
                 Code::ISSOME => match self.state.stack.pop() {
-                    Some(VmData::None) => self.state.stack.push(VmData::Bool(false)),
+                    Some(VmData::None(_)) => self.state.stack.push(VmData::Bool(false)),
                     None => (),
                     _ => self.state.stack.push(VmData::Bool(true)),
                 },
                 Code::UNWRAP => {
-                    if let Some(VmData::None) = self.state.stack.last() {
-                        // get the position of the error
-                        if let Some(pos) = self
-                            .runtime_errors_table
-                            .get(&self.state.current_instruction)
-                        {
-                            return Err(NovaError::RuntimeWithPos {
-                                msg: "Tried to Unwrap a None value".into(),
-                                position: pos.clone(),
-                            });
-                        } else {
-                            return Err(NovaError::Runtime {
-                                msg: "Tried to Unwrap a None value".into(),
-                            });
-                        }
+                    if let Some(VmData::None(origin)) = self.state.stack.last() {
+                        return Err(self.unwrap_error(*origin));
                     }
                 }
                 Code::DUP => self.state.stack.push(*self.state.stack.last().unwrap()),
@@ -117,7 +634,7 @@ impl Vm {
                 Code::NATIVE => {
                     let index = u64::from_le_bytes(self.state.next_arr());
 
-                    match self.native_functions[index as usize](&mut self.state) {
+                    match (self.native_functions[index as usize].0)(&mut self.state) {
                         Ok(_) => {}
                         Err(error) => return Err(error),
                     }
@@ -169,12 +686,18 @@ impl Vm {
                 // jumps back to the callsite of a function
                 Code::RET => {
                     let with_return = self.state.next_instruction();
+                    if !self.state.callstack.is_empty() {
+                        self.return_hook();
+                    }
                     if let Some(destination) = self.state.callstack.pop() {
                         if with_return == 1 {
                             self.state.deallocate_registers_with_return();
                         } else {
                             self.state.deallocate_registers();
                         }
+                        if destination == HOST_RETURN {
+                            break;
+                        }
                         self.state.goto(destination);
                         //dbg!(&self.state.stack);
                     } else {
@@ -193,19 +716,22 @@ impl Vm {
                             VmData::Int(v) => {
                                 write!(out, "{}", v).unwrap();
                             }
+                            VmData::Sized(v, width) => {
+                                write!(out, "{}", width.value(v)).unwrap();
+                            }
                             VmData::Float(v) => {
-                                write!(out, "{}", v).unwrap();
+                                write!(out, "{}", format_float(v)).unwrap();
                             }
                             VmData::Bool(v) => {
                                 write!(out, "{}", v).unwrap();
                             }
-                            VmData::None => {
+                            VmData::None(_) => {
                                 write!(out, "None").unwrap();
                             }
                             VmData::List(index) => {
                                 state.print_heap(index);
                             }
-                            VmData::String(index) => {
+                            VmData::String(index) | VmData::BigInt(index) => {
                                 state.print_heap(index);
                             }
                             VmData::Closure(v) => {
@@ -304,9 +830,10 @@ impl Vm {
                             .into(),
                         });
                     };
-                    let result = v1.checked_add(v2).ok_or_else(|| NovaError::Runtime {
-                        msg: "Integer overflow".into(),
-                    })?;
+                    let result =
+                        self.int_result(v2.checked_add(v1), v2.wrapping_add(v1), || {
+                            format!("{} + {}", v2, v1)
+                        })?;
                     self.state.stack.push(VmData::Int(result))
                 }
 
@@ -322,9 +849,10 @@ impl Vm {
                             .into(),
                         });
                     };
-                    let result = v2.checked_sub(v1).ok_or_else(|| NovaError::Runtime {
-                        msg: "Integer overflow".into(),
-                    })?;
+                    let result =
+                        self.int_result(v2.checked_sub(v1), v2.wrapping_sub(v1), || {
+                            format!("{} - {}", v2, v1)
+                        })?;
                     self.state.stack.push(VmData::Int(result))
                 }
 
@@ -340,11 +868,10 @@ impl Vm {
                             .into(),
                         });
                     };
-                    // safely multi
-
-                    let result = v1.checked_mul(v2).ok_or_else(|| NovaError::Runtime {
-                        msg: "Integer overflow".into(),
-                    })?;
+                    let result =
+                        self.int_result(v2.checked_mul(v1), v2.wrapping_mul(v1), || {
+                            format!("{} * {}", v2, v1)
+                        })?;
                     self.state.stack.push(VmData::Int(result))
                 }
 
@@ -360,9 +887,16 @@ impl Vm {
                             .into(),
                         });
                     };
-                    let result = v2.checked_div(v1).ok_or_else(|| NovaError::Runtime {
-                        msg: "Integer division by zero".into(),
-                    })?;
+                    if v1 == 0 {
+                        return Err(NovaError::Runtime {
+                            msg: "Integer division by zero".into(),
+                        });
+                    }
+                    // only Int's minimum divided by -1 can overflow
+                    let result =
+                        self.int_result(v2.checked_div(v1), v2.wrapping_div(v1), || {
+                            format!("{} / {}", v2, v1)
+                        })?;
                     self.state.stack.push(VmData::Int(result))
                 }
 
@@ -399,6 +933,7 @@ impl Vm {
                 }
 
                 Code::DIRECTCALL => {
+                    self.safepoint()?;
                     self.state
                         .callstack
                         .push(self.state.current_instruction + 4);
@@ -409,6 +944,7 @@ impl Vm {
                     match callee {
                         VmData::Function(target) => {
                             self.state.goto(target);
+                            self.call_hook();
                         }
                         VmData::Closure(target) => {
                             if let Heap::Closure(target, captured) = self.state.heap[target] {
@@ -417,6 +953,7 @@ impl Vm {
                                         self.state.stack.push(self.state.to_vmdata(i))
                                     }
                                     self.state.goto(target);
+                                    self.call_hook();
                                 } else {
                                     todo!()
                                 }
@@ -439,10 +976,31 @@ impl Vm {
                     todo!("Tail call");
                 }
 
-                Code::ILSS => {
-                    let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
-                        (self.state.stack.pop(), self.state.stack.pop())
+                Code::LSS | Code::GTR => {
+                    let greater =
+                        self.state.program[self.state.current_instruction - 1] == Code::GTR;
+                    let (Some(b), Some(a)) = (self.state.stack.pop(), self.state.stack.pop())
                     else {
+                        return Err(NovaError::Runtime {
+                            msg: "Stack is empty".into(),
+                        });
+                    };
+                    // a > b is b < a
+                    let result = if greater {
+                        self.less_than(b, a)?
+                    } else {
+                        self.less_than(a, b)?
+                    };
+                    self.state.stack.push(VmData::Bool(result));
+                }
+                Code::ILSS => match (self.state.stack.pop(), self.state.stack.pop()) {
+                    (Some(VmData::Int(v1)), Some(VmData::Int(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 < v1))
+                    }
+                    (Some(VmData::Char(v1)), Some(VmData::Char(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 < v1))
+                    }
+                    _ => {
                         return Err(NovaError::Runtime {
                             msg: format!(
                                 "Error Not enough arguments Opcode : {}",
@@ -450,16 +1008,17 @@ impl Vm {
                             )
                             .into(),
                         });
-                    };
-                    let result = v2 < v1;
-                    self.state.stack.push(VmData::Bool(result))
-                }
+                    }
+                },
 
                 Code::IGTR => match (self.state.stack.pop(), self.state.stack.pop()) {
                     (Some(VmData::Int(v1)), Some(VmData::Int(v2))) => {
                         let result = v2 > v1;
                         self.state.stack.push(VmData::Bool(result))
                     }
+                    (Some(VmData::Char(v1)), Some(VmData::Char(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 > v1))
+                    }
                     (a, b) => {
                         dbg!(a, b);
                         return Err(NovaError::Runtime {
@@ -509,6 +1068,7 @@ impl Vm {
                     self.state.current_instruction += jump as usize;
                 }
                 Code::BJMP => {
+                    self.safepoint()?;
                     let jump = u32::from_le_bytes(self.state.next_arr());
                     self.state.current_instruction -= jump as usize;
                 }
@@ -540,18 +1100,8 @@ impl Vm {
                             .into(),
                         });
                     };
-                    match (v1, v2) {
-                        (VmData::String(i1), VmData::String(i2)) => {
-                            let s1 = self.state.heap[i1].get_string();
-                            let s2 = self.state.heap[i2].get_string();
-                            let result = s1 == s2;
-                            self.state.stack.push(VmData::Bool(result))
-                        }
-                        _ => {
-                            let result = v2 == v1;
-                            self.state.stack.push(VmData::Bool(result))
-                        }
-                    }
+                    let result = self.equals(v2, v1)?;
+                    self.state.stack.push(VmData::Bool(result));
                 }
 
                 Code::NOT => match self.state.stack.pop() {
@@ -588,7 +1138,17 @@ impl Vm {
                 Code::NEG => {
                     if let Some(value) = self.state.stack.pop() {
                         match value {
-                            VmData::Int(v) => self.state.stack.push(VmData::Int(-v)),
+                            VmData::Int(v) => {
+                                let result =
+                                    self.int_result(v.checked_neg(), v.wrapping_neg(), || {
+                                        format!("-({})", v)
+                                    })?;
+                                self.state.stack.push(VmData::Int(result))
+                            }
+                            VmData::Sized(v, width) => self
+                                .state
+                                .stack
+                                .push(VmData::Sized(width.wrap(-width.value(v)), width)),
                             VmData::Float(v) => self.state.stack.push(VmData::Float(-v)),
                             _ => {
                                 return Err(NovaError::Runtime {
@@ -653,16 +1213,22 @@ impl Vm {
                             match item {
                                 VmData::Function(v) => self.state.heap[index] = Heap::Function(v),
                                 VmData::Int(v) => self.state.heap[index] = Heap::Int(v),
+                                VmData::Sized(v, width) => {
+                                    self.state.heap[index] = Heap::Sized(v, width)
+                                }
                                 VmData::Float(_) => todo!(),
                                 VmData::Bool(_) => todo!(),
                                 VmData::List(v) => {
                                     //dbg!(&self.state.heap[v]);
                                     self.state.heap[index] = Heap::ListAddress(v)
                                 }
-                                VmData::None => todo!(),
+                                VmData::None(_) => todo!(),
                                 VmData::String(v) => {
                                     self.state.heap[index] = Heap::StringAddress(v)
                                 }
+                                VmData::BigInt(v) => {
+                                    self.state.heap[index] = Heap::BigIntAddress(v)
+                                }
                                 VmData::Closure(_) => todo!(),
                                 VmData::StackAddress(_) => todo!(),
                                 VmData::Struct(_) => todo!(),
@@ -710,19 +1276,8 @@ impl Vm {
                                 &self.state.stack[self.state.offset + array_index]
                             {
                                 if let Heap::List(array) = self.state.get_ref(*newindex) {
-                                    if array.len() <= index_to_get as usize {
-                                        if let Some(pos) = self
-                                            .runtime_errors_table
-                                            .get(&self.state.current_instruction)
-                                        {
-                                            return Err(NovaError::RuntimeWithPos { msg: format!("Invalid array access , array length: {}, index tried: {}", array.len(), index_to_get).into(), position: pos.clone() });
-                                        } else {
-                                            return Err(NovaError::Runtime { msg: format!("Invalid array access , array length: {}, index tried: {}", array.len(), index_to_get).into() });
-                                        }
-                                    }
-                                    self.state
-                                        .stack
-                                        .push(VmData::List(array[index_to_get as usize]))
+                                    let index = self.check_index(index_to_get, array.len())?;
+                                    self.state.stack.push(VmData::List(array[index]))
                                 }
                             }
                         }
@@ -734,19 +1289,8 @@ impl Vm {
                                 });
                             };
                             if let Heap::List(array) = self.state.get_ref(*newindex) {
-                                if array.len() <= index_to_get as usize {
-                                    if let Some(pos) = self
-                                        .runtime_errors_table
-                                        .get(&self.state.current_instruction)
-                                    {
-                                        return Err(NovaError::RuntimeWithPos { msg: format!("Invalid array access , array length: {}, index tried: {}", array.len(), index_to_get).into(), position: pos.clone() });
-                                    } else {
-                                        return Err(NovaError::Runtime { msg: format!("Invalid array access , array length: {}, index tried: {}", array.len(), index_to_get).into() });
-                                    }
-                                }
-                                self.state
-                                    .stack
-                                    .push(VmData::List(array[index_to_get as usize]))
+                                let index = self.check_index(index_to_get, array.len())?;
+                                self.state.stack.push(VmData::List(array[index]))
                             }
                         }
                         (a, b) => {
@@ -766,23 +1310,16 @@ impl Vm {
                         (VmData::List(array), VmData::Int(index_to)) => {
                             match self.state.get_ref(array) {
                                 Heap::List(array) => {
-                                    // test if array is out of bounds
-                                    if array.len() <= index_to as usize {
-                                        if let Some(pos) = self
-                                            .runtime_errors_table
-                                            .get(&self.state.current_instruction)
-                                        {
-                                            return Err(NovaError::RuntimeWithPos { msg: format!("Invalid array access , array length: {}, index tried: {}", array.len(), index_to).into(), position: pos.clone() });
-                                        } else {
-                                            return Err(NovaError::Runtime { msg: format!("Invalid array access , array length: {}, index tried: {}", array.len(), index_to).into() });
-                                        }
-                                    }
-                                    let item = self.state.get_ref(array[index_to as usize]).clone();
+                                    let index = self.check_index(index_to, array.len())?;
+                                    let item = self.state.get_ref(array[index]).clone();
                                     match item {
                                         Heap::Function(v) => {
                                             self.state.stack.push(VmData::Function(v))
                                         }
                                         Heap::Int(v) => self.state.stack.push(VmData::Int(v)),
+                                        Heap::Sized(v, width) => {
+                                            self.state.stack.push(VmData::Sized(v, width))
+                                        }
                                         Heap::Float(v) => self.state.stack.push(VmData::Float(v)),
                                         Heap::Bool(v) => self.state.stack.push(VmData::Bool(v)),
                                         Heap::ListAddress(v) => {
@@ -790,10 +1327,16 @@ impl Vm {
                                         }
                                         Heap::List(_) => panic!(),
                                         Heap::String(_) => panic!(),
-                                        Heap::None => self.state.stack.push(VmData::None),
+                                        Heap::None(origin) => {
+                                            self.state.stack.push(VmData::None(origin))
+                                        }
                                         Heap::StringAddress(v) => {
                                             self.state.stack.push(VmData::String(v))
                                         }
+                                        Heap::BigIntAddress(v) => {
+                                            self.state.stack.push(VmData::BigInt(v))
+                                        }
+                                        Heap::BigInt(_) => panic!(),
                                         Heap::Closure(_, _) => todo!(),
                                         Heap::ClosureAddress(v) => {
                                             self.state.stack.push(VmData::Closure(v))
@@ -827,6 +1370,7 @@ impl Vm {
                 }
 
                 Code::CALL => {
+                    self.safepoint()?;
                     let Some(callee) = self.state.stack.pop() else {
                         todo!()
                     };
@@ -842,6 +1386,7 @@ impl Vm {
                                     }
                                     self.state.callstack.push(self.state.current_instruction);
                                     self.state.goto(*target);
+                                    self.call_hook();
                                 } else {
                                     dbg!(target, callee, captured);
                                     todo!()
@@ -853,6 +1398,7 @@ impl Vm {
                         VmData::Function(target) => {
                             self.state.callstack.push(self.state.current_instruction);
                             self.state.goto(target);
+                            self.call_hook();
                         }
                         a => {
                             dbg!(a);
@@ -878,7 +1424,7 @@ impl Vm {
                 }
 
                 Code::CHAR => {
-                    let char = self.state.next_instruction() as char;
+                    let char = char::from_u32(u32::from_le_bytes(self.state.next_arr())).unwrap();
                     self.state.stack.push(VmData::Char(char));
                 }
 
@@ -931,7 +1477,7 @@ impl Vm {
                     }
                 }
                 Code::NONE => {
-                    self.state.stack.push(VmData::None);
+                    self.state.stack.push(VmData::NONE);
                 }
                 error => {
                     dbg!(error);
@@ -951,8 +1497,297 @@ impl Vm {
         Ok(())
     }
 
+    // the event loop. once the program is done the timers it queued run in
+    // the order they are due, sleeping in between, until none are left
+    pub fn run_timers(&mut self) -> Result<(), NovaError> {
+        loop {
+            let Some(next) = (0..self.state.timers.len())
+                .min_by_key(|&i| (self.state.timers[i].due, self.state.timers[i].id))
+            else {
+                return Ok(());
+            };
+            let due = self.state.timers[next].due;
+            let now = Instant::now();
+            if due > now {
+                // short naps so an interrupt is noticed while waiting
+                thread::sleep((due - now).min(Duration::from_millis(50)));
+                self.safepoint()?;
+                continue;
+            }
+            let timer = match self.state.timers[next].interval {
+                Some(interval) => {
+                    // ticks missed by a slow callback are skipped, not run back to back
+                    self.state.timers[next].due = (due + interval).max(now);
+                    self.state.timers[next].clone()
+                }
+                None => self.state.timers.remove(next),
+            };
+            self.state.current_timer = Some(timer.id);
+            let result = self.call_value(timer.callback, &[]);
+            self.state.current_timer = None;
+            result?;
+        }
+    }
+
+    // calls a function value from outside the program and runs it to its
+    // return, the value it returned if it has one
+    pub fn call_value(
+        &mut self,
+        callee: VmData,
+        args: &[VmData],
+    ) -> Result<Option<VmData>, NovaError> {
+        let depth = self.state.stack.len();
+        let resume = self.state.current_instruction;
+        self.state.stack.extend_from_slice(args);
+        let target = match callee {
+            VmData::Function(target) => target,
+            VmData::Closure(index) => match self.state.heap.get(index) {
+                Some(Heap::Closure(target, captured)) => {
+                    let target = *target;
+                    if let Heap::List(list) = self.state.heap[*captured].clone() {
+                        for i in list {
+                            self.state.stack.push(self.state.to_vmdata(i))
+                        }
+                    }
+                    target
+                }
+                _ => {
+                    return Err(NovaError::Runtime {
+                        msg: "Expected a closure on the heap".into(),
+                    })
+                }
+            },
+            _ => {
+                return Err(NovaError::Runtime {
+                    msg: "Cannot call a value that is not a function".into(),
+                })
+            }
+        };
+        self.state.callstack.push(HOST_RETURN);
+        self.state.goto(target);
+        self.call_hook();
+        let result = self.run();
+        self.state.goto(resume);
+        result?;
+        let value = if self.state.stack.len() > depth {
+            self.state.stack.pop()
+        } else {
+            None
+        };
+        self.state.stack.truncate(depth);
+        Ok(value)
+    }
+
+    // closes what the with blocks that were still open hold, innermost first,
+    // after an error or exit ended the program inside them
+    pub fn unwind(&mut self) {
+        while let Some((close, resource)) = self.state.deferred.pop() {
+            let _ = self.call_value(close, &[resource]);
+        }
+    }
+
+    // whether a lazy value still has to be computed, reading it again while
+    // its initializer runs would never finish
+    fn lazy(&mut self) -> Result<(), NovaError> {
+        let position = self.runtime_errors_table[&self.state.current_instruction].clone();
+        let index = u32::from_le_bytes(self.state.next_arr()) as usize;
+        match self.state.lazy.get(&index) {
+            Some(true) => self.state.stack.push(VmData::Bool(false)),
+            Some(false) => {
+                return Err(NovaError::RuntimeWithPos {
+                    msg: "Lazy value depends on itself, it was read while being initialized".into(),
+                    position,
+                })
+            }
+            None => {
+                self.state.lazy.insert(index, false);
+                self.state.stack.push(VmData::Bool(true));
+            }
+        }
+        Ok(())
+    }
+
+    // whether the integer op about to run is on sized integers
+    fn sized_operands(&self) -> bool {
+        matches!(self.state.stack.last(), Some(VmData::Sized(..)))
+    }
+
+    // the integer ops on two sized integers of the same width, results wrap
+    // around at the width
+    fn sized_arithmetic(&mut self, code: u8) -> Result<(), NovaError> {
+        let (Some(VmData::Sized(rhs, width)), Some(VmData::Sized(lhs, _))) =
+            (self.state.stack.pop(), self.state.stack.pop())
+        else {
+            return Err(NovaError::Runtime {
+                msg: "Expected two sized integers on the stack".into(),
+            });
+        };
+        let (lhs, rhs) = (width.value(lhs), width.value(rhs));
+        let result = match code {
+            Code::IADD => lhs + rhs,
+            Code::ISUB => lhs - rhs,
+            // only the low bits are kept, so wrapping in i128 loses nothing
+            Code::IMUL => lhs.wrapping_mul(rhs),
+            Code::IDIV | Code::IMODULO if rhs == 0 => {
+                return Err(NovaError::Runtime {
+                    msg: "Integer division by zero".into(),
+                })
+            }
+            Code::IDIV => lhs / rhs,
+            Code::IMODULO => lhs.modulo(rhs),
+            Code::ILSS | Code::IGTR => {
+                let less = code == Code::ILSS;
+                let result = if less { lhs < rhs } else { lhs > rhs };
+                self.state.stack.push(VmData::Bool(result));
+                return Ok(());
+            }
+            _ => {
+                return Err(NovaError::Runtime {
+                    msg: format!("{} does not work on sized integers", byte_to_string(code)).into(),
+                })
+            }
+        };
+        self.state
+            .stack
+            .push(VmData::Sized(width.wrap(result), width));
+        Ok(())
+    }
+
+    // converts between Int and the sized integers, the overflow operand says
+    // what happens to a value that does not fit
+    fn convert(&mut self) -> Result<(), NovaError> {
+        let origin = Provenance(self.state.current_instruction as u32);
+        let position = self
+            .runtime_errors_table
+            .get(&self.state.current_instruction)
+            .cloned();
+        let width = Width::from_byte(self.state.next_instruction());
+        let overflow = Overflow::from_byte(self.state.next_instruction());
+        let value = match self.state.stack.pop() {
+            Some(VmData::Int(value)) => value as i128,
+            Some(VmData::Sized(value, from)) => from.value(value),
+            _ => {
+                return Err(NovaError::Runtime {
+                    msg: "Expected an integer to convert".into(),
+                })
+            }
+        };
+        let (fits, converted, name) = match width {
+            Some(width) => (
+                width.fits(value),
+                VmData::Sized(width.wrap(value), width),
+                width.name(),
+            ),
+            None => (
+                i64::try_from(value).is_ok(),
+                VmData::Int(value as i64),
+                "Int",
+            ),
+        };
+        match overflow {
+            _ if fits => self.state.stack.push(converted),
+            Overflow::Wrap => self.state.stack.push(converted),
+            Overflow::Checked => self.state.stack.push(VmData::None(origin)),
+            Overflow::Error => {
+                let msg = format!("{} does not fit in {}", value, name).into();
+                return Err(match position {
+                    Some(position) => NovaError::RuntimeWithPos { msg, position },
+                    None => NovaError::Runtime { msg },
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // a < b on the runtime type, types that define __lt__ are called
+    fn less_than(&mut self, a: VmData, b: VmData) -> Result<bool, NovaError> {
+        match (a, b) {
+            (VmData::Int(a), VmData::Int(b)) => Ok(a < b),
+            (VmData::Sized(a, width), VmData::Sized(b, _)) => Ok(width.value(a) < width.value(b)),
+            (VmData::BigInt(a), VmData::BigInt(b)) => {
+                Ok(self.state.get_bigint(a)? < self.state.get_bigint(b)?)
+            }
+            (VmData::Float(a), VmData::Float(b)) => Ok(a < b),
+            (VmData::Char(a), VmData::Char(b)) => Ok(a < b),
+            (VmData::String(a), VmData::String(b)) => {
+                match (self.state.get_ref(a), self.state.get_ref(b)) {
+                    (Heap::String(a), Heap::String(b)) => Ok(a < b),
+                    _ => Err(NovaError::Runtime {
+                        msg: "Expected strings in the heap".into(),
+                    }),
+                }
+            }
+            (VmData::List(index), VmData::List(_)) => {
+                let name = self.type_name(index);
+                let Some(slot) = name.as_ref().and_then(|name| self.operators.get(name)) else {
+                    return Err(NovaError::Runtime {
+                        msg: format!(
+                            "{} does not define __lt__",
+                            name.as_deref().unwrap_or("List")
+                        )
+                        .into(),
+                    });
+                };
+                let callee = self.state.stack[*slot as usize];
+                match self.call_value(callee, &[a, b])? {
+                    Some(VmData::Bool(less)) => Ok(less),
+                    _ => Err(NovaError::Runtime {
+                        msg: "__lt__ has to return a Bool".into(),
+                    }),
+                }
+            }
+            (a, b) => Err(NovaError::Runtime {
+                msg: format!("Cannot compare {:?} and {:?}", a, b).into(),
+            }),
+        }
+    }
+
+    // a == b on the runtime type, structs and enums that define __eq__ are
+    // called and other lists are the same list or not
+    fn equals(&mut self, a: VmData, b: VmData) -> Result<bool, NovaError> {
+        match (a, b) {
+            (VmData::String(a), VmData::String(b)) => {
+                Ok(self.state.heap[a].get_string() == self.state.heap[b].get_string())
+            }
+            (VmData::BigInt(a), VmData::BigInt(b)) => {
+                Ok(self.state.get_bigint(a)? == self.state.get_bigint(b)?)
+            }
+            (VmData::List(index), VmData::List(_)) if !self.equality.is_empty() && a != b => {
+                let slot = self
+                    .type_name(index)
+                    .and_then(|name| self.equality.get(&name).copied());
+                let Some(slot) = slot else {
+                    return Ok(false);
+                };
+                let callee = self.state.stack[slot as usize];
+                match self.call_value(callee, &[a, b])? {
+                    Some(VmData::Bool(equal)) => Ok(equal),
+                    _ => Err(NovaError::Runtime {
+                        msg: "__eq__ has to return a Bool".into(),
+                    }),
+                }
+            }
+            (VmData::None(_), VmData::None(_)) => Ok(true),
+            (a, b) => Ok(a == b),
+        }
+    }
+
+    // structs and enums keep their type name in their last field
+    fn type_name(&self, index: usize) -> Option<Rc<str>> {
+        let Heap::List(fields) = self.state.get_ref(index) else {
+            return None;
+        };
+        match self.state.to_vmdata(*fields.last()?) {
+            VmData::String(name) => match self.state.get_ref(name) {
+                Heap::String(name) => Some(name.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     #[inline(always)]
-    pub fn run_debug(&mut self) -> Result<(), NovaError> {
+    fn execute_debug(&mut self) -> Result<(), NovaError> {
         let mut tick = 0;
         let mut input = String::new();
         loop {
@@ -971,20 +1806,31 @@ impl Vm {
             // if let Err(e) = execute!(std::io::stdout(), Clear(ClearType::All)) {
             //     eprintln!("Failed to clear the terminal screen: {}", e);
             // }
+            self.line_hook();
             match self.state.next_instruction() {
+                Code::IADD
+                | Code::ISUB
+                | Code::IMUL
+                | Code::IDIV
+                | Code::IMODULO
+                | Code::ILSS
+                | Code::IGTR
+                    if self.sized_operands() =>
+                {
+                    let code = self.state.program[self.state.current_instruction - 1];
+                    self.sized_arithmetic(code)?
+                }
                 Code::ISSOME => {
                     if let Some(value) = self.state.stack.pop() {
                         match value {
-                            VmData::None => self.state.stack.push(VmData::Bool(false)),
+                            VmData::None(_) => self.state.stack.push(VmData::Bool(false)),
                             _ => self.state.stack.push(VmData::Bool(true)),
                         }
                     }
                 }
                 Code::UNWRAP => {
-                    if let Some(value) = self.state.stack.last() {
-                        if value == &VmData::None {
-                            panic!()
-                        }
+                    if let Some(VmData::None(origin)) = self.state.stack.last() {
+                        return Err(self.unwrap_error(*origin));
                     }
                 }
                 Code::DUP => self.state.stack.push(*self.state.stack.last().unwrap()),
@@ -1040,6 +1886,9 @@ impl Vm {
                 // jumps back to the callsite of a function
                 Code::RET => {
                     let with_return = self.state.next_instruction();
+                    if !self.state.callstack.is_empty() {
+                        self.return_hook();
+                    }
                     if let Some(destination) = self.state.callstack.pop() {
                         if with_return == 1 {
                             self.state.deallocate_registers_with_return();
@@ -1063,13 +1912,16 @@ impl Vm {
                         VmData::Int(v) => {
                             println!("{v}")
                         }
+                        VmData::Sized(v, width) => {
+                            println!("{}", width.value(v))
+                        }
                         VmData::Float(v) => {
-                            println!("{v}")
+                            println!("{}", format_float(v))
                         }
                         VmData::Bool(v) => {
                             println!("{v}")
                         }
-                        VmData::None => {
+                        VmData::None(_) => {
                             println!("None")
                         }
                         VmData::List(index) => {
@@ -1090,6 +1942,11 @@ impl Vm {
                                 println!("{str}")
                             }
                         }
+                        VmData::BigInt(index) => {
+                            if let Heap::BigInt(value) = self.state.get_ref(index) {
+                                println!("{value}")
+                            }
+                        }
                         VmData::Closure(_) => todo!(),
                         VmData::StackAddress(_) => todo!(),
                         VmData::Struct(_) => todo!(),
@@ -1137,7 +1994,10 @@ impl Vm {
                     if let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
                         (self.state.stack.pop(), self.state.stack.pop())
                     {
-                        let result = v1 + v2;
+                        let result =
+                            self.int_result(v2.checked_add(v1), v2.wrapping_add(v1), || {
+                                format!("{} + {}", v2, v1)
+                            })?;
                         self.state.stack.push(VmData::Int(result))
                     }
                 }
@@ -1146,7 +2006,10 @@ impl Vm {
                     if let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
                         (self.state.stack.pop(), self.state.stack.pop())
                     {
-                        let result = v2 - v1;
+                        let result =
+                            self.int_result(v2.checked_sub(v1), v2.wrapping_sub(v1), || {
+                                format!("{} - {}", v2, v1)
+                            })?;
                         self.state.stack.push(VmData::Int(result))
                     }
                 }
@@ -1155,7 +2018,10 @@ impl Vm {
                     if let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
                         (self.state.stack.pop(), self.state.stack.pop())
                     {
-                        let result = v1 * v2;
+                        let result =
+                            self.int_result(v2.checked_mul(v1), v2.wrapping_mul(v1), || {
+                                format!("{} * {}", v2, v1)
+                            })?;
                         self.state.stack.push(VmData::Int(result))
                     }
                 }
@@ -1164,7 +2030,15 @@ impl Vm {
                     if let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
                         (self.state.stack.pop(), self.state.stack.pop())
                     {
-                        let result = v2 / v1;
+                        if v1 == 0 {
+                            return Err(NovaError::Runtime {
+                                msg: "Integer division by zero".into(),
+                            });
+                        }
+                        let result =
+                            self.int_result(v2.checked_div(v1), v2.wrapping_div(v1), || {
+                                format!("{} / {}", v2, v1)
+                            })?;
                         self.state.stack.push(VmData::Int(result))
                     }
                 }
@@ -1202,12 +2076,14 @@ impl Vm {
                 }
 
                 Code::DIRECTCALL => {
+                    self.safepoint()?;
                     self.state
                         .callstack
                         .push(self.state.current_instruction + 4);
                     let index = u32::from_le_bytes(self.state.next_arr());
                     if let VmData::Function(target) = self.state.stack[index as usize] {
                         self.state.goto(target);
+                        self.call_hook();
                     }
                 }
 
@@ -1218,23 +2094,42 @@ impl Vm {
                     }
                 }
 
-                Code::ILSS => {
-                    if let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
-                        (self.state.stack.pop(), self.state.stack.pop())
-                    {
-                        let result = v2 < v1;
-                        self.state.stack.push(VmData::Bool(result))
-                    }
+                Code::LSS | Code::GTR => {
+                    let greater =
+                        self.state.program[self.state.current_instruction - 1] == Code::GTR;
+                    let (Some(b), Some(a)) = (self.state.stack.pop(), self.state.stack.pop())
+                    else {
+                        return Err(NovaError::Runtime {
+                            msg: "Stack is empty".into(),
+                        });
+                    };
+                    // a > b is b < a
+                    let result = if greater {
+                        self.less_than(b, a)?
+                    } else {
+                        self.less_than(a, b)?
+                    };
+                    self.state.stack.push(VmData::Bool(result));
                 }
+                Code::ILSS => match (self.state.stack.pop(), self.state.stack.pop()) {
+                    (Some(VmData::Int(v1)), Some(VmData::Int(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 < v1))
+                    }
+                    (Some(VmData::Char(v1)), Some(VmData::Char(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 < v1))
+                    }
+                    _ => {}
+                },
 
-                Code::IGTR => {
-                    if let (Some(VmData::Int(v1)), Some(VmData::Int(v2))) =
-                        (self.state.stack.pop(), self.state.stack.pop())
-                    {
-                        let result = v2 > v1;
-                        self.state.stack.push(VmData::Bool(result))
+                Code::IGTR => match (self.state.stack.pop(), self.state.stack.pop()) {
+                    (Some(VmData::Int(v1)), Some(VmData::Int(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 > v1))
                     }
-                }
+                    (Some(VmData::Char(v1)), Some(VmData::Char(v2))) => {
+                        self.state.stack.push(VmData::Bool(v2 > v1))
+                    }
+                    _ => {}
+                },
 
                 Code::FLSS => {
                     if let (Some(VmData::Float(v1)), Some(VmData::Float(v2))) =
@@ -1259,6 +2154,7 @@ impl Vm {
                     self.state.current_instruction += jump as usize;
                 }
                 Code::BJMP => {
+                    self.safepoint()?;
                     let jump = u32::from_le_bytes(self.state.next_arr());
                     self.state.current_instruction -= jump as usize;
                 }
@@ -1281,18 +2177,8 @@ impl Vm {
 
                 Code::EQUALS => {
                     if let (Some(v1), Some(v2)) = (self.state.stack.pop(), self.state.stack.pop()) {
-                        match (v1, v2) {
-                            (VmData::String(i1), VmData::String(i2)) => {
-                                let s1 = self.state.heap[i1].get_string();
-                                let s2 = self.state.heap[i2].get_string();
-                                let result = s1 == s2;
-                                self.state.stack.push(VmData::Bool(result))
-                            }
-                            _ => {
-                                let result = v2 == v1;
-                                self.state.stack.push(VmData::Bool(result))
-                            }
-                        }
+                        let result = self.equals(v2, v1)?;
+                        self.state.stack.push(VmData::Bool(result));
                     }
                 }
 
@@ -1321,7 +2207,17 @@ impl Vm {
                 Code::NEG => {
                     if let Some(value) = self.state.stack.pop() {
                         match value {
-                            VmData::Int(v) => self.state.stack.push(VmData::Int(-v)),
+                            VmData::Int(v) => {
+                                let result =
+                                    self.int_result(v.checked_neg(), v.wrapping_neg(), || {
+                                        format!("-({})", v)
+                                    })?;
+                                self.state.stack.push(VmData::Int(result))
+                            }
+                            VmData::Sized(v, width) => self
+                                .state
+                                .stack
+                                .push(VmData::Sized(width.wrap(-width.value(v)), width)),
                             VmData::Float(v) => self.state.stack.push(VmData::Float(-v)),
                             _ => {}
                         }
@@ -1360,15 +2256,21 @@ impl Vm {
                                 match item {
                                     VmData::Function(_) => todo!(),
                                     VmData::Int(v) => self.state.heap[index] = Heap::Int(v),
+                                    VmData::Sized(v, width) => {
+                                        self.state.heap[index] = Heap::Sized(v, width)
+                                    }
                                     VmData::Float(_) => todo!(),
                                     VmData::Bool(_) => todo!(),
                                     VmData::List(v) => {
                                         self.state.heap[index] = Heap::ListAddress(v)
                                     }
-                                    VmData::None => todo!(),
+                                    VmData::None(_) => todo!(),
                                     VmData::String(v) => {
                                         self.state.heap[index] = Heap::StringAddress(v)
                                     }
+                                    VmData::BigInt(v) => {
+                                        self.state.heap[index] = Heap::BigIntAddress(v)
+                                    }
                                     VmData::Closure(_) => todo!(),
                                     VmData::StackAddress(_) => todo!(),
                                     VmData::Struct(_) => todo!(),
@@ -1416,9 +2318,9 @@ impl Vm {
                                         &self.state.stack[self.state.offset + array_index]
                                     {
                                         if let Heap::List(array) = self.state.get_ref(*newindex) {
-                                            self.state
-                                                .stack
-                                                .push(VmData::List(array[index_to_get as usize]))
+                                            let index =
+                                                self.check_index(index_to_get, array.len())?;
+                                            self.state.stack.push(VmData::List(array[index]))
                                         }
                                     }
                                 } else {
@@ -1430,9 +2332,8 @@ impl Vm {
                                     &self.state.stack[self.state.offset + array_index]
                                 {
                                     if let Heap::List(array) = self.state.get_ref(*newindex) {
-                                        self.state
-                                            .stack
-                                            .push(VmData::List(array[index_to_get as usize]))
+                                        let index = self.check_index(index_to_get, array.len())?;
+                                        self.state.stack.push(VmData::List(array[index]))
                                     }
                                 }
                             }
@@ -1440,9 +2341,8 @@ impl Vm {
                                 if let Heap::ListAddress(newindex) = self.state.get_ref(array_index)
                                 {
                                     if let Heap::List(array) = self.state.get_ref(*newindex) {
-                                        self.state
-                                            .stack
-                                            .push(VmData::List(array[index_to_get as usize]))
+                                        let index = self.check_index(index_to_get, array.len())?;
+                                        self.state.stack.push(VmData::List(array[index]))
                                     }
                                 } else {
                                     todo!()
@@ -1465,18 +2365,23 @@ impl Vm {
                                 match self.state.get_ref(array).clone() {
                                     Heap::Function(_) => todo!(),
                                     Heap::Int(v) => self.state.stack.push(VmData::Int(v)),
+                                    Heap::Sized(_, _) => todo!(),
                                     Heap::Float(_) => todo!(),
                                     Heap::Bool(_) => todo!(),
                                     Heap::ListAddress(_) => todo!(),
                                     Heap::StringAddress(_) => todo!(),
+                                    Heap::BigIntAddress(_) | Heap::BigInt(_) => todo!(),
                                     Heap::List(array) => {
-                                        let item =
-                                            self.state.get_ref(array[index_to as usize]).clone();
+                                        let index = self.check_index(index_to, array.len())?;
+                                        let item = self.state.get_ref(array[index]).clone();
                                         match item {
                                             Heap::Function(v) => {
                                                 self.state.stack.push(VmData::Function(v))
                                             }
                                             Heap::Int(v) => self.state.stack.push(VmData::Int(v)),
+                                            Heap::Sized(v, width) => {
+                                                self.state.stack.push(VmData::Sized(v, width))
+                                            }
                                             Heap::Float(v) => {
                                                 self.state.stack.push(VmData::Float(v))
                                             }
@@ -1486,10 +2391,16 @@ impl Vm {
                                             }
                                             Heap::List(_) => todo!(),
                                             Heap::String(_) => todo!(),
-                                            Heap::None => self.state.stack.push(VmData::None),
+                                            Heap::None(origin) => {
+                                                self.state.stack.push(VmData::None(origin))
+                                            }
                                             Heap::StringAddress(v) => {
                                                 self.state.stack.push(VmData::String(v))
                                             }
+                                            Heap::BigIntAddress(v) => {
+                                                self.state.stack.push(VmData::BigInt(v))
+                                            }
+                                            Heap::BigInt(_) => panic!(),
                                             Heap::Closure(_, _) => todo!(),
                                             Heap::ClosureAddress(v) => {
                                                 self.state.stack.push(VmData::Closure(v))
@@ -1500,7 +2411,7 @@ impl Vm {
                                         }
                                     }
                                     Heap::String(_) => todo!(),
-                                    Heap::None => todo!(),
+                                    Heap::None(_) => todo!(),
                                     Heap::Closure(_, _) => todo!(),
                                     Heap::ClosureAddress(_) => todo!(),
                                     Heap::Struct(_, _) => todo!(),
@@ -1529,6 +2440,7 @@ impl Vm {
                 }
 
                 Code::CALL => {
+                    self.safepoint()?;
                     if let Some(callee) = self.state.stack.pop() {
                         match callee {
                             VmData::Closure(index) => {
@@ -1542,6 +2454,7 @@ impl Vm {
                                         }
                                         self.state.callstack.push(self.state.current_instruction);
                                         self.state.goto(*target);
+                                        self.call_hook();
                                     } else {
                                         dbg!(target, callee, captured);
                                         todo!()
@@ -1553,6 +2466,7 @@ impl Vm {
                             VmData::Function(target) => {
                                 self.state.callstack.push(self.state.current_instruction);
                                 self.state.goto(target);
+                                self.call_hook();
                             }
                             a => {
                                 dbg!(a);
@@ -1615,16 +2529,58 @@ impl Vm {
                     }
                 }
                 Code::NONE => {
-                    self.state.stack.push(VmData::None);
+                    let origin = Provenance(self.state.current_instruction as u32);
+                    self.state.stack.push(VmData::None(origin));
                 }
                 Code::NATIVE => {
                     let index = u64::from_le_bytes(self.state.next_arr());
 
-                    match self.native_functions[index as usize](&mut self.state) {
+                    match (self.native_functions[index as usize].0)(&mut self.state) {
                         Ok(_) => {}
                         Err(error) => return Err(error),
                     }
                 }
+                Code::LAZY => self.lazy()?,
+                Code::DEFER => {
+                    let close = self.state.stack.pop().unwrap();
+                    let resource = self.state.stack.pop().unwrap();
+                    self.state.deferred.push((close, resource));
+                }
+                Code::UNDEFER => {
+                    self.state.deferred.pop();
+                }
+                Code::TRY => self.open_try(),
+                Code::ENDTRY => {
+                    self.state.handlers.pop();
+                }
+                Code::RAISE => return Err(self.raise()),
+                Code::TYPENAME => {
+                    let value = self.state.stack.pop().unwrap();
+                    let name = self.runtime_type_name(value);
+                    let index = self.state.allocate_string(name);
+                    self.state.stack.push(VmData::String(index));
+                }
+                Code::SIZED => {
+                    let width = Width::from_byte(self.state.next_instruction()).unwrap();
+                    let value = i64::from_le_bytes(self.state.next_arr());
+                    self.state.stack.push(VmData::Sized(value, width));
+                }
+                Code::CONVERT => self.convert()?,
+                Code::LAZYSET => {
+                    let index = u32::from_le_bytes(self.state.next_arr()) as usize;
+                    self.state.stack[index] = *self.state.stack.last().unwrap();
+                    self.state.lazy.insert(index, true);
+                }
+                Code::REQUIRES | Code::ENSURES => {
+                    let Some(VmData::Bool(passed)) = self.state.stack.pop() else {
+                        return Err(NovaError::Runtime {
+                            msg: "Contract expects a bool".into(),
+                        });
+                    };
+                    if !passed {
+                        return Err(self.contract_error());
+                    }
+                }
                 error => {
                     dbg!(error);
                 }
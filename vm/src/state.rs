@@ -1,11 +1,22 @@
 use core::fmt;
 use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
     fmt::{Display, Formatter},
-    io::{self, Write},
+    fs,
+    io::{self, BufReader, Write},
+    net::{TcpListener, TcpStream, UdpSocket},
+    path::PathBuf,
+    process::{Child, ChildStdout},
     rc::Rc,
+    time::{Duration, Instant},
 };
 
-use common::table::Table;
+use common::{error::NovaError, table::Table, ttype::Width, utilities::format_float};
+use num_bigint::BigInt;
+use rand::rngs::StdRng;
+
+use crate::trace::Tracer;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Heap {
@@ -17,6 +28,7 @@ pub enum Heap {
 
     // basic types
     Int(i64),
+    Sized(i64, Width),
     Float(f64),
     Bool(bool),
     Char(char),
@@ -29,13 +41,22 @@ pub enum Heap {
     StringAddress(usize),
     String(Rc<str>),
 
+    // pointer and instance
+    BigIntAddress(usize),
+    BigInt(Rc<BigInt>),
+
     // pointer and instance
     StructAddress(usize),
     Struct(String, Vec<usize>),
 
-    None,
+    None(Provenance),
 }
 
+// the instruction that created a None value, 0 when it is not known. NONE only
+// fills it in when running in debug mode, natives always give their call site
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Provenance(pub u32);
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum VmData {
     // pointer to stack
@@ -47,6 +68,8 @@ pub enum VmData {
 
     // basic types
     Int(i64),
+    // a fixed width integer, stored the way Width::wrap gives it
+    Sized(i64, Width),
     Float(f64),
     Bool(bool),
     Char(char),
@@ -55,8 +78,17 @@ pub enum VmData {
     List(usize),
     Struct(usize),
     String(usize),
+    BigInt(usize),
+
+    None(Provenance),
+}
 
-    None,
+impl VmData {
+    pub const NONE: VmData = VmData::None(Provenance(0));
+}
+
+impl Heap {
+    pub const NONE: Heap = Heap::None(Provenance(0));
 }
 
 impl Display for Heap {
@@ -65,7 +97,8 @@ impl Display for Heap {
             Heap::ClosureAddress(v) => write!(f, "Closure Address ({})", v),
             Heap::Function(v) => write!(f, "Function Pointer ({})", v),
             Heap::Int(v) => write!(f, "{}", v),
-            Heap::Float(v) => write!(f, "{}", v),
+            Heap::Sized(v, width) => write!(f, "{}", width.value(*v)),
+            Heap::Float(v) => write!(f, "{}", format_float(*v)),
             Heap::Bool(v) => write!(f, "{}", v),
             Heap::ListAddress(v) => write!(f, "List Address ({})", v),
             Heap::List(v) => {
@@ -80,7 +113,9 @@ impl Display for Heap {
             }
             Heap::StringAddress(v) => write!(f, "String Address ({})", v),
             Heap::String(v) => write!(f, "{}", v),
-            Heap::None => write!(f, "None"),
+            Heap::BigIntAddress(v) => write!(f, "BigInt Address ({})", v),
+            Heap::BigInt(v) => write!(f, "{}", v),
+            Heap::None(_) => write!(f, "None"),
             Heap::Closure(_, _) => write!(f, "Closure"),
             Heap::Struct(_, _) => write!(f, "Struct"),
             Heap::StructAddress(v) => write!(f, "Struct Address ({})", v),
@@ -90,6 +125,24 @@ impl Display for Heap {
 }
 
 impl Heap {
+    // variant name used in trace events
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Heap::ClosureAddress(_) | Heap::Closure(_, _) => "Closure",
+            Heap::Function(_) => "Function",
+            Heap::Int(_) => "Int",
+            Heap::Sized(_, width) => width.name(),
+            Heap::Float(_) => "Float",
+            Heap::Bool(_) => "Bool",
+            Heap::Char(_) => "Char",
+            Heap::ListAddress(_) | Heap::List(_) => "List",
+            Heap::StringAddress(_) | Heap::String(_) => "String",
+            Heap::BigIntAddress(_) | Heap::BigInt(_) => "BigInt",
+            Heap::StructAddress(_) | Heap::Struct(_, _) => "Struct",
+            Heap::None(_) => "None",
+        }
+    }
+
     pub fn get_string(&self) -> &str {
         match self {
             Heap::String(s) => s,
@@ -100,6 +153,55 @@ impl Heap {
     }
 }
 
+// a callback waiting in the event loop, interval is set for repeating timers
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub id: i64,
+    pub due: Instant,
+    pub interval: Option<Duration>,
+    pub callback: VmData,
+}
+
+// an open try block, with how deep each stack was when it started so an
+// error inside can unwind to it
+#[derive(Debug, Clone)]
+pub struct Handler {
+    pub address: usize,
+    pub stack: usize,
+    pub callstack: usize,
+    pub window: usize,
+    pub offset: usize,
+    pub deferred: usize,
+}
+
+// a program started by Sys::processSpawn, its output is read a line at a time
+#[derive(Debug)]
+pub struct Process {
+    pub child: Child,
+    pub stdout: BufReader<ChildStdout>,
+}
+
+// a socket of std/net.nv, which wraps the handles in Socket, Listener and
+// UdpSocket
+#[derive(Debug)]
+pub enum Socket {
+    Stream {
+        reader: BufReader<TcpStream>,
+        // the start of a character cut in half by the last read
+        pending: Vec<u8>,
+    },
+    Listener(TcpListener),
+    Udp(UdpSocket),
+}
+
+// a nova process started by Worker::spawn, or in a worker the connection to
+// the program that spawned it, which has no child
+#[derive(Debug)]
+pub struct Worker {
+    pub stream: TcpStream,
+    pub child: Option<Child>,
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
     pub program: Vec<u8>,
@@ -115,6 +217,38 @@ pub struct State {
     pub gc_count: usize,
     pub garbage_collected: usize,
     pub gclock: bool,
+    pub trace: Option<Tracer>,
+    pub timers: Vec<Timer>,
+    pub next_timer: i64,
+    // the timer whose callback is running
+    pub current_timer: Option<i64>,
+    // globals caching a lazy value, false while its initializer is running
+    pub lazy: HashMap<usize, bool>,
+    // the close function and resource of each with block that is still open
+    pub deferred: Vec<(VmData, VmData)>,
+    // the try blocks that are still open, innermost last
+    pub handlers: Vec<Handler>,
+    pub files: HashMap<i64, Rc<fs::File>>,
+    pub next_file: i64,
+    pub processes: HashMap<i64, Rc<RefCell<Process>>>,
+    pub next_process: i64,
+    // by id, 0 is the parent of a worker
+    pub workers: HashMap<i64, Rc<RefCell<Worker>>>,
+    pub next_worker: i64,
+    pub sockets: HashMap<i64, Rc<RefCell<Socket>>>,
+    pub next_socket: i64,
+    // seeded generators of std/random.nv, handle 0 is the thread's own
+    pub generators: HashMap<i64, Rc<RefCell<StdRng>>>,
+    pub next_generator: i64,
+    // what time::instant counts nanoseconds from, so they fit an Int for a few
+    // hundred years of running
+    pub origin: Instant,
+    // see Config::nova_binary
+    pub nova_binary: Option<PathBuf>,
+    // what the command line passed after the script path
+    pub args: Vec<Rc<str>>,
+    // what the program passed to exit, the status the cli ends with
+    pub status: i32,
 }
 
 pub fn new() -> State {
@@ -132,6 +266,27 @@ pub fn new() -> State {
         gc_count: 0,
         garbage_collected: 0,
         gclock: false,
+        trace: None,
+        timers: vec![],
+        next_timer: 0,
+        current_timer: None,
+        lazy: HashMap::new(),
+        deferred: vec![],
+        handlers: vec![],
+        files: HashMap::new(),
+        next_file: 0,
+        processes: HashMap::new(),
+        next_process: 0,
+        workers: HashMap::new(),
+        next_worker: 0,
+        sockets: HashMap::new(),
+        next_socket: 0,
+        generators: HashMap::new(),
+        next_generator: 0,
+        origin: Instant::now(),
+        nova_binary: None,
+        args: vec![],
+        status: 0,
     }
 }
 
@@ -154,8 +309,11 @@ impl State {
             Heap::Int(v) => {
                 write!(out, "{v}").unwrap();
             }
+            Heap::Sized(v, width) => {
+                write!(out, "{}", width.value(*v)).unwrap();
+            }
             Heap::Float(v) => {
-                write!(out, "{v}").unwrap();
+                write!(out, "{}", format_float(*v)).unwrap();
             }
             Heap::Bool(v) => {
                 write!(out, "{v}").unwrap();
@@ -163,10 +321,10 @@ impl State {
             Heap::ListAddress(v) => {
                 self.print_heap(*v);
             }
-            Heap::StringAddress(v) => {
+            Heap::StringAddress(v) | Heap::BigIntAddress(v) => {
                 self.print_heap(*v);
             }
-            Heap::None => {
+            Heap::None(_) => {
                 write!(out, "None").unwrap();
             }
             Heap::Closure(function_poiner, capture_index) => {
@@ -191,6 +349,9 @@ impl State {
             Heap::String(v) => {
                 write!(out, "{v}").unwrap();
             }
+            Heap::BigInt(v) => {
+                write!(out, "{v}").unwrap();
+            }
             Heap::Struct(_, _) => {
                 todo!()
             }
@@ -210,14 +371,17 @@ impl State {
             Heap::ClosureAddress(v) => VmData::Closure(v),
             Heap::Function(v) => VmData::Function(v),
             Heap::Int(v) => VmData::Int(v),
+            Heap::Sized(v, width) => VmData::Sized(v, width),
             Heap::Float(v) => VmData::Float(v),
             Heap::Bool(v) => VmData::Bool(v),
             Heap::ListAddress(v) => VmData::List(v),
             Heap::StringAddress(v) => VmData::String(v),
-            Heap::None => VmData::None,
+            Heap::BigIntAddress(v) => VmData::BigInt(v),
+            Heap::None(origin) => VmData::None(origin),
             Heap::Closure(_, _) => todo!(),
             Heap::List(_) => todo!(),
             Heap::String(_) => todo!(),
+            Heap::BigInt(_) => todo!(),
             Heap::Struct(_, _) => todo!(),
             Heap::StructAddress(v) => VmData::Struct(v),
             Heap::Char(v) => VmData::Char(v),
@@ -229,6 +393,12 @@ impl State {
         self.program = program
     }
 
+    // a None returned by a native, which is remembered as created at its call
+    #[inline(always)]
+    pub fn none(&self) -> VmData {
+        VmData::None(Provenance(self.current_instruction as u32))
+    }
+
     #[inline(always)]
     pub fn next_instruction(&mut self) -> u8 {
         let result = &self.program[self.current_instruction];
@@ -262,6 +432,7 @@ impl State {
                 }
                 Heap::ListAddress(index) => self.check_usage(*index),
                 Heap::StringAddress(index) => self.check_usage(*index),
+                Heap::BigIntAddress(index) => self.check_usage(*index),
                 Heap::ClosureAddress(index) => self.check_usage(*index),
                 Heap::Closure(_, indextwo) => self.check_usage(*indextwo),
                 _ => {}
@@ -269,6 +440,23 @@ impl State {
         }
     }
 
+    // queues a callback, returns the id Timer::cancel takes
+    pub fn add_timer(
+        &mut self,
+        delay: Duration,
+        interval: Option<Duration>,
+        callback: VmData,
+    ) -> i64 {
+        self.next_timer += 1;
+        self.timers.push(Timer {
+            id: self.next_timer,
+            due: Instant::now() + delay,
+            interval,
+            callback,
+        });
+        self.next_timer
+    }
+
     #[inline(always)]
     pub fn collect_garbage(&mut self) {
         if self.gclock {
@@ -283,6 +471,8 @@ impl State {
             return;
         }
 
+        let started = self.trace.as_ref().filter(|t| t.gc).map(Tracer::now);
+        let collected = self.garbage_collected;
         self.gc_count += 1;
         self.used_data.clear();
         for item in self.stack.clone().iter() {
@@ -290,7 +480,7 @@ impl State {
                 VmData::List(index) => {
                     self.check_usage(*index);
                 }
-                VmData::String(index) => {
+                VmData::String(index) | VmData::BigInt(index) => {
                     self.check_usage(*index);
                 }
                 VmData::Closure(index) => {
@@ -299,6 +489,24 @@ impl State {
                 _ => {}
             }
         }
+        // closures waiting on a timer are not on the stack yet
+        for timer in self.timers.clone().iter() {
+            if let VmData::Closure(index) = timer.callback {
+                self.check_usage(index);
+            }
+        }
+        // nor is the close function of an open with block
+        for (close, resource) in self.deferred.clone() {
+            for item in [close, resource] {
+                if let VmData::List(index)
+                | VmData::String(index)
+                | VmData::BigInt(index)
+                | VmData::Closure(index) = item
+                {
+                    self.check_usage(index);
+                }
+            }
+        }
 
         for i in 0..self.heap.len() {
             if !self.used_data.has(&i) {
@@ -310,6 +518,60 @@ impl State {
             }
         }
         //dbg!(&self.garbage_collected);
+        if let (Some(trace), Some(started)) = (&self.trace, started) {
+            trace.collection(started, self.garbage_collected - collected, self.heap.len());
+        }
+    }
+
+    // keeps a file open until Sys::fileClose, returns its handle
+    pub fn add_file(&mut self, file: fs::File) -> i64 {
+        self.next_file += 1;
+        self.files.insert(self.next_file, Rc::new(file));
+        self.next_file
+    }
+
+    // keeps a process until Sys::processClose, returns its handle
+    pub fn add_process(&mut self, process: Process) -> i64 {
+        self.next_process += 1;
+        self.processes
+            .insert(self.next_process, Rc::new(RefCell::new(process)));
+        self.next_process
+    }
+
+    // keeps a socket until Socket::close, returns its handle
+    pub fn add_socket(&mut self, socket: Socket) -> i64 {
+        self.next_socket += 1;
+        self.sockets
+            .insert(self.next_socket, Rc::new(RefCell::new(socket)));
+        self.next_socket
+    }
+
+    pub fn add_generator(&mut self, generator: StdRng) -> i64 {
+        self.next_generator += 1;
+        self.generators
+            .insert(self.next_generator, Rc::new(RefCell::new(generator)));
+        self.next_generator
+    }
+
+    // keeps a worker until Worker::close, returns its id
+    pub fn add_worker(&mut self, worker: Worker) -> i64 {
+        self.next_worker += 1;
+        self.workers
+            .insert(self.next_worker, Rc::new(RefCell::new(worker)));
+        self.next_worker
+    }
+
+    #[inline(always)]
+    fn trace_alloc(&self, index: usize) {
+        if let Some(trace) = self.trace.as_ref().filter(|t| t.allocs) {
+            trace.alloc(self.heap[index].kind(), index, self.heap.len());
+        }
+    }
+
+    // heap slots that are not waiting on the free list
+    pub fn live_objects(&self) -> usize {
+        let free: HashSet<&usize> = self.free_space.iter().collect();
+        self.heap.len() - free.len()
     }
 
     #[inline(always)]
@@ -324,7 +586,7 @@ impl State {
 
     #[inline(always)]
     pub fn allocate_vmdata_to_heap(&mut self, item: VmData) -> usize {
-        match item {
+        let index = match item {
             VmData::Function(v) => {
                 if let Some(space) = self.free_space.pop() {
                     self.heap[space] = Heap::Function(v);
@@ -343,6 +605,15 @@ impl State {
                     self.heap.len() - 1
                 }
             }
+            VmData::Sized(v, width) => {
+                if let Some(space) = self.free_space.pop() {
+                    self.heap[space] = Heap::Sized(v, width);
+                    space
+                } else {
+                    self.heap.push(Heap::Sized(v, width));
+                    self.heap.len() - 1
+                }
+            }
             VmData::Float(v) => {
                 if let Some(space) = self.free_space.pop() {
                     self.heap[space] = Heap::Float(v);
@@ -370,12 +641,12 @@ impl State {
                     self.heap.len() - 1
                 }
             }
-            VmData::None => {
+            VmData::None(origin) => {
                 if let Some(space) = self.free_space.pop() {
-                    self.heap[space] = Heap::None;
+                    self.heap[space] = Heap::None(origin);
                     space
                 } else {
-                    self.heap.push(Heap::None);
+                    self.heap.push(Heap::None(origin));
                     self.heap.len() - 1
                 }
             }
@@ -388,6 +659,15 @@ impl State {
                     self.heap.len() - 1
                 }
             }
+            VmData::BigInt(v) => {
+                if let Some(space) = self.free_space.pop() {
+                    self.heap[space] = Heap::BigIntAddress(v);
+                    space
+                } else {
+                    self.heap.push(Heap::BigIntAddress(v));
+                    self.heap.len() - 1
+                }
+            }
             VmData::Closure(v) => {
                 if let Some(space) = self.free_space.pop() {
                     self.heap[space] = Heap::ClosureAddress(v);
@@ -416,7 +696,9 @@ impl State {
                     self.heap.len() - 1
                 }
             }
-        }
+        };
+        self.trace_alloc(index);
+        index
     }
 
     #[inline(always)]
@@ -426,39 +708,43 @@ impl State {
 
     #[inline(always)]
     pub fn delete_heap(&mut self, index: usize) {
-        self.heap[index] = Heap::None
+        self.heap[index] = Heap::NONE
     }
 
     #[inline(always)]
     pub fn allocate_new_heap(&mut self) -> usize {
-        if let Some(space) = self.free_space.pop() {
+        let index = if let Some(space) = self.free_space.pop() {
             space
         } else {
             self.collect_garbage();
             if let Some(space) = self.free_space.pop() {
-                self.heap[space] = Heap::None;
+                self.heap[space] = Heap::NONE;
                 space
             } else {
-                self.heap.push(Heap::None);
+                self.heap.push(Heap::NONE);
                 self.heap.len() - 1
             }
-        }
+        };
+        self.trace_alloc(index);
+        index
     }
 
     #[inline(always)]
     pub fn allocate_array(&mut self, array: Vec<usize>) -> usize {
-        if let Some(space) = self.free_space.pop() {
+        let index = if let Some(space) = self.free_space.pop() {
             self.heap[space] = Heap::List(array);
             space
         } else {
             self.heap.push(Heap::List(array));
             self.heap.len() - 1
-        }
+        };
+        self.trace_alloc(index);
+        index
     }
 
     #[inline(always)]
     pub fn allocate_string(&mut self, str: Rc<str>) -> usize {
-        if let Some(space) = self.free_space.pop() {
+        let index = if let Some(space) = self.free_space.pop() {
             self.heap[space] = Heap::String(str);
             space
         } else {
@@ -470,6 +756,37 @@ impl State {
                 self.heap.push(Heap::String(str));
                 self.heap.len() - 1
             }
+        };
+        self.trace_alloc(index);
+        index
+    }
+
+    #[inline(always)]
+    pub fn allocate_bigint(&mut self, value: BigInt) -> usize {
+        let value = Rc::new(value);
+        let index = if let Some(space) = self.free_space.pop() {
+            self.heap[space] = Heap::BigInt(value);
+            space
+        } else {
+            self.collect_garbage();
+            if let Some(space) = self.free_space.pop() {
+                self.heap[space] = Heap::BigInt(value);
+                space
+            } else {
+                self.heap.push(Heap::BigInt(value));
+                self.heap.len() - 1
+            }
+        };
+        self.trace_alloc(index);
+        index
+    }
+
+    pub fn get_bigint(&self, index: usize) -> Result<&BigInt, NovaError> {
+        match &self.heap[index] {
+            Heap::BigInt(value) => Ok(value),
+            _ => Err(NovaError::Runtime {
+                msg: "Expected a BigInt in the heap".into(),
+            }),
         }
     }
 
@@ -478,7 +795,7 @@ impl State {
         self.offset = self.stack.len() - size;
         self.window.push(self.offset);
         for _ in 0..locals {
-            self.stack.push(VmData::None)
+            self.stack.push(VmData::NONE)
         }
     }
 
@@ -487,7 +804,7 @@ impl State {
         self.offset = self.stack.len();
         self.window.push(self.offset);
         for _ in 0..size {
-            self.stack.push(VmData::None)
+            self.stack.push(VmData::NONE)
         }
     }
 
@@ -0,0 +1,128 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    time::Instant,
+};
+
+use common::fileposition::FilePosition;
+
+// writes --trace events to stderr as newline delimited json. every line is a
+// chrome trace event, so the log loads into perfetto or chrome://tracing
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    pub calls: bool,
+    pub allocs: bool,
+    pub gc: bool,
+    start: Instant,
+}
+
+impl Tracer {
+    // parses the category list given to --trace, an empty list traces everything
+    pub fn parse(categories: &str) -> Result<Tracer, String> {
+        let mut tracer = Tracer {
+            calls: false,
+            allocs: false,
+            gc: false,
+            start: Instant::now(),
+        };
+        if categories.is_empty() {
+            tracer.calls = true;
+            tracer.allocs = true;
+            tracer.gc = true;
+            return Ok(tracer);
+        }
+        for category in categories.split(',') {
+            match category.trim() {
+                "calls" => tracer.calls = true,
+                "allocs" => tracer.allocs = true,
+                "gc" => tracer.gc = true,
+                other => {
+                    return Err(format!(
+                        "Unknown trace category '{}', expected calls, allocs or gc",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(tracer)
+    }
+
+    // microseconds since tracing started, the unit chrome tracing expects
+    pub fn now(&self) -> u128 {
+        self.start.elapsed().as_micros()
+    }
+
+    pub fn call(&self, name: &str, callsite: Option<&FilePosition>) {
+        let args = match callsite {
+            Some(position) => format!(
+                ",\"args\":{{\"file\":{},\"line\":{},\"col\":{}}}",
+                json_string(&position_file(position)),
+                position.line,
+                position.col
+            ),
+            None => String::new(),
+        };
+        self.emit(format!(
+            "{{\"name\":{},\"cat\":\"call\",\"ph\":\"B\",\"ts\":{},\"pid\":1,\"tid\":1{}}}",
+            json_string(name),
+            self.now(),
+            args
+        ));
+    }
+
+    pub fn ret(&self) {
+        self.emit(format!(
+            "{{\"cat\":\"call\",\"ph\":\"E\",\"ts\":{},\"pid\":1,\"tid\":1}}",
+            self.now()
+        ));
+    }
+
+    pub fn alloc(&self, kind: &str, index: usize, heap: usize) {
+        self.emit(format!(
+            "{{\"name\":\"alloc\",\"cat\":\"alloc\",\"ph\":\"i\",\"s\":\"t\",\"ts\":{},\"pid\":1,\"tid\":1,\"args\":{{\"kind\":\"{}\",\"index\":{},\"heap\":{}}}}}",
+            self.now(),
+            kind,
+            index,
+            heap
+        ));
+    }
+
+    pub fn collection(&self, start: u128, collected: usize, heap: usize) {
+        self.emit(format!(
+            "{{\"name\":\"gc\",\"cat\":\"gc\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":1,\"args\":{{\"collected\":{},\"heap\":{}}}}}",
+            start,
+            self.now() - start,
+            collected,
+            heap
+        ));
+    }
+
+    fn emit(&self, event: String) {
+        let mut err = io::stderr().lock();
+        let _ = writeln!(err, "{}", event);
+    }
+}
+
+fn position_file(position: &FilePosition) -> Cow<'_, str> {
+    match &position.filepath {
+        Some(path) => path.to_string_lossy(),
+        None => "repl".into(),
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}